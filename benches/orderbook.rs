@@ -0,0 +1,310 @@
+use std::{cmp, hint::black_box, sync::mpsc::{self, Receiver, SyncSender}, time::{Duration, Instant}};
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+use rexchange::{common::{OrderId, Sequenced, OrderType, ParticipantId, Price, Quantity, Side, INVALID_PRICE, INVALID_QUANTITY, NO_ORDER_FLAGS}, latency::recorder::LatencyConfig, logging::logger::Logger, market_data::market_update::MarketUpdate, matching_engine::{matching_engine::{MatchingEngine, MatchingEngineConfig}, order::{NewOrder, OrderInfo}, orderbook::{OrderBook, OrderBookConfig}}, order_server::{participants_request::ParticipantRequest, participants_response::ParticipantResponse}};
+
+const MID_PRICE: Price = 10_000;
+const MAX_PRICE_LEVELS: usize = 4096;
+const BATCH: u64 = 1000;
+const DEEP_BOOK_LEVELS: u64 = 1000;
+const CHANNEL_CAPACITY: usize = 1 << 20;
+
+// an order book and the engine it publishes through, every benchmark measures the book alone
+struct Bench {
+    book: OrderBook,
+    engine: MatchingEngine,
+    responses: Receiver<Sequenced<ParticipantResponse>>,
+    market_updates: Receiver<Sequenced<MarketUpdate>>,
+    _requests: SyncSender<ParticipantRequest>,
+}
+
+impl Bench {
+    fn new() -> Self {
+        let (requests_tx, requests_rx) = mpsc::sync_channel(1);
+        let (responses_tx, responses_rx) = mpsc::sync_channel(CHANNEL_CAPACITY);
+        let (market_updates_tx, market_updates_rx) = mpsc::sync_channel(CHANNEL_CAPACITY);
+        let config = OrderBookConfig { max_price_levels: MAX_PRICE_LEVELS, ..OrderBookConfig::default() };
+        let engine_config = MatchingEngineConfig::single_shard(std::slice::from_ref(&config), &[0, 1], LatencyConfig { enabled: false, ..LatencyConfig::default() });
+
+        Self {
+            book: OrderBook::new(0, config.clone()),
+            engine: MatchingEngine::new(requests_rx, responses_tx, market_updates_tx, &engine_config, Logger::disabled()),
+            responses: responses_rx,
+            market_updates: market_updates_rx,
+            _requests: requests_tx,
+        }
+    }
+
+    #[inline]
+    fn add(&mut self, participant_id: ParticipantId, order_id: OrderId, side: Side, price: Price, qty: Quantity) {
+        self.book.add(NewOrder {
+            order_info: OrderInfo { participant_id, order_id },
+            order_type: OrderType::Limit,
+            side,
+            price,
+            stop_price: INVALID_PRICE,
+            qty,
+            display_qty: INVALID_QUANTITY,
+            flags: NO_ORDER_FLAGS,
+        }, &mut self.engine);
+    }
+
+    #[inline]
+    fn cancel(&mut self, participant_id: ParticipantId, order_id: OrderId) {
+        self.book.cancel(OrderInfo { participant_id, order_id }, &mut self.engine);
+    }
+
+    // the channels are bounded, they have to be emptied between batches
+    fn drain(&mut self) {
+        while let Ok(response) = self.responses.try_recv() {
+            black_box(response);
+        }
+
+        while let Ok(update) = self.market_updates.try_recv() {
+            black_box(update);
+        }
+    }
+}
+
+// runs `iters` operations at most `batch_size` at a time, `batch` times its operations and cleans up after itself untimed
+fn run_batches(iters: u64, batch_size: u64, mut batch: impl FnMut(u64) -> Duration) -> Duration {
+    let mut elapsed = Duration::ZERO;
+    let mut done = 0;
+
+    while done < iters {
+        let count = cmp::min(batch_size, iters - done);
+        elapsed += batch(count);
+        done += count;
+    }
+
+    elapsed
+}
+
+fn bench_add(c: &mut Criterion) {
+    let mut group = c.benchmark_group("add");
+    group.throughput(Throughput::Elements(1));
+
+    group.bench_function("passive_same_level", |b| {
+        let mut bench = Bench::new();
+
+        b.iter_custom(|iters| run_batches(iters, BATCH, |count| {
+            let start = Instant::now();
+            for order_id in 0..count {
+                bench.add(0, order_id, Side::Buy, MID_PRICE, 10);
+            }
+            let elapsed = start.elapsed();
+
+            for order_id in 0..count {
+                bench.cancel(0, order_id);
+            }
+            bench.drain();
+
+            elapsed
+        }));
+    });
+
+    // every order improves the best bid, so the new level goes straight to the top
+    group.bench_function("new_best_level", |b| {
+        let mut bench = Bench::new();
+
+        b.iter_custom(|iters| run_batches(iters, BATCH, |count| {
+            let start = Instant::now();
+            for order_id in 0..count {
+                bench.add(0, order_id, Side::Buy, MID_PRICE - BATCH + order_id, 10);
+            }
+            let elapsed = start.elapsed();
+
+            for order_id in 0..count {
+                bench.cancel(0, order_id);
+            }
+            bench.drain();
+
+            elapsed
+        }));
+    });
+
+    // new levels behind DEEP_BOOK_LEVELS existing ones, which add_order_at_price has to walk
+    group.bench_function("deep_in_book", |b| {
+        let mut bench = Bench::new();
+
+        for level in 0..DEEP_BOOK_LEVELS {
+            bench.add(1, level, Side::Buy, MID_PRICE - 2 * level, 10);
+        }
+        bench.drain();
+
+        let deepest_price = MID_PRICE - 2 * (DEEP_BOOK_LEVELS - 1);
+
+        b.iter_custom(|iters| run_batches(iters, DEEP_BOOK_LEVELS / 10, |count| {
+            let start = Instant::now();
+            for order_id in 0..count {
+                bench.add(0, order_id, Side::Buy, deepest_price + 2 * order_id + 1, 10);
+            }
+            let elapsed = start.elapsed();
+
+            for order_id in 0..count {
+                bench.cancel(0, order_id);
+            }
+            bench.drain();
+
+            elapsed
+        }));
+    });
+
+    group.finish();
+}
+
+fn bench_cancel(c: &mut Criterion) {
+    let mut group = c.benchmark_group("cancel");
+    group.throughput(Throughput::Elements(1));
+
+    for levels in [1, 10, 100] {
+        group.bench_with_input(BenchmarkId::new("levels", levels), &levels, |b, levels| {
+            let mut bench = Bench::new();
+
+            b.iter_custom(|iters| run_batches(iters, BATCH, |count| {
+                for order_id in 0..count {
+                    bench.add(0, order_id, Side::Buy, MID_PRICE - order_id % levels, 10);
+                }
+
+                let start = Instant::now();
+                for order_id in 0..count {
+                    bench.cancel(0, order_id);
+                }
+                let elapsed = start.elapsed();
+
+                bench.drain();
+                elapsed
+            }));
+        });
+    }
+
+    group.finish();
+}
+
+// one aggressive order taking every level of the book
+fn bench_sweep(c: &mut Criterion) {
+    let mut group = c.benchmark_group("sweep");
+
+    for levels in [1, 10, 100] {
+        group.throughput(Throughput::Elements(levels));
+        group.bench_with_input(BenchmarkId::new("levels", levels), &levels, |b, levels| {
+            let mut bench = Bench::new();
+            let mut aggressive_order_id = 0;
+
+            b.iter_custom(|iters| {
+                let mut elapsed = Duration::ZERO;
+
+                for _ in 0..iters {
+                    for level in 0..*levels {
+                        bench.add(1, level, Side::Sell, MID_PRICE + 1 + level, 10);
+                    }
+
+                    let start = Instant::now();
+                    bench.add(0, aggressive_order_id, Side::Buy, MID_PRICE + levels, 10 * *levels as Quantity);
+                    elapsed += start.elapsed();
+
+                    aggressive_order_id = (aggressive_order_id + 1) % BATCH;
+                    bench.drain();
+                }
+
+                elapsed
+            });
+        });
+    }
+
+    group.finish();
+}
+
+// xorshift, enough to draw a reproducible flow without pulling in a rng
+struct OrderFlow {
+    state: u64,
+    next_order_id: OrderId,
+    live_orders: Vec<OrderId>,
+}
+
+impl OrderFlow {
+    const MAX_LIVE_ORDERS: usize = 10_000;
+    const ORDER_IDS: OrderId = 1 << 16;
+
+    fn new(seed: u64) -> Self {
+        Self { state: seed, next_order_id: 0, live_orders: Vec::new() }
+    }
+
+    fn next_random(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+
+    fn next_order_id(&mut self) -> OrderId {
+        let order_id = self.next_order_id;
+        self.next_order_id = (self.next_order_id + 1) % Self::ORDER_IDS;
+        order_id
+    }
+
+    // 60% passive adds around the mid, 30% cancels, 10% aggressive orders crossing a few levels
+    fn step(&mut self, bench: &mut Bench) {
+        let action = self.next_random() % 100;
+        let side = if self.next_random().is_multiple_of(2) { Side::Buy } else { Side::Sell };
+        let qty = 1 + (self.next_random() % 50) as Quantity;
+
+        if action < 30 || self.live_orders.len() >= Self::MAX_LIVE_ORDERS {
+            if !self.live_orders.is_empty() {
+                let index = self.next_random() as usize % self.live_orders.len();
+                let order_id = self.live_orders.swap_remove(index);
+                bench.cancel(0, order_id);
+            }
+            return;
+        }
+
+        let order_id = self.next_order_id();
+        let offset = 1 + self.next_random() % 20;
+
+        let price = match (action < 90, side) {
+            (true, Side::Buy) => MID_PRICE - offset,
+            (true, _) => MID_PRICE + offset,
+            (false, Side::Buy) => MID_PRICE + offset / 4,
+            (false, _) => MID_PRICE - offset / 4,
+        };
+
+        bench.add(0, order_id, side, price, qty);
+        self.live_orders.push(order_id);
+    }
+}
+
+fn bench_mixed_flow(c: &mut Criterion) {
+    let mut group = c.benchmark_group("mixed_flow");
+    group.throughput(Throughput::Elements(1));
+
+    group.bench_function("steady_state", |b| {
+        let mut bench = Bench::new();
+        let mut flow = OrderFlow::new(0x2545_f491_4f6c_dd1d);
+
+        // let the book fill up before measuring
+        for step in 0..(4 * OrderFlow::MAX_LIVE_ORDERS as u64) {
+            flow.step(&mut bench);
+            if step.is_multiple_of(BATCH) {
+                bench.drain();
+            }
+        }
+        bench.drain();
+
+        b.iter_custom(|iters| run_batches(iters, BATCH, |count| {
+            let start = Instant::now();
+            for _ in 0..count {
+                flow.step(&mut bench);
+            }
+            let elapsed = start.elapsed();
+
+            bench.drain();
+            elapsed
+        }));
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_add, bench_cancel, bench_sweep, bench_mixed_flow);
+criterion_main!(benches);
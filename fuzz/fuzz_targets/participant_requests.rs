@@ -0,0 +1,100 @@
+#![no_main]
+
+use std::sync::mpsc;
+
+use libfuzzer_sys::fuzz_target;
+
+use rexchange::{common::{get_current_nanos, NANOS_TO_SECS, OrderFlags, OrderType, Side, INVALID_PRICE, INVALID_QUANTITY}, latency::recorder::LatencyConfig, logging::logger::Logger, matching_engine::{circuit_breaker::{BreachAction, CircuitBreakerConfig, HaltOrderPolicy}, matching_engine::{MatchingEngine, MatchingEngineConfig}, orderbook::OrderBookConfig}, order_server::participants_request::{ParticipantRequest, ParticipantRequestType}};
+
+const REQUEST_SIZE: usize = 14;
+const SYMBOLS: usize = 2;
+
+// small fields on purpose, so that ids collide, prices share level slots and every request kind shows up
+fn decode(bytes: &[u8]) -> ParticipantRequest {
+    let small_price = |byte: u8| match byte {
+        0 => INVALID_PRICE,
+        _ => 90 + byte as u64 % 64,
+    };
+
+    ParticipantRequest {
+        request_type: match bytes[0] % 3 {
+            0 => ParticipantRequestType::New,
+            1 => ParticipantRequestType::Cancel,
+            _ => ParticipantRequestType::Invalid,
+        },
+        participant_id: (bytes[1] % 5) as u32,
+        symbol_id: (bytes[2] % (SYMBOLS as u8 + 1)) as u32,
+        order_id: u16::from_le_bytes([bytes[3], bytes[4]]) as u64 % 80,
+        order_type: match bytes[5] % 5 {
+            0 => OrderType::Invalid,
+            1 => OrderType::Limit,
+            2 => OrderType::Market,
+            3 => OrderType::Stop,
+            _ => OrderType::StopLimit,
+        },
+        side: match bytes[6] % 3 {
+            0 => Side::Buy,
+            1 => Side::Sell,
+            _ => Side::Invalid,
+        },
+        price: small_price(bytes[7]),
+        stop_price: small_price(bytes[8]),
+        qty: u16::from_le_bytes([bytes[9], bytes[10]]) as u32 % 64,
+        display_qty: match bytes[11] {
+            0..=127 => INVALID_QUANTITY,
+            byte => (byte % 16) as u32,
+        },
+        flags: (bytes[12] & 0x07) as OrderFlags,
+        recv_time: bytes[13] as u64,
+    }
+}
+
+fuzz_target!(|data: &[u8]| {
+    let (requests_tx, requests_rx) = mpsc::sync_channel(1);
+    let (responses_tx, responses_rx) = mpsc::sync_channel(1 << 16);
+    let (market_updates_tx, market_updates_rx) = mpsc::sync_channel(1 << 16);
+
+    // the second symbol has tight bands, so halts, queued orders and auctions get exercised too
+    let configs = [
+        OrderBookConfig { max_price_levels: 32, max_order_ids: 64, max_participants: 4, ..OrderBookConfig::default() },
+        OrderBookConfig {
+            max_price_levels: 32,
+            max_order_ids: 64,
+            max_participants: 4,
+            circuit_breaker: CircuitBreakerConfig {
+                dynamic_band_bps: 500,
+                breach_action: BreachAction::VolatilityAuction,
+                halt_order_policy: HaltOrderPolicy::Queue,
+                halt_duration: NANOS_TO_SECS,
+                ..CircuitBreakerConfig::default()
+            },
+            ..OrderBookConfig::default()
+        },
+    ];
+    let engine_config = MatchingEngineConfig::single_shard(&configs, &[0, 1, 2, 3], LatencyConfig { enabled: false, ..LatencyConfig::default() });
+    let mut engine = MatchingEngine::new(requests_rx, responses_tx, market_updates_tx, &engine_config, Logger::disabled());
+
+    // a request with the top bit of its first byte set also fires the timers, as if the halt had expired
+    let mut now = get_current_nanos();
+
+    for bytes in data.chunks_exact(REQUEST_SIZE) {
+        let request = decode(bytes);
+        engine.process_participant_request(&request);
+
+        if bytes[0] & 0x80 != 0 {
+            now += 2 * NANOS_TO_SECS;
+            engine.check_timers(now);
+        }
+
+        for symbol_id in 0..SYMBOLS {
+            if let Err(error) = engine.order_book(symbol_id as u32).unwrap().check_invariants() {
+                panic!("{} after {}", error, request);
+            }
+        }
+
+        while responses_rx.try_recv().is_ok() {}
+        while market_updates_rx.try_recv().is_ok() {}
+    }
+
+    drop(requests_tx);
+});
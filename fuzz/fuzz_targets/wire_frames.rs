@@ -0,0 +1,26 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+use rexchange::wire::{self, LoginFrame, MarketUpdateFrame, PositionFrame, RequestFrame, ResponseFrame, TradeFrame, WireFrame};
+
+// reserved bytes aren't kept by a decode, frames that set them are left out of the round trip
+fn round_trip<F: WireFrame>(bytes: &[u8], reserved: &[usize]) {
+    if let Ok((message, size)) = wire::decode::<F>(bytes) {
+        let mut encoded = Vec::new();
+        wire::encode::<F>(&message, &mut encoded);
+
+        if reserved.iter().all(|&offset| bytes[offset] == 0) {
+            assert_eq!(encoded, &bytes[..size]);
+        }
+    }
+}
+
+fuzz_target!(|bytes: &[u8]| {
+    round_trip::<RequestFrame>(bytes, &[]);
+    round_trip::<ResponseFrame>(bytes, &[7]);
+    round_trip::<MarketUpdateFrame>(bytes, &[6, 7]);
+    round_trip::<TradeFrame>(bytes, &[5, 6, 7]);
+    round_trip::<LoginFrame>(bytes, &[5, 6, 7]);
+    round_trip::<PositionFrame>(bytes, &[]);
+});
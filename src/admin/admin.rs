@@ -0,0 +1,202 @@
+use std::{fmt, io, path::PathBuf, str::FromStr, sync::{mpsc::SyncSender, Arc, Mutex}};
+
+use crate::common::{Nanos, ParticipantId, Price, Quantity, ShardId, Side, TradeId};
+use crate::eod::end_of_day::EndOfDay;
+use crate::fees::billing::{Billing, FeeTotals};
+use crate::positions::{Position, PositionKeeper};
+use crate::trades::{trade::{Trade, TradeAmendment}, trade_store::TradeStore};
+
+use super::audit::{AuditLog, AuditRecord};
+
+// one line of the admin console
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum AdminCommand {
+    Trade(TradeId),
+    Fees(ParticipantId),
+    Positions(ParticipantId),
+    EndOfDay,
+    Bust { trade_id: TradeId, reason: String },
+    Correct { trade_id: TradeId, price: Price, qty: Quantity, reason: String },
+}
+
+impl AdminCommand {
+    // `trade <id>`, `fees <participant>`, `positions <participant>`, `eod`, `bust <id> <reason>` or `correct <id> <price> <qty> <reason>`, the reason is the rest of the line
+    pub fn parse(line: &str) -> Result<Self, AdminError> {
+        let mut words = line.split_whitespace();
+        let command = words.next().unwrap_or("");
+
+        let command = match command {
+            "trade" => AdminCommand::Trade(argument(words.next(), "trade id")?),
+            "fees" => AdminCommand::Fees(argument(words.next(), "participant")?),
+            "positions" => AdminCommand::Positions(argument(words.next(), "participant")?),
+            "eod" => AdminCommand::EndOfDay,
+            "bust" => AdminCommand::Bust {
+                trade_id: argument(words.next(), "trade id")?,
+                reason: reason(words)?,
+            },
+            "correct" => AdminCommand::Correct {
+                trade_id: argument(words.next(), "trade id")?,
+                price: argument(words.next(), "price")?,
+                qty: argument(words.next(), "qty")?,
+                reason: reason(words)?,
+            },
+            _ => return Err(AdminError::UnknownCommand(command.to_string())),
+        };
+
+        Ok(command)
+    }
+}
+
+fn argument<T: FromStr>(word: Option<&str>, name: &'static str) -> Result<T, AdminError> {
+    word.and_then(|word| word.parse().ok()).ok_or(AdminError::BadArgument(name))
+}
+
+fn reason<'a>(words: impl Iterator<Item = &'a str>) -> Result<String, AdminError> {
+    let reason = words.collect::<Vec<_>>().join(" ");
+    if reason.is_empty() { Err(AdminError::MissingReason) } else { Ok(reason) }
+}
+
+// what a command answers with
+#[derive(Clone, PartialEq, Debug)]
+pub enum AdminReply {
+    Trade(Trade),
+    Fees(FeeTotals),
+    Positions(Vec<Position>), //by symbol id, the symbols the participant traded
+    EndOfDay(PathBuf),
+}
+
+impl fmt::Display for AdminReply {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AdminReply::Trade(trade) => write!(f, "{}", trade),
+            AdminReply::Fees(totals) => write!(f, "{}", totals),
+            AdminReply::Positions(positions) if positions.is_empty() => write!(f, "no positions"),
+            AdminReply::Positions(positions) => write!(f, "{}", positions.iter().map(Position::to_string).collect::<Vec<_>>().join(", ")),
+            AdminReply::EndOfDay(directory) => write!(f, "end of day written to {}", directory.display()),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum AdminError {
+    UnknownCommand(String),
+    BadArgument(&'static str),
+    MissingReason,
+    UnknownTrade(TradeId),
+    AlreadyBusted(TradeId),
+    Unchanged(TradeId),
+    EngineStopped,
+    EndOfDayOff,
+    Io(io::Error),
+}
+
+impl fmt::Display for AdminError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AdminError::UnknownCommand(command) => write!(f, "unknown command '{}'", command),
+            AdminError::BadArgument(name) => write!(f, "missing or invalid {}", name),
+            AdminError::MissingReason => write!(f, "a reason is required"),
+            AdminError::UnknownTrade(trade_id) => write!(f, "unknown trade {}", trade_id),
+            AdminError::AlreadyBusted(trade_id) => write!(f, "trade {} is busted", trade_id),
+            AdminError::Unchanged(trade_id) => write!(f, "trade {} already has this price and quantity", trade_id),
+            AdminError::EngineStopped => write!(f, "the matching engine has stopped"),
+            AdminError::EndOfDayOff => write!(f, "end of day reports are not configured"),
+            AdminError::Io(error) => write!(f, "cannot write: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for AdminError {}
+
+impl From<io::Error> for AdminError {
+    fn from(error: io::Error) -> Self {
+        AdminError::Io(error)
+    }
+}
+
+// busts and corrections are stored and audited before the engine owning the symbol publishes them, the trade store
+// lock is held throughout so that two of them can't act on the same trade at once
+pub struct Admin {
+    trade_store: Arc<Mutex<TradeStore>>,
+    billing: Arc<Mutex<Billing>>,
+    positions: Arc<Mutex<PositionKeeper>>,
+    symbol_shards: Vec<ShardId>, //indexed by symbol id
+    shards: Vec<SyncSender<TradeAmendment>>, //indexed by shard id
+    audit: AuditLog,
+    end_of_day: Option<EndOfDay>,
+}
+
+impl Admin {
+    pub fn new(trade_store: Arc<Mutex<TradeStore>>, billing: Arc<Mutex<Billing>>, positions: Arc<Mutex<PositionKeeper>>, symbol_shards: Vec<ShardId>, shards: Vec<SyncSender<TradeAmendment>>, audit: AuditLog) -> Self {
+        assert!(symbol_shards.iter().all(|shard_id| (*shard_id as usize) < shards.len()), "symbol routed to a missing shard");
+
+        Self {
+            trade_store,
+            billing,
+            positions,
+            symbol_shards,
+            shards,
+            audit,
+            end_of_day: None,
+        }
+    }
+
+    pub fn set_end_of_day(&mut self, end_of_day: EndOfDay) {
+        self.end_of_day = Some(end_of_day);
+    }
+
+    // the trade as it is after the command, the fees or positions of a participant, or where the end of day of the session
+    // `now` is in was written. `user` and `now` are only recorded for busts and corrections
+    pub fn execute(&mut self, user: &str, command: &AdminCommand, now: Nanos) -> Result<AdminReply, AdminError> {
+        // takes the trade store lock itself
+        if let AdminCommand::EndOfDay = command {
+            let end_of_day = self.end_of_day.as_ref().ok_or(AdminError::EndOfDayOff)?;
+            return Ok(AdminReply::EndOfDay(end_of_day.run(now)?));
+        }
+
+        let mut trade_store = self.trade_store.lock().unwrap();
+
+        let (trade_id, reason) = match command {
+            AdminCommand::Trade(trade_id) => return trade_store.trade(*trade_id).cloned().map(AdminReply::Trade).ok_or(AdminError::UnknownTrade(*trade_id)),
+            AdminCommand::Fees(participant_id) => return Ok(AdminReply::Fees(self.billing.lock().unwrap().totals(*participant_id))),
+            AdminCommand::Positions(participant_id) => return Ok(AdminReply::Positions(self.positions.lock().unwrap().positions(*participant_id))),
+            AdminCommand::Bust { trade_id, reason } | AdminCommand::Correct { trade_id, reason, .. } => (*trade_id, reason.as_str()),
+            AdminCommand::EndOfDay => unreachable!(),
+        };
+
+        let original = trade_store.trade(trade_id).cloned().ok_or(AdminError::UnknownTrade(trade_id))?;
+
+        if original.is_busted() {
+            return Err(AdminError::AlreadyBusted(trade_id));
+        }
+
+        let amended = match *command {
+            AdminCommand::Correct { qty: 0, .. } => return Err(AdminError::BadArgument("qty, bust the trade instead")),
+            AdminCommand::Correct { price, qty, .. } => Trade { price, qty, ..original.clone() },
+            _ => Trade { qty: 0, ..original.clone() },
+        };
+
+        if amended == original {
+            return Err(AdminError::Unchanged(trade_id));
+        }
+
+        trade_store.amend(amended.clone())?;
+        trade_store.flush()?;
+
+        self.audit.record(&AuditRecord { timestamp: now, user, reason, original: &original, amended: &amended })?;
+
+        // refunded at what was charged, whatever the tier now
+        let (buy_fee, sell_fee) = {
+            let billing = self.billing.lock().unwrap();
+            (billing.side_fee(original.buy_participant_id, trade_id, Side::Buy), billing.side_fee(original.sell_participant_id, trade_id, Side::Sell))
+        };
+
+        let amendment = TradeAmendment { original, amended: amended.clone(), buy_fee, sell_fee };
+        self.positions.lock().unwrap().amend(&amendment);
+
+        let shard_id = self.symbol_shards.get(amendment.original.symbol_id as usize).copied().unwrap_or(0);
+        self.shards[shard_id as usize].send(amendment).map_err(|_| AdminError::EngineStopped)?;
+
+        Ok(AdminReply::Trade(amended))
+    }
+}
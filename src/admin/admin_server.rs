@@ -0,0 +1,115 @@
+use std::{io::{self, BufRead, BufReader, BufWriter, Write}, net::{SocketAddr, TcpListener, TcpStream}, path::PathBuf, sync::{Arc, Mutex}, thread};
+
+use serde::Deserialize;
+
+use crate::common::get_current_nanos;
+use crate::logging::logger::{LogPayload, Logger};
+
+use super::admin::{Admin, AdminCommand};
+
+// an empty address leaves the admin console off
+#[derive(Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AdminConfig {
+    pub address: String,
+    pub audit_path: PathBuf,
+    pub users: Vec<AdminUser>,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AdminUser {
+    pub username: String,
+    pub password: String,
+}
+
+// a line based console, usable with nc: `login <username> <password>` first, then one command per line, each answered
+// by `OK <trade, fees or positions>` or `ERROR <reason>`. A bad login closes the connection. Returns the address it listens on
+pub fn spawn(config: &AdminConfig, admin: Admin, logger: Logger) -> io::Result<SocketAddr> {
+    let listener = TcpListener::bind(&config.address)?;
+    let local_addr = listener.local_addr()?;
+
+    let users = Arc::new(config.users.clone());
+    let admin = Arc::new(Mutex::new(admin));
+
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    let users = users.clone();
+                    let admin = admin.clone();
+                    let logger = logger.clone();
+                    thread::spawn(move || serve(stream, &users, &admin, logger));
+                },
+                Err(_) => logger.warn("admin: cannot accept connection", LogPayload::None),
+            }
+        }
+    });
+
+    Ok(local_addr)
+}
+
+fn serve(stream: TcpStream, users: &[AdminUser], admin: &Mutex<Admin>, logger: Logger) {
+    let mut lines = match stream.try_clone() {
+        Ok(stream) => BufReader::new(stream).lines(),
+        Err(_) => return,
+    };
+    let mut writer = BufWriter::new(stream);
+
+    let user = match lines.next() {
+        Some(Ok(line)) => login(&line, users),
+        _ => None,
+    };
+
+    let user = match user {
+        Some(user) => user,
+        None => {
+            logger.warn("admin: login rejected", LogPayload::None);
+            let _ = reply(&mut writer, "ERROR not authorized");
+            return;
+        },
+    };
+
+    logger.info("admin: logged in", LogPayload::None);
+
+    if reply(&mut writer, "OK").is_err() {
+        return;
+    }
+
+    for line in lines.map_while(Result::ok) {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let result = AdminCommand::parse(&line).and_then(|command| admin.lock().unwrap().execute(user, &command, get_current_nanos()));
+
+        let answer = match result {
+            Ok(reply) => format!("OK {}", reply),
+            Err(error) => {
+                logger.warn("admin: command failed", LogPayload::None);
+                format!("ERROR {}", error)
+            },
+        };
+
+        if reply(&mut writer, &answer).is_err() {
+            break;
+        }
+    }
+
+    logger.info("admin: logged out", LogPayload::None);
+}
+
+// the username the line logs in as
+fn login<'a>(line: &str, users: &'a [AdminUser]) -> Option<&'a str> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+
+    match words[..] {
+        ["login", username, password] => users.iter().find(|user| user.username == username && user.password == password).map(|user| user.username.as_str()),
+        _ => None,
+    }
+}
+
+fn reply(writer: &mut impl Write, line: &str) -> io::Result<()> {
+    writeln!(writer, "{}", line)?;
+    writer.flush()
+}
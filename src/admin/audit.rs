@@ -0,0 +1,60 @@
+use std::{fs::{self, File, OpenOptions}, io::{self, Write}, path::Path};
+
+use crate::common::Nanos;
+use crate::trades::trade::Trade;
+
+const HEADER: &str = "timestamp,user,action,trade_id,symbol_id,price,qty,new_price,new_qty,reason";
+
+// who busted or corrected a trade and why, with the trade before and after
+pub struct AuditRecord<'a> {
+    pub timestamp: Nanos,
+    pub user: &'a str,
+    pub reason: &'a str,
+    pub original: &'a Trade,
+    pub amended: &'a Trade,
+}
+
+// an append-only CSV file, one line per record, written through as soon as it is recorded
+pub struct AuditLog {
+    file: File,
+}
+
+impl AuditLog {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        if let Some(directory) = path.parent().filter(|directory| !directory.as_os_str().is_empty()) {
+            fs::create_dir_all(directory)?;
+        }
+
+        let mut file = OpenOptions::new().append(true).create(true).open(path)?;
+
+        if file.metadata()?.len() == 0 {
+            writeln!(file, "{}", HEADER)?;
+        }
+
+        Ok(Self { file })
+    }
+
+    pub fn record(&mut self, record: &AuditRecord) -> io::Result<()> {
+        let (original, amended) = (record.original, record.amended);
+
+        // one write per line, so that a line is never split by another writer
+        let line = format!("{},{},{},{},{},{},{},{},{},{}\n",
+            record.timestamp,
+            quote(record.user),
+            if amended.is_busted() { "bust" } else { "correct" },
+            original.trade_id,
+            original.symbol_id,
+            original.price,
+            original.qty,
+            amended.price,
+            amended.qty,
+            quote(record.reason));
+
+        self.file.write_all(line.as_bytes())?;
+        self.file.sync_data()
+    }
+}
+
+fn quote(field: &str) -> String {
+    format!("\"{}\"", field.replace('"', "\"\""))
+}
@@ -0,0 +1,4 @@
+#[allow(clippy::module_inception)]
+pub mod admin;
+pub mod admin_server;
+pub mod audit;
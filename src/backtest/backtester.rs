@@ -0,0 +1,633 @@
+use std::{cmp::{self, Ordering, Reverse}, collections::{BinaryHeap, HashMap}, fmt, path::PathBuf, sync::{mpsc::{self, Receiver, Sender}, Arc}};
+
+use serde::Deserialize;
+
+use crate::client::strategy::Strategy;
+use crate::common::{side_to_index, Fee, Nanos, OrderId, OrderType, ParticipantId, Price, Quantity, Rng, Sequenced, Side, SymbolId, MAX_MARKET_UPDATES, MAX_PARTICIPANTS_UPDATES, NO_ORDER_FLAGS};
+use crate::fees::fee_schedule::FeeSchedule;
+use crate::latency::recorder::LatencyConfig;
+use crate::logging::logger::Logger;
+use crate::market_data::{market_book::MarketBook, market_update::MarketUpdate};
+use crate::matching_engine::{matching_engine::{MatchingEngine, MatchingEngineConfig}, order::OrderInfo, orderbook::OrderBookConfig};
+use crate::order_server::{participants_request::{ParticipantRequest, ParticipantRequestType}, participants_response::{ParticipantResponse, ParticipantResponseType}};
+use crate::pnl::PositionPnl;
+
+use super::historical_data::{DataError, HistoricalEvent, HistoricalEventType};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BacktestStrategy {
+    MarketMaker,
+    LiquidityTaker,
+}
+
+// the strategy is the first configured bot of its kind, trading against the replayed data
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BacktestConfig {
+    pub data: PathBuf,
+    pub strategy: BacktestStrategy,
+    pub latency: LatencyModel,
+}
+
+impl Default for BacktestConfig {
+    fn default() -> Self {
+        Self {
+            data: PathBuf::new(),
+            strategy: BacktestStrategy::MarketMaker,
+            latency: LatencyModel::default(),
+        }
+    }
+}
+
+// one way delays between the strategy and the exchange, each leg gets a uniform jitter of up to jitter_ns
+// messages on a leg never overtake each other, as on a TCP session
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LatencyModel {
+    pub order_entry_ns: Nanos,
+    pub response_ns: Nanos,
+    pub market_data_ns: Nanos,
+    pub jitter_ns: Nanos,
+    pub seed: u64,
+}
+
+impl Default for LatencyModel {
+    fn default() -> Self {
+        Self {
+            order_entry_ns: 50_000,
+            response_ns: 50_000,
+            market_data_ns: 20_000,
+            jitter_ns: 0,
+            seed: 1,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Leg {
+    OrderEntry = 0,
+    Response,
+    MarketData,
+}
+
+// orders of the strategy that rested in the book, queue_ahead_qty is what was in front of them at the price when they joined
+#[derive(Clone, Copy, Default)]
+pub struct QueueStats {
+    pub rested_orders: u64,
+    pub filled_orders: u64,
+    pub queue_ahead_qty: u64,
+    pub passive_fill_qty: u64,
+    pub time_to_first_fill: Nanos,
+}
+
+impl QueueStats {
+    pub fn avg_queue_ahead(&self) -> f64 {
+        self.queue_ahead_qty as f64 / cmp::max(self.rested_orders, 1) as f64
+    }
+
+    // over the orders that got filled while resting
+    pub fn avg_time_to_first_fill(&self) -> f64 {
+        self.time_to_first_fill as f64 / cmp::max(self.filled_orders, 1) as f64
+    }
+}
+
+impl fmt::Display for QueueStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "QueueStats [rested: {}, filled: {}, avg_queue_ahead: {:.1}, passive_fill_qty: {}, avg_time_to_first_fill: {:.0}ns]",
+        self.rested_orders, self.filled_orders, self.avg_queue_ahead(), self.passive_fill_qty, self.avg_time_to_first_fill())
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct SymbolResult {
+    pub symbol_id: SymbolId,
+    pub pnl: PositionPnl,
+    pub mark_price: Option<Price>, //mid, or else the last trade, at the end of the data
+}
+
+impl SymbolResult {
+    pub fn unrealized(&self) -> f64 {
+        self.mark_price.map_or(0.0, |mark_price| self.pnl.unrealized(mark_price))
+    }
+}
+
+impl fmt::Display for SymbolResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "SymbolResult [symb: {}, position: {}, avg_price: {:.2}, realized: {:.2}, unrealized: {:.2}, traded_qty: {}, fills: {}]",
+        self.symbol_id, self.pnl.position, self.pnl.open_price(), self.pnl.realized, self.unrealized(), self.pnl.traded_qty, self.pnl.fills)
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct BacktestReport {
+    pub historical_events: u64,
+    pub dropped_events: u64, //historical orders the book couldn't take, out of order ids or on a taken price level slot
+    pub requests: u64,
+    pub rejected_requests: u64,
+    pub symbols: Vec<SymbolResult>, //the symbols the strategy traded
+    pub queue: QueueStats,
+    pub fees: Fee, //what the strategy's fills were charged, rebates taken off
+}
+
+impl BacktestReport {
+    pub fn total_pnl(&self) -> f64 {
+        self.symbols.iter().map(|symbol| symbol.pnl.realized + symbol.unrealized()).sum()
+    }
+
+    pub fn net_pnl(&self) -> f64 {
+        self.total_pnl() - self.fees as f64
+    }
+}
+
+impl fmt::Display for BacktestReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "BacktestReport [events: {}, dropped: {}, requests: {}, rejected: {}, pnl: {:.2}, fees: {}, net_pnl: {:.2}]",
+        self.historical_events, self.dropped_events, self.requests, self.rejected_requests, self.total_pnl(), self.fees, self.net_pnl())?;
+
+        for symbol in &self.symbols {
+            writeln!(f, "  {}", symbol)?;
+        }
+
+        write!(f, "  {}", self.queue)
+    }
+}
+
+enum Delivery {
+    Request(ParticipantRequest),
+    Response(ParticipantResponse),
+    MarketUpdate(MarketUpdate),
+}
+
+// ordered by time, then by when it was scheduled
+struct Scheduled {
+    time: Nanos,
+    seq: u64,
+    delivery: Delivery,
+}
+
+impl PartialEq for Scheduled {
+    fn eq(&self, other: &Self) -> bool {
+        (self.time, self.seq) == (other.time, other.seq)
+    }
+}
+
+impl Eq for Scheduled {}
+
+impl PartialOrd for Scheduled {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Scheduled {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.time, self.seq).cmp(&(other.time, other.seq))
+    }
+}
+
+// a historical order as the book holds it, qty is what history says is left of it
+struct HistoryOrder {
+    symbol_id: SymbolId,
+    side: Side,
+    price: Price,
+    qty: Quantity,
+    book_qty: Quantity,
+}
+
+struct RestingOrder {
+    rest_time: Nanos,
+    first_fill_time: Option<Nanos>,
+}
+
+// replays historical market data into the live matching engine as the orders of a history participant,
+// historical trades are sent by an aggressor participant so that they take the queue as it is, strategy orders included.
+// The strategy trades through the same engine, behind the latency model, and the report is built from the engine's responses
+pub struct Backtester {
+    engine: MatchingEngine,
+    responses: Receiver<Sequenced<ParticipantResponse>>,
+    market_updates: Receiver<Sequenced<MarketUpdate>>,
+    gateway: Sender<ParticipantRequest>,
+    requests: Receiver<ParticipantRequest>,
+    participant_id: ParticipantId,
+    history_participant: ParticipantId,
+    aggressor_participant: ParticipantId,
+    latency: LatencyModel,
+    rng: Rng,
+    last_arrivals: [Nanos; 3], //indexed by leg
+    now: Nanos,
+    scheduled: BinaryHeap<Reverse<Scheduled>>,
+    next_seq: u64,
+    history_orders: Vec<Option<HistoryOrder>>, //indexed by order id of the history participant
+    free_order_ids: Vec<OrderId>,
+    l3_orders: HashMap<(SymbolId, u64), OrderId>, //historical order id to history participant order id
+    l2_levels: HashMap<(SymbolId, usize, Price), Vec<OrderId>>, //orders making up a level, oldest first
+    books: Vec<MarketBook>, //indexed by symbol id
+    pnls: Vec<Option<PositionPnl>>, //indexed by symbol id, None until the strategy trades it
+    resting_orders: HashMap<OrderId, RestingOrder>,
+    report: BacktestReport,
+}
+
+impl Backtester {
+    // the two highest participant ids replay the history, the strategy trades as participant_id
+    pub fn new(order_book_configs: &[OrderBookConfig], participant_id: ParticipantId, latency: LatencyModel) -> Self {
+        let max_participants = order_book_configs.iter().map(|config| config.max_participants).min().unwrap_or(0) as ParticipantId;
+        let max_order_ids = order_book_configs.iter().map(|config| config.max_order_ids).min().unwrap_or(0);
+        let (history_participant, aggressor_participant) = (max_participants - 1, max_participants - 2);
+        assert!(participant_id < aggressor_participant, "participant {} is taken by the history replay", participant_id);
+
+        let (_, requests_rx) = mpsc::sync_channel(1);
+        let (responses_tx, responses_rx) = mpsc::sync_channel(MAX_PARTICIPANTS_UPDATES);
+        let (market_updates_tx, market_updates_rx) = mpsc::sync_channel(MAX_MARKET_UPDATES);
+        let engine_config = MatchingEngineConfig::single_shard(order_book_configs, &[participant_id, history_participant, aggressor_participant], LatencyConfig { enabled: false, ..LatencyConfig::default() });
+        let (gateway, requests) = mpsc::channel();
+
+        Self {
+            engine: MatchingEngine::new(requests_rx, responses_tx, market_updates_tx, &engine_config, Logger::disabled()),
+            responses: responses_rx,
+            market_updates: market_updates_rx,
+            gateway,
+            requests,
+            participant_id,
+            history_participant,
+            aggressor_participant,
+            rng: Rng::new(latency.seed),
+            latency,
+            last_arrivals: [0; 3],
+            now: 0,
+            scheduled: BinaryHeap::new(),
+            next_seq: 0,
+            history_orders: (0..max_order_ids).map(|_| None).collect(),
+            free_order_ids: (0..max_order_ids as OrderId).rev().collect(),
+            l3_orders: HashMap::new(),
+            l2_levels: HashMap::new(),
+            books: (0..order_book_configs.len()).map(|_| MarketBook::new()).collect(),
+            pnls: vec![None; order_book_configs.len()],
+            resting_orders: HashMap::new(),
+            report: BacktestReport::default(),
+        }
+    }
+
+    // what the strategy sends its orders through
+    pub fn gateway(&self) -> Sender<ParticipantRequest> {
+        self.gateway.clone()
+    }
+
+    pub fn engine(&self) -> &MatchingEngine {
+        &self.engine
+    }
+
+    pub fn set_fee_schedule(&mut self, fees: Arc<FeeSchedule>) {
+        self.engine.set_fee_schedule(fees);
+    }
+
+    // history goes first when it is due at the same time as a delivery, what is still in flight at the end is delivered
+    pub fn run(mut self, strategy: &mut impl Strategy, events: impl IntoIterator<Item = Result<HistoricalEvent, DataError>>) -> Result<BacktestReport, DataError> {
+        let mut events = events.into_iter().peekable();
+        let mut has_started = false;
+
+        loop {
+            let next_event_time = match events.peek() {
+                Some(Ok(event)) => Some(event.timestamp),
+                Some(Err(_)) => return Err(events.next().unwrap().unwrap_err()),
+                None => None,
+            };
+
+            let next_delivery_time = self.scheduled.peek().map(|scheduled| scheduled.0.time);
+
+            let is_history_next = match (next_event_time, next_delivery_time) {
+                (None, None) => break,
+                (Some(event_time), Some(delivery_time)) => event_time <= delivery_time,
+                (event_time, _) => event_time.is_some(),
+            };
+
+            if is_history_next {
+                let event = events.next().unwrap()?;
+                self.advance(event.timestamp);
+                self.replay(&event);
+
+                if !has_started {
+                    has_started = true;
+                    self.act(strategy);
+                }
+            } else {
+                let scheduled = self.scheduled.pop().unwrap().0;
+                self.advance(scheduled.time);
+                self.deliver(scheduled.delivery, strategy);
+            }
+        }
+
+        Ok(self.finish())
+    }
+
+    fn advance(&mut self, now: Nanos) {
+        self.now = now;
+        self.engine.set_simulated_time(now);
+        self.engine.check_timers(now);
+        self.drain();
+    }
+
+    fn replay(&mut self, event: &HistoricalEvent) {
+        self.report.historical_events += 1;
+        let symbol_id = event.symbol_id;
+
+        match event.event_type {
+            HistoricalEventType::Add { order_id, side, price, qty } => {
+                if self.l3_orders.contains_key(&(symbol_id, order_id)) {
+                    self.report.dropped_events += 1;
+                    return;
+                }
+
+                if let Some(history_order_id) = self.add_history_order(symbol_id, side, price, qty) {
+                    self.l3_orders.insert((symbol_id, order_id), history_order_id);
+                }
+            },
+            HistoricalEventType::Execute { order_id, qty } => {
+                let history_order_id = match self.l3_orders.get(&(symbol_id, order_id)) {
+                    Some(history_order_id) => *history_order_id,
+                    None => return,
+                };
+
+                let (side, price) = {
+                    let order = self.history_orders[history_order_id as usize].as_mut().unwrap();
+                    order.qty = order.qty.saturating_sub(qty);
+                    (order.side, order.price)
+                };
+
+                self.take(symbol_id, opposite_side(side), price, qty);
+
+                // strategy orders ahead of it took the trade, the order gives up what history says is gone and its priority
+                let order = self.history_orders[history_order_id as usize].as_ref().unwrap();
+                if order.book_qty > order.qty {
+                    let qty = order.qty;
+                    self.remove_history_order(history_order_id);
+                    self.l3_orders.remove(&(symbol_id, order_id));
+
+                    if qty > 0 {
+                        if let Some(history_order_id) = self.add_history_order(symbol_id, side, price, qty) {
+                            self.l3_orders.insert((symbol_id, order_id), history_order_id);
+                        }
+                    }
+                } else if order.qty == 0 {
+                    self.remove_history_order(history_order_id);
+                    self.l3_orders.remove(&(symbol_id, order_id));
+                }
+            },
+            HistoricalEventType::Delete { order_id } => {
+                if let Some(history_order_id) = self.l3_orders.remove(&(symbol_id, order_id)) {
+                    self.remove_history_order(history_order_id);
+                }
+            },
+            HistoricalEventType::Level { side, price, qty } => self.resize_level(symbol_id, side, price, qty),
+            HistoricalEventType::Trade { aggressor_side, price, qty } => self.take(symbol_id, aggressor_side, price, qty),
+        }
+    }
+
+    // new liquidity joins the back of the level, what leaves it is taken from the newest orders
+    fn resize_level(&mut self, symbol_id: SymbolId, side: Side, price: Price, qty: Quantity) {
+        let mut level = self.l2_levels.remove(&(symbol_id, side_to_index(side), price)).unwrap_or_default();
+
+        level.retain(|history_order_id| {
+            let is_resting = self.history_orders[*history_order_id as usize].as_ref().is_some_and(|order| order.book_qty > 0);
+            if !is_resting {
+                self.history_orders[*history_order_id as usize] = None;
+                self.free_order_ids.push(*history_order_id);
+            }
+            is_resting
+        });
+
+        let mut level_qty: Quantity = level.iter().map(|history_order_id| self.history_orders[*history_order_id as usize].as_ref().unwrap().book_qty).sum();
+
+        while level_qty > qty {
+            let history_order_id = level.pop().unwrap();
+            let order_qty = self.history_orders[history_order_id as usize].as_ref().unwrap().book_qty;
+            self.remove_history_order(history_order_id);
+            level_qty -= order_qty;
+        }
+
+        if level_qty < qty {
+            if let Some(history_order_id) = self.add_history_order(symbol_id, side, price, qty - level_qty) {
+                level.push(history_order_id);
+            }
+        }
+
+        if !level.is_empty() {
+            self.l2_levels.insert((symbol_id, side_to_index(side), price), level);
+        }
+    }
+
+    fn add_history_order(&mut self, symbol_id: SymbolId, side: Side, price: Price, qty: Quantity) -> Option<OrderId> {
+        let history_order_id = match self.free_order_ids.pop() {
+            Some(history_order_id) => history_order_id,
+            None => {
+                self.report.dropped_events += 1;
+                return None;
+            },
+        };
+
+        self.history_orders[history_order_id as usize] = Some(HistoryOrder { symbol_id, side, price, qty, book_qty: qty });
+        self.process(&ParticipantRequest {
+            request_type: ParticipantRequestType::New,
+            participant_id: self.history_participant,
+            symbol_id,
+            order_id: history_order_id,
+            order_type: OrderType::Limit,
+            side,
+            price,
+            qty,
+            flags: NO_ORDER_FLAGS,
+            ..ParticipantRequest::default()
+        });
+
+        if self.history_orders[history_order_id as usize].as_ref().unwrap().book_qty == 0 {
+            self.history_orders[history_order_id as usize] = None;
+            self.free_order_ids.push(history_order_id);
+            return None;
+        }
+
+        Some(history_order_id)
+    }
+
+    fn remove_history_order(&mut self, history_order_id: OrderId) {
+        let order = self.history_orders[history_order_id as usize].take().unwrap();
+
+        if order.book_qty > 0 {
+            self.process(&ParticipantRequest {
+                request_type: ParticipantRequestType::Cancel,
+                participant_id: self.history_participant,
+                symbol_id: order.symbol_id,
+                order_id: history_order_id,
+                ..ParticipantRequest::default()
+            });
+        }
+
+        self.free_order_ids.push(history_order_id);
+    }
+
+    // a historical trade, whatever doesn't match at the price is cancelled
+    fn take(&mut self, symbol_id: SymbolId, side: Side, price: Price, qty: Quantity) {
+        let request = |request_type| ParticipantRequest {
+            request_type,
+            participant_id: self.aggressor_participant,
+            symbol_id,
+            order_id: 0,
+            order_type: OrderType::Limit,
+            side,
+            price,
+            qty,
+            flags: NO_ORDER_FLAGS,
+            ..ParticipantRequest::default()
+        };
+
+        let (new, cancel) = (request(ParticipantRequestType::New), request(ParticipantRequestType::Cancel));
+        self.process(&new);
+        self.process(&cancel);
+    }
+
+    fn process(&mut self, request: &ParticipantRequest) {
+        self.engine.process_participant_request(request);
+        self.drain();
+    }
+
+    fn drain(&mut self) {
+        while let Ok(response) = self.responses.try_recv() {
+            let response = response.payload;
+
+            if response.participant_id == self.history_participant {
+                if let Some(order) = self.history_orders.get_mut(response.participant_order_id as usize).and_then(Option::as_mut) {
+                    order.book_qty = match response.response_type {
+                        ParticipantResponseType::Accepted | ParticipantResponseType::Filled => response.leaves_qty,
+                        ParticipantResponseType::Cancelled | ParticipantResponseType::Rejected => 0,
+                        _ => order.book_qty,
+                    };
+
+                    if response.response_type == ParticipantResponseType::Rejected {
+                        self.report.dropped_events += 1;
+                    }
+                }
+            } else if response.participant_id == self.participant_id {
+                self.on_strategy_response(&response);
+                self.schedule(Leg::Response, Delivery::Response(response));
+            }
+        }
+
+        while let Ok(update) = self.market_updates.try_recv() {
+            let update = update.payload;
+
+            if let Some(book) = self.books.get_mut(update.symbol_id as usize) {
+                book.on_update(&update);
+            }
+
+            self.schedule(Leg::MarketData, Delivery::MarketUpdate(update));
+        }
+    }
+
+    // the exchange's view of the strategy's orders, as of when the engine answered
+    fn on_strategy_response(&mut self, response: &ParticipantResponse) {
+        match response.response_type {
+            ParticipantResponseType::Filled => {
+                let pnl = self.pnls[response.symbol_id as usize].get_or_insert_with(PositionPnl::new);
+                pnl.on_fill(response.side, response.price, response.exec_qty);
+                self.report.fees += response.fee;
+
+                if let Some(resting_order) = self.resting_orders.get_mut(&response.participant_order_id) {
+                    self.report.queue.passive_fill_qty += response.exec_qty as u64;
+
+                    if resting_order.first_fill_time.is_none() {
+                        resting_order.first_fill_time = Some(self.now);
+                        self.report.queue.filled_orders += 1;
+                        self.report.queue.time_to_first_fill += self.now - resting_order.rest_time;
+                    }
+                }
+
+                if response.leaves_qty == 0 {
+                    self.resting_orders.remove(&response.participant_order_id);
+                }
+            },
+            ParticipantResponseType::Cancelled => {
+                self.resting_orders.remove(&response.participant_order_id);
+            },
+            ParticipantResponseType::Rejected | ParticipantResponseType::CancelRejected => self.report.rejected_requests += 1,
+            _ => {},
+        }
+    }
+
+    fn deliver(&mut self, delivery: Delivery, strategy: &mut impl Strategy) {
+        match delivery {
+            Delivery::Request(request) => {
+                self.report.requests += 1;
+                self.process(&request);
+
+                if request.request_type == ParticipantRequestType::New {
+                    self.on_strategy_order_added(&request);
+                }
+
+                return;
+            },
+            Delivery::Response(response) => strategy.on_response(&response),
+            Delivery::MarketUpdate(update) => strategy.on_market_update(&update),
+        }
+
+        self.act(strategy);
+    }
+
+    // an order that rests is queued behind what was at its price already
+    fn on_strategy_order_added(&mut self, request: &ParticipantRequest) {
+        let order_book = match self.engine.order_book(request.symbol_id) {
+            Some(order_book) => order_book,
+            None => return,
+        };
+
+        let order_info = OrderInfo { participant_id: self.participant_id, order_id: request.order_id };
+        let levels = order_book.resting_orders(request.side);
+        let level = levels.iter().find(|(_, orders)| orders.iter().any(|(resting_order_info, _)| *resting_order_info == order_info));
+
+        if let Some((_, orders)) = level {
+            let queue_ahead_qty: Quantity = orders.iter().take_while(|(resting_order_info, _)| *resting_order_info != order_info).map(|(_, qty)| qty).sum();
+
+            self.report.queue.rested_orders += 1;
+            self.report.queue.queue_ahead_qty += queue_ahead_qty as u64;
+            self.resting_orders.insert(request.order_id, RestingOrder { rest_time: self.now, first_fill_time: None });
+        }
+    }
+
+    fn act(&mut self, strategy: &mut impl Strategy) {
+        // the strategy's own gateway can't go away, other errors are the strategy's to handle
+        let _ = strategy.act();
+
+        while let Ok(mut request) = self.requests.try_recv() {
+            request.recv_time = 0;
+            self.schedule(Leg::OrderEntry, Delivery::Request(request));
+        }
+    }
+
+    fn schedule(&mut self, leg: Leg, delivery: Delivery) {
+        let delay = match leg {
+            Leg::OrderEntry => self.latency.order_entry_ns,
+            Leg::Response => self.latency.response_ns,
+            Leg::MarketData => self.latency.market_data_ns,
+        } + if self.latency.jitter_ns > 0 { self.rng.below(self.latency.jitter_ns + 1) } else { 0 };
+
+        let time = cmp::max(self.now + delay, self.last_arrivals[leg as usize]);
+        self.last_arrivals[leg as usize] = time;
+
+        self.scheduled.push(Reverse(Scheduled { time, seq: self.next_seq, delivery }));
+        self.next_seq += 1;
+    }
+
+    fn finish(mut self) -> BacktestReport {
+        self.report.symbols = self.pnls.iter().enumerate().filter_map(|(symbol_id, pnl)| pnl.map(|pnl| {
+            let book = &self.books[symbol_id];
+            SymbolResult { symbol_id: symbol_id as SymbolId, pnl, mark_price: book.mid_price().or(book.last_trade_price()) }
+        })).collect();
+
+        self.report
+    }
+}
+
+fn opposite_side(side: Side) -> Side {
+    match side {
+        Side::Buy => Side::Sell,
+        _ => Side::Buy,
+    }
+}
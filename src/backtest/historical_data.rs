@@ -0,0 +1,151 @@
+use std::{fmt, fs::File, io::{self, BufRead, BufReader}, path::Path, str::FromStr};
+
+use crate::common::{Nanos, Price, Quantity, Side, SymbolId};
+
+// L3 events follow individual orders by their historical id, L2 events the aggregated size of a price level
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum HistoricalEventType {
+    Add { order_id: u64, side: Side, price: Price, qty: Quantity },
+    Execute { order_id: u64, qty: Quantity },
+    Delete { order_id: u64 },
+    Level { side: Side, price: Price, qty: Quantity },
+    Trade { aggressor_side: Side, price: Price, qty: Quantity },
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct HistoricalEvent {
+    pub timestamp: Nanos,
+    pub symbol_id: SymbolId,
+    pub event_type: HistoricalEventType,
+}
+
+#[derive(Debug)]
+pub enum DataError {
+    Io(io::Error),
+    Parse(usize, String), //line number, what is wrong with it
+}
+
+impl fmt::Display for DataError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DataError::Io(error) => write!(f, "cannot read historical data: {}", error),
+            DataError::Parse(line, message) => write!(f, "historical data line {}: {}", line, message),
+        }
+    }
+}
+
+impl std::error::Error for DataError {}
+
+// one event per line, `timestamp_ns,symbol_id,EVENT,...` with timestamps that never go back
+//   ADD,order_id,side,price,qty    EXECUTE,order_id,qty    DELETE,order_id
+//   LEVEL,side,price,qty (the new size of the level, 0 removes it)    TRADE,aggressor_side,price,qty
+// sides are B or S, blank lines and lines starting with # are skipped
+pub struct HistoricalReader<R: BufRead> {
+    reader: R,
+    line: String,
+    line_number: usize,
+    last_timestamp: Nanos,
+}
+
+impl HistoricalReader<BufReader<File>> {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        Ok(Self::new(BufReader::new(File::open(path)?)))
+    }
+}
+
+impl<R: BufRead> HistoricalReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            line: String::new(),
+            line_number: 0,
+            last_timestamp: 0,
+        }
+    }
+
+    fn parse_line(&self) -> Result<HistoricalEvent, String> {
+        let mut fields = self.line.trim().split(',').map(str::trim);
+        let mut next = |name: &str| fields.next().ok_or_else(|| format!("missing {}", name));
+
+        let timestamp = parse(next("timestamp")?, "timestamp")?;
+        let symbol_id = parse(next("symbol")?, "symbol")?;
+
+        let event_type = match next("event")? {
+            "ADD" => HistoricalEventType::Add {
+                order_id: parse(next("order id")?, "order id")?,
+                side: parse_side(next("side")?)?,
+                price: parse(next("price")?, "price")?,
+                qty: parse(next("qty")?, "qty")?,
+            },
+            "EXECUTE" => HistoricalEventType::Execute {
+                order_id: parse(next("order id")?, "order id")?,
+                qty: parse(next("qty")?, "qty")?,
+            },
+            "DELETE" => HistoricalEventType::Delete {
+                order_id: parse(next("order id")?, "order id")?,
+            },
+            "LEVEL" => HistoricalEventType::Level {
+                side: parse_side(next("side")?)?,
+                price: parse(next("price")?, "price")?,
+                qty: parse(next("qty")?, "qty")?,
+            },
+            "TRADE" => HistoricalEventType::Trade {
+                aggressor_side: parse_side(next("side")?)?,
+                price: parse(next("price")?, "price")?,
+                qty: parse(next("qty")?, "qty")?,
+            },
+            event => return Err(format!("unknown event {}", event)),
+        };
+
+        if fields.next().is_some() {
+            return Err("too many fields".to_string());
+        }
+
+        if timestamp < self.last_timestamp {
+            return Err(format!("timestamp {} is before the previous one", timestamp));
+        }
+
+        Ok(HistoricalEvent { timestamp, symbol_id, event_type })
+    }
+}
+
+impl<R: BufRead> Iterator for HistoricalReader<R> {
+    type Item = Result<HistoricalEvent, DataError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            self.line.clear();
+            self.line_number += 1;
+
+            match self.reader.read_line(&mut self.line) {
+                Ok(0) => return None,
+                Ok(_) => {},
+                Err(error) => return Some(Err(DataError::Io(error))),
+            }
+
+            let line = self.line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let event = self.parse_line().map_err(|message| DataError::Parse(self.line_number, message));
+            if let Ok(event) = &event {
+                self.last_timestamp = event.timestamp;
+            }
+
+            return Some(event);
+        }
+    }
+}
+
+fn parse<T: FromStr>(field: &str, name: &str) -> Result<T, String> {
+    field.parse().map_err(|_| format!("invalid {} {}", name, field))
+}
+
+fn parse_side(field: &str) -> Result<Side, String> {
+    match field {
+        "B" => Ok(Side::Buy),
+        "S" => Ok(Side::Sell),
+        _ => Err(format!("invalid side {}", field)),
+    }
+}
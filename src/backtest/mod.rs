@@ -0,0 +1,2 @@
+pub mod backtester;
+pub mod historical_data;
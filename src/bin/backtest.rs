@@ -0,0 +1,55 @@
+use std::{env, path::PathBuf, process, sync::Arc};
+
+use rexchange::{backtest::{backtester::{BacktestConfig, Backtester, BacktestStrategy}, historical_data::HistoricalReader}, client::{liquidity_taker::LiquidityTaker, market_maker::MarketMaker}, config::{Config, DEFAULT_CONFIG_PATH}, fees::fee_schedule::FeeSchedule, logging::logger::Logger};
+
+// runs the configured strategy against historical data through the matching engine and prints its report
+fn main() {
+    let config_path = env::args().nth(1).map(PathBuf::from).unwrap_or_else(|| PathBuf::from(DEFAULT_CONFIG_PATH));
+
+    let config = match Config::load(&config_path) {
+        Ok(config) => config,
+        Err(error) => {
+            eprintln!("backtest: {}", error);
+            process::exit(1);
+        }
+    };
+
+    let (backtest_config, participant) = match (config.backtest.clone(), config.backtest_participant()) {
+        (Some(backtest_config), Some(participant)) => (backtest_config, participant),
+        _ => {
+            eprintln!("backtest: no [backtest] section in {}", config_path.display());
+            process::exit(1);
+        }
+    };
+
+    let events = match HistoricalReader::open(&backtest_config.data) {
+        Ok(events) => events,
+        Err(error) => {
+            eprintln!("backtest: cannot open {}: {}", backtest_config.data.display(), error);
+            process::exit(1);
+        }
+    };
+
+    let BacktestConfig { strategy, latency, .. } = backtest_config;
+    let mut backtester = Backtester::new(&config.order_book_configs(), participant, latency);
+    backtester.set_fee_schedule(Arc::new(FeeSchedule::new(&config.fees, config.limits.max_participants)));
+
+    let report = match strategy {
+        BacktestStrategy::MarketMaker => {
+            let mut market_maker = MarketMaker::new(config.market_makers[0].clone(), backtester.gateway(), Logger::disabled());
+            backtester.run(&mut market_maker, events)
+        },
+        BacktestStrategy::LiquidityTaker => {
+            let mut liquidity_taker = LiquidityTaker::new(config.liquidity_takers[0].clone(), backtester.gateway(), Logger::disabled());
+            backtester.run(&mut liquidity_taker, events)
+        },
+    };
+
+    match report {
+        Ok(report) => println!("{}", report),
+        Err(error) => {
+            eprintln!("backtest: {}", error);
+            process::exit(1);
+        }
+    }
+}
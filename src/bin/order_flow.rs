@@ -0,0 +1,43 @@
+use std::{env, path::PathBuf, process, thread};
+
+use rexchange::{client::{order_flow::OrderFlow, tcp_gateway::TcpOrderGateway}, config::{Config, DEFAULT_CONFIG_PATH}};
+
+// sends the configured order flow to the order gateway of a running exchange
+fn main() {
+    let config_path = env::args().nth(1).map(PathBuf::from).unwrap_or_else(|| PathBuf::from(DEFAULT_CONFIG_PATH));
+
+    let config = match Config::load(&config_path) {
+        Ok(config) => config,
+        Err(error) => {
+            eprintln!("order_flow: {}", error);
+            process::exit(1);
+        }
+    };
+
+    let order_flow_config = match config.order_flow.clone() {
+        Some(order_flow_config) => order_flow_config,
+        None => {
+            eprintln!("order_flow: no [order_flow] section in {}", config_path.display());
+            process::exit(1);
+        }
+    };
+
+    let (mut gateway, responses) = match TcpOrderGateway::connect(&config.gateway.address, &config.order_flow_logins()) {
+        Ok(gateway) => gateway,
+        Err(error) => {
+            eprintln!("order_flow: cannot connect to {}: {}", config.gateway.address, error);
+            process::exit(1);
+        }
+    };
+
+    // the flow doesn't wait on its responses, they are only drained
+    thread::spawn(move || responses.iter().count());
+
+    let mut order_flow = OrderFlow::new(order_flow_config);
+
+    if order_flow.run(&mut gateway).is_err() {
+        eprintln!("order_flow: the exchange closed the connection");
+    }
+
+    println!("{}", order_flow.stats());
+}
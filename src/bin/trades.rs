@@ -0,0 +1,55 @@
+use std::{env, path::PathBuf, process, str::FromStr};
+
+use rexchange::{config::{Config, DEFAULT_CONFIG_PATH}, trades::trade_store::{TradeQuery, TradeStore}};
+
+const USAGE: &str = "usage: trades [config] [--symbol id] [--participant id] [--from nanos] [--to nanos]";
+
+// prints the stored trades matching the filters, in the order they were stored
+fn main() {
+    let mut args = env::args().skip(1).peekable();
+    let config_path = match args.next_if(|arg| !arg.starts_with("--")) {
+        Some(path) => PathBuf::from(path),
+        None => PathBuf::from(DEFAULT_CONFIG_PATH),
+    };
+
+    let mut query = TradeQuery::default();
+
+    while let Some(arg) = args.next() {
+        let value = args.next();
+
+        match arg.as_str() {
+            "--symbol" => query.symbol_id = Some(parse(value)),
+            "--participant" => query.participant_id = Some(parse(value)),
+            "--from" => query.from = Some(parse(value)),
+            "--to" => query.to = Some(parse(value)),
+            _ => exit(USAGE),
+        }
+    }
+
+    let config = match Config::load(&config_path) {
+        Ok(config) => config,
+        Err(error) => exit(&error.to_string()),
+    };
+
+    if config.trade_store.path.as_os_str().is_empty() {
+        exit(&format!("no [trade_store] path in {}", config_path.display()));
+    }
+
+    let trade_store = match TradeStore::open(&config.trade_store.path) {
+        Ok(trade_store) => trade_store,
+        Err(error) => exit(&format!("cannot open {}: {}", config.trade_store.path.display(), error)),
+    };
+
+    for trade in trade_store.query(&query) {
+        println!("{}", trade);
+    }
+}
+
+fn parse<T: FromStr>(value: Option<String>) -> T {
+    value.and_then(|value| value.parse().ok()).unwrap_or_else(|| exit(USAGE))
+}
+
+fn exit(message: &str) -> ! {
+    eprintln!("trades: {}", message);
+    process::exit(1);
+}
@@ -0,0 +1,289 @@
+use std::{cmp, collections::{HashSet, VecDeque}};
+
+use serde::Deserialize;
+
+use crate::common::{side_to_index, OrderId, ParticipantId, Price, Quantity, Side, SymbolId, TradeId, INVALID_PARTICIPANT_ID};
+use crate::logging::logger::{LogLevel, LogPayload, Logger, OwnFill};
+use crate::market_data::{market_book::MarketBook, market_update::{MarketUpdate, MarketUpdateType}};
+use crate::order_server::participants_response::{ParticipantResponse, ParticipantResponseType};
+use crate::pnl::PositionPnl;
+
+use super::order_manager::{ClientError, OrderGateway, OrderManager};
+use super::strategy::Strategy;
+
+// momentum trades along the imbalance, contrarian against it
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SignalDirection {
+    Momentum,
+    Contrarian,
+}
+
+// the signal is the aggressor volume imbalance, (buy - sell) / (buy + sell), over the last window_trades trades
+// it fires once the window holds min_window_qty and the imbalance reaches imbalance_threshold, then the window starts over
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LiquidityTakerConfig {
+    pub participant: ParticipantId,
+    pub core: isize,
+    pub symbols: Vec<SymbolId>,
+    pub window_trades: usize,
+    pub min_window_qty: u64,
+    pub imbalance_threshold: f64,
+    pub direction: SignalDirection,
+    pub order_qty: Quantity,
+    pub max_position: i64,
+    pub order_ids: usize,
+}
+
+impl Default for LiquidityTakerConfig {
+    fn default() -> Self {
+        Self {
+            participant: INVALID_PARTICIPANT_ID,
+            core: -1,
+            symbols: Vec::new(),
+            window_trades: 20,
+            min_window_qty: 100,
+            imbalance_threshold: 0.6,
+            direction: SignalDirection::Momentum,
+            order_qty: 50,
+            max_position: 500,
+            order_ids: 1024,
+        }
+    }
+}
+
+struct TradedSymbol {
+    book: MarketBook,
+    window: VecDeque<(TradeId, Side, Quantity)>,
+    window_qty: [u64; 2], //aggressor volume in the window, indexed by side
+    own_trade_ids: HashSet<TradeId>, //our own fills whose trades haven't been seen yet
+    last_trade_id: Option<TradeId>, //a symbol's trade ids only grow, the fills of older ones came after their trade
+    pnl: PositionPnl,
+    order_id: Option<OrderId>, //the order in flight, one at a time
+    to_check: bool,
+}
+
+impl TradedSymbol {
+    fn new() -> Self {
+        Self {
+            book: MarketBook::new(),
+            window: VecDeque::new(),
+            window_qty: [0; 2],
+            own_trade_ids: HashSet::new(),
+            last_trade_id: None,
+            pnl: PositionPnl::new(),
+            order_id: None,
+            to_check: false,
+        }
+    }
+
+    // our own trades are left out of the window, they would feed the signal they come from. The fills and the trades
+    // come on different streams, either can be first
+    fn on_trade(&mut self, trade_id: TradeId, side: Side, qty: Quantity, window_trades: usize) {
+        self.last_trade_id = Some(trade_id);
+
+        if self.own_trade_ids.remove(&trade_id) {
+            return;
+        }
+
+        self.window.push_back((trade_id, side, qty));
+        self.window_qty[side_to_index(side)] += qty as u64;
+
+        while self.window.len() > window_trades {
+            let (_, side, qty) = self.window.pop_front().unwrap();
+            self.window_qty[side_to_index(side)] -= qty as u64;
+        }
+
+        self.to_check = true;
+    }
+
+    fn on_own_fill(&mut self, trade_id: TradeId) {
+        if self.last_trade_id.is_none_or(|last_trade_id| trade_id > last_trade_id) {
+            self.own_trade_ids.insert(trade_id);
+            return;
+        }
+
+        // its trade was seen first, taken back out of the window unless it has already left it
+        if let Some(idx) = self.window.iter().position(|(window_trade_id, _, _)| *window_trade_id == trade_id) {
+            let (_, side, qty) = self.window.remove(idx).unwrap();
+            self.window_qty[side_to_index(side)] -= qty as u64;
+        }
+    }
+
+    fn imbalance(&self) -> (u64, f64) {
+        let (buy_qty, sell_qty) = (self.window_qty[side_to_index(Side::Buy)], self.window_qty[side_to_index(Side::Sell)]);
+        let total_qty = buy_qty + sell_qty;
+
+        if total_qty == 0 {
+            return (0, 0.0);
+        }
+
+        (total_qty, (buy_qty as f64 - sell_qty as f64) / total_qty as f64)
+    }
+
+    fn reset_window(&mut self) {
+        self.window.clear();
+        self.window_qty = [0; 2];
+    }
+
+    fn mark_price(&self) -> Option<Price> {
+        self.book.mid_price().or(self.book.last_trade_price())
+    }
+}
+
+// sends market orders sized by the top of book when the trade flow signal fires
+pub struct LiquidityTaker<G: OrderGateway> {
+    config: LiquidityTakerConfig,
+    orders: OrderManager<G>,
+    symbols: Vec<Option<TradedSymbol>>, //indexed by symbol id, None for the symbols it doesn't trade
+    logger: Logger,
+}
+
+impl<G: OrderGateway> LiquidityTaker<G> {
+    pub fn new(config: LiquidityTakerConfig, gateway: G, logger: Logger) -> Self {
+        let symbol_count = config.symbols.iter().max().map_or(0, |symbol_id| *symbol_id as usize + 1);
+        let mut symbols: Vec<Option<TradedSymbol>> = (0..symbol_count).map(|_| None).collect();
+        config.symbols.iter().for_each(|symbol_id| symbols[*symbol_id as usize] = Some(TradedSymbol::new()));
+
+        Self {
+            orders: OrderManager::new(gateway, config.participant, config.order_ids),
+            symbols,
+            config,
+            logger,
+        }
+    }
+
+    pub fn orders(&self) -> &OrderManager<G> {
+        &self.orders
+    }
+
+    pub fn pnl(&self, symbol_id: SymbolId) -> Option<&PositionPnl> {
+        Some(&self.symbols.get(symbol_id as usize)?.as_ref()?.pnl)
+    }
+
+    // window volume and imbalance of a symbol's trade flow
+    pub fn imbalance(&self, symbol_id: SymbolId) -> Option<(u64, f64)> {
+        Some(self.symbols.get(symbol_id as usize)?.as_ref()?.imbalance())
+    }
+
+    fn check_signal(&mut self, symbol_id: SymbolId) -> Result<(), ClientError> {
+        let config = &self.config;
+        let symbol = self.symbols[symbol_id as usize].as_mut().unwrap();
+        symbol.to_check = false;
+
+        if symbol.order_id.is_some() || !symbol.book.is_continuous() {
+            return Ok(());
+        }
+
+        let (window_qty, imbalance) = symbol.imbalance();
+
+        if window_qty < config.min_window_qty || imbalance.abs() < config.imbalance_threshold {
+            return Ok(());
+        }
+
+        let side = match (imbalance > 0.0, config.direction) {
+            (true, SignalDirection::Momentum) | (false, SignalDirection::Contrarian) => Side::Buy,
+            _ => Side::Sell,
+        };
+
+        let opposite_side = if side == Side::Buy { Side::Sell } else { Side::Buy };
+        let position = symbol.pnl.position;
+        let room = match side {
+            Side::Buy => config.max_position - position,
+            _ => config.max_position + position,
+        };
+
+        let top_qty = symbol.book.best(opposite_side).map_or(0, |(_, qty)| qty);
+        let qty = cmp::min(cmp::min(config.order_qty, top_qty) as i64, cmp::max(room, 0)) as Quantity;
+
+        if qty == 0 {
+            return Ok(());
+        }
+
+        symbol.reset_window();
+        symbol.order_id = Some(self.orders.send_market(symbol_id, side, qty)?);
+
+        Ok(())
+    }
+}
+
+impl<G: OrderGateway> Strategy for LiquidityTaker<G> {
+    fn on_response(&mut self, response: &ParticipantResponse) {
+        let order = match self.orders.on_response(response) {
+            Ok(Some(order)) => order,
+            Ok(None) => return,
+            Err(_) => {
+                self.logger.warn("liquidity taker: unexpected response", LogPayload::ParticipantResponse(response.clone()));
+                return;
+            },
+        };
+
+        let is_done = order.state.is_terminal();
+        let symbol = match self.symbols.get_mut(response.symbol_id as usize) {
+            Some(Some(symbol)) => symbol,
+            _ => return,
+        };
+
+        if is_done && symbol.order_id == Some(response.participant_order_id) {
+            symbol.order_id = None;
+            symbol.to_check = true;
+        }
+
+        if response.response_type != ParticipantResponseType::Filled {
+            return;
+        }
+
+        symbol.on_own_fill(response.trade_id);
+        symbol.pnl.on_fill(response.side, response.price, response.exec_qty);
+
+        if !self.logger.is_enabled(LogLevel::Info) {
+            return;
+        }
+
+        let pnl = &symbol.pnl;
+        self.logger.info("liquidity taker fill", LogPayload::OwnFill(OwnFill {
+            participant_id: response.participant_id,
+            symbol_id: response.symbol_id,
+            side: response.side,
+            price: response.price,
+            qty: response.exec_qty,
+            position: pnl.position,
+            open_price: pnl.open_price(),
+            realized: pnl.realized,
+            // marked at the mid or else the last trade
+            unrealized: pnl.unrealized(symbol.mark_price().unwrap_or(response.price)),
+        }));
+    }
+
+    fn on_market_update(&mut self, update: &MarketUpdate) {
+        let symbol = match self.symbols.get_mut(update.symbol_id as usize) {
+            Some(Some(symbol)) => symbol,
+            _ => return,
+        };
+
+        symbol.book.on_update(update);
+
+        match update.update_type {
+            MarketUpdateType::Trade => symbol.on_trade(update.trade_id, update.side, update.qty, self.config.window_trades),
+            MarketUpdateType::Resume => symbol.to_check = true,
+            _ => {},
+        }
+    }
+
+    fn act(&mut self) -> Result<(), ClientError> {
+        for symbol_id in 0..self.symbols.len() {
+            if !self.symbols[symbol_id].as_ref().is_some_and(|symbol| symbol.to_check) {
+                continue;
+            }
+
+            match self.check_signal(symbol_id as SymbolId) {
+                Err(ClientError::Disconnected) => return Err(ClientError::Disconnected),
+                Err(_) => self.logger.warn("liquidity taker: cannot send order", LogPayload::None),
+                Ok(()) => {},
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,215 @@
+use std::cmp;
+
+use serde::Deserialize;
+
+use crate::common::{side_to_index, OrderId, ParticipantId, Price, Quantity, Side, SymbolId, INVALID_PARTICIPANT_ID};
+use crate::logging::logger::{LogPayload, Logger};
+use crate::market_data::{market_book::MarketBook, market_update::MarketUpdate};
+use crate::order_server::participants_response::{ParticipantResponse, ParticipantResponseType};
+
+use super::order_manager::{ClientError, OrderGateway, OrderManager, OrderState};
+use super::strategy::Strategy;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RequoteMode {
+    Modify,
+    CancelNew,
+}
+
+// quotes are centred on the mid, or the last trade or reference_price while a side is empty,
+// shifted by inventory_skew ticks per unit of position and moved once they are requote_threshold ticks off
+// order_ids is how many client order ids are cycled through, it must not exceed limits.max_order_ids
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MarketMakerConfig {
+    pub participant: ParticipantId,
+    pub core: isize,
+    pub symbols: Vec<SymbolId>,
+    pub reference_price: Price,
+    pub half_spread: Price,
+    pub quote_qty: Quantity,
+    pub max_position: i64,
+    pub inventory_skew: f64,
+    pub requote_threshold: Price,
+    pub requote: RequoteMode,
+    pub order_ids: usize,
+}
+
+impl Default for MarketMakerConfig {
+    fn default() -> Self {
+        Self {
+            participant: INVALID_PARTICIPANT_ID,
+            core: -1,
+            symbols: Vec::new(),
+            reference_price: 10_000,
+            half_spread: 2,
+            quote_qty: 100,
+            max_position: 1000,
+            inventory_skew: 0.01,
+            requote_threshold: 1,
+            requote: RequoteMode::Modify,
+            order_ids: 1024,
+        }
+    }
+}
+
+// one bid and one ask per symbol
+pub struct MarketMaker<G: OrderGateway> {
+    config: MarketMakerConfig,
+    orders: OrderManager<G>,
+    books: Vec<MarketBook>, //indexed by symbol id
+    quotes: Vec<[Option<OrderId>; 2]>, //indexed by symbol id then side
+    is_quoted: Vec<bool>, //indexed by symbol id
+    to_requote: Vec<bool>, //indexed by symbol id
+    logger: Logger,
+}
+
+impl<G: OrderGateway> MarketMaker<G> {
+    pub fn new(config: MarketMakerConfig, gateway: G, logger: Logger) -> Self {
+        let symbol_count = config.symbols.iter().max().map_or(0, |symbol_id| *symbol_id as usize + 1);
+        let mut is_quoted = vec![false; symbol_count];
+        config.symbols.iter().for_each(|symbol_id| is_quoted[*symbol_id as usize] = true);
+
+        Self {
+            orders: OrderManager::new(gateway, config.participant, config.order_ids),
+            books: (0..symbol_count).map(|_| MarketBook::new()).collect(),
+            quotes: vec![[None; 2]; symbol_count],
+            to_requote: is_quoted.clone(),
+            is_quoted,
+            config,
+            logger,
+        }
+    }
+
+    pub fn orders(&self) -> &OrderManager<G> {
+        &self.orders
+    }
+
+    pub fn book(&self, symbol_id: SymbolId) -> Option<&MarketBook> {
+        self.books.get(symbol_id as usize)
+    }
+
+    pub fn fair_value(&self, symbol_id: SymbolId) -> Price {
+        match (self.best_other_price(symbol_id, Side::Buy), self.best_other_price(symbol_id, Side::Sell)) {
+            (Some(bid), Some(ask)) => (bid + ask) / 2,
+            _ => self.books[symbol_id as usize].last_trade_price().unwrap_or(self.config.reference_price),
+        }
+    }
+
+    // our own quotes are left out, quoting around a mid we set ourselves would drift with the skew. A side holds at most
+    // our quote and the one replacing it, the best level of others is within the first three
+    fn best_other_price(&self, symbol_id: SymbolId, side: Side) -> Option<Price> {
+        self.books[symbol_id as usize].levels(side, 3).into_iter().find(|(price, qty)| {
+            let own_qty: Quantity = self.orders.open_orders_for(symbol_id)
+                .filter(|order| order.request.side == side && order.request.price == *price)
+                .map(|order| order.leaves_qty)
+                .sum();
+            *qty > own_qty
+        }).map(|(price, _)| price)
+    }
+
+    // price and quantity of a side's quote, a long position lowers both prices and shrinks the bid
+    fn target_quote(&self, symbol_id: SymbolId, side: Side) -> (Price, Quantity) {
+        let position = self.orders.position(symbol_id);
+        let skew = (position as f64 * self.config.inventory_skew).round() as i64;
+        let fair_value = self.fair_value(symbol_id) as i64 - skew;
+
+        let (price, room) = match side {
+            Side::Buy => (fair_value - self.config.half_spread as i64, self.config.max_position - position),
+            _ => (fair_value + self.config.half_spread as i64, self.config.max_position + position),
+        };
+
+        let qty = cmp::min(self.config.quote_qty as i64, cmp::max(room, 0)) as Quantity;
+
+        (cmp::max(price, 1) as Price, qty)
+    }
+
+    fn requote_side(&mut self, symbol_id: SymbolId, side: Side) -> Result<(), ClientError> {
+        let (price, qty) = self.target_quote(symbol_id, side);
+        let quote = &mut self.quotes[symbol_id as usize][side_to_index(side)];
+
+        let current = quote.and_then(|order_id| self.orders.order(order_id)).filter(|order| !order.state.is_terminal());
+
+        let order = match current {
+            None if qty == 0 => {
+                *quote = None;
+                return Ok(());
+            },
+            None => {
+                *quote = Some(self.orders.send_limit(symbol_id, side, price, qty)?);
+                return Ok(());
+            },
+            Some(order) => order,
+        };
+
+        // wait for the exchange to answer before touching the quote again
+        if matches!(order.state, OrderState::PendingNew | OrderState::PendingCancel) {
+            return Ok(());
+        }
+
+        let order_id = order.order_id();
+
+        if qty == 0 {
+            return self.orders.send_cancel(order_id);
+        }
+
+        if order.request.price.abs_diff(price) < self.config.requote_threshold && order.leaves_qty == qty {
+            return Ok(());
+        }
+
+        *quote = Some(match self.config.requote {
+            RequoteMode::Modify => self.orders.send_modify(order_id, price, qty)?,
+            RequoteMode::CancelNew => {
+                self.orders.send_cancel(order_id)?;
+                self.orders.send_limit(symbol_id, side, price, qty)?
+            },
+        });
+
+        Ok(())
+    }
+}
+
+impl<G: OrderGateway> Strategy for MarketMaker<G> {
+    fn on_market_update(&mut self, update: &MarketUpdate) {
+        if let Some(book) = self.books.get_mut(update.symbol_id as usize) {
+            book.on_update(update);
+            self.to_requote[update.symbol_id as usize] = self.is_quoted[update.symbol_id as usize];
+        }
+    }
+
+    // requotes after fills and whatever else moves the position, never after rejects which would only be rejected again
+    fn on_response(&mut self, response: &ParticipantResponse) {
+        if self.orders.on_response(response).is_err() {
+            self.logger.warn("market maker: unexpected response", LogPayload::ParticipantResponse(response.clone()));
+            return;
+        }
+
+        if response.participant_id == self.config.participant && matches!(response.response_type, ParticipantResponseType::Filled | ParticipantResponseType::TradeBusted | ParticipantResponseType::TradeCorrected) {
+            if let Some(to_requote) = self.to_requote.get_mut(response.symbol_id as usize) {
+                *to_requote = self.is_quoted[response.symbol_id as usize];
+            }
+        }
+    }
+
+    // requotes every symbol whose book or position changed since the last call
+    fn act(&mut self) -> Result<(), ClientError> {
+        for symbol_id in 0..self.to_requote.len() {
+            if !self.to_requote[symbol_id] || !self.books[symbol_id].is_continuous() {
+                continue;
+            }
+
+            self.to_requote[symbol_id] = false;
+
+            for side in [Side::Buy, Side::Sell] {
+                match self.requote_side(symbol_id as SymbolId, side) {
+                    Err(ClientError::Disconnected) => return Err(ClientError::Disconnected),
+                    Err(_) => self.logger.warn("market maker: cannot requote", LogPayload::None),
+                    Ok(()) => {},
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,6 @@
+pub mod liquidity_taker;
+pub mod market_maker;
+pub mod order_flow;
+pub mod order_manager;
+pub mod strategy;
+pub mod tcp_gateway;
@@ -0,0 +1,258 @@
+use std::{collections::VecDeque, fmt, hint, thread, time::{Duration, Instant}};
+
+use serde::Deserialize;
+
+use crate::common::{OrderId, OrderType, ParticipantId, Price, Quantity, Rng, Side, SymbolId};
+use crate::order_server::participants_request::{ParticipantRequest, ParticipantRequestType};
+
+use super::order_manager::{ClientError, OrderGateway};
+
+// sleeping is only worth it for waits longer than this, shorter ones spin
+const MIN_SLEEP: Duration = Duration::from_micros(200);
+
+// the flow is drawn from `seed` alone, the same config always produces the same requests
+// prices are within price_range ticks of a per symbol mid which moves one tick with mid_step_probability per message,
+// marketable_pct of the new orders are priced through the mid. Messages go out in bursts of burst_size,
+// paced to `rate` messages per second on average, 0 sends as fast as the gateway takes them
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OrderFlowConfig {
+    pub in_process: bool,
+    pub core: isize,
+    pub seed: u64,
+    pub participants: Vec<ParticipantId>,
+    pub symbols: Vec<SymbolId>,
+    pub initial_mid: Price,
+    pub mid_step_probability: f64,
+    pub price_range: Price,
+    pub marketable_pct: u64,
+    pub max_qty: Quantity,
+    pub new_weight: u64,
+    pub cancel_weight: u64,
+    pub modify_weight: u64,
+    pub rate: u64,
+    pub burst_size: u64,
+    pub messages: u64,
+    pub order_ids: usize,
+}
+
+impl Default for OrderFlowConfig {
+    fn default() -> Self {
+        Self {
+            in_process: false,
+            core: -1,
+            seed: 1,
+            participants: Vec::new(),
+            symbols: Vec::new(),
+            initial_mid: 10_000,
+            mid_step_probability: 0.01,
+            price_range: 20,
+            marketable_pct: 10,
+            max_qty: 100,
+            new_weight: 60,
+            cancel_weight: 30,
+            modify_weight: 10,
+            rate: 0,
+            burst_size: 1000,
+            messages: 0,
+            order_ids: 65_536,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct FlowStats {
+    pub messages: u64,
+    pub new_orders: u64,
+    pub cancels: u64,
+    pub modifies: u64,
+    pub elapsed: Duration,
+}
+
+impl fmt::Display for FlowStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "FlowStats [messages: {}, new: {}, cancel: {}, modify: {}, elapsed: {}ms, rate: {:.0} msg/s]",
+        self.messages, self.new_orders, self.cancels, self.modifies, self.elapsed.as_millis(), self.messages as f64 / self.elapsed.as_secs_f64().max(f64::EPSILON))
+    }
+}
+
+struct LiveOrder {
+    order_id: OrderId,
+    symbol_idx: usize,
+    side: Side,
+}
+
+// the generator never sees responses, filled orders stay live here until a cancel for them is rejected
+struct FlowParticipant {
+    participant_id: ParticipantId,
+    free_order_ids: Vec<OrderId>,
+    live_orders: Vec<LiveOrder>,
+}
+
+pub struct OrderFlow {
+    config: OrderFlowConfig,
+    rng: Rng,
+    mids: Vec<Price>, //indexed by position in config.symbols
+    participants: Vec<FlowParticipant>,
+    pending: VecDeque<ParticipantRequest>,
+    stats: FlowStats,
+}
+
+impl OrderFlow {
+    pub fn new(config: OrderFlowConfig) -> Self {
+        let participants = config.participants.iter().map(|participant_id| FlowParticipant {
+            participant_id: *participant_id,
+            free_order_ids: (0..config.order_ids as OrderId).rev().collect(),
+            live_orders: Vec::new(),
+        }).collect();
+
+        Self {
+            rng: Rng::new(config.seed),
+            mids: vec![config.initial_mid; config.symbols.len()],
+            participants,
+            pending: VecDeque::new(),
+            stats: FlowStats::default(),
+            config,
+        }
+    }
+
+    pub fn stats(&self) -> FlowStats {
+        self.stats
+    }
+
+    pub fn next_request(&mut self) -> ParticipantRequest {
+        if let Some(request) = self.pending.pop_front() {
+            return request;
+        }
+
+        let symbol_idx = self.rng.below(self.mids.len() as u64) as usize;
+        if self.rng.chance(self.config.mid_step_probability) {
+            let mid = &mut self.mids[symbol_idx];
+            *mid = if self.rng.below(2) == 0 { mid.saturating_sub(1).max(self.config.price_range + 1) } else { *mid + 1 };
+        }
+
+        let participant_idx = self.rng.below(self.participants.len() as u64) as usize;
+        let total_weight = self.config.new_weight + self.config.cancel_weight + self.config.modify_weight;
+        let action = self.rng.below(total_weight);
+        let participant = &self.participants[participant_idx];
+
+        // out of ids the participant cancels, with nothing live it sends a new order
+        if participant.live_orders.is_empty() || (action < self.config.new_weight && !participant.free_order_ids.is_empty()) {
+            self.stats.new_orders += 1;
+            let side = if self.rng.below(2) == 0 { Side::Buy } else { Side::Sell };
+            return self.new_order(participant_idx, symbol_idx, side);
+        }
+
+        let live_idx = self.rng.below(participant.live_orders.len() as u64) as usize;
+        let (cancel, symbol_idx, side) = self.cancel(participant_idx, live_idx);
+
+        // a modify is a cancel followed by a new order on the same symbol and side, at a new price and quantity
+        if action >= self.config.new_weight + self.config.cancel_weight {
+            self.stats.modifies += 1;
+            let replacement = self.new_order(participant_idx, symbol_idx, side);
+            self.pending.push_back(replacement);
+        } else {
+            self.stats.cancels += 1;
+        }
+
+        cancel
+    }
+
+    // sends `messages` requests, or until the gateway goes away when that is 0
+    // the stats cover what was sent either way
+    pub fn run(&mut self, gateway: &mut impl OrderGateway) -> Result<FlowStats, ClientError> {
+        let start = Instant::now();
+        let result = self.send_bursts(gateway, start);
+        self.stats.elapsed = start.elapsed();
+        result.map(|_| self.stats)
+    }
+
+    fn send_bursts(&mut self, gateway: &mut impl OrderGateway, start: Instant) -> Result<(), ClientError> {
+        let burst_period = match self.config.rate {
+            0 => Duration::ZERO,
+            rate => Duration::from_secs_f64(self.config.burst_size as f64 / rate as f64),
+        };
+        let mut next_burst = start;
+
+        while self.config.messages == 0 || self.stats.messages < self.config.messages {
+            for _ in 0..self.config.burst_size {
+                if self.config.messages != 0 && self.stats.messages == self.config.messages {
+                    break;
+                }
+
+                let request = self.next_request();
+                gateway.send(request)?;
+                self.stats.messages += 1;
+            }
+
+            gateway.flush()?;
+
+            next_burst += burst_period;
+            wait_until(next_burst);
+        }
+
+        Ok(())
+    }
+
+    fn new_order(&mut self, participant_idx: usize, symbol_idx: usize, side: Side) -> ParticipantRequest {
+        let offset = 1 + self.rng.below(self.config.price_range);
+        let is_marketable = self.rng.below(100) < self.config.marketable_pct;
+        let mid = self.mids[symbol_idx];
+
+        let price = match (side, is_marketable) {
+            (Side::Buy, false) | (Side::Sell, true) => mid - offset,
+            _ => mid + offset,
+        };
+
+        let qty = 1 + self.rng.below(self.config.max_qty as u64) as Quantity;
+        let participant = &mut self.participants[participant_idx];
+        let order_id = participant.free_order_ids.pop().unwrap();
+
+        participant.live_orders.push(LiveOrder { order_id, symbol_idx, side });
+
+        ParticipantRequest {
+            request_type: ParticipantRequestType::New,
+            participant_id: participant.participant_id,
+            symbol_id: self.config.symbols[symbol_idx],
+            order_id,
+            order_type: OrderType::Limit,
+            side,
+            price,
+            qty,
+            ..ParticipantRequest::default()
+        }
+    }
+
+    // also returns the symbol and side of the cancelled order
+    fn cancel(&mut self, participant_idx: usize, live_idx: usize) -> (ParticipantRequest, usize, Side) {
+        let participant = &mut self.participants[participant_idx];
+        let order = participant.live_orders.swap_remove(live_idx);
+        participant.free_order_ids.push(order.order_id);
+
+        let request = ParticipantRequest {
+            request_type: ParticipantRequestType::Cancel,
+            participant_id: participant.participant_id,
+            symbol_id: self.config.symbols[order.symbol_idx],
+            order_id: order.order_id,
+            ..ParticipantRequest::default()
+        };
+
+        (request, order.symbol_idx, order.side)
+    }
+}
+
+fn wait_until(deadline: Instant) {
+    loop {
+        let now = Instant::now();
+
+        if now >= deadline {
+            return;
+        }
+
+        if deadline - now > MIN_SLEEP {
+            thread::sleep(deadline - now - MIN_SLEEP);
+        } else {
+            hint::spin_loop();
+        }
+    }
+}
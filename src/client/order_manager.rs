@@ -0,0 +1,394 @@
+use std::{fmt, sync::mpsc::{Sender, SyncSender}};
+
+use crate::common::{OrderId, OrderType, ParticipantId, Price, Quantity, Side, SymbolId, INVALID_ORDER_ID};
+use crate::order_server::{participants_request::{ParticipantRequest, ParticipantRequestType}, participants_response::{ParticipantResponse, ParticipantResponseType}, shard_router::ShardRouter};
+
+// where the order manager sends its requests, the exchange answers through the participant's response stream
+pub trait OrderGateway {
+    fn send(&mut self, request: ParticipantRequest) -> Result<(), ClientError>;
+
+    // pushes out requests a buffering gateway still holds
+    fn flush(&mut self) -> Result<(), ClientError> {
+        Ok(())
+    }
+}
+
+impl OrderGateway for SyncSender<ParticipantRequest> {
+    fn send(&mut self, request: ParticipantRequest) -> Result<(), ClientError> {
+        SyncSender::send(self, request).map_err(|_| ClientError::Disconnected)
+    }
+}
+
+// unbounded, for a strategy running on the same thread as what drains it
+impl OrderGateway for Sender<ParticipantRequest> {
+    fn send(&mut self, request: ParticipantRequest) -> Result<(), ClientError> {
+        Sender::send(self, request).map_err(|_| ClientError::Disconnected)
+    }
+}
+
+impl OrderGateway for ShardRouter {
+    fn send(&mut self, request: ParticipantRequest) -> Result<(), ClientError> {
+        self.route(request).map_err(|_| ClientError::Disconnected)
+    }
+}
+
+#[derive(Debug)]
+pub enum ClientError {
+    Disconnected,
+    NotLoggedIn(ParticipantId),
+    NoFreeOrderId,
+    UnknownOrder(OrderId),
+    NotCancelable(OrderId, OrderState),
+    UnexpectedTransition(OrderId, OrderState, ParticipantResponseType),
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ClientError::Disconnected => write!(f, "order gateway disconnected"),
+            ClientError::NotLoggedIn(participant_id) => write!(f, "participant {} is not logged in on the order gateway", participant_id),
+            ClientError::NoFreeOrderId => write!(f, "every client order id is in use"),
+            ClientError::UnknownOrder(order_id) => write!(f, "unknown order {}", order_id),
+            ClientError::NotCancelable(order_id, state) => write!(f, "order {} cannot be cancelled in state {}", order_id, state),
+            ClientError::UnexpectedTransition(order_id, state, response_type) => write!(f, "unexpected {} response for order {} in state {}", response_type, order_id, state),
+        }
+    }
+}
+
+impl std::error::Error for ClientError {}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum OrderState {
+    PendingNew,
+    Live,
+    PartiallyFilled,
+    PendingCancel,
+    Cancelled,
+    Filled,
+    Rejected,
+}
+
+impl OrderState {
+    pub fn is_terminal(self) -> bool {
+        matches!(self, OrderState::Cancelled | OrderState::Filled | OrderState::Rejected)
+    }
+}
+
+impl fmt::Display for OrderState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OrderState::PendingNew => write!(f, "PENDING-NEW"),
+            OrderState::Live => write!(f, "LIVE"),
+            OrderState::PartiallyFilled => write!(f, "PARTIALLY-FILLED"),
+            OrderState::PendingCancel => write!(f, "PENDING-CANCEL"),
+            OrderState::Cancelled => write!(f, "CANCELLED"),
+            OrderState::Filled => write!(f, "FILLED"),
+            OrderState::Rejected => write!(f, "REJECTED"),
+        }
+    }
+}
+
+// an order as the client sees it, `request` is the last new order request sent for it
+#[derive(Clone)]
+pub struct ClientOrder {
+    pub request: ParticipantRequest,
+    pub state: OrderState,
+    pub internal_order_id: OrderId,
+    pub filled_qty: Quantity,
+    pub leaves_qty: Quantity,
+    pub replaced_by: Option<OrderId>, // the replacement sent once this order's cancel is confirmed
+    pub replaces: Option<OrderId>, // set while this replacement is held back
+}
+
+impl ClientOrder {
+    pub fn order_id(&self) -> OrderId {
+        self.request.order_id
+    }
+
+    fn working_state(&self) -> OrderState {
+        if self.filled_qty > 0 { OrderState::PartiallyFilled } else { OrderState::Live }
+    }
+}
+
+impl fmt::Display for ClientOrder {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ClientOrder [order: {}, state: {}, symb: {}, side: {}, price: {}, filled_qty: {}, leaves_qty: {}]",
+        self.request.order_id, self.state, self.request.symbol_id, self.request.side, self.request.price, self.filled_qty, self.leaves_qty)
+    }
+}
+
+// client order ids are assigned here and recycled once their order is done, the exchange only accepts ids below its max_order_ids
+pub struct OrderManager<G: OrderGateway> {
+    gateway: G,
+    participant_id: ParticipantId,
+    max_order_ids: usize,
+    next_order_id: OrderId,
+    orders: Vec<Option<ClientOrder>>, //indexed by client order id
+    positions: Vec<i64>, //indexed by symbol id
+}
+
+impl<G: OrderGateway> OrderManager<G> {
+    pub fn new(gateway: G, participant_id: ParticipantId, max_order_ids: usize) -> Self {
+        Self {
+            gateway,
+            participant_id,
+            max_order_ids,
+            next_order_id: 0,
+            orders: Vec::new(),
+            positions: Vec::new(),
+        }
+    }
+
+    pub fn participant_id(&self) -> ParticipantId {
+        self.participant_id
+    }
+
+    pub fn order(&self, order_id: OrderId) -> Option<&ClientOrder> {
+        self.orders.get(order_id as usize)?.as_ref()
+    }
+
+    pub fn open_orders(&self) -> impl Iterator<Item = &ClientOrder> {
+        self.orders.iter().flatten().filter(|order| !order.state.is_terminal())
+    }
+
+    pub fn open_orders_for(&self, symbol_id: SymbolId) -> impl Iterator<Item = &ClientOrder> {
+        self.open_orders().filter(move |order| order.request.symbol_id == symbol_id)
+    }
+
+    // net filled quantity, positive when long
+    pub fn position(&self, symbol_id: SymbolId) -> i64 {
+        self.positions.get(symbol_id as usize).copied().unwrap_or(0)
+    }
+
+    pub fn send_limit(&mut self, symbol_id: SymbolId, side: Side, price: Price, qty: Quantity) -> Result<OrderId, ClientError> {
+        self.send_new(ParticipantRequest {
+            symbol_id,
+            order_type: OrderType::Limit,
+            side,
+            price,
+            qty,
+            ..ParticipantRequest::default()
+        })
+    }
+
+    pub fn send_market(&mut self, symbol_id: SymbolId, side: Side, qty: Quantity) -> Result<OrderId, ClientError> {
+        self.send_new(ParticipantRequest {
+            symbol_id,
+            order_type: OrderType::Market,
+            side,
+            qty,
+            ..ParticipantRequest::default()
+        })
+    }
+
+    // `request` describes the order, its type, participant and order id are filled in here
+    pub fn send_new(&mut self, mut request: ParticipantRequest) -> Result<OrderId, ClientError> {
+        request.request_type = ParticipantRequestType::New;
+        request.participant_id = self.participant_id;
+        request.order_id = self.allocate_order_id()?;
+
+        self.gateway.send(request.clone())?;
+        Ok(self.insert(request))
+    }
+
+    pub fn send_cancel(&mut self, order_id: OrderId) -> Result<(), ClientError> {
+        let order = self.open_order_mut(order_id)?;
+
+        // never sent, there is nothing to cancel on the exchange
+        if let Some(original_id) = order.replaces.take() {
+            order.state = OrderState::Cancelled;
+            self.orders[original_id as usize].as_mut().unwrap().replaced_by = None;
+            return Ok(());
+        }
+
+        if order.state == OrderState::PendingCancel {
+            return Err(ClientError::NotCancelable(order_id, order.state));
+        }
+
+        let request = ParticipantRequest {
+            request_type: ParticipantRequestType::Cancel,
+            participant_id: order.request.participant_id,
+            symbol_id: order.request.symbol_id,
+            order_id,
+            ..ParticipantRequest::default()
+        };
+
+        self.gateway.send(request)?;
+        self.open_order_mut(order_id)?.state = OrderState::PendingCancel;
+        Ok(())
+    }
+
+    // cancel/replace, the replacement is held back until the cancel is confirmed and dropped if the order fills first
+    pub fn send_modify(&mut self, order_id: OrderId, price: Price, qty: Quantity) -> Result<OrderId, ClientError> {
+        let order = self.open_order_mut(order_id)?;
+
+        // a replacement still held back is simply amended
+        if order.replaces.is_some() {
+            order.request.price = price;
+            order.request.qty = qty;
+            order.leaves_qty = qty;
+            return Ok(order_id);
+        }
+
+        let mut replacement = order.request.clone();
+        replacement.price = price;
+        replacement.qty = qty;
+        replacement.order_id = self.allocate_order_id()?;
+
+        self.send_cancel(order_id)?;
+        self.open_order_mut(order_id)?.replaced_by = Some(replacement.order_id);
+
+        let replacement_id = self.insert(replacement);
+        self.orders[replacement_id as usize].as_mut().unwrap().replaces = Some(order_id);
+        Ok(replacement_id)
+    }
+
+    // applies one response of the exchange, responses for other participants are ignored
+    pub fn on_response(&mut self, response: &ParticipantResponse) -> Result<Option<&ClientOrder>, ClientError> {
+        if response.participant_id != self.participant_id {
+            return Ok(None);
+        }
+
+        // the order's id may have been reused since, only the position changes
+        if matches!(response.response_type, ParticipantResponseType::TradeBusted | ParticipantResponseType::TradeCorrected) {
+            let qty = response.exec_qty as i64 - response.leaves_qty as i64;
+            self.add_to_position(response.symbol_id, response.side, qty);
+            return Ok(None);
+        }
+
+        let order_id = response.participant_order_id;
+        let order = self.orders.get_mut(order_id as usize).and_then(Option::as_mut).ok_or(ClientError::UnknownOrder(order_id))?;
+        let state = order.state;
+        let unexpected = || ClientError::UnexpectedTransition(order_id, state, response.response_type);
+
+        match (response.response_type, state) {
+            (ParticipantResponseType::Accepted, OrderState::PendingNew | OrderState::PendingCancel) => {
+                order.internal_order_id = response.internal_order_id;
+                order.leaves_qty = response.leaves_qty;
+                if state == OrderState::PendingNew {
+                    order.state = OrderState::Live;
+                }
+            },
+            (ParticipantResponseType::Filled, OrderState::Live | OrderState::PartiallyFilled | OrderState::PendingCancel) => {
+                if response.exec_qty > order.leaves_qty || response.leaves_qty != order.leaves_qty - response.exec_qty {
+                    return Err(unexpected());
+                }
+
+                order.filled_qty += response.exec_qty;
+                order.leaves_qty = response.leaves_qty;
+                if order.leaves_qty == 0 {
+                    order.state = OrderState::Filled;
+                } else if state != OrderState::PendingCancel {
+                    order.state = OrderState::PartiallyFilled;
+                }
+
+                let (symbol_id, side) = (order.request.symbol_id, order.request.side);
+                self.add_to_position(symbol_id, side, response.exec_qty as i64);
+            },
+            // unsolicited when the rest of a market order is dropped
+            (ParticipantResponseType::Cancelled, state) if !state.is_terminal() => {
+                order.leaves_qty = 0;
+                order.state = OrderState::Cancelled;
+            },
+            (ParticipantResponseType::CancelRejected, OrderState::PendingCancel) => {
+                order.state = order.working_state();
+            },
+            // the order was done before the cancel reached the exchange
+            (ParticipantResponseType::CancelRejected, state) if state.is_terminal() => {},
+            (ParticipantResponseType::Rejected, OrderState::PendingNew | OrderState::PendingCancel) => {
+                order.leaves_qty = 0;
+                order.state = OrderState::Rejected;
+            },
+            _ => return Err(unexpected()),
+        }
+
+        if self.orders[order_id as usize].as_ref().unwrap().state != state {
+            self.on_state_changed(order_id)?;
+        }
+
+        Ok(self.order(order_id))
+    }
+
+    fn on_state_changed(&mut self, order_id: OrderId) -> Result<(), ClientError> {
+        let order = self.orders[order_id as usize].as_mut().unwrap();
+
+        let replacement_id = match (order.state, order.replaced_by) {
+            (OrderState::Cancelled, Some(replacement_id)) => replacement_id,
+            (OrderState::PendingCancel, _) | (_, None) => return Ok(()),
+            (_, Some(replacement_id)) => {
+                order.replaced_by = None;
+                let replacement = self.orders[replacement_id as usize].as_mut().unwrap();
+                replacement.replaces = None;
+                replacement.state = OrderState::Cancelled;
+                return Ok(());
+            },
+        };
+
+        let replacement = self.orders[replacement_id as usize].as_mut().unwrap();
+        replacement.replaces = None;
+
+        if let Err(error) = self.gateway.send(replacement.request.clone()) {
+            replacement.state = OrderState::Cancelled;
+            return Err(error);
+        }
+
+        Ok(())
+    }
+
+    // a negative quantity undoes fills
+    fn add_to_position(&mut self, symbol_id: SymbolId, side: Side, qty: i64) {
+        let idx = symbol_id as usize;
+        if self.positions.len() <= idx {
+            self.positions.resize(idx + 1, 0);
+        }
+
+        self.positions[idx] += match side {
+            Side::Buy => qty,
+            _ => -qty,
+        };
+    }
+
+    fn open_order_mut(&mut self, order_id: OrderId) -> Result<&mut ClientOrder, ClientError> {
+        match self.orders.get_mut(order_id as usize).and_then(Option::as_mut) {
+            Some(order) if !order.state.is_terminal() => Ok(order),
+            Some(order) => Err(ClientError::NotCancelable(order_id, order.state)),
+            None => Err(ClientError::UnknownOrder(order_id)),
+        }
+    }
+
+    // the next id whose slot is free or holds a finished order
+    fn allocate_order_id(&mut self) -> Result<OrderId, ClientError> {
+        for _ in 0..self.max_order_ids {
+            let order_id = self.next_order_id;
+            self.next_order_id = (self.next_order_id + 1) % self.max_order_ids as OrderId;
+
+            match self.orders.get(order_id as usize) {
+                Some(Some(order)) if !order.state.is_terminal() => continue,
+                _ => return Ok(order_id),
+            }
+        }
+
+        Err(ClientError::NoFreeOrderId)
+    }
+
+    fn insert(&mut self, request: ParticipantRequest) -> OrderId {
+        let order_id = request.order_id;
+        let idx = order_id as usize;
+
+        if self.orders.len() <= idx {
+            self.orders.resize_with(idx + 1, || None);
+        }
+
+        self.orders[idx] = Some(ClientOrder {
+            leaves_qty: request.qty,
+            request,
+            state: OrderState::PendingNew,
+            internal_order_id: INVALID_ORDER_ID,
+            filled_qty: 0,
+            replaced_by: None,
+            replaces: None,
+        });
+
+        order_id
+    }
+}
@@ -0,0 +1,52 @@
+use std::{sync::mpsc::{Receiver, TryRecvError}, thread};
+
+use crate::common::Sequenced;
+use crate::logging::logger::{LogPayload, Logger};
+use crate::market_data::market_update::MarketUpdate;
+use crate::order_server::participants_response::ParticipantResponse;
+
+use super::order_manager::ClientError;
+
+// a trading bot fed by its participant's responses and the market data, `act` sends whatever the new state calls for
+pub trait Strategy {
+    fn on_response(&mut self, response: &ParticipantResponse);
+    fn on_market_update(&mut self, update: &MarketUpdate);
+    fn act(&mut self) -> Result<(), ClientError>;
+}
+
+// spins over both streams until the market data stops or the gateway goes away
+pub fn run(strategy: &mut impl Strategy, responses: Receiver<Sequenced<ParticipantResponse>>, market_updates: Receiver<Sequenced<MarketUpdate>>, logger: &Logger) {
+    logger.info("strategy started", LogPayload::None);
+
+    loop {
+        let mut is_idle = true;
+
+        while let Ok(response) = responses.try_recv() {
+            strategy.on_response(&response.payload);
+            is_idle = false;
+        }
+
+        loop {
+            match market_updates.try_recv() {
+                Ok(update) => {
+                    strategy.on_market_update(&update.payload);
+                    is_idle = false;
+                },
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    logger.info("strategy stopped", LogPayload::None);
+                    return;
+                },
+            }
+        }
+
+        if let Err(ClientError::Disconnected) = strategy.act() {
+            logger.info("strategy disconnected", LogPayload::None);
+            return;
+        }
+
+        if is_idle {
+            thread::yield_now();
+        }
+    }
+}
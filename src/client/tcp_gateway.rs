@@ -0,0 +1,76 @@
+use std::{io::{self, BufReader, BufWriter, ErrorKind, Write}, net::{TcpStream, ToSocketAddrs}, sync::mpsc::{self, Receiver, Sender}, thread};
+
+use crate::common::ParticipantId;
+use crate::order_server::{participants_request::ParticipantRequest, participants_response::ParticipantResponse, tcp_gateway::{GatewayLogin, Login}};
+use crate::wire::{self, LoginFrame, RequestFrame, ResponseFrame, WireFrame, MAX_FRAME_SIZE};
+
+use super::order_manager::{ClientError, OrderGateway};
+
+// one connection per login, a request goes out on its participant's. Requests are buffered until flushed, the
+// responses of every connection are read on threads of their own into one stream
+pub struct TcpOrderGateway {
+    connections: Vec<(ParticipantId, BufWriter<TcpStream>)>,
+}
+
+impl TcpOrderGateway {
+    pub fn connect(address: impl ToSocketAddrs, logins: &[GatewayLogin]) -> io::Result<(Self, Receiver<ParticipantResponse>)> {
+        let addresses: Vec<_> = address.to_socket_addrs()?.collect();
+        let (responses_tx, responses_rx) = mpsc::channel();
+        let mut connections = Vec::new();
+
+        for login in logins {
+            let stream = TcpStream::connect(&addresses[..])?;
+            stream.set_nodelay(true)?;
+
+            let mut reader = BufReader::new(stream.try_clone()?);
+            log_in(&stream, &mut reader, login)?;
+
+            let responses_tx = responses_tx.clone();
+            thread::spawn(move || read_responses(reader, responses_tx));
+
+            connections.push((login.participant, BufWriter::with_capacity(64 * 1024, stream)));
+        }
+
+        Ok((Self { connections }, responses_rx))
+    }
+}
+
+fn log_in(mut stream: &TcpStream, reader: &mut BufReader<TcpStream>, login: &GatewayLogin) -> io::Result<()> {
+    stream.write_all(LoginFrame::encode(&Login { participant_id: login.participant, password: login.password.clone(), accepted: false }).as_bytes())?;
+
+    let mut buf = [0; MAX_FRAME_SIZE];
+    let length = wire::read_frame(reader, &mut buf)?;
+
+    match wire::decode::<LoginFrame>(&buf[..length]) {
+        Ok((reply, _)) if reply.accepted => Ok(()),
+        Ok(_) => Err(io::Error::new(ErrorKind::PermissionDenied, format!("login of participant {} refused", login.participant))),
+        Err(error) => Err(io::Error::new(ErrorKind::InvalidData, error)),
+    }
+}
+
+// until the connection or the stream is closed
+fn read_responses(mut reader: BufReader<TcpStream>, responses: Sender<ParticipantResponse>) {
+    let mut buf = [0; MAX_FRAME_SIZE];
+
+    while let Ok(length) = wire::read_frame(&mut reader, &mut buf) {
+        let response = match wire::decode::<ResponseFrame>(&buf[..length]) {
+            Ok((response, _)) => response,
+            Err(_) => return,
+        };
+
+        if responses.send(response).is_err() {
+            return;
+        }
+    }
+}
+
+impl OrderGateway for TcpOrderGateway {
+    fn send(&mut self, request: ParticipantRequest) -> Result<(), ClientError> {
+        let (_, writer) = self.connections.iter_mut().find(|(participant_id, _)| *participant_id == request.participant_id).ok_or(ClientError::NotLoggedIn(request.participant_id))?;
+        writer.write_all(RequestFrame::encode(&request).as_bytes()).map_err(|_| ClientError::Disconnected)
+    }
+
+    fn flush(&mut self) -> Result<(), ClientError> {
+        self.connections.iter_mut().try_for_each(|(_, writer)| writer.flush()).map_err(|_| ClientError::Disconnected)
+    }
+}
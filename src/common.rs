@@ -1,6 +1,6 @@
-use std::{fmt, thread};
+use std::{fmt, thread, time::{SystemTime, UNIX_EPOCH}};
 
-#[derive(Clone)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
 #[repr(u8)]
 pub enum Side {
     Invalid = 0,
@@ -18,6 +18,59 @@ impl fmt::Display for Side {
     }
 }
 
+impl TryFrom<u8> for Side {
+    type Error = u8;
+
+    // the value back when it isn't a discriminant
+    fn try_from(value: u8) -> Result<Self, u8> {
+        [Side::Invalid, Side::Buy, Side::Sell].into_iter().find(|variant| *variant as u8 == value).ok_or(value)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[repr(u8)]
+pub enum OrderType {
+    Invalid = 0,
+    Limit,
+    Market,
+    Stop,
+    StopLimit,
+}
+
+impl fmt::Display for OrderType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OrderType::Limit => write!(f, "LIMIT"),
+            OrderType::Market => write!(f, "MARKET"),
+            OrderType::Stop => write!(f, "STOP"),
+            OrderType::StopLimit => write!(f, "STOP-LIMIT"),
+            OrderType::Invalid => write!(f, "INVALID"),
+        }
+    }
+}
+
+impl TryFrom<u8> for OrderType {
+    type Error = u8;
+
+    fn try_from(value: u8) -> Result<Self, u8> {
+        [OrderType::Invalid, OrderType::Limit, OrderType::Market, OrderType::Stop, OrderType::StopLimit].into_iter().find(|variant| *variant as u8 == value).ok_or(value)
+    }
+}
+
+pub type OrderFlags = u8;
+pub const NO_ORDER_FLAGS: OrderFlags = 0;
+pub const ORDER_FLAG_POST_ONLY: OrderFlags = 1; // rejected if it would take liquidity
+pub const ORDER_FLAG_POST_ONLY_SLIDE: OrderFlags = 1 << 1; // post-only, repriced one tick away instead of rejected
+pub const ORDER_FLAG_HIDDEN: OrderFlags = 1 << 2; // rests behind displayed orders and is never published
+
+pub fn side_to_index(side: Side) -> usize {
+    match side {
+        Side::Buy => 0,
+        Side::Sell => 1,
+        Side::Invalid => panic!("INVALID side has no index"),
+    }
+}
+
 pub type OrderId = u64;
 pub const INVALID_ORDER_ID: u64 = u64::MAX;
 
@@ -37,14 +90,93 @@ pub const INVALID_QUANTITY: u32 = u32::MAX;
 pub type Priority = u64;
 pub const INVALID_PRIORITY: u64 = u64::MAX;
 
-pub const MAX_SYMBOL: usize = 8;
+pub type TradeId = u64;
+pub const INVALID_TRADE_ID: u64 = u64::MAX;
+
+// the shard id is in the top bits of a trade id, the shards count their trades on their own
+pub const TRADE_ID_SHARD_SHIFT: u32 = 48;
+
+// in price ticks, negative for a rebate
+pub type Fee = i64;
+
+pub type ShardId = u32;
+pub type SeqNum = u64;
+
+// an engine output stamped by the shard that produced it, sequence numbers start at 1 and have no gaps within a shard
+#[derive(Clone)]
+pub struct Sequenced<T> {
+    pub shard_id: ShardId,
+    pub seq_num: SeqNum,
+    pub payload: T,
+}
+
+impl<T: fmt::Display> fmt::Display for Sequenced<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "[shard: {}, seq: {}] {}", self.shard_id, self.seq_num, self.payload)
+    }
+}
+
+pub type Nanos = u64;
+pub const NANOS_TO_MICROS: Nanos = 1000;
+pub const MICROS_TO_MILLIS: Nanos = 1000;
+pub const MILLIS_TO_SECS: Nanos = 1000;
+pub const NANOS_TO_SECS: Nanos = NANOS_TO_MICROS * MICROS_TO_MILLIS * MILLIS_TO_SECS;
+pub const NANOS_PER_DAY: Nanos = 24 * 60 * 60 * NANOS_TO_SECS;
+
 pub const MAX_PARTICIPANTS_UPDATES: usize = 256 * 1024;
 pub const MAX_MARKET_UPDATES: usize = 256 * 1024;
 pub const MAX_PARTICIPANTS_NUMBER: usize = 256;
 pub const MAX_ORDER_IDS: usize = 1024 * 1024;
 pub const MAX_PRICE_LEVELS: usize = 256;
 
-fn spawn_pinned<F>(f: F, core_id: isize) -> thread::JoinHandle<()>
+pub fn get_current_nanos() -> Nanos {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos() as Nanos
+}
+
+// the UTC (year, month, day) of a time, months and days start at 1
+pub fn civil_date(time: Nanos) -> (i64, u32, u32) {
+    // days to civil, from Howard Hinnant's date algorithms
+    let days = (time / NANOS_PER_DAY) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    (year, month, day)
+}
+
+// splitmix64, seeded runs must not change with the version of a rng crate
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    pub fn below(&mut self, bound: u64) -> u64 {
+        self.next_u64() % bound
+    }
+
+    pub fn chance(&mut self, probability: f64) -> bool {
+        ((self.next_u64() >> 11) as f64 / (1u64 << 53) as f64) < probability
+    }
+}
+
+pub fn spawn_pinned<F>(f: F, core_id: isize) -> thread::JoinHandle<()>
 where
     F: FnOnce() + Send + 'static,
 {
@@ -58,4 +190,4 @@ where
         }
         f();
     })
-}
\ No newline at end of file
+}
@@ -0,0 +1,662 @@
+use std::{collections::HashSet, fmt, fs, io, path::{Path, PathBuf}};
+
+use serde::Deserialize;
+
+use crate::{admin::admin_server::AdminConfig, backtest::backtester::{BacktestConfig, BacktestStrategy}, client::{liquidity_taker::LiquidityTakerConfig, market_maker::MarketMakerConfig, order_flow::OrderFlowConfig}, order_server::{drop_copy::DropCopyConfig, ouch_gateway::{OuchAccount, OuchConfig}, tcp_gateway::{GatewayConfig, GatewayLogin}}, latency::recorder::LatencyConfig, logging::logger::LoggerConfig, trades::trade_store::TradeStoreConfig, fees::fee_schedule::{FeeConfig, FeeTier}, positions::PositionsConfig, eod::end_of_day::EodConfig, common::{ParticipantId, ShardId, SymbolId, INVALID_PRICE, MAX_MARKET_UPDATES, MAX_ORDER_IDS, MAX_PARTICIPANTS_NUMBER, MAX_PARTICIPANTS_UPDATES, MAX_PRICE_LEVELS}, matching_engine::{allocation::AllocationPolicy, circuit_breaker::CircuitBreakerConfig, matching_engine::MatchingEngineConfig, orderbook::OrderBookConfig}, wire::LOGIN_PASSWORD_SIZE};
+
+pub const DEFAULT_CONFIG_PATH: &str = "rexchange.toml";
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(PathBuf, io::Error),
+    Parse(PathBuf, toml::de::Error),
+    Invalid(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::Io(path, error) => write!(f, "cannot read {}: {}", path.display(), error),
+            ConfigError::Parse(path, error) => write!(f, "cannot parse {}: {}", path.display(), error),
+            ConfigError::Invalid(message) => write!(f, "invalid configuration: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LimitsConfig {
+    pub max_order_ids: usize,
+    pub max_price_levels: usize,
+    pub max_participants: usize,
+    pub participant_requests_capacity: usize,
+    pub participant_responses_capacity: usize,
+    pub market_updates_capacity: usize,
+}
+
+impl Default for LimitsConfig {
+    fn default() -> Self {
+        Self {
+            max_order_ids: MAX_ORDER_IDS,
+            max_price_levels: MAX_PRICE_LEVELS,
+            max_participants: MAX_PARTICIPANTS_NUMBER,
+            participant_requests_capacity: MAX_PARTICIPANTS_UPDATES,
+            participant_responses_capacity: MAX_PARTICIPANTS_UPDATES,
+            market_updates_capacity: MAX_MARKET_UPDATES,
+        }
+    }
+}
+
+// symbols are spread over `shards` matching engine threads, each owning the order books of its symbols
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EngineConfig {
+    pub shards: usize,
+}
+
+impl Default for EngineConfig {
+    fn default() -> Self {
+        Self {
+            shards: 1,
+        }
+    }
+}
+
+// core each thread is pinned to, a negative id leaves the thread unpinned
+// `matching_engines` has one core per shard, an empty list leaves every shard unpinned
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CoresConfig {
+    pub matching_engines: Vec<isize>,
+    pub logger: isize,
+}
+
+impl Default for CoresConfig {
+    fn default() -> Self {
+        Self {
+            matching_engines: Vec::new(),
+            logger: -1,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ParticipantConfig {
+    pub id: ParticipantId,
+    pub name: String,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SymbolConfig {
+    pub id: SymbolId,
+    pub name: String,
+    // defaults to the symbol id modulo the number of shards
+    #[serde(default)]
+    pub shard: Option<ShardId>,
+    #[serde(default)]
+    pub circuit_breaker: CircuitBreakerConfig,
+    #[serde(default)]
+    pub allocation: AllocationPolicy,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    #[serde(default)]
+    pub limits: LimitsConfig,
+    #[serde(default)]
+    pub engine: EngineConfig,
+    #[serde(default)]
+    pub cores: CoresConfig,
+    #[serde(default)]
+    pub gateway: GatewayConfig,
+    #[serde(default)]
+    pub ouch: OuchConfig,
+    #[serde(default)]
+    pub drop_copy: DropCopyConfig,
+    #[serde(default)]
+    pub trade_store: TradeStoreConfig,
+    #[serde(default)]
+    pub admin: AdminConfig,
+    #[serde(default)]
+    pub fees: FeeConfig,
+    #[serde(default)]
+    pub positions: PositionsConfig,
+    #[serde(default)]
+    pub eod: EodConfig,
+    #[serde(default)]
+    pub logging: LoggerConfig,
+    #[serde(default)]
+    pub latency: LatencyConfig,
+    pub participants: Vec<ParticipantConfig>,
+    pub symbols: Vec<SymbolConfig>,
+    // reference bots run in process, each trading as its own participant
+    #[serde(default)]
+    pub market_makers: Vec<MarketMakerConfig>,
+    #[serde(default)]
+    pub liquidity_takers: Vec<LiquidityTakerConfig>,
+    // synthetic load, generated in process or sent to the gateway by the order_flow binary
+    #[serde(default)]
+    pub order_flow: Option<OrderFlowConfig>,
+    #[serde(default)]
+    pub backtest: Option<BacktestConfig>,
+}
+
+impl Config {
+    pub fn load(path: &Path) -> Result<Self, ConfigError> {
+        let content = fs::read_to_string(path).map_err(|error| ConfigError::Io(path.to_path_buf(), error))?;
+        let config: Config = toml::from_str(&content).map_err(|error| ConfigError::Parse(path.to_path_buf(), error))?;
+
+        config.validate()?;
+
+        Ok(config)
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        self.validate_limits()?;
+        self.validate_cores()?;
+        self.validate_logging()?;
+        self.validate_participants()?;
+        self.validate_symbols()?;
+        self.validate_bots()?;
+        self.validate_order_flow()?;
+        self.validate_ouch()?;
+        self.validate_gateway()?;
+        self.validate_drop_copy()?;
+        self.validate_admin()?;
+        self.validate_fees()?;
+        self.validate_eod()?;
+        self.validate_backtest()
+    }
+
+    fn validate_limits(&self) -> Result<(), ConfigError> {
+        let limits = [
+            ("max_order_ids", self.limits.max_order_ids),
+            ("max_price_levels", self.limits.max_price_levels),
+            ("max_participants", self.limits.max_participants),
+            ("participant_requests_capacity", self.limits.participant_requests_capacity),
+            ("participant_responses_capacity", self.limits.participant_responses_capacity),
+            ("market_updates_capacity", self.limits.market_updates_capacity),
+        ];
+
+        for (name, value) in limits {
+            if value == 0 {
+                return Err(invalid(format!("limits.{} must be greater than 0", name)));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn validate_cores(&self) -> Result<(), ConfigError> {
+        if self.engine.shards == 0 {
+            return Err(invalid("engine.shards must be greater than 0".to_string()));
+        }
+
+        if !self.cores.matching_engines.is_empty() && self.cores.matching_engines.len() != self.engine.shards {
+            return Err(invalid(format!("cores.matching_engines lists {} cores but there are {} shards", self.cores.matching_engines.len(), self.engine.shards)));
+        }
+
+        let core_count = core_affinity::get_core_ids().map(|core_ids| core_ids.len()).unwrap_or(0);
+
+        for (name, core_id) in self.pinned_threads() {
+            if core_id >= 0 && core_id as usize >= core_count {
+                return Err(invalid(format!("cores.{} is {} but only {} cores are available", name, core_id, core_count)));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn pinned_threads(&self) -> Vec<(&'static str, isize)> {
+        let mut pinned_threads: Vec<(&'static str, isize)> = self.cores.matching_engines.iter().map(|core_id| ("matching_engines", *core_id)).collect();
+        pinned_threads.push(("logger", self.cores.logger));
+        pinned_threads.extend(self.market_makers.iter().map(|market_maker| ("market_makers.core", market_maker.core)));
+        pinned_threads.extend(self.liquidity_takers.iter().map(|liquidity_taker| ("liquidity_takers.core", liquidity_taker.core)));
+        pinned_threads.extend(self.order_flow.iter().map(|order_flow| ("order_flow.core", order_flow.core)));
+        pinned_threads
+    }
+
+    fn validate_logging(&self) -> Result<(), ConfigError> {
+        if self.logging.file_name.is_empty() {
+            return Err(invalid("logging.file_name must not be empty".to_string()));
+        }
+
+        if self.logging.queue_capacity == 0 || self.logging.max_file_size == 0 || self.logging.max_files == 0 {
+            return Err(invalid("logging.queue_capacity, logging.max_file_size and logging.max_files must be greater than 0".to_string()));
+        }
+
+        Ok(())
+    }
+
+    fn validate_participants(&self) -> Result<(), ConfigError> {
+        let mut ids = HashSet::new();
+
+        for participant in &self.participants {
+            if participant.id as usize >= self.limits.max_participants {
+                return Err(invalid(format!("participant {} ({}) is not below limits.max_participants ({})", participant.id, participant.name, self.limits.max_participants)));
+            }
+
+            if !ids.insert(participant.id) {
+                return Err(invalid(format!("participant id {} is defined more than once", participant.id)));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn validate_symbols(&self) -> Result<(), ConfigError> {
+        if self.symbols.is_empty() {
+            return Err(invalid("at least one symbol must be defined".to_string()));
+        }
+
+        let mut names = HashSet::new();
+        let mut ids = HashSet::new();
+
+        for symbol in &self.symbols {
+            if symbol.name.is_empty() || !names.insert(symbol.name.as_str()) {
+                return Err(invalid(format!("symbol {} must have a unique, non empty name", symbol.id)));
+            }
+
+            if symbol.id as usize >= self.symbols.len() || !ids.insert(symbol.id) {
+                return Err(invalid(format!("symbol {} ({}): ids must be unique and go from 0 to {}", symbol.id, symbol.name, self.symbols.len() - 1)));
+            }
+
+            if let Some(shard) = symbol.shard {
+                if shard as usize >= self.engine.shards {
+                    return Err(invalid(format!("symbol {}: shard {} but there are {} shards", symbol.name, shard, self.engine.shards)));
+                }
+            }
+
+            let circuit_breaker = &symbol.circuit_breaker;
+
+            if circuit_breaker.static_band_bps > 0 && circuit_breaker.previous_close == INVALID_PRICE {
+                return Err(invalid(format!("symbol {}: a static band needs a previous_close", symbol.name)));
+            }
+
+            if let AllocationPolicy::SplitFifoProRata { fifo_pct, .. } = symbol.allocation {
+                if fifo_pct > 100 {
+                    return Err(invalid(format!("symbol {}: fifo_pct is {} but must be at most 100", symbol.name, fifo_pct)));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn validate_bots(&self) -> Result<(), ConfigError> {
+        let market_makers = self.market_makers.iter().map(|market_maker| ("market maker", market_maker.participant, &market_maker.symbols, market_maker.order_ids));
+        let liquidity_takers = self.liquidity_takers.iter().map(|liquidity_taker| ("liquidity taker", liquidity_taker.participant, &liquidity_taker.symbols, liquidity_taker.order_ids));
+        let mut participants = HashSet::new();
+
+        for (kind, participant, symbols, order_ids) in market_makers.chain(liquidity_takers) {
+            if !self.participants.iter().any(|configured| configured.id == participant) {
+                return Err(invalid(format!("{} participant {} is not a configured participant", kind, participant)));
+            }
+
+            if !participants.insert(participant) {
+                return Err(invalid(format!("participant {} runs more than one bot", participant)));
+            }
+
+            if let Some(symbol_id) = symbols.iter().find(|symbol_id| **symbol_id as usize >= self.symbols.len()) {
+                return Err(invalid(format!("{} {}: unknown symbol {}", kind, participant, symbol_id)));
+            }
+
+            if order_ids == 0 || order_ids > self.limits.max_order_ids {
+                return Err(invalid(format!("{} {}: order_ids must be between 1 and limits.max_order_ids ({})", kind, participant, self.limits.max_order_ids)));
+            }
+        }
+
+        for market_maker in &self.market_makers {
+            if market_maker.half_spread == 0 || market_maker.quote_qty == 0 || market_maker.requote_threshold == 0 {
+                return Err(invalid(format!("market maker {}: half_spread, quote_qty and requote_threshold must be greater than 0", market_maker.participant)));
+            }
+        }
+
+        for liquidity_taker in &self.liquidity_takers {
+            if liquidity_taker.window_trades == 0 || liquidity_taker.order_qty == 0 {
+                return Err(invalid(format!("liquidity taker {}: window_trades and order_qty must be greater than 0", liquidity_taker.participant)));
+            }
+
+            if !(0.0..=1.0).contains(&liquidity_taker.imbalance_threshold) {
+                return Err(invalid(format!("liquidity taker {}: imbalance_threshold must be between 0 and 1", liquidity_taker.participant)));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn validate_order_flow(&self) -> Result<(), ConfigError> {
+        let order_flow = match &self.order_flow {
+            Some(order_flow) => order_flow,
+            None => return Ok(()),
+        };
+
+        if order_flow.participants.is_empty() || order_flow.symbols.is_empty() {
+            return Err(invalid("order_flow needs at least one participant and one symbol".to_string()));
+        }
+
+        let bot_participants: HashSet<ParticipantId> = self.market_makers.iter().map(|market_maker| market_maker.participant)
+            .chain(self.liquidity_takers.iter().map(|liquidity_taker| liquidity_taker.participant))
+            .collect();
+
+        for participant in &order_flow.participants {
+            if !self.participants.iter().any(|configured| configured.id == *participant) {
+                return Err(invalid(format!("order_flow participant {} is not a configured participant", participant)));
+            }
+
+            // the bots would see responses to orders they never sent
+            if bot_participants.contains(participant) {
+                return Err(invalid(format!("order_flow participant {} already runs a bot", participant)));
+            }
+        }
+
+        if let Some(symbol_id) = order_flow.symbols.iter().find(|symbol_id| **symbol_id as usize >= self.symbols.len()) {
+            return Err(invalid(format!("order_flow: unknown symbol {}", symbol_id)));
+        }
+
+        if order_flow.price_range == 0 || order_flow.max_qty == 0 || order_flow.burst_size == 0 || order_flow.new_weight + order_flow.cancel_weight + order_flow.modify_weight == 0 {
+            return Err(invalid("order_flow: price_range, max_qty, burst_size and the sum of the weights must be greater than 0".to_string()));
+        }
+
+        if order_flow.initial_mid <= order_flow.price_range || order_flow.marketable_pct > 100 {
+            return Err(invalid("order_flow: initial_mid must be above price_range and marketable_pct at most 100".to_string()));
+        }
+
+        if order_flow.order_ids == 0 || order_flow.order_ids > self.limits.max_order_ids {
+            return Err(invalid(format!("order_flow: order_ids must be between 1 and limits.max_order_ids ({})", self.limits.max_order_ids)));
+        }
+
+        if !order_flow.in_process {
+            if self.gateway.address.is_empty() {
+                return Err(invalid("order_flow is sent over TCP but gateway.address is empty".to_string()));
+            }
+
+            // the order_flow binary logs in as each of its participants
+            if let Some(participant) = order_flow.participants.iter().find(|participant| !self.gateway.logins.iter().any(|login| login.participant == **participant)) {
+                return Err(invalid(format!("order_flow participant {} has no gateway login", participant)));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn validate_ouch(&self) -> Result<(), ConfigError> {
+        if self.ouch.address.is_empty() {
+            return Ok(());
+        }
+
+        if self.ouch.heartbeat_interval_ms == 0 || self.ouch.idle_timeout_ms <= self.ouch.heartbeat_interval_ms {
+            return Err(invalid("ouch: heartbeat_interval_ms must be greater than 0 and below idle_timeout_ms".to_string()));
+        }
+
+        // a session keeps its own client order ids, nothing else can send orders as its participant
+        let taken: HashSet<ParticipantId> = self.market_makers.iter().map(|market_maker| market_maker.participant)
+            .chain(self.liquidity_takers.iter().map(|liquidity_taker| liquidity_taker.participant))
+            .chain(self.order_flow.iter().flat_map(|order_flow| order_flow.participants.iter().copied()))
+            .collect();
+        let mut participants = HashSet::new();
+
+        for login in &self.ouch.logins {
+            let participant = match self.participants.iter().find(|configured| configured.id == login.participant) {
+                Some(participant) => participant,
+                None => return Err(invalid(format!("ouch login participant {} is not a configured participant", login.participant))),
+            };
+
+            if taken.contains(&login.participant) || !participants.insert(login.participant) {
+                return Err(invalid(format!("ouch login participant {} already sends orders", login.participant)));
+            }
+
+            // the soup login fields are 6 and 10 characters
+            if participant.name.len() > 6 || login.password.is_empty() || login.password.len() > 10 {
+                return Err(invalid(format!("ouch login {}: the name must be at most 6 characters and the password 1 to 10", participant.name)));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn validate_gateway(&self) -> Result<(), ConfigError> {
+        if self.gateway.address.is_empty() {
+            return Ok(());
+        }
+
+        // a login gets its participant's responses, nothing else can be sent them
+        let taken: HashSet<ParticipantId> = self.market_makers.iter().map(|market_maker| market_maker.participant)
+            .chain(self.liquidity_takers.iter().map(|liquidity_taker| liquidity_taker.participant))
+            .chain(self.ouch.logins.iter().map(|login| login.participant))
+            .chain(self.order_flow.iter().filter(|order_flow| order_flow.in_process).flat_map(|order_flow| order_flow.participants.iter().copied()))
+            .collect();
+        let mut participants = HashSet::new();
+
+        for login in &self.gateway.logins {
+            if !self.participants.iter().any(|configured| configured.id == login.participant) {
+                return Err(invalid(format!("gateway login participant {} is not a configured participant", login.participant)));
+            }
+
+            if taken.contains(&login.participant) || !participants.insert(login.participant) {
+                return Err(invalid(format!("gateway login participant {} already sends orders", login.participant)));
+            }
+
+            if login.password.is_empty() || login.password.len() > LOGIN_PASSWORD_SIZE || login.password.contains('\0') {
+                return Err(invalid(format!("gateway login {}: the password must be 1 to {} bytes, without NUL", login.participant, LOGIN_PASSWORD_SIZE)));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn validate_drop_copy(&self) -> Result<(), ConfigError> {
+        if self.drop_copy.address.is_empty() {
+            return Ok(());
+        }
+
+        if self.drop_copy.heartbeat_interval_ms == 0 || self.drop_copy.idle_timeout_ms <= self.drop_copy.heartbeat_interval_ms {
+            return Err(invalid("drop_copy: heartbeat_interval_ms must be greater than 0 and below idle_timeout_ms".to_string()));
+        }
+
+        let mut usernames = HashSet::new();
+
+        for login in &self.drop_copy.logins {
+            if login.username.is_empty() || login.username.len() > 6 || login.password.is_empty() || login.password.len() > 10 {
+                return Err(invalid(format!("drop copy login {}: the username must be 1 to 6 characters and the password 1 to 10", login.username)));
+            }
+
+            if !usernames.insert(login.username.as_str()) {
+                return Err(invalid(format!("drop copy login {} is duplicated", login.username)));
+            }
+
+            if let Some(participant) = login.participants.iter().find(|&&participant| !self.participants.iter().any(|configured| configured.id == participant)) {
+                return Err(invalid(format!("drop copy login {}: participant {} is not a configured participant", login.username, participant)));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn validate_admin(&self) -> Result<(), ConfigError> {
+        if self.admin.address.is_empty() {
+            return Ok(());
+        }
+
+        // busts and corrections act on the stored trades
+        if self.trade_store.path.as_os_str().is_empty() || self.admin.audit_path.as_os_str().is_empty() {
+            return Err(invalid("admin: trade_store.path and admin.audit_path must be set".to_string()));
+        }
+
+        if self.admin.users.is_empty() {
+            return Err(invalid("admin: at least one user must be defined".to_string()));
+        }
+
+        let mut usernames = HashSet::new();
+
+        for user in &self.admin.users {
+            let is_word = |field: &str| !field.is_empty() && !field.contains(char::is_whitespace);
+
+            if !is_word(&user.username) || !is_word(&user.password) {
+                return Err(invalid(format!("admin user {}: the username and password must be non empty and without spaces", user.username)));
+            }
+
+            if !usernames.insert(user.username.as_str()) {
+                return Err(invalid(format!("admin user {} is duplicated", user.username)));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn validate_fees(&self) -> Result<(), ConfigError> {
+        validate_tiers("fees", &self.fees.tiers)?;
+
+        let mut symbols = HashSet::new();
+
+        for symbol in &self.fees.symbols {
+            if symbol.symbol as usize >= self.symbols.len() || !symbols.insert(symbol.symbol) {
+                return Err(invalid(format!("fees: symbol {} is unknown or has two schedules", symbol.symbol)));
+            }
+
+            validate_tiers(&format!("fees of symbol {}", symbol.symbol), &symbol.tiers)?;
+        }
+
+        Ok(())
+    }
+
+    fn validate_eod(&self) -> Result<(), ConfigError> {
+        if self.eod.directory.as_os_str().is_empty() {
+            return Ok(());
+        }
+
+        // the session's trades are read back from the store
+        if self.trade_store.path.as_os_str().is_empty() {
+            return Err(invalid("eod: trade_store.path must be set".to_string()));
+        }
+
+        if !self.eod.close_time.is_empty() && self.eod.close_time().is_none() {
+            return Err(invalid(format!("eod: close_time {} is not a HH:MM time", self.eod.close_time)));
+        }
+
+        Ok(())
+    }
+
+    fn validate_backtest(&self) -> Result<(), ConfigError> {
+        let backtest = match &self.backtest {
+            Some(backtest) => backtest,
+            None => return Ok(()),
+        };
+
+        if backtest.data.as_os_str().is_empty() {
+            return Err(invalid("backtest.data must name a historical data file".to_string()));
+        }
+
+        let participant = match self.backtest_participant() {
+            Some(participant) => participant,
+            None => return Err(invalid(format!("backtest.strategy is {:?} but no such bot is configured", backtest.strategy))),
+        };
+
+        // the two highest participant ids replay the history
+        if participant as usize + 2 >= self.limits.max_participants {
+            return Err(invalid(format!("backtest: participant {} must be below limits.max_participants - 2", participant)));
+        }
+
+        Ok(())
+    }
+
+    // participant of the bot the backtest runs
+    pub fn backtest_participant(&self) -> Option<ParticipantId> {
+        match self.backtest.as_ref()?.strategy {
+            BacktestStrategy::MarketMaker => self.market_makers.first().map(|market_maker| market_maker.participant),
+            BacktestStrategy::LiquidityTaker => self.liquidity_takers.first().map(|liquidity_taker| liquidity_taker.participant),
+        }
+    }
+
+    // one config per symbol, indexed by symbol id
+    pub fn order_book_configs(&self) -> Vec<OrderBookConfig> {
+        let mut symbols: Vec<&SymbolConfig> = self.symbols.iter().collect();
+        symbols.sort_by_key(|symbol| symbol.id);
+
+        symbols.into_iter().map(|symbol| OrderBookConfig {
+            circuit_breaker: symbol.circuit_breaker.clone(),
+            allocation_policy: symbol.allocation,
+            max_order_ids: self.limits.max_order_ids,
+            max_price_levels: self.limits.max_price_levels,
+            max_participants: self.limits.max_participants,
+        }).collect()
+    }
+
+    // the gateway logins of the order flow's participants, for the order_flow binary
+    pub fn order_flow_logins(&self) -> Vec<GatewayLogin> {
+        let participants = self.order_flow.as_ref().map_or(&[][..], |order_flow| order_flow.participants.as_slice());
+        self.gateway.logins.iter().filter(|login| participants.contains(&login.participant)).cloned().collect()
+    }
+
+    pub fn ouch_accounts(&self) -> Vec<OuchAccount> {
+        self.ouch.logins.iter().filter_map(|login| {
+            let participant = self.participants.iter().find(|participant| participant.id == login.participant)?;
+            Some(OuchAccount { participant_id: participant.id, username: participant.name.clone(), password: login.password.clone() })
+        }).collect()
+    }
+
+    // indexed by symbol id
+    pub fn symbol_names(&self) -> Vec<&str> {
+        let mut symbols: Vec<&SymbolConfig> = self.symbols.iter().collect();
+        symbols.sort_by_key(|symbol| symbol.id);
+        symbols.into_iter().map(|symbol| symbol.name.as_str()).collect()
+    }
+
+    pub fn participant_ids(&self) -> Vec<ParticipantId> {
+        self.participants.iter().map(|participant| participant.id).collect()
+    }
+
+    // shard of each symbol, indexed by symbol id
+    pub fn symbol_shards(&self) -> Vec<ShardId> {
+        let mut symbol_shards = vec![0; self.symbols.len()];
+
+        for symbol in &self.symbols {
+            symbol_shards[symbol.id as usize] = symbol.shard.unwrap_or(symbol.id % self.engine.shards as ShardId);
+        }
+
+        symbol_shards
+    }
+
+    // one config per shard, indexed by shard id
+    pub fn engine_configs(&self) -> Vec<MatchingEngineConfig> {
+        let mut engine_configs: Vec<MatchingEngineConfig> = (0..self.engine.shards).map(|shard_id| MatchingEngineConfig {
+            shard_id: shard_id as ShardId,
+            order_books: Vec::new(),
+            participant_ids: self.participant_ids(),
+            latency: self.latency.clone(),
+        }).collect();
+
+        for (symbol_id, (order_book_config, shard_id)) in self.order_book_configs().into_iter().zip(self.symbol_shards()).enumerate() {
+            engine_configs[shard_id as usize].order_books.push((symbol_id as SymbolId, order_book_config));
+        }
+
+        engine_configs
+    }
+
+    pub fn engine_core(&self, shard_id: ShardId) -> isize {
+        self.cores.matching_engines.get(shard_id as usize).copied().unwrap_or(-1)
+    }
+}
+
+// the base fees apply below the first tier
+fn validate_tiers(name: &str, tiers: &[FeeTier]) -> Result<(), ConfigError> {
+    let mut min_volume = 0;
+
+    for tier in tiers {
+        if tier.min_volume <= min_volume {
+            return Err(invalid(format!("{}: tier min_volume {} must be above 0 and above the tier before", name, tier.min_volume)));
+        }
+        min_volume = tier.min_volume;
+    }
+
+    Ok(())
+}
+
+fn invalid(message: String) -> ConfigError {
+    ConfigError::Invalid(message)
+}
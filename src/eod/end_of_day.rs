@@ -0,0 +1,86 @@
+use std::{io, path::PathBuf, sync::{Arc, Mutex}, thread, time::Duration};
+
+use serde::Deserialize;
+
+use crate::common::{civil_date, get_current_nanos, Nanos, Price, NANOS_PER_DAY, NANOS_TO_SECS};
+use crate::fees::billing::Billing;
+use crate::logging::logger::{LogPayload, Logger};
+use crate::positions::PositionKeeper;
+use crate::trades::trade_store::{TradeQuery, TradeStore};
+
+use super::{files, report::{EodReport, SettlementPrice}};
+
+// an empty directory leaves the end of day off. `close_time` is the UTC time of day, as HH:MM, the session closes and
+// the reports are written at, empty to only write them from the admin console
+#[derive(Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EodConfig {
+    pub directory: PathBuf,
+    pub close_time: String,
+    pub settlement_price: SettlementPrice,
+}
+
+impl EodConfig {
+    // since midnight UTC, None when not set or not a time
+    pub fn close_time(&self) -> Option<Nanos> {
+        let (hours, minutes) = self.close_time.split_once(':')?;
+        let (hours, minutes): (Nanos, Nanos) = (hours.parse().ok()?, minutes.parse().ok()?);
+        (hours < 24 && minutes < 60).then_some((hours * 60 + minutes) * 60 * NANOS_TO_SECS)
+    }
+}
+
+// the session is the UTC day, its trades are read back from the trade store. Positions and fees are those the
+// exchange kept since it started
+#[derive(Clone)]
+pub struct EndOfDay {
+    config: EodConfig,
+    trade_store: Arc<Mutex<TradeStore>>,
+    billing: Arc<Mutex<Billing>>,
+    positions: Arc<Mutex<PositionKeeper>>,
+    previous_closes: Vec<Price>, //indexed by symbol id, INVALID_PRICE when unknown
+}
+
+impl EndOfDay {
+    pub fn new(config: &EodConfig, trade_store: Arc<Mutex<TradeStore>>, billing: Arc<Mutex<Billing>>, positions: Arc<Mutex<PositionKeeper>>, previous_closes: Vec<Price>) -> Self {
+        Self {
+            config: config.clone(),
+            trade_store,
+            billing,
+            positions,
+            previous_closes,
+        }
+    }
+
+    pub fn report(&self, now: Nanos) -> EodReport {
+        let session_start = now - now % NANOS_PER_DAY;
+        let query = TradeQuery { from: Some(session_start), to: Some(session_start + NANOS_PER_DAY), ..TradeQuery::default() };
+
+        let trade_store = self.trade_store.lock().unwrap();
+        let trades = trade_store.query(&query);
+        let positions = self.positions.lock().unwrap().all();
+        let billing = self.billing.lock().unwrap();
+
+        EodReport::build(civil_date(now), &trades, &positions, |participant_id, trade_id| billing.trade_fee(participant_id, trade_id), self.config.settlement_price, &self.previous_closes)
+    }
+
+    // writes the reports of the session `now` is in, returns the directory they are in
+    pub fn run(&self, now: Nanos) -> io::Result<PathBuf> {
+        files::write(&self.report(now), &self.config.directory)
+    }
+}
+
+// runs the end of day at the close of every session, on its own thread
+pub fn schedule(end_of_day: EndOfDay, close_time: Nanos, logger: Logger) {
+    thread::spawn(move || loop {
+        let now = get_current_nanos();
+        let today_close = now - now % NANOS_PER_DAY + close_time;
+        let next_close = if today_close > now { today_close } else { today_close + NANOS_PER_DAY };
+
+        thread::sleep(Duration::from_nanos(next_close - now));
+
+        match end_of_day.run(next_close) {
+            Ok(_) => logger.info("end of day written", LogPayload::None),
+            Err(_) => logger.error("end of day: cannot write reports", LogPayload::None),
+        }
+    });
+}
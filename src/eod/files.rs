@@ -0,0 +1,151 @@
+use std::{fmt::Write as _, fs, io, path::{Path, PathBuf}};
+
+use crate::common::Price;
+
+use super::report::{CashMovements, EodReport, Statement};
+
+// writes the report into a directory named after its date, which it returns. Every file is written to a temporary name
+// and renamed, downstream systems never see half of one. Prices and values are in ticks, a missing price is empty in
+// CSV and null in JSON
+pub fn write(report: &EodReport, directory: &Path) -> io::Result<PathBuf> {
+    let directory = directory.join(date(report));
+    fs::create_dir_all(&directory)?;
+
+    write_file(&directory, "symbols.csv", &symbols_csv(report))?;
+    write_file(&directory, "symbols.json", &symbols_json(report))?;
+    write_file(&directory, "obligations.csv", &obligations_csv(report))?;
+    write_file(&directory, "obligations.json", &obligations_json(report))?;
+
+    for statement in &report.statements {
+        write_file(&directory, &format!("statement-{}.csv", statement.participant_id), &statement_csv(statement))?;
+        write_file(&directory, &format!("statement-{}.json", statement.participant_id), &statement_json(report, statement))?;
+    }
+
+    Ok(directory)
+}
+
+fn write_file(directory: &Path, name: &str, content: &str) -> io::Result<()> {
+    let path = directory.join(name);
+    let temporary_path = directory.join(format!("{}.tmp", name));
+    fs::write(&temporary_path, content)?;
+    fs::rename(&temporary_path, &path)
+}
+
+fn date(report: &EodReport) -> String {
+    let (year, month, day) = report.date;
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+fn csv_price(price: Option<Price>) -> String {
+    price.map_or(String::new(), |price| price.to_string())
+}
+
+fn json_price(price: Option<Price>) -> String {
+    price.map_or("null".to_string(), |price| price.to_string())
+}
+
+fn traded(price: Price, trades: u64) -> Option<Price> {
+    (trades > 0).then_some(price)
+}
+
+fn symbols_csv(report: &EodReport) -> String {
+    let mut csv = String::from("symbol_id,open,high,low,close,volume,trades,value,vwap,settlement_price\n");
+
+    for symbol in &report.symbols {
+        let _ = writeln!(csv, "{},{},{},{},{},{},{},{},{},{}",
+            symbol.symbol_id,
+            csv_price(traded(symbol.open, symbol.trades)),
+            csv_price(traded(symbol.high, symbol.trades)),
+            csv_price(traded(symbol.low, symbol.trades)),
+            csv_price(traded(symbol.close, symbol.trades)),
+            symbol.volume,
+            symbol.trades,
+            symbol.value,
+            csv_price(symbol.vwap()),
+            csv_price(symbol.settlement_price));
+    }
+
+    csv
+}
+
+fn symbols_json(report: &EodReport) -> String {
+    let symbols: Vec<String> = report.symbols.iter().map(|symbol| format!(
+        "{{\"symbol_id\":{},\"open\":{},\"high\":{},\"low\":{},\"close\":{},\"volume\":{},\"trades\":{},\"value\":{},\"vwap\":{},\"settlement_price\":{}}}",
+        symbol.symbol_id,
+        json_price(traded(symbol.open, symbol.trades)),
+        json_price(traded(symbol.high, symbol.trades)),
+        json_price(traded(symbol.low, symbol.trades)),
+        json_price(traded(symbol.close, symbol.trades)),
+        symbol.volume,
+        symbol.trades,
+        symbol.value,
+        json_price(symbol.vwap()),
+        json_price(symbol.settlement_price))).collect();
+
+    format!("{{\"date\":\"{}\",\"symbols\":[{}]}}\n", date(report), symbols.join(","))
+}
+
+fn obligations_csv(report: &EodReport) -> String {
+    let mut csv = String::from("participant_id,symbol_id,bought_qty,sold_qty,net_qty,bought_value,sold_value,fees,net_cash\n");
+
+    for obligation in &report.obligations {
+        let cash = &obligation.cash;
+        let _ = writeln!(csv, "{},{},{},{},{},{},{},{},{}",
+            obligation.participant_id,
+            obligation.symbol_id,
+            obligation.bought_qty,
+            obligation.sold_qty,
+            obligation.net_qty(),
+            cash.bought_value,
+            cash.sold_value,
+            cash.fees,
+            cash.net());
+    }
+
+    csv
+}
+
+fn obligations_json(report: &EodReport) -> String {
+    let obligations: Vec<String> = report.obligations.iter().map(|obligation| format!(
+        "{{\"participant_id\":{},\"symbol_id\":{},\"bought_qty\":{},\"sold_qty\":{},\"net_qty\":{},{}}}",
+        obligation.participant_id,
+        obligation.symbol_id,
+        obligation.bought_qty,
+        obligation.sold_qty,
+        obligation.net_qty(),
+        cash_json(&obligation.cash))).collect();
+
+    format!("{{\"date\":\"{}\",\"obligations\":[{}]}}\n", date(report), obligations.join(","))
+}
+
+fn cash_json(cash: &CashMovements) -> String {
+    format!("\"bought_value\":{},\"sold_value\":{},\"fees\":{},\"net_cash\":{}", cash.bought_value, cash.sold_value, cash.fees, cash.net())
+}
+
+// the trade blotter, the JSON statement has the positions and totals too
+fn statement_csv(statement: &Statement) -> String {
+    let mut csv = String::from("trade_id,timestamp,symbol_id,side,price,qty,fee,cash\n");
+
+    for trade in &statement.trades {
+        let _ = writeln!(csv, "{},{},{},{},{},{},{},{}", trade.trade_id, trade.timestamp, trade.symbol_id, trade.side, trade.price, trade.qty, trade.fee, trade.cash);
+    }
+
+    csv
+}
+
+fn statement_json(report: &EodReport, statement: &Statement) -> String {
+    let trades: Vec<String> = statement.trades.iter().map(|trade| format!(
+        "{{\"trade_id\":{},\"timestamp\":{},\"symbol_id\":{},\"side\":\"{}\",\"price\":{},\"qty\":{},\"fee\":{},\"cash\":{}}}",
+        trade.trade_id, trade.timestamp, trade.symbol_id, trade.side, trade.price, trade.qty, trade.fee, trade.cash)).collect();
+
+    let positions: Vec<String> = statement.positions.iter().map(|position| format!(
+        "{{\"symbol_id\":{},\"position\":{},\"open_price\":{:.2},\"realized\":{:.2},\"settlement_price\":{},\"unrealized\":{:.2}}}",
+        position.symbol_id, position.position, position.open_price, position.realized, json_price(position.settlement_price), position.unrealized)).collect();
+
+    format!("{{\"date\":\"{}\",\"participant_id\":{},\"trades\":[{}],\"positions\":[{}],\"cash\":{{{}}}}}\n",
+        date(report),
+        statement.participant_id,
+        trades.join(","),
+        positions.join(","),
+        cash_json(&statement.cash))
+}
@@ -0,0 +1,3 @@
+pub mod end_of_day;
+pub mod files;
+pub mod report;
@@ -0,0 +1,217 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+use crate::common::{Fee, Nanos, ParticipantId, Price, Side, SymbolId, TradeId, INVALID_PRICE};
+use crate::positions::Position;
+use crate::trades::trade::Trade;
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SettlementPrice {
+    #[default]
+    Close,
+    Vwap,
+}
+
+// a symbol's session, prices are INVALID_PRICE when it didn't trade. Without trades it settles at its previous close
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct SymbolSummary {
+    pub symbol_id: SymbolId,
+    pub open: Price,
+    pub high: Price,
+    pub low: Price,
+    pub close: Price,
+    pub volume: u64,
+    pub trades: u64,
+    pub value: u64,
+    pub settlement_price: Option<Price>,
+}
+
+impl SymbolSummary {
+    pub fn new(symbol_id: SymbolId) -> Self {
+        Self { symbol_id, open: INVALID_PRICE, high: INVALID_PRICE, low: INVALID_PRICE, close: INVALID_PRICE, volume: 0, trades: 0, value: 0, settlement_price: None }
+    }
+
+    // rounded to the nearest tick
+    pub fn vwap(&self) -> Option<Price> {
+        (self.volume > 0).then(|| (self.value + self.volume / 2) / self.volume)
+    }
+
+    // trades come in time order
+    fn add(&mut self, trade: &Trade) {
+        if self.trades == 0 {
+            (self.open, self.high, self.low) = (trade.price, trade.price, trade.price);
+        }
+
+        self.high = self.high.max(trade.price);
+        self.low = self.low.min(trade.price);
+        self.close = trade.price;
+        self.volume += trade.qty as u64;
+        self.value += trade.price * trade.qty as u64;
+        self.trades += 1;
+    }
+
+    fn settle(&mut self, settlement_price: SettlementPrice, previous_close: Price) {
+        self.settlement_price = match (self.trades, settlement_price) {
+            (0, _) => (previous_close != INVALID_PRICE).then_some(previous_close),
+            (_, SettlementPrice::Close) => Some(self.close),
+            (_, SettlementPrice::Vwap) => self.vwap(),
+        };
+    }
+}
+
+// the value of shares bought and sold and the fees, in price ticks. Net is what the participant receives
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct CashMovements {
+    pub bought_value: u64,
+    pub sold_value: u64,
+    pub fees: Fee,
+}
+
+impl CashMovements {
+    pub fn net(&self) -> i64 {
+        self.sold_value as i64 - self.bought_value as i64 - self.fees
+    }
+
+    fn add(&mut self, side: Side, value: u64, fee: Fee) {
+        match side {
+            Side::Buy => self.bought_value += value,
+            _ => self.sold_value += value,
+        }
+        self.fees += fee;
+    }
+}
+
+// one side of a trade, cash is what it moved for the participant, fee included
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct StatementTrade {
+    pub trade_id: TradeId,
+    pub timestamp: Nanos,
+    pub symbol_id: SymbolId,
+    pub side: Side,
+    pub price: Price,
+    pub qty: u64,
+    pub fee: Fee,
+    pub cash: i64,
+}
+
+// a final position valued at the settlement price of its symbol
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct StatementPosition {
+    pub symbol_id: SymbolId,
+    pub position: i64,
+    pub open_price: f64,
+    pub realized: f64,
+    pub settlement_price: Option<Price>,
+    pub unrealized: f64,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Statement {
+    pub participant_id: ParticipantId,
+    pub trades: Vec<StatementTrade>,
+    pub positions: Vec<StatementPosition>,
+    pub cash: CashMovements,
+}
+
+// what a participant has to deliver or receive in a symbol, shares and cash, once its trades of the day are netted
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Obligation {
+    pub participant_id: ParticipantId,
+    pub symbol_id: SymbolId,
+    pub bought_qty: u64,
+    pub sold_qty: u64,
+    pub cash: CashMovements,
+}
+
+impl Obligation {
+    pub fn net_qty(&self) -> i64 {
+        self.bought_qty as i64 - self.sold_qty as i64
+    }
+}
+
+// the end of day of one session, statements and obligations by participant id then symbol id
+#[derive(Clone, PartialEq, Debug)]
+pub struct EodReport {
+    pub date: (i64, u32, u32),
+    pub symbols: Vec<SymbolSummary>, //indexed by symbol id
+    pub statements: Vec<Statement>,
+    pub obligations: Vec<Obligation>,
+}
+
+impl EodReport {
+    // `trades` are those of the session, busted ones left out, `positions` every position held at the close and `fee`
+    // what a participant was charged for a trade
+    pub fn build(date: (i64, u32, u32), trades: &[&Trade], positions: &[Position], fee: impl Fn(ParticipantId, TradeId) -> Fee, settlement_price: SettlementPrice, previous_closes: &[Price]) -> Self {
+        let mut trades = trades.to_vec();
+        trades.sort_by_key(|trade| (trade.timestamp, trade.trade_id));
+
+        let mut symbols: Vec<SymbolSummary> = (0..previous_closes.len()).map(|symbol_id| SymbolSummary::new(symbol_id as SymbolId)).collect();
+        let mut statements: Vec<Option<Statement>> = Vec::new(); //indexed by participant id
+        let mut obligations: HashMap<(ParticipantId, SymbolId), Obligation> = HashMap::new();
+
+        for trade in &trades {
+            let value = trade.price * trade.qty as u64;
+
+            if let Some(symbol) = symbols.get_mut(trade.symbol_id as usize) {
+                symbol.add(trade);
+            }
+
+            for (participant_id, side) in [(trade.buy_participant_id, Side::Buy), (trade.sell_participant_id, Side::Sell)] {
+                let fee = fee(participant_id, trade.trade_id);
+                let cash = match side {
+                    Side::Buy => -(value as i64),
+                    _ => value as i64,
+                } - fee;
+
+                let statement = statement_mut(&mut statements, participant_id);
+                statement.trades.push(StatementTrade { trade_id: trade.trade_id, timestamp: trade.timestamp, symbol_id: trade.symbol_id, side, price: trade.price, qty: trade.qty as u64, fee, cash });
+                statement.cash.add(side, value, fee);
+
+                let obligation = obligations.entry((participant_id, trade.symbol_id)).or_insert(Obligation { participant_id, symbol_id: trade.symbol_id, bought_qty: 0, sold_qty: 0, cash: CashMovements::default() });
+
+                match side {
+                    Side::Buy => obligation.bought_qty += trade.qty as u64,
+                    _ => obligation.sold_qty += trade.qty as u64,
+                }
+                obligation.cash.add(side, value, fee);
+            }
+        }
+
+        for (symbol, previous_close) in symbols.iter_mut().zip(previous_closes) {
+            symbol.settle(settlement_price, *previous_close);
+        }
+
+        for position in positions {
+            let settlement_price = symbols.get(position.symbol_id as usize).and_then(|symbol| symbol.settlement_price);
+
+            statement_mut(&mut statements, position.participant_id).positions.push(StatementPosition {
+                symbol_id: position.symbol_id,
+                position: position.position,
+                open_price: position.open_price,
+                realized: position.realized,
+                settlement_price,
+                unrealized: settlement_price.map_or(0.0, |price| position.position as f64 * (price as f64 - position.open_price)),
+            });
+        }
+
+        let mut statements: Vec<Statement> = statements.into_iter().flatten().collect();
+        for statement in &mut statements {
+            statement.positions.sort_by_key(|position| position.symbol_id);
+        }
+
+        let mut obligations: Vec<Obligation> = obligations.into_values().collect();
+        obligations.sort_by_key(|obligation| (obligation.participant_id, obligation.symbol_id));
+
+        Self { date, symbols, statements, obligations }
+    }
+}
+
+fn statement_mut(statements: &mut Vec<Option<Statement>>, participant_id: ParticipantId) -> &mut Statement {
+    if statements.len() <= participant_id as usize {
+        statements.resize(participant_id as usize + 1, None);
+    }
+
+    statements[participant_id as usize].get_or_insert_with(|| Statement { participant_id, trades: Vec::new(), positions: Vec::new(), cash: CashMovements::default() })
+}
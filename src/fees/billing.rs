@@ -0,0 +1,99 @@
+use std::{collections::HashMap, fmt, sync::{mpsc::Receiver, Arc, Mutex}};
+
+use crate::common::{Fee, ParticipantId, Sequenced, Side, TradeId};
+use crate::order_server::participants_response::{ParticipantResponse, ParticipantResponseType};
+
+// what a participant was charged since the start, busts and corrections take back the shares and fees of their fills
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct FeeTotals {
+    pub fills: u64,
+    pub maker_qty: i64,
+    pub taker_qty: i64,
+    pub maker_fees: Fee,
+    pub taker_fees: Fee,
+}
+
+impl FeeTotals {
+    // what the participant owes, negative when the rebates are worth more than the fees
+    pub fn net(&self) -> Fee {
+        self.maker_fees + self.taker_fees
+    }
+}
+
+impl fmt::Display for FeeTotals {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "FeeTotals [fills: {}, maker_qty: {}, taker_qty: {}, maker_fees: {}, taker_fees: {}, net: {}]",
+        self.fills,
+        self.maker_qty,
+        self.taker_qty,
+        self.maker_fees,
+        self.taker_fees,
+        self.net())
+    }
+}
+
+#[derive(Default)]
+pub struct Billing {
+    totals: Vec<FeeTotals>, //indexed by participant id
+    trade_fees: HashMap<(ParticipantId, TradeId, Side), Fee>, //what each side was charged for a trade, refunds taken off
+}
+
+impl Billing {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn on_response(&mut self, response: &ParticipantResponse) {
+        let qty = match response.response_type {
+            ParticipantResponseType::Filled => response.exec_qty as i64,
+            ParticipantResponseType::TradeBusted | ParticipantResponseType::TradeCorrected => response.exec_qty as i64 - response.leaves_qty as i64,
+            _ => return,
+        };
+
+        if self.totals.len() <= response.participant_id as usize {
+            self.totals.resize(response.participant_id as usize + 1, FeeTotals::default());
+        }
+
+        let totals = &mut self.totals[response.participant_id as usize];
+
+        if response.response_type == ParticipantResponseType::Filled {
+            totals.fills += 1;
+        }
+
+        *self.trade_fees.entry((response.participant_id, response.trade_id, response.side)).or_insert(0) += response.fee;
+
+        if response.aggressor {
+            totals.taker_qty += qty;
+            totals.taker_fees += response.fee;
+        } else {
+            totals.maker_qty += qty;
+            totals.maker_fees += response.fee;
+        }
+    }
+
+    pub fn totals(&self, participant_id: ParticipantId) -> FeeTotals {
+        self.totals.get(participant_id as usize).copied().unwrap_or_default()
+    }
+
+    // 0 for the trades made before the exchange started, both sides of a trade with itself
+    pub fn trade_fee(&self, participant_id: ParticipantId, trade_id: TradeId) -> Fee {
+        self.side_fee(participant_id, trade_id, Side::Buy) + self.side_fee(participant_id, trade_id, Side::Sell)
+    }
+
+    // what the participant was charged for its side of the trade
+    pub fn side_fee(&self, participant_id: ParticipantId, trade_id: TradeId, side: Side) -> Fee {
+        self.trade_fees.get(&(participant_id, trade_id, side)).copied().unwrap_or(0)
+    }
+
+    // the participants that traded, with their totals
+    pub fn iter(&self) -> impl Iterator<Item = (ParticipantId, &FeeTotals)> {
+        self.totals.iter().enumerate().filter(|(_, totals)| **totals != FeeTotals::default()).map(|(participant_id, totals)| (participant_id as ParticipantId, totals))
+    }
+}
+
+// keeps the totals of every response of every shard, returns once the response router stops
+pub fn run(billing: Arc<Mutex<Billing>>, responses: Receiver<Sequenced<ParticipantResponse>>) {
+    for response in responses.iter() {
+        billing.lock().unwrap().on_response(&response.payload);
+    }
+}
@@ -0,0 +1,133 @@
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+
+use serde::Deserialize;
+
+use crate::common::{civil_date, Fee, Nanos, ParticipantId, Quantity, SymbolId};
+use crate::trades::trade::Trade;
+
+// fees are per share, in price ticks, a negative fee is a rebate. The tiers, by ascending volume, replace them once a
+// participant has traded `min_volume` shares in the month, whatever the symbol
+#[derive(Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FeeConfig {
+    pub maker_fee: Fee,
+    pub taker_fee: Fee,
+    pub tiers: Vec<FeeTier>,
+    pub symbols: Vec<SymbolFeeConfig>,
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FeeTier {
+    pub min_volume: u64,
+    pub maker_fee: Fee,
+    pub taker_fee: Fee,
+}
+
+// replaces the whole schedule, tiers included, for one symbol
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SymbolFeeConfig {
+    pub symbol: SymbolId,
+    pub maker_fee: Fee,
+    pub taker_fee: Fee,
+    #[serde(default)]
+    pub tiers: Vec<FeeTier>,
+}
+
+// shared by every shard, which all count into the same monthly volumes. The volumes start again with the first fill
+// of a month, fills of other shards landing while they are reset may count in either month
+pub struct FeeSchedule {
+    tiers: Vec<FeeTier>, //the base fees at volume 0 then the tiers, by ascending volume
+    symbol_tiers: Vec<Option<Vec<FeeTier>>>, //indexed by symbol id, None for symbols without their own schedule
+    volumes: Vec<AtomicU64>, //shares traded this month, indexed by participant id
+    month: AtomicU32, //months since year 0 of the volumes
+}
+
+impl FeeSchedule {
+    pub fn new(config: &FeeConfig, max_participants: usize) -> Self {
+        let tiers = |maker_fee, taker_fee, tiers: &[FeeTier]| [&[FeeTier { min_volume: 0, maker_fee, taker_fee }], tiers].concat();
+
+        let mut symbol_tiers = Vec::new();
+
+        for symbol in &config.symbols {
+            if symbol_tiers.len() <= symbol.symbol as usize {
+                symbol_tiers.resize(symbol.symbol as usize + 1, None);
+            }
+            symbol_tiers[symbol.symbol as usize] = Some(tiers(symbol.maker_fee, symbol.taker_fee, &symbol.tiers));
+        }
+
+        Self {
+            tiers: tiers(config.maker_fee, config.taker_fee, &config.tiers),
+            symbol_tiers,
+            volumes: (0..max_participants).map(|_| AtomicU64::new(0)).collect(),
+            month: AtomicU32::new(0),
+        }
+    }
+
+    // the tier a participant with this monthly volume trades the symbol at
+    pub fn tier(&self, symbol_id: SymbolId, volume: u64) -> FeeTier {
+        let tiers = self.symbol_tiers.get(symbol_id as usize).and_then(Option::as_ref).unwrap_or(&self.tiers);
+        *tiers.iter().rev().find(|tier| tier.min_volume <= volume).unwrap_or(&tiers[0])
+    }
+
+    pub fn volume(&self, participant_id: ParticipantId) -> u64 {
+        self.volumes.get(participant_id as usize).map_or(0, |volume| volume.load(Ordering::Relaxed))
+    }
+
+    // the fee of a fill, at the tier of the volume traded before it, which the fill then adds to
+    pub fn charge(&self, participant_id: ParticipantId, symbol_id: SymbolId, is_taker: bool, qty: Quantity, time: Nanos) -> Fee {
+        self.start_month(time);
+
+        let volume = match self.volumes.get(participant_id as usize) {
+            Some(volume) => volume,
+            None => return 0,
+        };
+
+        let tier = self.tier(symbol_id, volume.fetch_add(qty as u64, Ordering::Relaxed));
+        qty as Fee * if is_taker { tier.taker_fee } else { tier.maker_fee }
+    }
+
+    // a bust or correction takes shares off the volume of the month the trade was made in, or adds them. The volumes of
+    // a past month are gone, a trade made then changes nothing
+    pub fn amend(&self, participant_id: ParticipantId, qty: i64, trade_time: Nanos, time: Nanos) {
+        self.start_month(time);
+
+        if month_of(trade_time) != self.month.load(Ordering::Relaxed) {
+            return;
+        }
+
+        if let Some(volume) = self.volumes.get(participant_id as usize) {
+            let _ = volume.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |volume| Some(volume.saturating_add_signed(qty)));
+        }
+    }
+
+    // counts the trades of the month, from the trade store, after a restart
+    pub fn restore<'a>(&self, trades: impl IntoIterator<Item = &'a Trade>, now: Nanos) {
+        self.start_month(now);
+        let month = self.month.load(Ordering::Relaxed);
+
+        for trade in trades.into_iter().filter(|trade| month_of(trade.timestamp) == month) {
+            for participant_id in [trade.buy_participant_id, trade.sell_participant_id] {
+                if let Some(volume) = self.volumes.get(participant_id as usize) {
+                    volume.fetch_add(trade.qty as u64, Ordering::Relaxed);
+                }
+            }
+        }
+    }
+
+    fn start_month(&self, time: Nanos) {
+        let month = month_of(time);
+
+        if self.month.load(Ordering::Relaxed) != month && self.month.swap(month, Ordering::Relaxed) != month {
+            for volume in &self.volumes {
+                volume.store(0, Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+fn month_of(time: Nanos) -> u32 {
+    let (year, month, _) = civil_date(time);
+    year as u32 * 12 + month
+}
@@ -0,0 +1,82 @@
+use std::{sync::OnceLock, thread, time::{Duration, Instant}};
+
+use crate::common::Nanos;
+
+const CALIBRATION_PERIOD: Duration = Duration::from_millis(10);
+
+struct Calibration {
+    origin: Instant,
+    origin_ticks: u64,
+    nanos_per_tick: f64,
+    use_tsc: bool,
+}
+
+static CALIBRATION: OnceLock<Calibration> = OnceLock::new();
+
+// calibrating takes a few milliseconds, call it at startup rather than on the first timestamp
+pub fn init() {
+    CALIBRATION.get_or_init(calibrate);
+}
+
+// monotonic nanoseconds since the clock was initialised, read from the TSC when it is invariant
+#[inline]
+pub fn now() -> Nanos {
+    let calibration = CALIBRATION.get_or_init(calibrate);
+
+    if calibration.use_tsc {
+        (read_tsc().wrapping_sub(calibration.origin_ticks) as f64 * calibration.nanos_per_tick) as Nanos
+    } else {
+        calibration.origin.elapsed().as_nanos() as Nanos
+    }
+}
+
+pub fn is_tsc() -> bool {
+    CALIBRATION.get_or_init(calibrate).use_tsc
+}
+
+fn calibrate() -> Calibration {
+    let origin = Instant::now();
+    let origin_ticks = read_tsc();
+
+    if !has_invariant_tsc() {
+        return Calibration { origin, origin_ticks, nanos_per_tick: 1.0, use_tsc: false };
+    }
+
+    thread::sleep(CALIBRATION_PERIOD);
+
+    let elapsed_nanos = origin.elapsed().as_nanos() as f64;
+    let elapsed_ticks = read_tsc().wrapping_sub(origin_ticks) as f64;
+
+    Calibration {
+        origin,
+        origin_ticks,
+        nanos_per_tick: elapsed_nanos / elapsed_ticks,
+        use_tsc: elapsed_ticks > 0.0,
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+#[inline]
+fn read_tsc() -> u64 {
+    unsafe { std::arch::x86_64::_rdtsc() }
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+#[inline]
+fn read_tsc() -> u64 {
+    0
+}
+
+// an invariant TSC ticks at a constant rate across frequency changes and sleep states
+#[cfg(target_arch = "x86_64")]
+fn has_invariant_tsc() -> bool {
+    use std::arch::x86_64::__cpuid;
+
+    let max_extended_leaf = __cpuid(0x8000_0000).eax;
+    max_extended_leaf >= 0x8000_0007 && __cpuid(0x8000_0007).edx & (1 << 8) != 0
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+fn has_invariant_tsc() -> bool {
+    false
+}
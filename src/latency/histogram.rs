@@ -0,0 +1,122 @@
+use std::cmp;
+
+// log-linear buckets as in HDR histograms: values below 2^SUB_BUCKET_BITS are exact, above that every
+// power of two is split into 2^SUB_BUCKET_BITS buckets, which keeps the error below 1%
+const SUB_BUCKET_BITS: u32 = 7;
+const SUB_BUCKETS: usize = 1 << SUB_BUCKET_BITS;
+const BUCKETS: usize = SUB_BUCKETS + (64 - SUB_BUCKET_BITS as usize) * SUB_BUCKETS;
+
+pub struct Histogram {
+    counts: Box<[u64]>,
+    count: u64,
+    sum: u128,
+    min: u64,
+    max: u64,
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Histogram {
+    pub fn new() -> Self {
+        Self {
+            counts: vec![0; BUCKETS].into_boxed_slice(),
+            count: 0,
+            sum: 0,
+            min: u64::MAX,
+            max: 0,
+        }
+    }
+
+    #[inline]
+    pub fn record(&mut self, value: u64) {
+        self.counts[bucket_index(value)] += 1;
+        self.count += 1;
+        self.sum += value as u128;
+        self.min = cmp::min(self.min, value);
+        self.max = cmp::max(self.max, value);
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn min(&self) -> u64 {
+        if self.count == 0 { 0 } else { self.min }
+    }
+
+    pub fn max(&self) -> u64 {
+        self.max
+    }
+
+    pub fn mean(&self) -> u64 {
+        if self.count == 0 { 0 } else { (self.sum / self.count as u128) as u64 }
+    }
+
+    // highest value of the bucket holding the percentile, capped by the largest recorded value
+    pub fn value_at_percentile(&self, percentile: f64) -> u64 {
+        if self.count == 0 {
+            return 0;
+        }
+
+        let rank = cmp::max(1, (percentile / 100.0 * self.count as f64).ceil() as u64);
+        let mut seen = 0;
+
+        for (index, count) in self.counts.iter().enumerate() {
+            seen += count;
+
+            if seen >= rank {
+                return cmp::min(bucket_highest_value(index), self.max);
+            }
+        }
+
+        self.max
+    }
+
+    pub fn add(&mut self, other: &Histogram) {
+        for (count, other_count) in self.counts.iter_mut().zip(other.counts.iter()) {
+            *count += other_count;
+        }
+
+        self.count += other.count;
+        self.sum += other.sum;
+        self.min = cmp::min(self.min, other.min);
+        self.max = cmp::max(self.max, other.max);
+    }
+
+    pub fn reset(&mut self) {
+        self.counts.fill(0);
+        self.count = 0;
+        self.sum = 0;
+        self.min = u64::MAX;
+        self.max = 0;
+    }
+}
+
+#[inline]
+fn bucket_index(value: u64) -> usize {
+    if value < SUB_BUCKETS as u64 {
+        return value as usize;
+    }
+
+    let exponent = 63 - value.leading_zeros();
+    let shift = exponent - SUB_BUCKET_BITS;
+    let sub_bucket = (value >> shift) as usize - SUB_BUCKETS;
+
+    SUB_BUCKETS + shift as usize * SUB_BUCKETS + sub_bucket
+}
+
+fn bucket_highest_value(index: usize) -> u64 {
+    if index < SUB_BUCKETS {
+        return index as u64;
+    }
+
+    let shift = (index - SUB_BUCKETS) / SUB_BUCKETS;
+    let sub_bucket = (index - SUB_BUCKETS) % SUB_BUCKETS;
+    let lowest_value = ((SUB_BUCKETS + sub_bucket) as u64) << shift;
+
+    lowest_value + ((1u64 << shift) - 1)
+}
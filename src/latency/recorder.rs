@@ -0,0 +1,170 @@
+use std::fmt;
+
+use serde::Deserialize;
+
+use crate::common::{Nanos, ShardId, NANOS_TO_SECS};
+use crate::logging::logger::{LogPayload, Logger};
+
+use super::{clock, histogram::Histogram};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[repr(u8)]
+pub enum Stage {
+    GatewayToEngine = 0,   // gateway receive to engine dequeue
+    OrderBookAdd,          // OrderBook::add entry to exit
+    OrderBookCancel,       // OrderBook::cancel entry to exit
+    Response,              // engine dequeue to response enqueue
+    MarketData,            // engine dequeue to market data publish
+    TickToTrade,           // gateway receive to trade publish
+}
+
+const STAGES: [Stage; 6] = [Stage::GatewayToEngine, Stage::OrderBookAdd, Stage::OrderBookCancel, Stage::Response, Stage::MarketData, Stage::TickToTrade];
+
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Stage::GatewayToEngine => write!(f, "GATEWAY-TO-ENGINE"),
+            Stage::OrderBookAdd => write!(f, "ORDERBOOK-ADD"),
+            Stage::OrderBookCancel => write!(f, "ORDERBOOK-CANCEL"),
+            Stage::Response => write!(f, "RESPONSE"),
+            Stage::MarketData => write!(f, "MARKET-DATA"),
+            Stage::TickToTrade => write!(f, "TICK-TO-TRADE"),
+        }
+    }
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LatencyConfig {
+    pub enabled: bool,
+    #[serde(rename = "report_interval_ns")]
+    pub report_interval: Nanos,
+}
+
+impl Default for LatencyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            report_interval: 10 * NANOS_TO_SECS,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct LatencySummary {
+    pub shard_id: ShardId,
+    pub stage: Stage,
+    pub count: u64,
+    pub p50: Nanos,
+    pub p99: Nanos,
+    pub p999: Nanos,
+    pub max: Nanos,
+}
+
+impl fmt::Display for LatencySummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "LatencySummary [shard: {}, stage: {}, count: {}, p50: {}ns, p99: {}ns, p99.9: {}ns, max: {}ns]",
+        self.shard_id, self.stage, self.count, self.p50, self.p99, self.p999, self.max)
+    }
+}
+
+impl LatencySummary {
+    pub fn new(shard_id: ShardId, stage: Stage, histogram: &Histogram) -> Self {
+        Self {
+            shard_id,
+            stage,
+            count: histogram.count(),
+            p50: histogram.value_at_percentile(50.0),
+            p99: histogram.value_at_percentile(99.0),
+            p999: histogram.value_at_percentile(99.9),
+            max: histogram.max(),
+        }
+    }
+}
+
+// per-stage histograms owned by the thread taking the timestamps, a 0 timestamp means the hop wasn't stamped
+pub struct LatencyRecorder {
+    shard_id: ShardId,
+    enabled: bool,
+    report_interval: Nanos,
+    next_report_time: Nanos,
+    interval: Vec<Histogram>,
+    total: Vec<Histogram>,
+}
+
+impl LatencyRecorder {
+    pub fn new(config: &LatencyConfig, shard_id: ShardId) -> Self {
+        if config.enabled {
+            clock::init();
+        }
+
+        Self {
+            shard_id,
+            enabled: config.enabled,
+            report_interval: config.report_interval,
+            next_report_time: 0,
+            interval: STAGES.iter().map(|_| Histogram::new()).collect(),
+            total: STAGES.iter().map(|_| Histogram::new()).collect(),
+        }
+    }
+
+    #[inline]
+    pub fn now(&self) -> Nanos {
+        if self.enabled { clock::now() } else { 0 }
+    }
+
+    #[inline]
+    pub fn record(&mut self, stage: Stage, start: Nanos, end: Nanos) {
+        if self.enabled && start != 0 {
+            self.interval[stage as usize].record(end.saturating_sub(start));
+        }
+    }
+
+    pub fn summary(&self, stage: Stage) -> LatencySummary {
+        let mut histogram = Histogram::new();
+        histogram.add(&self.total[stage as usize]);
+        histogram.add(&self.interval[stage as usize]);
+
+        LatencySummary::new(self.shard_id, stage, &histogram)
+    }
+
+    // `now` is wall clock time, summaries cover what was recorded since the previous report
+    pub fn report(&mut self, now: Nanos, logger: &Logger) {
+        if !self.enabled || self.report_interval == 0 || now < self.next_report_time {
+            return;
+        }
+
+        if self.next_report_time != 0 {
+            self.flush_interval("latency", logger);
+        }
+
+        self.next_report_time = now.saturating_add(self.report_interval);
+    }
+
+    // summaries over the whole run
+    pub fn report_totals(&mut self, logger: &Logger) {
+        if !self.enabled {
+            return;
+        }
+
+        self.flush_interval("latency interval", logger);
+
+        for stage in STAGES {
+            logger.info("latency total", LogPayload::Latency(LatencySummary::new(self.shard_id, stage, &self.total[stage as usize])));
+        }
+    }
+
+    fn flush_interval(&mut self, message: &'static str, logger: &Logger) {
+        for stage in STAGES {
+            let interval = &mut self.interval[stage as usize];
+
+            if interval.count() == 0 {
+                continue;
+            }
+
+            logger.info(message, LogPayload::Latency(LatencySummary::new(self.shard_id, stage, interval)));
+            self.total[stage as usize].add(interval);
+            interval.reset();
+        }
+    }
+}
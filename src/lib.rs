@@ -0,0 +1,16 @@
+pub mod admin;
+pub mod backtest;
+pub mod client;
+pub mod common;
+pub mod config;
+pub mod eod;
+pub mod fees;
+pub mod latency;
+pub mod logging;
+pub mod order_server;
+pub mod market_data;
+pub mod matching_engine;
+pub mod pnl;
+pub mod positions;
+pub mod trades;
+pub mod wire;
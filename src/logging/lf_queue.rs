@@ -0,0 +1,107 @@
+use std::{cell::UnsafeCell, mem::MaybeUninit, ops::Deref, sync::atomic::{AtomicUsize, Ordering}};
+
+// keeps the producer and consumer cursors on separate cache lines
+#[repr(align(64))]
+struct CachePadded<T>(T);
+
+impl<T> Deref for CachePadded<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+struct Slot<T> {
+    sequence: AtomicUsize,
+    value: UnsafeCell<MaybeUninit<T>>,
+}
+
+// bounded multi-producer multi-consumer queue, each slot carries a sequence number telling
+// whether it is ready to be written (sequence == position) or read (sequence == position + 1)
+pub struct LFQueue<T> {
+    slots: Box<[Slot<T>]>,
+    mask: usize,
+    enqueue_pos: CachePadded<AtomicUsize>,
+    dequeue_pos: CachePadded<AtomicUsize>,
+}
+
+unsafe impl<T: Send> Send for LFQueue<T> {}
+unsafe impl<T: Send> Sync for LFQueue<T> {}
+
+impl<T> LFQueue<T> {
+    // the capacity is rounded up to a power of two
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(2).next_power_of_two();
+
+        Self {
+            slots: (0..capacity).map(|position| Slot {
+                sequence: AtomicUsize::new(position),
+                value: UnsafeCell::new(MaybeUninit::uninit()),
+            }).collect(),
+            mask: capacity - 1,
+            enqueue_pos: CachePadded(AtomicUsize::new(0)),
+            dequeue_pos: CachePadded(AtomicUsize::new(0)),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    // hands the value back when the queue is full
+    pub fn push(&self, value: T) -> Result<(), T> {
+        let mut position = self.enqueue_pos.load(Ordering::Relaxed);
+
+        loop {
+            let slot = &self.slots[position & self.mask];
+            let sequence = slot.sequence.load(Ordering::Acquire);
+
+            if sequence == position {
+                match self.enqueue_pos.compare_exchange_weak(position, position.wrapping_add(1), Ordering::Relaxed, Ordering::Relaxed) {
+                    Ok(_) => {
+                        unsafe { (*slot.value.get()).write(value) };
+                        slot.sequence.store(position.wrapping_add(1), Ordering::Release);
+                        return Ok(());
+                    },
+                    Err(current) => position = current,
+                }
+            } else if (sequence.wrapping_sub(position) as isize) < 0 {
+                return Err(value);
+            } else {
+                position = self.enqueue_pos.load(Ordering::Relaxed);
+            }
+        }
+    }
+
+    pub fn pop(&self) -> Option<T> {
+        let mut position = self.dequeue_pos.load(Ordering::Relaxed);
+
+        loop {
+            let slot = &self.slots[position & self.mask];
+            let sequence = slot.sequence.load(Ordering::Acquire);
+            let expected = position.wrapping_add(1);
+
+            if sequence == expected {
+                match self.dequeue_pos.compare_exchange_weak(position, expected, Ordering::Relaxed, Ordering::Relaxed) {
+                    Ok(_) => {
+                        let value = unsafe { (*slot.value.get()).assume_init_read() };
+                        slot.sequence.store(position.wrapping_add(self.mask + 1), Ordering::Release);
+                        return Some(value);
+                    },
+                    Err(current) => position = current,
+                }
+            } else if (sequence.wrapping_sub(expected) as isize) < 0 {
+                return None;
+            } else {
+                position = self.dequeue_pos.load(Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+impl<T> Drop for LFQueue<T> {
+    fn drop(&mut self) {
+        while self.pop().is_some() {}
+    }
+}
@@ -0,0 +1,271 @@
+use std::{fmt::{self, Write}, hint, path::PathBuf, sync::{atomic::{self, AtomicU64, Ordering}, Arc}, thread, time::Duration};
+
+use serde::Deserialize;
+
+use crate::common::{get_current_nanos, spawn_pinned, Nanos, ParticipantId, Price, Quantity, Side, SymbolId, NANOS_TO_SECS};
+use crate::latency::recorder::LatencySummary;
+use crate::market_data::market_update::MarketUpdate;
+use crate::matching_engine::order::{NewOrder, Order};
+use crate::order_server::participants_request::ParticipantRequest;
+use crate::order_server::participants_response::ParticipantResponse;
+use crate::trades::trade::Trade;
+
+use super::lf_queue::LFQueue;
+use super::rotating_file::RotatingFile;
+
+const IDLE_SLEEP: Duration = Duration::from_millis(1);
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LogLevel {
+    Debug,
+    Info,
+    Warn,
+    Error,
+    Off,
+}
+
+impl fmt::Display for LogLevel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // padded, the level column of the log lines is aligned
+        f.pad(match self {
+            LogLevel::Debug => "DEBUG",
+            LogLevel::Info => "INFO",
+            LogLevel::Warn => "WARN",
+            LogLevel::Error => "ERROR",
+            LogLevel::Off => "OFF",
+        })
+    }
+}
+
+// what a thread logging into a full queue does
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OverflowPolicy {
+    Drop,
+    Block,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoggerConfig {
+    pub directory: PathBuf,
+    pub file_name: String,
+    pub level: LogLevel,
+    pub overflow_policy: OverflowPolicy,
+    pub queue_capacity: usize,
+    pub max_file_size: u64,
+    pub max_files: usize,
+}
+
+impl Default for LoggerConfig {
+    fn default() -> Self {
+        Self {
+            directory: PathBuf::from("logs"),
+            file_name: "rexchange".to_string(),
+            level: LogLevel::Info,
+            overflow_policy: OverflowPolicy::Drop,
+            queue_capacity: 64 * 1024,
+            max_file_size: 64 * 1024 * 1024,
+            max_files: 8,
+        }
+    }
+}
+
+// a client's own fill and the position it leaves, PnL in price ticks. Only made of plain values, the logger doesn't
+// depend on the clients logging it
+#[derive(Clone, Copy)]
+pub struct OwnFill {
+    pub participant_id: ParticipantId,
+    pub symbol_id: SymbolId,
+    pub side: Side,
+    pub price: Price,
+    pub qty: Quantity,
+    pub position: i64,
+    pub open_price: f64,
+    pub realized: f64,
+    pub unrealized: f64,
+}
+
+impl fmt::Display for OwnFill {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "OwnFill [ptid: {}, symb: {}, side: {}, price: {}, qty: {}, position: {}, open_price: {:.2}, realized: {:.2}, unrealized: {:.2}]",
+        self.participant_id, self.symbol_id, self.side, self.price, self.qty, self.position, self.open_price, self.realized, self.unrealized)
+    }
+}
+
+// the raw arguments of a record, only turned into text by the logger thread. The types of the layers above the logger
+// come as plain values, or as text formatted by their callers when off the hot path
+pub enum LogPayload {
+    None,
+    Order(Order),
+    NewOrder(NewOrder),
+    ParticipantRequest(ParticipantRequest),
+    ParticipantResponse(ParticipantResponse),
+    MarketUpdate(MarketUpdate),
+    Trade(Trade),
+    Latency(LatencySummary),
+    OwnFill(OwnFill),
+    Text(String),
+}
+
+impl fmt::Display for LogPayload {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LogPayload::None => Ok(()),
+            LogPayload::Order(order) => write!(f, " {}", order),
+            LogPayload::NewOrder(new_order) => write!(f, " {}", new_order),
+            LogPayload::ParticipantRequest(request) => write!(f, " {}", request),
+            LogPayload::ParticipantResponse(response) => write!(f, " {}", response),
+            LogPayload::MarketUpdate(update) => write!(f, " {}", update),
+            LogPayload::Trade(trade) => write!(f, " {}", trade),
+            LogPayload::Latency(summary) => write!(f, " {}", summary),
+            LogPayload::OwnFill(fill) => write!(f, " {}", fill),
+            LogPayload::Text(text) => write!(f, " {}", text),
+        }
+    }
+}
+
+pub struct LogRecord {
+    pub time: Nanos,
+    pub level: LogLevel,
+    pub message: &'static str,
+    pub payload: LogPayload,
+}
+
+struct Shared {
+    queue: LFQueue<LogRecord>,
+    level: LogLevel,
+    overflow_policy: OverflowPolicy,
+    dropped: AtomicU64,
+}
+
+// cheap to clone handle, the logger thread exits once every handle is dropped and the queue is drained
+#[derive(Clone)]
+pub struct Logger {
+    shared: Arc<Shared>,
+}
+
+impl Logger {
+    pub fn spawn(config: &LoggerConfig, core_id: isize) -> std::io::Result<(Self, thread::JoinHandle<()>)> {
+        let file = RotatingFile::open(&config.directory, &config.file_name, config.max_file_size, config.max_files)?;
+        let logger = Self::with_queue(config.level, config.overflow_policy, config.queue_capacity);
+        let shared = logger.shared.clone();
+
+        let handle = spawn_pinned(move || run(shared, file), core_id);
+
+        Ok((logger, handle))
+    }
+
+    // a logger without thread nor file, every record is filtered out
+    pub fn disabled() -> Self {
+        Self::with_queue(LogLevel::Off, OverflowPolicy::Drop, 2)
+    }
+
+    fn with_queue(level: LogLevel, overflow_policy: OverflowPolicy, queue_capacity: usize) -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                queue: LFQueue::new(queue_capacity),
+                level,
+                overflow_policy,
+                dropped: AtomicU64::new(0),
+            }),
+        }
+    }
+
+    #[inline]
+    pub fn is_enabled(&self, level: LogLevel) -> bool {
+        level >= self.shared.level && level != LogLevel::Off
+    }
+
+    pub fn dropped(&self) -> u64 {
+        self.shared.dropped.load(Ordering::Relaxed)
+    }
+
+    pub fn log(&self, level: LogLevel, message: &'static str, payload: LogPayload) {
+        if !self.is_enabled(level) {
+            return;
+        }
+
+        let mut record = LogRecord { time: get_current_nanos(), level, message, payload };
+
+        loop {
+            match self.shared.queue.push(record) {
+                Ok(()) => return,
+                Err(rejected) => match self.shared.overflow_policy {
+                    OverflowPolicy::Drop => {
+                        self.shared.dropped.fetch_add(1, Ordering::Relaxed);
+                        return;
+                    },
+                    OverflowPolicy::Block => {
+                        record = rejected;
+                        hint::spin_loop();
+                    },
+                },
+            }
+        }
+    }
+
+    pub fn debug(&self, message: &'static str, payload: LogPayload) {
+        self.log(LogLevel::Debug, message, payload);
+    }
+
+    pub fn info(&self, message: &'static str, payload: LogPayload) {
+        self.log(LogLevel::Info, message, payload);
+    }
+
+    pub fn warn(&self, message: &'static str, payload: LogPayload) {
+        self.log(LogLevel::Warn, message, payload);
+    }
+
+    pub fn error(&self, message: &'static str, payload: LogPayload) {
+        self.log(LogLevel::Error, message, payload);
+    }
+}
+
+fn run(shared: Arc<Shared>, mut file: RotatingFile) {
+    let mut line = String::new();
+    let mut reported_dropped = 0;
+
+    loop {
+        let is_last_handle = Arc::strong_count(&shared) == 1;
+        atomic::fence(Ordering::Acquire);
+
+        while let Some(record) = shared.queue.pop() {
+            line.clear();
+            let _ = writeln!(line, "{}.{:09} {:<5} {}{}",
+                record.time / NANOS_TO_SECS,
+                record.time % NANOS_TO_SECS,
+                record.level,
+                record.message,
+                record.payload);
+
+            write(&mut file, &line);
+        }
+
+        let dropped = shared.dropped.load(Ordering::Relaxed);
+
+        if dropped != reported_dropped {
+            line.clear();
+            let _ = writeln!(line, "logger dropped {} records, queue capacity is {}", dropped - reported_dropped, shared.queue.capacity());
+            write(&mut file, &line);
+            reported_dropped = dropped;
+        }
+
+        if let Err(error) = file.flush() {
+            eprintln!("logger: cannot flush log file: {}", error);
+        }
+
+        if is_last_handle {
+            break;
+        }
+
+        thread::sleep(IDLE_SLEEP);
+    }
+}
+
+fn write(file: &mut RotatingFile, line: &str) {
+    if let Err(error) = file.write_line(line.as_bytes()) {
+        eprintln!("logger: cannot write log file: {}", error);
+    }
+}
@@ -0,0 +1,3 @@
+pub mod lf_queue;
+pub mod logger;
+pub mod rotating_file;
@@ -0,0 +1,70 @@
+use std::{fs::{self, File, OpenOptions}, io::{self, BufWriter, Write}, path::{Path, PathBuf}};
+
+// `<name>.log` is the file being written, older files are shifted to `<name>.1.log`, `<name>.2.log`...
+// and the oldest one is overwritten once `max_files` are on disk
+pub struct RotatingFile {
+    directory: PathBuf,
+    file_name: String,
+    max_file_size: u64,
+    max_files: usize,
+    writer: BufWriter<File>,
+    size: u64,
+}
+
+impl RotatingFile {
+    pub fn open(directory: &Path, file_name: &str, max_file_size: u64, max_files: usize) -> io::Result<Self> {
+        fs::create_dir_all(directory)?;
+
+        let path = file_path(directory, file_name, 0);
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let size = file.metadata()?.len();
+
+        Ok(Self {
+            directory: directory.to_path_buf(),
+            file_name: file_name.to_string(),
+            max_file_size,
+            max_files,
+            writer: BufWriter::new(file),
+            size,
+        })
+    }
+
+    pub fn write_line(&mut self, line: &[u8]) -> io::Result<()> {
+        if self.size > 0 && self.size + line.len() as u64 > self.max_file_size {
+            self.rotate()?;
+        }
+
+        self.writer.write_all(line)?;
+        self.size += line.len() as u64;
+
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.writer.flush()?;
+
+        for index in (1..self.max_files).rev() {
+            match fs::rename(file_path(&self.directory, &self.file_name, index - 1), file_path(&self.directory, &self.file_name, index)) {
+                Err(error) if error.kind() != io::ErrorKind::NotFound => return Err(error),
+                _ => {},
+            }
+        }
+
+        let file = File::create(file_path(&self.directory, &self.file_name, 0))?;
+        self.writer = BufWriter::new(file);
+        self.size = 0;
+
+        Ok(())
+    }
+}
+
+fn file_path(directory: &Path, file_name: &str, index: usize) -> PathBuf {
+    match index {
+        0 => directory.join(format!("{}.log", file_name)),
+        _ => directory.join(format!("{}.{}.log", file_name, index)),
+    }
+}
@@ -1,25 +1,249 @@
-use crate::{market_data::market_update, order_server::{participants_request, participants_response}};
+use std::{env, path::PathBuf, process, sync::{mpsc, Arc, Mutex}, thread};
 
-mod common;
-mod order_server;
-mod market_data;
-mod matching_engine;
+use rexchange::{admin::{admin::Admin, admin_server, audit::AuditLog}, client::{liquidity_taker::LiquidityTaker, market_maker::MarketMaker, order_flow::OrderFlow, strategy}, config::{Config, DEFAULT_CONFIG_PATH}, common, eod::end_of_day::{self, EndOfDay}, fees::{billing::{self, Billing}, fee_schedule::FeeSchedule}, latency::clock, logging::logger::{LogPayload, Logger}, market_data::publisher::MarketDataPublisher, matching_engine::matching_engine::MatchingEngine, positions::{self, PositionKeeper}, order_server::{drop_copy, ouch_gateway, response_router::ResponseRouter, shard_router::ShardRouter, tcp_gateway}, trades::trade_store::{self, TradeQuery, TradeStore}};
 
 fn main() {
-    let e = common::INVALID_ORDER_ID;
+    let config_path = env::args().nth(1).map(PathBuf::from).unwrap_or_else(|| PathBuf::from(DEFAULT_CONFIG_PATH));
 
-    let c = common::Side::Buy;
-    let d = common::Side::Sell;
-    let e = common::Side::Invalid;
+    let config = match Config::load(&config_path) {
+        Ok(config) => config,
+        Err(error) => {
+            eprintln!("rexchange: {}", error);
+            process::exit(1);
+        }
+    };
 
+    let (logger, logger_thread) = match Logger::spawn(&config.logging, config.cores.logger) {
+        Ok(logger) => logger,
+        Err(error) => {
+            eprintln!("rexchange: cannot open log file in {}: {}", config.logging.directory.display(), error);
+            process::exit(1);
+        }
+    };
 
-    let req = participants_request::ParticipantRequest::default();
-    let res = participants_response::ParticipantResponse::default();
-    let market_update = market_update::MarketUpdate::default();
+    logger.info("starting", LogPayload::None);
 
-    
+    if config.latency.enabled {
+        clock::init();
+        logger.info(if clock::is_tsc() { "latency clock is the invariant TSC" } else { "latency clock is the monotonic OS clock" }, LogPayload::None);
+    }
 
-    println!("{}", req);
-    println!("{}", res);
-    println!("{}", market_update);
+    // every shard publishes into the same two streams, each message carries its shard's sequence number
+    let (participants_response_tx, participants_response_rx) = mpsc::sync_channel(config.limits.participant_responses_capacity);
+    let (market_updates_tx, market_updates_rx) = mpsc::sync_channel(config.limits.market_updates_capacity);
+
+    // the engines only capture their trades when there is a store to keep them
+    let trade_store = if config.trade_store.path.as_os_str().is_empty() {
+        None
+    } else {
+        match TradeStore::open(&config.trade_store.path) {
+            Ok(trade_store) => Some(Arc::new(Mutex::new(trade_store))),
+            Err(error) => {
+                eprintln!("rexchange: cannot open trade store {}: {}", config.trade_store.path.display(), error);
+                process::exit(1);
+            }
+        }
+    };
+
+    // the monthly volumes the tiers go by start again from the stored trades of the month
+    let fee_schedule = Arc::new(FeeSchedule::new(&config.fees, config.limits.max_participants));
+
+    if let Some(trade_store) = &trade_store {
+        let trade_store = trade_store.lock().unwrap();
+        fee_schedule.restore(trade_store.query(&TradeQuery::default()), common::get_current_nanos());
+    }
+
+    let billing = Arc::new(Mutex::new(Billing::new()));
+    let position_keeper = Arc::new(Mutex::new(PositionKeeper::new(&config.positions)));
+
+    let (trades_tx, trades_rx) = mpsc::sync_channel(config.limits.market_updates_capacity);
+    let trades_tx = trade_store.as_ref().map(|_| trades_tx);
+
+    // busts and corrections reach the engine owning the symbol through a queue of their own
+    let is_admin_enabled = !config.admin.address.is_empty();
+    let mut shard_amendments = Vec::new();
+
+    let mut shard_requests = Vec::new();
+    let mut matching_engines = Vec::new();
+
+    for engine_config in config.engine_configs() {
+        let (participants_requests_tx, participants_requests_rx) = mpsc::sync_channel(config.limits.participant_requests_capacity);
+        let participants_response_tx = participants_response_tx.clone();
+        let market_updates_tx = market_updates_tx.clone();
+        let trades_tx = trades_tx.clone();
+        let fee_schedule = fee_schedule.clone();
+        let amendments_rx = is_admin_enabled.then(|| {
+            let (amendments_tx, amendments_rx) = mpsc::sync_channel(config.limits.participant_requests_capacity);
+            shard_amendments.push(amendments_tx);
+            amendments_rx
+        });
+        let engine_logger = logger.clone();
+        let core_id = config.engine_core(engine_config.shard_id);
+        let last_trade_id = trade_store.as_ref().and_then(|trade_store| trade_store.lock().unwrap().last_trade_id(engine_config.shard_id));
+
+        // the order pools aren't Send, the engine has to be built on its own thread
+        matching_engines.push(common::spawn_pinned(move || {
+            let mut engine = MatchingEngine::new(participants_requests_rx, participants_response_tx, market_updates_tx, &engine_config, engine_logger);
+            engine.set_fee_schedule(fee_schedule);
+            if let Some(last_trade_id) = last_trade_id {
+                engine.set_last_trade_id(last_trade_id);
+            }
+            if let Some(trades_tx) = trades_tx {
+                engine.set_trade_sink(trades_tx);
+            }
+            if let Some(amendments_rx) = amendments_rx {
+                engine.set_amendment_source(amendments_rx);
+            }
+            engine.run();
+        }, core_id));
+
+        shard_requests.push(participants_requests_tx);
+    }
+
+    drop(participants_response_tx);
+    drop(market_updates_tx);
+    drop(trades_tx);
+
+    // the end of day needs the trade store, checked by the configuration
+    let end_of_day = (!config.eod.directory.as_os_str().is_empty()).then(|| {
+        let previous_closes = config.order_book_configs().iter().map(|order_book| order_book.circuit_breaker.previous_close).collect();
+        EndOfDay::new(&config.eod, trade_store.clone().unwrap(), billing.clone(), position_keeper.clone(), previous_closes)
+    });
+
+    if let (Some(end_of_day), Some(close_time)) = (&end_of_day, config.eod.close_time()) {
+        end_of_day::schedule(end_of_day.clone(), close_time, logger.clone());
+    }
+
+    // the admin console is only allowed with a trade store, checked by the configuration
+    if is_admin_enabled {
+        let audit = match AuditLog::open(&config.admin.audit_path) {
+            Ok(audit) => audit,
+            Err(error) => {
+                eprintln!("rexchange: cannot open audit log {}: {}", config.admin.audit_path.display(), error);
+                process::exit(1);
+            }
+        };
+
+        let mut admin = Admin::new(trade_store.clone().unwrap(), billing.clone(), position_keeper.clone(), config.symbol_shards(), shard_amendments, audit);
+        if let Some(end_of_day) = end_of_day {
+            admin.set_end_of_day(end_of_day);
+        }
+
+        match admin_server::spawn(&config.admin, admin, logger.clone()) {
+            Ok(_) => logger.info("admin listening", LogPayload::None),
+            Err(error) => {
+                eprintln!("rexchange: cannot listen on {}: {}", config.admin.address, error);
+                process::exit(1);
+            }
+        }
+    }
+
+    let trade_store_thread = trade_store.map(|trade_store| {
+        let trade_store_logger = logger.clone();
+        thread::spawn(move || trade_store::run(trade_store, trades_rx, trade_store_logger))
+    });
+
+    let router = ShardRouter::new(config.symbol_shards(), shard_requests);
+    let mut response_router = ResponseRouter::new();
+    let mut market_data_publisher = MarketDataPublisher::new();
+
+    for market_maker_config in config.market_makers.iter().cloned() {
+        let responses = response_router.subscribe(market_maker_config.participant);
+        let market_updates = market_data_publisher.subscribe();
+        let router = router.clone();
+        let market_maker_logger = logger.clone();
+        let core_id = market_maker_config.core;
+
+        common::spawn_pinned(move || {
+            let mut market_maker = MarketMaker::new(market_maker_config, router, market_maker_logger.clone());
+            strategy::run(&mut market_maker, responses, market_updates, &market_maker_logger);
+        }, core_id);
+    }
+
+    for liquidity_taker_config in config.liquidity_takers.iter().cloned() {
+        let responses = response_router.subscribe(liquidity_taker_config.participant);
+        let market_updates = market_data_publisher.subscribe();
+        let router = router.clone();
+        let liquidity_taker_logger = logger.clone();
+        let core_id = liquidity_taker_config.core;
+
+        common::spawn_pinned(move || {
+            let mut liquidity_taker = LiquidityTaker::new(liquidity_taker_config, router, liquidity_taker_logger.clone());
+            strategy::run(&mut liquidity_taker, responses, market_updates, &liquidity_taker_logger);
+        }, core_id);
+    }
+
+    // its sessions subscribe to their participants' responses
+    if !config.ouch.address.is_empty() {
+        match ouch_gateway::spawn(&config.ouch, config.ouch_accounts(), &config.symbol_names(), config.limits.max_order_ids, router.clone(), &mut response_router, logger.clone()) {
+            Ok(_) => logger.info("ouch gateway listening", LogPayload::None),
+            Err(error) => {
+                eprintln!("rexchange: cannot listen on {}: {}", config.ouch.address, error);
+                process::exit(1);
+            }
+        }
+    }
+
+    // and so do its logins
+    if !config.gateway.address.is_empty() {
+        match tcp_gateway::spawn(&config.gateway, router.clone(), &mut response_router, config.latency.enabled, logger.clone()) {
+            Ok(_) => logger.info("gateway listening", LogPayload::None),
+            Err(error) => {
+                eprintln!("rexchange: cannot listen on {}: {}", config.gateway.address, error);
+                process::exit(1);
+            }
+        }
+    }
+
+    // the position keeper reports to the drop copy, whether it is on or not
+    let (position_reports_tx, position_reports_rx) = mpsc::channel();
+
+    // it copies every response and the trades, both streams have to be subscribed to before they run
+    if !config.drop_copy.address.is_empty() {
+        match drop_copy::spawn(&config.drop_copy, &mut response_router, &mut market_data_publisher, position_reports_rx, logger.clone()) {
+            Ok(_) => logger.info("drop copy listening", LogPayload::None),
+            Err(error) => {
+                eprintln!("rexchange: cannot listen on {}: {}", config.drop_copy.address, error);
+                process::exit(1);
+            }
+        }
+    }
+
+    let billing_responses = response_router.subscribe_all();
+    thread::spawn(move || billing::run(billing, billing_responses));
+
+    let position_responses = response_router.subscribe_all();
+    let position_updates = market_data_publisher.subscribe();
+    let positions_config = config.positions.clone();
+    thread::spawn(move || positions::run(position_keeper, position_responses, position_updates, position_reports_tx, &positions_config));
+
+    thread::spawn(move || response_router.run(participants_response_rx));
+    thread::spawn(move || market_data_publisher.run(market_updates_rx));
+
+    if let Some(order_flow_config) = config.order_flow.clone().filter(|order_flow_config| order_flow_config.in_process) {
+        let mut router = router.clone();
+        let order_flow_logger = logger.clone();
+        let core_id = order_flow_config.core;
+
+        common::spawn_pinned(move || {
+            let mut order_flow = OrderFlow::new(order_flow_config);
+            let _ = order_flow.run(&mut router);
+            order_flow_logger.info("order flow done", LogPayload::Text(order_flow.stats().to_string()));
+        }, core_id);
+    }
+
+    // keep the router alive so the shards keep running, the gateway connections hold their own clones
+    let _router = router;
+
+    for matching_engine in matching_engines {
+        matching_engine.join().unwrap();
+    }
+
+    if let Some(trade_store_thread) = trade_store_thread {
+        trade_store_thread.join().unwrap();
+    }
+
+    logger.info("stopped", LogPayload::None);
+    drop(logger);
+    logger_thread.join().unwrap();
 }
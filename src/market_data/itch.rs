@@ -0,0 +1,264 @@
+use std::{mem, slice};
+
+// NASDAQ TotalView-ITCH 5.0 message layouts, field for field, except that integers are little endian.
+// Prices are in ticks rather than 1/10000ths and the stock locate is the symbol id.
+// Every message is made of bytes only, so a buffer can be read as a message in place and a message written as its bytes
+
+// a little endian unsigned integer of N bytes, with no alignment requirement
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[repr(transparent)]
+pub struct LeInt<const N: usize>([u8; N]);
+
+impl<const N: usize> Default for LeInt<N> {
+    fn default() -> Self {
+        Self([0; N])
+    }
+}
+
+impl<const N: usize> LeInt<N> {
+    // the value is truncated to N bytes
+    pub fn new(value: u64) -> Self {
+        let mut bytes = [0; N];
+        bytes.copy_from_slice(&value.to_le_bytes()[..N]);
+        Self(bytes)
+    }
+
+    pub fn get(&self) -> u64 {
+        let mut bytes = [0; 8];
+        bytes[..N].copy_from_slice(&self.0);
+        u64::from_le_bytes(bytes)
+    }
+}
+
+pub const MESSAGE_SYSTEM_EVENT: u8 = b'S';
+pub const MESSAGE_STOCK_DIRECTORY: u8 = b'R';
+pub const MESSAGE_STOCK_TRADING_ACTION: u8 = b'H';
+pub const MESSAGE_ADD_ORDER: u8 = b'A';
+pub const MESSAGE_ORDER_EXECUTED: u8 = b'E';
+pub const MESSAGE_ORDER_CANCEL: u8 = b'X';
+pub const MESSAGE_ORDER_DELETE: u8 = b'D';
+pub const MESSAGE_ORDER_REPLACE: u8 = b'U';
+pub const MESSAGE_TRADE: u8 = b'P';
+pub const MESSAGE_BROKEN_TRADE: u8 = b'B';
+
+pub const SIDE_BUY: u8 = b'B';
+pub const SIDE_SELL: u8 = b'S';
+
+// timestamps are nanoseconds since midnight, on 6 bytes
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+#[repr(C)]
+pub struct MessageHeader {
+    pub message_type: u8,
+    pub stock_locate: LeInt<2>,
+    pub tracking_number: LeInt<2>,
+    pub timestamp: LeInt<6>,
+}
+
+// event codes: O start of messages, S start of system hours, Q start of market hours, M end of market hours, E end of system hours, C end of messages
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+#[repr(C)]
+pub struct SystemEvent {
+    pub header: MessageHeader,
+    pub event_code: u8,
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+#[repr(C)]
+pub struct StockDirectory {
+    pub header: MessageHeader,
+    pub stock: [u8; 8],
+    pub market_category: u8,
+    pub financial_status_indicator: u8,
+    pub round_lot_size: LeInt<4>,
+    pub round_lots_only: u8,
+    pub issue_classification: u8,
+    pub issue_sub_type: [u8; 2],
+    pub authenticity: u8,
+    pub short_sale_threshold_indicator: u8,
+    pub ipo_flag: u8,
+    pub luld_reference_price_tier: u8,
+    pub etp_flag: u8,
+    pub etp_leverage_factor: LeInt<4>,
+    pub inverse_indicator: u8,
+}
+
+// trading states: H halted, P paused, Q quotation only, T trading
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+#[repr(C)]
+pub struct StockTradingAction {
+    pub header: MessageHeader,
+    pub stock: [u8; 8],
+    pub trading_state: u8,
+    pub reserved: u8,
+    pub reason: [u8; 4],
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+#[repr(C)]
+pub struct AddOrder {
+    pub header: MessageHeader,
+    pub order_reference: LeInt<8>,
+    pub side: u8,
+    pub shares: LeInt<4>,
+    pub stock: [u8; 8],
+    pub price: LeInt<4>,
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+#[repr(C)]
+pub struct OrderExecuted {
+    pub header: MessageHeader,
+    pub order_reference: LeInt<8>,
+    pub executed_shares: LeInt<4>,
+    pub match_number: LeInt<8>,
+}
+
+// a partial cancel, the order stays on the book with fewer shares
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+#[repr(C)]
+pub struct OrderCancel {
+    pub header: MessageHeader,
+    pub order_reference: LeInt<8>,
+    pub cancelled_shares: LeInt<4>,
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+#[repr(C)]
+pub struct OrderDelete {
+    pub header: MessageHeader,
+    pub order_reference: LeInt<8>,
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+#[repr(C)]
+pub struct OrderReplace {
+    pub header: MessageHeader,
+    pub original_order_reference: LeInt<8>,
+    pub new_order_reference: LeInt<8>,
+    pub shares: LeInt<4>,
+    pub price: LeInt<4>,
+}
+
+// a match against an order that isn't displayed, it never had an Add Order
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+#[repr(C)]
+pub struct Trade {
+    pub header: MessageHeader,
+    pub order_reference: LeInt<8>,
+    pub side: u8,
+    pub shares: LeInt<4>,
+    pub stock: [u8; 8],
+    pub price: LeInt<4>,
+    pub match_number: LeInt<8>,
+}
+
+// every execution with this match number is void
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+#[repr(C)]
+pub struct BrokenTrade {
+    pub header: MessageHeader,
+    pub match_number: LeInt<8>,
+}
+
+// the sizes of the ITCH 5.0 specification
+const _: () = assert!(mem::size_of::<SystemEvent>() == 12);
+const _: () = assert!(mem::size_of::<StockDirectory>() == 39);
+const _: () = assert!(mem::size_of::<StockTradingAction>() == 25);
+const _: () = assert!(mem::size_of::<AddOrder>() == 36);
+const _: () = assert!(mem::size_of::<OrderExecuted>() == 31);
+const _: () = assert!(mem::size_of::<OrderCancel>() == 23);
+const _: () = assert!(mem::size_of::<OrderDelete>() == 19);
+const _: () = assert!(mem::size_of::<OrderReplace>() == 35);
+const _: () = assert!(mem::size_of::<Trade>() == 44);
+const _: () = assert!(mem::size_of::<BrokenTrade>() == 19);
+
+/// # Safety
+/// Implementors are `#[repr(C)]` and only made of `u8`, byte arrays and `LeInt`s: they have an alignment of 1,
+/// no padding, and any bytes are a valid value.
+pub unsafe trait ItchMessage: Sized {
+    const MESSAGE_TYPE: u8;
+
+    fn as_bytes(&self) -> &[u8] {
+        unsafe { slice::from_raw_parts(self as *const Self as *const u8, mem::size_of::<Self>()) }
+    }
+
+    // None when the buffer is too short or holds another message type
+    fn view(buf: &[u8]) -> Option<&Self> {
+        if buf.len() < mem::size_of::<Self>() || buf[0] != Self::MESSAGE_TYPE {
+            return None;
+        }
+
+        Some(unsafe { &*(buf.as_ptr() as *const Self) })
+    }
+}
+
+unsafe impl ItchMessage for SystemEvent { const MESSAGE_TYPE: u8 = MESSAGE_SYSTEM_EVENT; }
+unsafe impl ItchMessage for StockDirectory { const MESSAGE_TYPE: u8 = MESSAGE_STOCK_DIRECTORY; }
+unsafe impl ItchMessage for StockTradingAction { const MESSAGE_TYPE: u8 = MESSAGE_STOCK_TRADING_ACTION; }
+unsafe impl ItchMessage for AddOrder { const MESSAGE_TYPE: u8 = MESSAGE_ADD_ORDER; }
+unsafe impl ItchMessage for OrderExecuted { const MESSAGE_TYPE: u8 = MESSAGE_ORDER_EXECUTED; }
+unsafe impl ItchMessage for OrderCancel { const MESSAGE_TYPE: u8 = MESSAGE_ORDER_CANCEL; }
+unsafe impl ItchMessage for OrderDelete { const MESSAGE_TYPE: u8 = MESSAGE_ORDER_DELETE; }
+unsafe impl ItchMessage for OrderReplace { const MESSAGE_TYPE: u8 = MESSAGE_ORDER_REPLACE; }
+unsafe impl ItchMessage for Trade { const MESSAGE_TYPE: u8 = MESSAGE_TRADE; }
+unsafe impl ItchMessage for BrokenTrade { const MESSAGE_TYPE: u8 = MESSAGE_BROKEN_TRADE; }
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Message<'a> {
+    SystemEvent(&'a SystemEvent),
+    StockDirectory(&'a StockDirectory),
+    StockTradingAction(&'a StockTradingAction),
+    AddOrder(&'a AddOrder),
+    OrderExecuted(&'a OrderExecuted),
+    OrderCancel(&'a OrderCancel),
+    OrderDelete(&'a OrderDelete),
+    OrderReplace(&'a OrderReplace),
+    Trade(&'a Trade),
+    BrokenTrade(&'a BrokenTrade),
+}
+
+impl Message<'_> {
+    pub fn header(&self) -> &MessageHeader {
+        match self {
+            Message::SystemEvent(message) => &message.header,
+            Message::StockDirectory(message) => &message.header,
+            Message::StockTradingAction(message) => &message.header,
+            Message::AddOrder(message) => &message.header,
+            Message::OrderExecuted(message) => &message.header,
+            Message::OrderCancel(message) => &message.header,
+            Message::OrderDelete(message) => &message.header,
+            Message::OrderReplace(message) => &message.header,
+            Message::Trade(message) => &message.header,
+            Message::BrokenTrade(message) => &message.header,
+        }
+    }
+}
+
+// the message at the start of the buffer and its length, None when the type is unknown or the message is cut short
+pub fn decode(buf: &[u8]) -> Option<(Message<'_>, usize)> {
+    fn view<'a, T: ItchMessage>(buf: &'a [u8], message: fn(&'a T) -> Message<'a>) -> Option<(Message<'a>, usize)> {
+        T::view(buf).map(|view| (message(view), mem::size_of::<T>()))
+    }
+
+    match *buf.first()? {
+        MESSAGE_SYSTEM_EVENT => view(buf, Message::SystemEvent),
+        MESSAGE_STOCK_DIRECTORY => view(buf, Message::StockDirectory),
+        MESSAGE_STOCK_TRADING_ACTION => view(buf, Message::StockTradingAction),
+        MESSAGE_ADD_ORDER => view(buf, Message::AddOrder),
+        MESSAGE_ORDER_EXECUTED => view(buf, Message::OrderExecuted),
+        MESSAGE_ORDER_CANCEL => view(buf, Message::OrderCancel),
+        MESSAGE_ORDER_DELETE => view(buf, Message::OrderDelete),
+        MESSAGE_ORDER_REPLACE => view(buf, Message::OrderReplace),
+        MESSAGE_TRADE => view(buf, Message::Trade),
+        MESSAGE_BROKEN_TRADE => view(buf, Message::BrokenTrade),
+        _ => None,
+    }
+}
+
+// the symbol name as an ITCH stock field, space padded or cut to 8 characters
+pub fn stock(name: &str) -> [u8; 8] {
+    let mut stock = [b' '; 8];
+    let len = name.len().min(8);
+    stock[..len].copy_from_slice(&name.as_bytes()[..len]);
+    stock
+}
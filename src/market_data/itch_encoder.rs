@@ -0,0 +1,275 @@
+use std::collections::HashMap;
+
+use crate::common::{Nanos, OrderId, Price, Quantity, Side, SymbolId, NANOS_PER_DAY};
+use crate::market_data::itch::{self, AddOrder, BrokenTrade, ItchMessage, LeInt, MessageHeader, OrderCancel, OrderDelete, OrderExecuted, StockDirectory, StockTradingAction, SystemEvent, Trade};
+use crate::market_data::market_update::{MarketUpdate, MarketUpdateType};
+
+// the internal order ids of the books restart at 1 for every symbol
+const ORDER_ID_BITS: u32 = 40;
+
+// a trade whose executions haven't all been seen yet, whatever no displayed order accounts for matched hidden orders
+struct PendingMatch {
+    timestamp: Nanos,
+    match_number: u64,
+    aggressor_side: Side,
+    price: Price,
+    qty: Quantity,
+    unexecuted_qty: u64, //both sides of an auction trade are resting orders
+    last_order_reference: u64,
+}
+
+// turns the engine's market updates into ITCH messages. The engine publishes a trade and then the resting order it
+// filled as a Modify, or a Cancel with no quantity left, which become Order Executed messages whose match number is the trade id.
+// Cancels with quantity left are Order Deletes and matches of hidden orders are Trade messages. A busted trade is a
+// Broken Trade, a corrected one is broken and reported again as a Trade message under the same match number.
+// The updates of each symbol must come in the engine's order, those of different symbols can be interleaved
+pub struct ItchEncoder {
+    stocks: Vec<[u8; 8]>, //indexed by symbol id
+    tracking_number: u16,
+    orders: HashMap<u64, Quantity>, //displayed shares, indexed by order reference
+    pending_matches: Vec<Option<PendingMatch>>, //indexed by symbol id
+}
+
+impl ItchEncoder {
+    // symbol names indexed by symbol id, the tracking number tells the shards apart
+    pub fn new(symbol_names: &[&str], tracking_number: u16) -> Self {
+        Self {
+            stocks: symbol_names.iter().map(|name| itch::stock(name)).collect(),
+            tracking_number,
+            orders: HashMap::new(),
+            pending_matches: symbol_names.iter().map(|_| None).collect(),
+        }
+    }
+
+    pub fn system_event(&mut self, event_code: u8, timestamp: Nanos, out: &mut Vec<u8>) {
+        let message = SystemEvent {
+            header: self.header(SystemEvent::MESSAGE_TYPE, 0, timestamp),
+            event_code,
+        };
+
+        out.extend_from_slice(message.as_bytes());
+    }
+
+    // a normal market, not a test issue, with the round lot size and everything else left blank
+    pub fn stock_directory(&mut self, symbol_id: SymbolId, round_lot_size: Quantity, timestamp: Nanos, out: &mut Vec<u8>) {
+        let message = StockDirectory {
+            header: self.header(StockDirectory::MESSAGE_TYPE, symbol_id, timestamp),
+            stock: self.stocks[symbol_id as usize],
+            market_category: b'Q',
+            financial_status_indicator: b'N',
+            round_lot_size: LeInt::new(round_lot_size as u64),
+            round_lots_only: b'N',
+            issue_classification: b' ',
+            issue_sub_type: [b' '; 2],
+            authenticity: b'P',
+            short_sale_threshold_indicator: b' ',
+            ipo_flag: b' ',
+            luld_reference_price_tier: b' ',
+            etp_flag: b' ',
+            etp_leverage_factor: LeInt::new(0),
+            inverse_indicator: b' ',
+        };
+
+        out.extend_from_slice(message.as_bytes());
+    }
+
+    pub fn encode(&mut self, update: &MarketUpdate, timestamp: Nanos, out: &mut Vec<u8>) {
+        let symbol_id = update.symbol_id;
+        let order_reference = order_reference(symbol_id, update.order_id);
+
+        match update.update_type {
+            MarketUpdateType::Add => {
+                // an iceberg's next peak keeps its order id and comes right after the previous one was executed
+                let is_refresh = self.pending_matches[symbol_id as usize].as_ref().is_some_and(|pending_match| pending_match.last_order_reference == order_reference);
+                if !is_refresh {
+                    self.flush_symbol(symbol_id, out);
+                }
+
+                let message = AddOrder {
+                    header: self.header(AddOrder::MESSAGE_TYPE, symbol_id, timestamp),
+                    order_reference: LeInt::new(order_reference),
+                    side: side(update.side),
+                    shares: LeInt::new(update.qty as u64),
+                    stock: self.stocks[symbol_id as usize],
+                    price: LeInt::new(price(update.price)),
+                };
+
+                self.orders.insert(order_reference, update.qty);
+                out.extend_from_slice(message.as_bytes());
+            },
+            MarketUpdateType::Modify => {
+                let shares = self.orders.insert(order_reference, update.qty).unwrap_or(update.qty);
+                self.execute(symbol_id, order_reference, shares.saturating_sub(update.qty), timestamp, out);
+            },
+            MarketUpdateType::Cancel if update.qty == 0 => {
+                let shares = self.orders.remove(&order_reference).unwrap_or(0);
+                self.execute(symbol_id, order_reference, shares, timestamp, out);
+            },
+            MarketUpdateType::Cancel => {
+                self.flush_symbol(symbol_id, out);
+
+                let message = OrderDelete {
+                    header: self.header(OrderDelete::MESSAGE_TYPE, symbol_id, timestamp),
+                    order_reference: LeInt::new(order_reference),
+                };
+
+                self.orders.remove(&order_reference);
+                out.extend_from_slice(message.as_bytes());
+            },
+            MarketUpdateType::Trade => {
+                self.flush_symbol(symbol_id, out);
+
+                let sides = if update.side == Side::Invalid { 2 } else { 1 };
+                self.pending_matches[symbol_id as usize] = Some(PendingMatch {
+                    timestamp,
+                    match_number: update.trade_id,
+                    aggressor_side: update.side,
+                    price: update.price,
+                    qty: update.qty,
+                    unexecuted_qty: update.qty as u64 * sides,
+                    last_order_reference: 0,
+                });
+            },
+            MarketUpdateType::TradeCancel | MarketUpdateType::TradeCorrect => {
+                self.flush_symbol(symbol_id, out);
+
+                let message = BrokenTrade {
+                    header: self.header(BrokenTrade::MESSAGE_TYPE, symbol_id, timestamp),
+                    match_number: LeInt::new(update.trade_id),
+                };
+
+                out.extend_from_slice(message.as_bytes());
+
+                if update.update_type == MarketUpdateType::TradeCorrect {
+                    let message = Trade {
+                        header: self.header(Trade::MESSAGE_TYPE, symbol_id, timestamp),
+                        order_reference: LeInt::new(0),
+                        side: side(resting_side(update.side)),
+                        shares: LeInt::new(update.qty as u64),
+                        stock: self.stocks[symbol_id as usize],
+                        price: LeInt::new(price(update.price)),
+                        match_number: LeInt::new(update.trade_id),
+                    };
+
+                    out.extend_from_slice(message.as_bytes());
+                }
+            },
+            MarketUpdateType::Halt | MarketUpdateType::VolatilityAuction | MarketUpdateType::Resume => {
+                self.flush_symbol(symbol_id, out);
+
+                let message = StockTradingAction {
+                    header: self.header(StockTradingAction::MESSAGE_TYPE, symbol_id, timestamp),
+                    stock: self.stocks[symbol_id as usize],
+                    trading_state: match update.update_type {
+                        MarketUpdateType::Halt => b'H',
+                        MarketUpdateType::VolatilityAuction => b'Q',
+                        _ => b'T',
+                    },
+                    reserved: 0,
+                    reason: [b' '; 4],
+                };
+
+                out.extend_from_slice(message.as_bytes());
+            },
+            MarketUpdateType::Invalid => {},
+        }
+    }
+
+    // writes out the hidden matches still held back, once nothing more comes for now
+    pub fn flush(&mut self, out: &mut Vec<u8>) {
+        for symbol_id in 0..self.pending_matches.len() {
+            self.flush_symbol(symbol_id as SymbolId, out);
+        }
+    }
+
+    fn execute(&mut self, symbol_id: SymbolId, order_reference: u64, executed_shares: Quantity, timestamp: Nanos, out: &mut Vec<u8>) {
+        if executed_shares == 0 {
+            return;
+        }
+
+        let pending_match = self.pending_matches[symbol_id as usize].as_mut().filter(|pending_match| pending_match.unexecuted_qty >= executed_shares as u64);
+
+        // the engine only reduces a displayed order when it trades, anything else is a plain cancel
+        let pending_match = match pending_match {
+            Some(pending_match) => pending_match,
+            None => {
+                self.flush_symbol(symbol_id, out);
+
+                let message = OrderCancel {
+                    header: self.header(OrderCancel::MESSAGE_TYPE, symbol_id, timestamp),
+                    order_reference: LeInt::new(order_reference),
+                    cancelled_shares: LeInt::new(executed_shares as u64),
+                };
+
+                out.extend_from_slice(message.as_bytes());
+                return;
+            },
+        };
+
+        pending_match.unexecuted_qty -= executed_shares as u64;
+        pending_match.last_order_reference = order_reference;
+        let match_number = pending_match.match_number;
+
+        let message = OrderExecuted {
+            header: self.header(OrderExecuted::MESSAGE_TYPE, symbol_id, timestamp),
+            order_reference: LeInt::new(order_reference),
+            executed_shares: LeInt::new(executed_shares as u64),
+            match_number: LeInt::new(match_number),
+        };
+
+        out.extend_from_slice(message.as_bytes());
+    }
+
+    fn flush_symbol(&mut self, symbol_id: SymbolId, out: &mut Vec<u8>) {
+        let pending_match = match self.pending_matches.get_mut(symbol_id as usize).and_then(Option::take) {
+            Some(pending_match) if pending_match.unexecuted_qty > 0 => pending_match,
+            _ => return,
+        };
+
+        let message = Trade {
+            header: self.header(Trade::MESSAGE_TYPE, symbol_id, pending_match.timestamp),
+            order_reference: LeInt::new(0),
+            side: side(resting_side(pending_match.aggressor_side)),
+            shares: LeInt::new(pending_match.unexecuted_qty.min(pending_match.qty as u64)),
+            stock: self.stocks[symbol_id as usize],
+            price: LeInt::new(price(pending_match.price)),
+            match_number: LeInt::new(pending_match.match_number),
+        };
+
+        out.extend_from_slice(message.as_bytes());
+    }
+
+    fn header(&self, message_type: u8, symbol_id: SymbolId, timestamp: Nanos) -> MessageHeader {
+        MessageHeader {
+            message_type,
+            stock_locate: LeInt::new(symbol_id as u64),
+            tracking_number: LeInt::new(self.tracking_number as u64),
+            timestamp: LeInt::new(timestamp % NANOS_PER_DAY),
+        }
+    }
+}
+
+// unique across symbols, the symbol id is in the top bits
+pub fn order_reference(symbol_id: SymbolId, order_id: OrderId) -> u64 {
+    (symbol_id as u64) << ORDER_ID_BITS | order_id
+}
+
+// the side of the hidden order, an auction trade is reported on its buy side
+fn resting_side(aggressor_side: Side) -> Side {
+    match aggressor_side {
+        Side::Buy => Side::Sell,
+        _ => Side::Buy,
+    }
+}
+
+fn side(side: Side) -> u8 {
+    match side {
+        Side::Sell => itch::SIDE_SELL,
+        _ => itch::SIDE_BUY,
+    }
+}
+
+// ITCH prices are 4 bytes
+fn price(price: Price) -> u64 {
+    price.min(u32::MAX as Price)
+}
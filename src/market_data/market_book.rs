@@ -0,0 +1,108 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::common::{OrderId, Price, Quantity, Side};
+use super::market_update::{MarketUpdate, MarketUpdateType};
+
+// a symbol's book rebuilt from the order by order market data, aggregated per price level
+pub struct MarketBook {
+    orders: HashMap<OrderId, (Side, Price, Quantity)>, //keyed by internal order id
+    bids: BTreeMap<Price, Quantity>,
+    asks: BTreeMap<Price, Quantity>,
+    last_trade_price: Option<Price>,
+    is_continuous: bool,
+}
+
+impl MarketBook {
+    pub fn new() -> Self {
+        Self {
+            orders: HashMap::new(),
+            bids: BTreeMap::new(),
+            asks: BTreeMap::new(),
+            last_trade_price: None,
+            is_continuous: true,
+        }
+    }
+
+    pub fn on_update(&mut self, update: &MarketUpdate) {
+        match update.update_type {
+            MarketUpdateType::Add => {
+                self.orders.insert(update.order_id, (update.side, update.price, update.qty));
+                *self.levels_mut(update.side).entry(update.price).or_insert(0) += update.qty;
+            },
+            MarketUpdateType::Modify | MarketUpdateType::Cancel => {
+                let (side, price, qty) = match self.orders.get_mut(&update.order_id) {
+                    Some(order) => order,
+                    None => return,
+                };
+
+                let removed_qty = *qty - if update.update_type == MarketUpdateType::Modify { update.qty } else { 0 };
+                let (side, price) = (*side, *price);
+                *qty -= removed_qty;
+
+                if update.update_type == MarketUpdateType::Cancel {
+                    self.orders.remove(&update.order_id);
+                }
+
+                let levels = self.levels_mut(side);
+                let level_qty = levels.get_mut(&price).unwrap();
+                *level_qty -= removed_qty;
+                if *level_qty == 0 {
+                    levels.remove(&price);
+                }
+            },
+            MarketUpdateType::Trade => self.last_trade_price = Some(update.price),
+            MarketUpdateType::Halt | MarketUpdateType::VolatilityAuction => self.is_continuous = false,
+            MarketUpdateType::Resume => self.is_continuous = true,
+            // the trades that made the book stand, busting or correcting one doesn't change it
+            MarketUpdateType::TradeCancel | MarketUpdateType::TradeCorrect | MarketUpdateType::Invalid => {},
+        }
+    }
+
+    pub fn best_bid(&self) -> Option<(Price, Quantity)> {
+        self.bids.iter().next_back().map(|(price, qty)| (*price, *qty))
+    }
+
+    pub fn best_ask(&self) -> Option<(Price, Quantity)> {
+        self.asks.iter().next().map(|(price, qty)| (*price, *qty))
+    }
+
+    pub fn best(&self, side: Side) -> Option<(Price, Quantity)> {
+        match side {
+            Side::Buy => self.best_bid(),
+            _ => self.best_ask(),
+        }
+    }
+
+    // the `depth` best levels of a side, best first
+    pub fn levels(&self, side: Side, depth: usize) -> Vec<(Price, Quantity)> {
+        match side {
+            Side::Buy => self.bids.iter().rev().take(depth).map(|(price, qty)| (*price, *qty)).collect(),
+            _ => self.asks.iter().take(depth).map(|(price, qty)| (*price, *qty)).collect(),
+        }
+    }
+
+    pub fn mid_price(&self) -> Option<Price> {
+        Some((self.best_bid()?.0 + self.best_ask()?.0) / 2)
+    }
+
+    pub fn last_trade_price(&self) -> Option<Price> {
+        self.last_trade_price
+    }
+
+    pub fn is_continuous(&self) -> bool {
+        self.is_continuous
+    }
+
+    fn levels_mut(&mut self, side: Side) -> &mut BTreeMap<Price, Quantity> {
+        match side {
+            Side::Buy => &mut self.bids,
+            _ => &mut self.asks,
+        }
+    }
+}
+
+impl Default for MarketBook {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -2,14 +2,19 @@ use std::fmt;
 
 use crate::common;
 
-#[derive(Clone)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 #[repr(u8)]
 pub enum MarketUpdateType {
     Invalid = 0,
     Add,
     Modify,
     Cancel,
-    Trade
+    Trade,
+    Halt,
+    VolatilityAuction,
+    Resume,
+    TradeCancel,
+    TradeCorrect
 }
 
 impl fmt::Display for MarketUpdateType {
@@ -19,11 +24,26 @@ impl fmt::Display for MarketUpdateType {
             MarketUpdateType::Modify => write!(f, "MODIFY"),
             MarketUpdateType::Cancel => write!(f, "CANCEL"),
             MarketUpdateType::Trade => write!(f, "TRADE"),
+            MarketUpdateType::Halt => write!(f, "HALT"),
+            MarketUpdateType::VolatilityAuction => write!(f, "VOLATILITY-AUCTION"),
+            MarketUpdateType::Resume => write!(f, "RESUME"),
+            MarketUpdateType::TradeCancel => write!(f, "TRADE-CANCEL"),
+            MarketUpdateType::TradeCorrect => write!(f, "TRADE-CORRECT"),
             MarketUpdateType::Invalid => write!(f, "INVALID"),
         }
     }
 }
 
+impl TryFrom<u8> for MarketUpdateType {
+    type Error = u8;
+
+    fn try_from(value: u8) -> Result<Self, u8> {
+        [MarketUpdateType::Invalid, MarketUpdateType::Add, MarketUpdateType::Modify, MarketUpdateType::Cancel, MarketUpdateType::Trade, MarketUpdateType::Halt, MarketUpdateType::VolatilityAuction, MarketUpdateType::Resume, MarketUpdateType::TradeCancel, MarketUpdateType::TradeCorrect].into_iter().find(|variant| *variant as u8 == value).ok_or(value)
+    }
+}
+
+// trade_id is only set on trades and on their cancels and corrections, which carry the corrected price and quantity
+#[derive(Clone)]
 pub struct MarketUpdate {
     pub update_type: MarketUpdateType,
     pub order_id: common::OrderId,
@@ -31,13 +51,14 @@ pub struct MarketUpdate {
     pub side: common::Side,
     pub price: common::Price,
     pub qty: common::Quantity,
-    pub priority: common::Priority
+    pub priority: common::Priority,
+    pub trade_id: common::TradeId,
 }
 
 impl fmt::Display for MarketUpdate {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "MarketUpdate [type: {}, order:{}, symb:{}, side:{}, price:{}, qty:{}, prio:{}]", 
-        self.update_type, self.order_id, self.symbol_id, self.side, self.price, self.qty, self.priority)
+        write!(f, "MarketUpdate [type: {}, order:{}, symb:{}, side:{}, price:{}, qty:{}, prio:{}, trade:{}]", 
+        self.update_type, self.order_id, self.symbol_id, self.side, self.price, self.qty, self.priority, self.trade_id)
     }
 }
 
@@ -50,7 +71,8 @@ impl Default for MarketUpdate {
             side: common::Side::Invalid,
             price: common::INVALID_PRICE,
             qty: common::INVALID_QUANTITY,
-            priority: common::INVALID_PRIORITY
+            priority: common::INVALID_PRIORITY,
+            trade_id: common::INVALID_TRADE_ID,
         }
     }
 }
\ No newline at end of file
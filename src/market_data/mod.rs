@@ -1 +1,5 @@
-pub mod market_update;
\ No newline at end of file
+pub mod itch;
+pub mod itch_encoder;
+pub mod market_book;
+pub mod market_update;
+pub mod publisher;
@@ -0,0 +1,37 @@
+use std::sync::mpsc::{self, Receiver, Sender};
+
+use crate::common::Sequenced;
+
+use super::market_update::MarketUpdate;
+
+// copies the market data stream to every subscriber, unbounded like the response streams
+pub struct MarketDataPublisher {
+    subscribers: Vec<Sender<Sequenced<MarketUpdate>>>,
+}
+
+impl MarketDataPublisher {
+    pub fn new() -> Self {
+        Self {
+            subscribers: Vec::new(),
+        }
+    }
+
+    pub fn subscribe(&mut self) -> Receiver<Sequenced<MarketUpdate>> {
+        let (tx, rx) = mpsc::channel();
+        self.subscribers.push(tx);
+        rx
+    }
+
+    // returns once every engine has stopped, subscribers that went away are dropped
+    pub fn run(mut self, market_updates: Receiver<Sequenced<MarketUpdate>>) {
+        for update in market_updates.iter() {
+            self.subscribers.retain(|subscriber| subscriber.send(update.clone()).is_ok());
+        }
+    }
+}
+
+impl Default for MarketDataPublisher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,93 @@
+use std::{cmp, fmt};
+
+use serde::Deserialize;
+
+use crate::common::Quantity;
+
+// how an incoming order's quantity is split across the resting orders of a price level
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Deserialize)]
+#[serde(tag = "policy", rename_all = "kebab-case", deny_unknown_fields)]
+pub enum AllocationPolicy {
+    #[default]
+    Fifo,
+    // shares below `min_allocation` are dropped, what rounding leaves over goes FIFO
+    ProRata { min_allocation: Quantity },
+    // the first order in time is filled first, the rest is shared pro-rata
+    TopOrderProRata { min_allocation: Quantity },
+    // `fifo_pct` percent of the quantity goes FIFO, the rest pro-rata
+    SplitFifoProRata { fifo_pct: u8, min_allocation: Quantity },
+}
+
+impl fmt::Display for AllocationPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AllocationPolicy::Fifo => write!(f, "FIFO"),
+            AllocationPolicy::ProRata { min_allocation } => write!(f, "PRO-RATA [min: {}]", min_allocation),
+            AllocationPolicy::TopOrderProRata { min_allocation } => write!(f, "TOP-ORDER-PRO-RATA [min: {}]", min_allocation),
+            AllocationPolicy::SplitFifoProRata { fifo_pct, min_allocation } => write!(f, "SPLIT-FIFO-PRO-RATA [fifo: {}%, min: {}]", fifo_pct, min_allocation),
+        }
+    }
+}
+
+impl AllocationPolicy {
+    // `resting_qtys` are in time priority, `allocations` gets one entry per resting order
+    pub fn allocate(&self, qty: Quantity, resting_qtys: &[Quantity], allocations: &mut Vec<Quantity>) {
+        allocations.clear();
+        allocations.resize(resting_qtys.len(), 0);
+
+        let level_qty: u64 = resting_qtys.iter().map(|qty| *qty as u64).sum();
+        let mut qty = cmp::min(qty as u64, level_qty) as Quantity;
+
+        match *self {
+            AllocationPolicy::Fifo => {},
+            AllocationPolicy::ProRata { min_allocation } => {
+                qty = allocate_pro_rata(qty, min_allocation, resting_qtys, allocations);
+            },
+            AllocationPolicy::TopOrderProRata { min_allocation } => {
+                if let Some(top_qty) = resting_qtys.first() {
+                    allocations[0] = cmp::min(qty, *top_qty);
+                    qty -= allocations[0];
+                }
+                qty = allocate_pro_rata(qty, min_allocation, resting_qtys, allocations);
+            },
+            AllocationPolicy::SplitFifoProRata { fifo_pct, min_allocation } => {
+                let fifo_qty = (qty as u64 * cmp::min(fifo_pct, 100) as u64 / 100) as Quantity;
+                qty -= fifo_qty;
+                allocate_fifo(fifo_qty, resting_qtys, allocations);
+                qty = allocate_pro_rata(qty, min_allocation, resting_qtys, allocations);
+            },
+        }
+
+        allocate_fifo(qty, resting_qtys, allocations);
+    }
+}
+
+fn allocate_fifo(mut qty: Quantity, resting_qtys: &[Quantity], allocations: &mut [Quantity]) {
+    for (resting_qty, allocation) in resting_qtys.iter().zip(allocations.iter_mut()) {
+        let fill_qty = cmp::min(qty, resting_qty - *allocation);
+        *allocation += fill_qty;
+        qty -= fill_qty;
+    }
+}
+
+// returns the quantity left over by rounding down and by the minimum allocation
+fn allocate_pro_rata(qty: Quantity, min_allocation: Quantity, resting_qtys: &[Quantity], allocations: &mut [Quantity]) -> Quantity {
+    let open_qty: u64 = resting_qtys.iter().zip(allocations.iter()).map(|(resting_qty, allocation)| (resting_qty - allocation) as u64).sum();
+
+    if open_qty == 0 {
+        return qty;
+    }
+
+    let mut allocated_qty = 0;
+
+    for (resting_qty, allocation) in resting_qtys.iter().zip(allocations.iter_mut()) {
+        let share = (qty as u64 * (resting_qty - *allocation) as u64 / open_qty) as Quantity;
+
+        if share >= min_allocation {
+            *allocation += share;
+            allocated_qty += share;
+        }
+    }
+
+    qty - allocated_qty
+}
@@ -0,0 +1,134 @@
+use std::fmt;
+
+use serde::Deserialize;
+
+use crate::common::{Nanos, Price, INVALID_PRICE, NANOS_TO_SECS};
+
+const BPS_DENOMINATOR: u128 = 10_000;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[repr(u8)]
+pub enum TradingState {
+    Continuous = 0,
+    Halted,
+    VolatilityAuction,
+}
+
+impl fmt::Display for TradingState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TradingState::Continuous => write!(f, "CONTINUOUS"),
+            TradingState::Halted => write!(f, "HALTED"),
+            TradingState::VolatilityAuction => write!(f, "VOLATILITY-AUCTION"),
+        }
+    }
+}
+
+// what the symbol moves into when a trade would print outside a band
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BreachAction {
+    Halt,
+    VolatilityAuction,
+}
+
+// what happens to the new orders of a halted symbol
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum HaltOrderPolicy {
+    Reject,
+    Queue,
+}
+
+// per symbol price bands in basis points, 0 disables a band
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CircuitBreakerConfig {
+    pub previous_close: Price,
+    pub static_band_bps: u32,
+    pub dynamic_band_bps: u32,
+    pub breach_action: BreachAction,
+    pub halt_order_policy: HaltOrderPolicy,
+    #[serde(rename = "halt_duration_ns")]
+    pub halt_duration: Nanos,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            previous_close: INVALID_PRICE,
+            static_band_bps: 0,
+            dynamic_band_bps: 0,
+            breach_action: BreachAction::Halt,
+            halt_order_policy: HaltOrderPolicy::Reject,
+            halt_duration: 5 * 60 * NANOS_TO_SECS,
+        }
+    }
+}
+
+pub struct CircuitBreaker {
+    config: CircuitBreakerConfig,
+    state: TradingState,
+    last_trade_price: Price,
+    resume_time: Nanos,
+}
+
+impl CircuitBreaker {
+    pub fn new(config: CircuitBreakerConfig) -> Self {
+        Self {
+            last_trade_price: config.previous_close,
+            config,
+            state: TradingState::Continuous,
+            resume_time: 0,
+        }
+    }
+
+    pub fn state(&self) -> TradingState {
+        self.state
+    }
+
+    pub fn halt_order_policy(&self) -> HaltOrderPolicy {
+        self.config.halt_order_policy
+    }
+
+    pub fn last_trade_price(&self) -> Price {
+        self.last_trade_price
+    }
+
+    // whether a trade at `price` stays inside both the static and the dynamic band
+    pub fn is_within_bands(&self, price: Price) -> bool {
+        is_within_band(price, self.config.previous_close, self.config.static_band_bps) &&
+        is_within_band(price, self.last_trade_price, self.config.dynamic_band_bps)
+    }
+
+    pub fn on_trade(&mut self, price: Price) {
+        self.last_trade_price = price;
+    }
+
+    // out of continuous trading, returns the state the symbol moved into
+    pub fn trip(&mut self, now: Nanos) -> TradingState {
+        self.state = match self.config.breach_action {
+            BreachAction::Halt => TradingState::Halted,
+            BreachAction::VolatilityAuction => TradingState::VolatilityAuction,
+        };
+        self.resume_time = now.saturating_add(self.config.halt_duration);
+        self.state
+    }
+
+    pub fn should_resume(&self, now: Nanos) -> bool {
+        self.state != TradingState::Continuous && now >= self.resume_time
+    }
+
+    pub fn resume(&mut self) {
+        self.state = TradingState::Continuous;
+    }
+}
+
+fn is_within_band(price: Price, reference_price: Price, band_bps: u32) -> bool {
+    if band_bps == 0 || reference_price == INVALID_PRICE {
+        return true;
+    }
+
+    let distance = price.abs_diff(reference_price) as u128;
+    distance * BPS_DENOMINATOR <= reference_price as u128 * band_bps as u128
+}
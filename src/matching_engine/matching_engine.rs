@@ -1,27 +1,351 @@
+use std::mem;
+use std::sync::Arc;
 use std::sync::mpsc::Receiver;
-use std::sync::mpsc::Sender;
+use std::sync::mpsc::SyncSender;
+use std::sync::mpsc::TryRecvError;
 
-use crate::market_data::market_update::MarketUpdate;
-use crate::order_server::participants_request::ParticipantRequest;
-use crate::order_server::participants_response::ParticipantResponse;
+use crate::common::{get_current_nanos, Fee, Nanos, ParticipantId, SeqNum, Sequenced, ShardId, Side, SymbolId, TradeId, INVALID_ORDER_ID, INVALID_QUANTITY, INVALID_TRADE_ID, TRADE_ID_SHARD_SHIFT};
+use crate::fees::fee_schedule::FeeSchedule;
+use crate::latency::recorder::{LatencyConfig, LatencyRecorder, Stage};
+use crate::logging::logger::{LogPayload, Logger};
+use crate::market_data::market_update::{MarketUpdate, MarketUpdateType};
+use crate::order_server::participants_request::{ParticipantRequest, ParticipantRequestType};
+use crate::order_server::participants_response::{ParticipantResponse, ParticipantResponseType};
+use crate::trades::trade::{Trade, TradeAmendment};
+
+use super::order::{NewOrder, OrderInfo};
+use super::orderbook::{OrderBook, OrderBookConfig, OrderbookHashmap};
+
+// what one engine shard runs, `order_books` lists the symbols it owns
+#[derive(Clone, Default)]
+pub struct MatchingEngineConfig {
+    pub shard_id: ShardId,
+    pub order_books: Vec<(SymbolId, OrderBookConfig)>,
+    pub participant_ids: Vec<ParticipantId>,
+    pub latency: LatencyConfig,
+}
+
+impl MatchingEngineConfig {
+    // a single shard owning every symbol, symbol ids follow the order of `order_book_configs`
+    pub fn single_shard(order_book_configs: &[OrderBookConfig], participant_ids: &[ParticipantId], latency: LatencyConfig) -> Self {
+        Self {
+            shard_id: 0,
+            order_books: order_book_configs.iter().cloned().enumerate().map(|(symbol_id, config)| (symbol_id as SymbolId, config)).collect(),
+            participant_ids: participant_ids.to_vec(),
+            latency,
+        }
+    }
+}
 
 pub struct MatchingEngine {
     participants_requests: Receiver<ParticipantRequest>,
-    participants_response: Sender<ParticipantResponse>,
-    market_data_updates: Sender<MarketUpdate>
+    participants_response: SyncSender<Sequenced<ParticipantResponse>>,
+    market_data_updates: SyncSender<Sequenced<MarketUpdate>>,
+    trades: Option<SyncSender<Trade>>, // the trade store's feed, nothing is captured without one
+    amendments: Option<Receiver<TradeAmendment>>, // the busts and corrections to publish, made by the admin server
+    fees: Option<Arc<FeeSchedule>>, // shared by the shards, fills are free without one
+    order_books: OrderbookHashmap, //indexed by symbol id, None for the symbols of other shards
+    shard_id: ShardId,
+    next_response_seq_num: SeqNum,
+    next_market_update_seq_num: SeqNum,
+    next_trade_id: TradeId,
+    known_participants: Vec<bool>, //indexed by participant id
+    logger: Logger,
+    latency: LatencyRecorder,
+    request_recv_time: Nanos,     // stamps of the request being processed, 0 outside of a request
+    request_dequeue_time: Nanos,
+    simulated_time: Option<Nanos>, // replaces the wall clock when the engine is driven by a backtest
 }
 
 
 impl MatchingEngine {
-    pub fn run() {
+    pub fn new(participants_requests: Receiver<ParticipantRequest>,
+               participants_response: SyncSender<Sequenced<ParticipantResponse>>,
+               market_data_updates: SyncSender<Sequenced<MarketUpdate>>,
+               config: &MatchingEngineConfig,
+               logger: Logger) -> Self {
+        let mut known_participants = Vec::new();
+        let mut order_books = OrderbookHashmap::new();
+
+        for participant_id in &config.participant_ids {
+            if known_participants.len() <= *participant_id as usize {
+                known_participants.resize(*participant_id as usize + 1, false);
+            }
+            known_participants[*participant_id as usize] = true;
+        }
+
+        for (symbol_id, order_book_config) in &config.order_books {
+            if order_books.len() <= *symbol_id as usize {
+                order_books.resize_with(*symbol_id as usize + 1, || None);
+            }
+            order_books[*symbol_id as usize] = Some(OrderBook::new(*symbol_id, order_book_config.clone()));
+        }
+
+        Self {
+            participants_requests,
+            participants_response,
+            market_data_updates,
+            trades: None,
+            amendments: None,
+            fees: None,
+            order_books,
+            shard_id: config.shard_id,
+            next_response_seq_num: 1,
+            next_market_update_seq_num: 1,
+            next_trade_id: (config.shard_id as TradeId) << TRADE_ID_SHARD_SHIFT | 1,
+            known_participants,
+            logger,
+            latency: LatencyRecorder::new(&config.latency, config.shard_id),
+            request_recv_time: 0,
+            request_dequeue_time: 0,
+            simulated_time: None,
+        }
+    }
+
+    pub fn logger(&self) -> &Logger {
+        &self.logger
+    }
+
+    pub fn latency(&self) -> &LatencyRecorder {
+        &self.latency
+    }
+
+    pub fn order_book(&self, symbol_id: SymbolId) -> Option<&OrderBook> {
+        self.order_books.get(symbol_id as usize)?.as_ref()
+    }
+
+    pub fn shard_id(&self) -> ShardId {
+        self.shard_id
+    }
+
+    // the time circuit breakers are tripped and resumed at
+    pub fn now(&self) -> Nanos {
+        self.simulated_time.unwrap_or_else(get_current_nanos)
+    }
+
+    pub fn set_simulated_time(&mut self, now: Nanos) {
+        self.simulated_time = Some(now);
+    }
+
+    pub fn set_trade_sink(&mut self, trades: SyncSender<Trade>) {
+        self.trades = Some(trades);
+    }
+
+    pub fn set_amendment_source(&mut self, amendments: Receiver<TradeAmendment>) {
+        self.amendments = Some(amendments);
+    }
+
+    pub fn set_fee_schedule(&mut self, fees: Arc<FeeSchedule>) {
+        self.fees = Some(fees);
+    }
+
+    // the shard's trades stored before a restart keep their ids, the count goes on after the last one
+    pub fn set_last_trade_id(&mut self, trade_id: TradeId) {
+        self.next_trade_id = self.next_trade_id.max(trade_id + 1);
+    }
+
+    pub fn next_trade_id(&mut self) -> TradeId {
+        let trade_id = self.next_trade_id;
+        self.next_trade_id += 1;
+        trade_id
+    }
+
+    // busy polls the request channel, returns once every request sender is dropped
+    pub fn run(&mut self) {
+        loop {
+            match self.participants_requests.try_recv() {
+                Ok(request) => self.process_participant_request(&request),
+                Err(TryRecvError::Empty) => {},
+                Err(TryRecvError::Disconnected) => break,
+            }
+
+            if let Some(amendment) = self.amendments.as_ref().and_then(|amendments| amendments.try_recv().ok()) {
+                self.amend_trade(&amendment);
+            }
 
+            let now = self.now();
+            self.check_timers(now);
+            self.latency.report(now, &self.logger);
+        }
+
+        self.latency.report_totals(&self.logger);
+    }
+
+    pub fn process_participant_request(&mut self, request: &ParticipantRequest) {
+        self.request_recv_time = request.recv_time;
+        self.request_dequeue_time = self.latency.now();
+        self.latency.record(Stage::GatewayToEngine, self.request_recv_time, self.request_dequeue_time);
+
+        self.logger.info("request", LogPayload::ParticipantRequest(request.clone()));
+
+        let is_valid = self.order_book(request.symbol_id).is_some() &&
+                       self.known_participants.get(request.participant_id as usize).copied().unwrap_or(false);
+
+        if !is_valid {
+            self.reject(request);
+            self.end_request();
+            return;
+        }
+
+        let order_info = OrderInfo { participant_id: request.participant_id, order_id: request.order_id };
+
+        // the books call back into the engine to publish, so they are moved out while a request is processed
+        let mut order_books = mem::take(&mut self.order_books);
+        let order_book = order_books[request.symbol_id as usize].as_mut().unwrap();
+
+        match request.request_type {
+            ParticipantRequestType::New => {
+                let start = self.latency.now();
+                order_book.add(NewOrder {
+                    order_info,
+                    order_type: request.order_type,
+                    side: request.side,
+                    price: request.price,
+                    stop_price: request.stop_price,
+                    qty: request.qty,
+                    display_qty: request.display_qty,
+                    flags: request.flags,
+                }, self);
+                self.latency.record(Stage::OrderBookAdd, start, self.latency.now());
+            },
+            ParticipantRequestType::Cancel => {
+                let start = self.latency.now();
+                order_book.cancel(order_info, self);
+                self.latency.record(Stage::OrderBookCancel, start, self.latency.now());
+            },
+            ParticipantRequestType::Invalid => self.reject(request),
+        }
+
+        self.order_books = order_books;
+        self.end_request();
+    }
+
+    // what is published outside of a request (timers, auctions) isn't attributed to one
+    fn end_request(&mut self) {
+        self.request_recv_time = 0;
+        self.request_dequeue_time = 0;
+    }
+
+    pub fn check_timers(&mut self, now: Nanos) {
+        let mut order_books = mem::take(&mut self.order_books);
+
+        for order_book in order_books.iter_mut().flatten() {
+            order_book.on_timer(now, self);
+        }
+
+        self.order_books = order_books;
+    }
+
+    // the books are left alone, a trade that is busted or corrected is told to both sides and to the market and its
+    // volume moved in the fee schedule. The position keeper is moved by the admin console, which has the trade as it
+    // was, and there is no other position or risk state on the server to adjust
+    pub fn amend_trade(&mut self, amendment: &TradeAmendment) {
+        let (original, amended) = (&amendment.original, &amendment.amended);
+        let (response_type, update_type, message) = if amendment.is_bust() {
+            (ParticipantResponseType::TradeBusted, MarketUpdateType::TradeCancel, "trade busted")
+        } else {
+            (ParticipantResponseType::TradeCorrected, MarketUpdateType::TradeCorrect, "trade corrected")
+        };
+
+        self.logger.info(message, LogPayload::Trade(amended.clone()));
+
+        // shares taken back, or added by a correction
+        let qty = amended.qty as i64 - original.qty as i64;
+
+        for (participant_id, order_id, side, charged) in [(original.buy_participant_id, original.buy_order_id, Side::Buy, amendment.buy_fee), (original.sell_participant_id, original.sell_order_id, Side::Sell, amendment.sell_fee)] {
+            if let Some(fees) = &self.fees {
+                fees.amend(participant_id, qty, original.timestamp, self.now());
+            }
+
+            self.send_participant_response(&ParticipantResponse {
+                response_type,
+                participant_id,
+                symbol_id: original.symbol_id,
+                participant_order_id: order_id,
+                internal_order_id: INVALID_ORDER_ID,
+                side,
+                price: amended.price,
+                exec_qty: amended.qty,
+                leaves_qty: original.qty,
+                aggressor: side == original.aggressor_side,
+                trade_id: original.trade_id,
+                fee: if original.qty == 0 { 0 } else { charged * qty / original.qty as i64 },
+            });
+        }
+
+        self.send_market_update(&MarketUpdate {
+            update_type,
+            symbol_id: original.symbol_id,
+            side: original.aggressor_side,
+            price: amended.price,
+            qty: amended.qty,
+            trade_id: original.trade_id,
+            ..MarketUpdate::default()
+        });
+    }
+
+    fn reject(&mut self, request: &ParticipantRequest) {
+        let response = ParticipantResponse {
+            response_type: match request.request_type {
+                ParticipantRequestType::Cancel => ParticipantResponseType::CancelRejected,
+                _ => ParticipantResponseType::Rejected,
+            },
+            participant_id: request.participant_id,
+            symbol_id: request.symbol_id,
+            participant_order_id: request.order_id,
+            internal_order_id: INVALID_ORDER_ID,
+            side: request.side,
+            price: request.price,
+            exec_qty: INVALID_QUANTITY,
+            leaves_qty: request.qty,
+            aggressor: false,
+            trade_id: INVALID_TRADE_ID,
+            fee: 0,
+        };
+
+        self.send_participant_response(&response);
     }
 
     pub fn send_participant_response(&mut self, response: &ParticipantResponse) {
+        self.latency.record(Stage::Response, self.request_dequeue_time, self.latency.now());
+        let response = ParticipantResponse { fee: self.fee(response), ..response.clone() };
+        self.logger.info("response", LogPayload::ParticipantResponse(response.clone()));
+        let seq_num = self.next_response_seq_num;
+        self.next_response_seq_num += 1;
 
+        self.participants_response.send(Sequenced { shard_id: self.shard_id, seq_num, payload: response }).expect("participant response channel closed");
+    }
+
+    // the aggressor takes liquidity, the resting side and both sides of an auction make it. Busts and corrections
+    // come with their refund
+    fn fee(&self, response: &ParticipantResponse) -> Fee {
+        match (response.response_type, &self.fees) {
+            (ParticipantResponseType::Filled, Some(fees)) => fees.charge(response.participant_id, response.symbol_id, response.aggressor, response.exec_qty, self.now()),
+            (ParticipantResponseType::TradeBusted | ParticipantResponseType::TradeCorrected, _) => response.fee,
+            _ => 0,
+        }
+    }
+
+    pub fn send_trade(&mut self, trade: &Trade) {
+        self.logger.info("trade", LogPayload::Trade(trade.clone()));
+
+        if let Some(trades) = &self.trades {
+            trades.send(trade.clone()).expect("trade channel closed");
+        }
     }
 
     pub fn send_market_update(&mut self, update: &MarketUpdate) {
-        
+        let now = self.latency.now();
+        self.latency.record(Stage::MarketData, self.request_dequeue_time, now);
+
+        if update.update_type == MarketUpdateType::Trade {
+            self.latency.record(Stage::TickToTrade, self.request_recv_time, now);
+        }
+
+        self.logger.debug("market update", LogPayload::MarketUpdate(update.clone()));
+        let seq_num = self.next_market_update_seq_num;
+        self.next_market_update_seq_num += 1;
+
+        self.market_data_updates.send(Sequenced { shard_id: self.shard_id, seq_num, payload: update.clone() }).expect("market data channel closed");
     }
 }
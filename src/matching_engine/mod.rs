@@ -1,3 +1,7 @@
+pub mod allocation;
+pub mod circuit_breaker;
 pub mod order;
+#[allow(clippy::module_inception)]
 pub mod matching_engine;
-pub mod orderbook;
\ No newline at end of file
+pub mod orderbook;
+pub mod trigger_book;
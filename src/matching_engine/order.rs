@@ -1,8 +1,8 @@
-use std::fmt;
+use std::{fmt, iter};
 
-use crate::common::{self, OrderId, INVALID_ORDER_ID};
+use crate::common;
 
-#[derive(Clone)]
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
 pub struct OrderInfo {
     pub participant_id: common::ParticipantId,
     pub order_id: common::OrderId,
@@ -23,6 +23,35 @@ impl fmt::Display for OrderInfo {
     }
 }
 
+// an order as entered by a participant, before it reaches the book
+#[derive(Clone)]
+pub struct NewOrder {
+    pub order_info: OrderInfo,
+    pub order_type: common::OrderType,
+    pub side: common::Side,
+    pub price: common::Price,
+    pub stop_price: common::Price,
+    pub qty: common::Quantity,
+    pub display_qty: common::Quantity,
+    pub flags: common::OrderFlags,
+}
+
+impl fmt::Display for NewOrder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f,  "NewOrder [order: {}, type: {}, side: {}, price: {}, stop_price: {}, qty: {}, display_qty: {}, flags: {:#04x}]",
+        self.order_info,
+        self.order_type,
+        self.side,
+        self.price,
+        self.stop_price,
+        self.qty,
+        self.display_qty,
+        self.flags)
+    }
+}
+
+// `qty` is what is displayed, an iceberg refreshes it from `reserve_qty` up to `display_qty` at a time
+#[derive(Clone)]
 pub struct Order {
     pub symbol_id: common::SymbolId,
     pub order_info: OrderInfo,
@@ -30,6 +59,9 @@ pub struct Order {
     pub side: common::Side,
     pub price: common::Price,
     pub qty: common::Quantity,
+    pub display_qty: common::Quantity,
+    pub reserve_qty: common::Quantity,
+    pub is_hidden: bool,
     pub priority: common::Priority,
     pub prev_order_info: OrderInfo,
     pub next_order_info: OrderInfo,
@@ -37,13 +69,16 @@ pub struct Order {
 
 impl fmt::Display for Order {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f,  "Order [symb: {}, order: {}, ioid: {}, side: {}, price: {}, qty: {}, priority: {}, prev: {}, next:{}]",
+        write!(f,  "Order [symb: {}, order: {}, ioid: {}, side: {}, price: {}, qty: {}, display_qty: {}, reserve_qty: {}, hidden: {}, priority: {}, prev: {}, next:{}]",
         self.symbol_id,
         self.order_info,
         self.internal_order_id,
         self.side,
         self.price,
         self.qty,
+        self.display_qty,
+        self.reserve_qty,
+        self.is_hidden,
         self.priority,
         self.prev_order_info,
         self.next_order_info)
@@ -59,6 +94,9 @@ impl Default for Order {
             side: common::Side::Invalid,
             price: common::INVALID_PRICE,
             qty: common::INVALID_QUANTITY,
+            display_qty: common::INVALID_QUANTITY,
+            reserve_qty: 0,
+            is_hidden: false,
             priority: common::INVALID_PRIORITY,
             prev_order_info: OrderInfo::default(),
             next_order_info: OrderInfo::default(),
@@ -105,21 +143,15 @@ pub type OrderAtPriceLevelHashMap = Vec<OrderAtPricePtr>; //MAP OrderAtPrice Wit
 pub type ParticipantOrderHashMap = Vec<OrderHashMap>; // MAP PARTICIPANT AND ORDERS
 
 
-pub fn create_order_at_price_level_hash_map() -> OrderAtPriceLevelHashMap {
-    let mut orders: OrderAtPriceLevelHashMap = Vec::new();
-    orders.reserve(common::MAX_PRICE_LEVELS);
-    orders
+pub fn create_order_at_price_level_hash_map(max_price_levels: usize) -> OrderAtPriceLevelHashMap {
+    iter::repeat_with(|| None).take(max_price_levels).collect()
 }
 
+// grown on demand up to max_order_ids, a fully sized map per participant would cost 8MB each
 pub fn create_order_hash_map() -> OrderHashMap {
-    let mut orders: OrderHashMap = Vec::new();
-    orders.reserve(common::MAX_ORDER_IDS);
-    orders
+    Vec::new()
 }
 
-pub fn create_participant_order_hash_map() -> ParticipantOrderHashMap  {
-    let mut orders: ParticipantOrderHashMap = Vec::new();
-    orders.reserve(common::MAX_PARTICIPANTS_NUMBER);
-    orders
+pub fn create_participant_order_hash_map(max_participants: usize) -> ParticipantOrderHashMap  {
+    iter::repeat_with(create_order_hash_map).take(max_participants).collect()
 }
-
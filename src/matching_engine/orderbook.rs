@@ -1,14 +1,35 @@
-use std::iter;
+use std::{cmp, collections::VecDeque, iter, mem};
 
 use refpool::PoolBox;
 
-use crate::{common::{OrderId, ParticipantId, Price, Priority, Quantity, Side, SymbolId, INVALID_ORDER_ID, INVALID_PRICE, INVALID_QUANTITY, MAX_ORDER_IDS, MAX_PRICE_LEVELS}, market_data::market_update::{MarketUpdate, MarketUpdateType}, order_server::participants_response::{ParticipantResponse, ParticipantResponseType}};
+use crate::{common::{side_to_index, Nanos, OrderId, OrderType, ORDER_FLAG_HIDDEN, ORDER_FLAG_POST_ONLY, ORDER_FLAG_POST_ONLY_SLIDE, Price, Priority, Quantity, Side, SymbolId, TradeId, INVALID_ORDER_ID, INVALID_PRICE, INVALID_PRIORITY, INVALID_QUANTITY, INVALID_TRADE_ID, MAX_ORDER_IDS, MAX_PARTICIPANTS_NUMBER, MAX_PRICE_LEVELS}, logging::logger::{LogLevel, LogPayload}, market_data::market_update::{MarketUpdate, MarketUpdateType}, order_server::participants_response::{ParticipantResponse, ParticipantResponseType}, trades::trade::Trade};
 
-use super::{matching_engine::MatchingEngine, order::{self, create_order_at_price_level_hash_map, create_participant_order_hash_map, Order, OrderAtPrice, OrderAtPriceLevelHashMap, OrderAtPricePtr, OrderInfo, OrderPtr, ParticipantOrderHashMap}};
+use super::{allocation::AllocationPolicy, circuit_breaker::{CircuitBreaker, CircuitBreakerConfig, HaltOrderPolicy, TradingState}, matching_engine::MatchingEngine, trigger_book::{StopOrder, TriggerBook}, order::{create_order_at_price_level_hash_map, create_participant_order_hash_map, NewOrder, Order, OrderAtPrice, OrderAtPriceLevelHashMap, OrderAtPricePtr, OrderInfo, OrderPtr, ParticipantOrderHashMap}};
 
-struct OrderBook {
+#[derive(Clone)]
+pub struct OrderBookConfig {
+    pub circuit_breaker: CircuitBreakerConfig,
+    pub allocation_policy: AllocationPolicy,
+    pub max_order_ids: usize,
+    pub max_price_levels: usize,
+    pub max_participants: usize,
+}
+
+impl Default for OrderBookConfig {
+    fn default() -> Self {
+        Self {
+            circuit_breaker: CircuitBreakerConfig::default(),
+            allocation_policy: AllocationPolicy::default(),
+            max_order_ids: MAX_ORDER_IDS,
+            max_price_levels: MAX_PRICE_LEVELS,
+            max_participants: MAX_PARTICIPANTS_NUMBER,
+        }
+    }
+}
+
+pub struct OrderBook {
     participants_orders: ParticipantOrderHashMap,
-    orders_at_price_level: OrderAtPriceLevelHashMap, 
+    orders_at_price_level: Vec<OrderAtPriceLevelHashMap>, //one map per side, an auction can hold a bid and an ask at the same price
     order_at_price_level_pool: refpool::Pool<OrderAtPrice>,
     order_pool: refpool::Pool<Order>,
     symbol_id: SymbolId,
@@ -17,231 +38,898 @@ struct OrderBook {
     next_internal_order_id: OrderId,
     participant_response: ParticipantResponse,
     market_update: MarketUpdate,
+    circuit_breaker: CircuitBreaker,
+    queued_orders: VecDeque<NewOrder>,
+    trigger_book: TriggerBook,
+    allocation_policy: AllocationPolicy,
+    level_order_infos: Vec<OrderInfo>,
+    level_qtys: Vec<Quantity>,
+    allocations: Vec<Quantity>,
+    max_order_ids: usize,
+    max_price_levels: usize, //also the index of a missing level
 }
 
 
 impl OrderBook {
-    pub fn new(symbol_id: SymbolId) -> Self {
+    pub fn new(symbol_id: SymbolId, config: OrderBookConfig) -> Self {
         Self {
-            participants_orders: create_participant_order_hash_map(),
-            orders_at_price_level: create_order_at_price_level_hash_map(),
-            order_at_price_level_pool: refpool::Pool::new(MAX_PRICE_LEVELS as usize),
-            order_pool: refpool::Pool::new(MAX_ORDER_IDS as usize),
+            participants_orders: create_participant_order_hash_map(config.max_participants),
+            orders_at_price_level: iter::repeat_with(|| create_order_at_price_level_hash_map(config.max_price_levels)).take(2).collect(),
+            order_at_price_level_pool: refpool::Pool::new(config.max_price_levels),
+            order_pool: refpool::Pool::new(config.max_order_ids),
             symbol_id,
-            best_bid_idx: MAX_PRICE_LEVELS,
-            best_ask_idx: MAX_PRICE_LEVELS,
+            best_bid_idx: config.max_price_levels,
+            best_ask_idx: config.max_price_levels,
             next_internal_order_id: 1,
             participant_response: ParticipantResponse::default(),
-            market_update: MarketUpdate::default()
+            market_update: MarketUpdate::default(),
+            circuit_breaker: CircuitBreaker::new(config.circuit_breaker),
+            queued_orders: VecDeque::new(),
+            trigger_book: TriggerBook::new(),
+            allocation_policy: config.allocation_policy,
+            level_order_infos: Vec::new(),
+            level_qtys: Vec::new(),
+            allocations: Vec::new(),
+            max_order_ids: config.max_order_ids,
+            max_price_levels: config.max_price_levels,
         }
     }
 
+    pub fn trading_state(&self) -> TradingState {
+        self.circuit_breaker.state()
+    }
+
+    fn price_to_index(&self, price: Price) -> usize {
+        price as usize % self.max_price_levels
+    }
+
     fn generate_new_order_id(&mut self) -> OrderId {
         let id = self.next_internal_order_id;
         self.next_internal_order_id += 1;
         id
     }
 
-    fn get_order_at_price(&self, price: Price) -> &OrderAtPricePtr {
-        &self.orders_at_price_level[price_to_index(price)]
+    fn get_order_at_price(&self, side: Side, price: Price) -> &OrderAtPricePtr {
+        &self.orders_at_price_level[side_to_index(side)][self.price_to_index(price)]
     }
 
     fn get_participant_order(&self, order_info: OrderInfo) -> &OrderPtr {
-        &self.participants_orders[order_info.participant_id as usize][order_info.order_id as usize]
+        self.participants_orders[order_info.participant_id as usize].get(order_info.order_id as usize).unwrap_or(&None)
+    }
+
+    fn get_best_idx(&self, side: Side) -> usize {
+        match side {
+            Side::Buy => self.best_bid_idx,
+            Side::Sell => self.best_ask_idx,
+            Side::Invalid => panic!("INVALID side aren't taken into account"),
+        }
+    }
+
+    fn set_best_idx(&mut self, side: Side, idx: usize) {
+        match side {
+            Side::Buy => self.best_bid_idx = idx,
+            Side::Sell => self.best_ask_idx = idx,
+            Side::Invalid => panic!("INVALID side aren't taken into account"),
+        }
+    }
+
+    fn get_best_order_at_price(&self, side: Side) -> Option<&PoolBox<OrderAtPrice>> {
+        let best_idx = self.get_best_idx(side);
+
+        if best_idx == self.max_price_levels {
+            return None;
+        }
+
+        self.orders_at_price_level[side_to_index(side)][best_idx].as_ref()
+    }
+
+    // a price can only rest if its slot is free or already holds the same price, prices max_price_levels apart collide
+    fn is_price_available(&self, side: Side, price: Price) -> bool {
+        match self.get_order_at_price(side, price) {
+            Some(order_at_price) => order_at_price.price == price,
+            None => true,
+        }
+    }
+
+    fn check_for_match(&mut self, order_info: OrderInfo, side: Side, price: Price, qty: Quantity, internal_order_id: OrderId, engine: &mut MatchingEngine) -> Quantity {
+        let mut leaves_qty = qty;
+        let passive_side = opposite_side(side);
+
+        while leaves_qty > 0 {
+            let (passive_price, passive_order_info) = match self.get_best_order_at_price(passive_side) {
+                Some(order_at_price) => (order_at_price.price, order_at_price.head_order_info.clone()),
+                None => break,
+            };
+
+            if !is_marketable(side, price, passive_price) {
+                break;
+            }
+
+            // the remainder rests, possibly crossed, and is matched when the book is uncrossed on resume
+            if !self.circuit_breaker.is_within_bands(passive_price) {
+                self.trip_circuit_breaker(passive_price, engine);
+                break;
+            }
+
+            leaves_qty = match self.allocation_policy {
+                AllocationPolicy::Fifo => self.match_head(order_info.clone(), side, internal_order_id, leaves_qty, passive_order_info, engine),
+                _ => self.match_level(order_info.clone(), side, internal_order_id, leaves_qty, passive_order_info, engine),
+            };
+        }
+
+        leaves_qty
+    }
+
+    fn match_head(&mut self, order_info: OrderInfo, side: Side, internal_order_id: OrderId, leaves_qty: Quantity, head_order_info: OrderInfo, engine: &mut MatchingEngine) -> Quantity {
+        let fill_qty = cmp::min(leaves_qty, self.get_participant_order(head_order_info.clone()).as_ref().unwrap().qty);
+        self.match_order(order_info, side, internal_order_id, leaves_qty, head_order_info, fill_qty, engine)
+    }
+
+    // splits the incoming quantity across the displayed orders of the level, hidden orders behind them only trade FIFO once they are gone
+    fn match_level(&mut self, order_info: OrderInfo, side: Side, internal_order_id: OrderId, mut leaves_qty: Quantity, head_order_info: OrderInfo, engine: &mut MatchingEngine) -> Quantity {
+        let mut level_order_infos = mem::take(&mut self.level_order_infos);
+        let mut level_qtys = mem::take(&mut self.level_qtys);
+        let mut allocations = mem::take(&mut self.allocations);
+
+        level_order_infos.clear();
+        level_qtys.clear();
+
+        let mut passive_order_info = head_order_info.clone();
+
+        loop {
+            let passive_order = self.get_participant_order(passive_order_info.clone()).as_ref().unwrap();
+
+            if passive_order.is_hidden {
+                break;
+            }
+
+            level_order_infos.push(passive_order_info);
+            level_qtys.push(passive_order.qty);
+            passive_order_info = passive_order.next_order_info.clone();
+
+            if passive_order_info == head_order_info {
+                break;
+            }
+        }
+
+        if level_order_infos.is_empty() {
+            leaves_qty = self.match_head(order_info, side, internal_order_id, leaves_qty, head_order_info, engine);
+        } else {
+            self.allocation_policy.allocate(leaves_qty, &level_qtys, &mut allocations);
+
+            for (passive_order_info, fill_qty) in level_order_infos.iter().zip(allocations.iter()) {
+                if *fill_qty > 0 {
+                    leaves_qty = self.match_order(order_info.clone(), side, internal_order_id, leaves_qty, passive_order_info.clone(), *fill_qty, engine);
+                }
+            }
+        }
+
+        self.level_order_infos = level_order_infos;
+        self.level_qtys = level_qtys;
+        self.allocations = allocations;
+
+        leaves_qty
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn match_order(&mut self, order_info: OrderInfo, side: Side, internal_order_id: OrderId, leaves_qty: Quantity, passive_order_info: OrderInfo, fill_qty: Quantity, engine: &mut MatchingEngine) -> Quantity {
+        let price = self.get_participant_order(passive_order_info.clone()).as_ref().unwrap().price;
+
+        let leaves_qty = leaves_qty - fill_qty;
+        let trade_id = engine.next_trade_id();
+
+        self.participant_response = ParticipantResponse {
+            response_type: ParticipantResponseType::Filled,
+            participant_id: order_info.participant_id,
+            participant_order_id: order_info.order_id,
+            symbol_id: self.symbol_id,
+            internal_order_id,
+            side,
+            price,
+            exec_qty: fill_qty,
+            leaves_qty,
+            aggressor: true,
+            trade_id,
+            fee: 0,
+        };
+
+        engine.send_participant_response(&self.participant_response);
+
+        let (buy_order_info, sell_order_info) = match side {
+            Side::Buy => (&order_info, &passive_order_info),
+            _ => (&passive_order_info, &order_info),
+        };
+
+        self.publish_trade(trade_id, side, buy_order_info, sell_order_info, price, fill_qty, engine);
+        self.fill_resting_order(passive_order_info, price, fill_qty, trade_id, engine);
+
+        leaves_qty
     }
 
-    fn check_for_match(&self, order_info: OrderInfo, price: Price, qty: Quantity, internal_order_id: OrderId) -> Quantity {
-        return 0;
+    #[allow(clippy::too_many_arguments)]
+    fn publish_trade(&mut self, trade_id: TradeId, aggressor_side: Side, buy_order_info: &OrderInfo, sell_order_info: &OrderInfo, price: Price, qty: Quantity, engine: &mut MatchingEngine) {
+        self.circuit_breaker.on_trade(price);
+        self.trigger_book.on_trade(price);
+
+        engine.send_trade(&Trade {
+            trade_id,
+            symbol_id: self.symbol_id,
+            aggressor_side,
+            price,
+            qty,
+            buy_participant_id: buy_order_info.participant_id,
+            buy_order_id: buy_order_info.order_id,
+            sell_participant_id: sell_order_info.participant_id,
+            sell_order_id: sell_order_info.order_id,
+            timestamp: engine.now(),
+        });
+
+        self.market_update = MarketUpdate {
+            update_type: MarketUpdateType::Trade,
+            order_id: INVALID_ORDER_ID,
+            symbol_id: self.symbol_id,
+            side: aggressor_side,
+            price,
+            qty,
+            priority: INVALID_PRIORITY,
+            trade_id,
+        };
+
+        engine.send_market_update(&self.market_update);
     }
 
-    fn get_next_priority(&self, price: Price) -> Priority {
-        let order_at_price_level_opt = self.get_order_at_price(price);
+    fn fill_resting_order(&mut self, order_info: OrderInfo, price: Price, fill_qty: Quantity, trade_id: TradeId, engine: &mut MatchingEngine) {
+        let (is_filled, is_hidden) = {
+            let order = self.participants_orders[order_info.participant_id as usize][order_info.order_id as usize].as_mut().unwrap();
+            order.qty -= fill_qty;
+
+            self.participant_response = ParticipantResponse {
+                response_type: ParticipantResponseType::Filled,
+                participant_id: order_info.participant_id,
+                participant_order_id: order_info.order_id,
+                symbol_id: self.symbol_id,
+                internal_order_id: order.internal_order_id,
+                side: order.side,
+                price,
+                exec_qty: fill_qty,
+                leaves_qty: order.qty + order.reserve_qty,
+                aggressor: false,
+                trade_id,
+                fee: 0,
+            };
 
-        if let Some(order_at_price_level) = order_at_price_level_opt {
-            let head_order_info = order_at_price_level.head_order_info.clone();
-            let head_order = self.get_participant_order(head_order_info).as_ref().unwrap();
+            self.market_update = MarketUpdate {
+                update_type: if order.qty == 0 { MarketUpdateType::Cancel } else { MarketUpdateType::Modify },
+                order_id: order.internal_order_id,
+                symbol_id: self.symbol_id,
+                side: order.side,
+                price: order.price,
+                qty: order.qty,
+                priority: order.priority,
+                trade_id: INVALID_TRADE_ID,
+            };
 
-            let last_order_info = head_order.prev_order_info.clone();
+            (order.qty == 0, order.is_hidden)
+        };
+
+        engine.send_participant_response(&self.participant_response);
+
+        if !is_hidden {
+            engine.send_market_update(&self.market_update);
+        }
+
+        if is_filled {
+            let order = self.remove_order(order_info);
+
+            if order.reserve_qty > 0 {
+                self.refresh_iceberg(order, engine);
+            }
+        }
+    }
+
+    // the next peak comes out of the reserve and joins the back of the queue with a new priority
+    fn refresh_iceberg(&mut self, mut order: PoolBox<Order>, engine: &mut MatchingEngine) {
+        order.qty = cmp::min(order.display_qty, order.reserve_qty);
+        order.reserve_qty -= order.qty;
+        order.priority = self.get_next_priority(order.side, order.price, order.is_hidden);
+
+        self.market_update = MarketUpdate {
+            update_type: MarketUpdateType::Add,
+            order_id: order.internal_order_id,
+            symbol_id: self.symbol_id,
+            side: order.side,
+            price: order.price,
+            qty: order.qty,
+            priority: order.priority,
+            trade_id: INVALID_TRADE_ID,
+        };
+
+        self.add_order(order);
+
+        engine.send_market_update(&self.market_update);
+    }
+
+    fn trip_circuit_breaker(&mut self, price: Price, engine: &mut MatchingEngine) {
+        let update_type = match self.circuit_breaker.trip(engine.now()) {
+            TradingState::Halted => MarketUpdateType::Halt,
+            TradingState::VolatilityAuction => MarketUpdateType::VolatilityAuction,
+            TradingState::Continuous => unreachable!(),
+        };
+
+        self.publish_trading_status(update_type, price, engine);
+    }
+
+    fn publish_trading_status(&mut self, update_type: MarketUpdateType, price: Price, engine: &mut MatchingEngine) {
+        self.market_update = MarketUpdate {
+            update_type,
+            order_id: INVALID_ORDER_ID,
+            symbol_id: self.symbol_id,
+            side: Side::Invalid,
+            price,
+            qty: INVALID_QUANTITY,
+            priority: INVALID_PRIORITY,
+            trade_id: INVALID_TRADE_ID,
+        };
+
+        engine.send_market_update(&self.market_update);
+    }
+
+    pub fn on_timer(&mut self, now: Nanos, engine: &mut MatchingEngine) {
+        if self.circuit_breaker.should_resume(now) {
+            self.resume(engine);
+        }
+    }
+
+    fn resume(&mut self, engine: &mut MatchingEngine) {
+        self.uncross(engine);
+        self.circuit_breaker.resume();
+
+        let last_trade_price = self.circuit_breaker.last_trade_price();
+        self.publish_trading_status(MarketUpdateType::Resume, last_trade_price, engine);
+
+        self.release_triggered_stops(engine);
+
+        // queued orders are acknowledged when they are released, a new breach keeps the rest queued in arrival order
+        while self.circuit_breaker.state() == TradingState::Continuous {
+            match self.queued_orders.pop_front() {
+                Some(new_order) => self.add(new_order, engine),
+                None => break,
+            }
+        }
+    }
+
+    fn get_level_qty(&self, order_at_price: &OrderAtPrice) -> u64 {
+        let mut qty = 0;
+        let mut order_info = order_at_price.head_order_info.clone();
+
+        loop {
+            let order = self.get_participant_order(order_info).as_ref().unwrap();
+            qty += (order.qty + order.reserve_qty) as u64;
+            order_info = order.next_order_info.clone();
+
+            if order_info == order_at_price.head_order_info {
+                break;
+            }
+        }
+
+        qty
+    }
+
+    // quantity resting on `side` at prices at least as aggressive as `price`
+    fn get_cumulative_qty(&self, side: Side, price: Price) -> u64 {
+        let mut qty = 0;
+        let levels = &self.orders_at_price_level[side_to_index(side)];
+        let best_idx = self.get_best_idx(side);
+        let mut idx = best_idx;
+
+        while idx != self.max_price_levels {
+            let order_at_price = levels[idx].as_ref().unwrap();
+
+            if !is_marketable(opposite_side(side), price, order_at_price.price) {
+                break;
+            }
+
+            qty += self.get_level_qty(order_at_price);
+            idx = order_at_price.next_idx;
+
+            if idx == best_idx {
+                break;
+            }
+        }
+
+        qty
+    }
+
+    // the price maximizing executed volume, ties go to the price closest to the last trade
+    fn get_auction_price(&self) -> Option<Price> {
+        let best_bid_price = self.get_best_order_at_price(Side::Buy)?.price;
+        let best_ask_price = self.get_best_order_at_price(Side::Sell)?.price;
+
+        if best_bid_price < best_ask_price {
+            return None;
+        }
+
+        let reference_price = self.circuit_breaker.last_trade_price();
+        let mut auction_price = None;
+        let mut auction_volume = 0;
+
+        for side in [Side::Buy, Side::Sell] {
+            let levels = &self.orders_at_price_level[side_to_index(side)];
+            let best_idx = self.get_best_idx(side);
+            let mut idx = best_idx;
+
+            loop {
+                let candidate_price = levels[idx].as_ref().unwrap().price;
+
+                if candidate_price < best_ask_price || candidate_price > best_bid_price {
+                    break;
+                }
+
+                let volume = cmp::min(self.get_cumulative_qty(Side::Buy, candidate_price), self.get_cumulative_qty(Side::Sell, candidate_price));
+                let is_closer = auction_price.is_some_and(|price: Price| reference_price != INVALID_PRICE && candidate_price.abs_diff(reference_price) < price.abs_diff(reference_price));
+
+                if volume > auction_volume || (volume == auction_volume && is_closer) {
+                    auction_price = Some(candidate_price);
+                    auction_volume = volume;
+                }
+
+                idx = levels[idx].as_ref().unwrap().next_idx;
+
+                if idx == best_idx {
+                    break;
+                }
+            }
+        }
+
+        auction_price
+    }
+
+    fn uncross(&mut self, engine: &mut MatchingEngine) {
+        let auction_price = match self.get_auction_price() {
+            Some(price) => price,
+            None => return,
+        };
+
+        loop {
+            let (bid_order_info, ask_order_info) = match (self.get_best_order_at_price(Side::Buy), self.get_best_order_at_price(Side::Sell)) {
+                (Some(bid), Some(ask)) if bid.price >= auction_price && ask.price <= auction_price => (bid.head_order_info.clone(), ask.head_order_info.clone()),
+                _ => break,
+            };
+
+            let fill_qty = cmp::min(self.get_participant_order(bid_order_info.clone()).as_ref().unwrap().qty,
+                                    self.get_participant_order(ask_order_info.clone()).as_ref().unwrap().qty);
+
+            // an auction trade has no aggressor, both fills are resting orders and are billed as making liquidity
+            let trade_id = engine.next_trade_id();
+            self.publish_trade(trade_id, Side::Invalid, &bid_order_info, &ask_order_info, auction_price, fill_qty, engine);
+            self.fill_resting_order(bid_order_info, auction_price, fill_qty, trade_id, engine);
+            self.fill_resting_order(ask_order_info, auction_price, fill_qty, trade_id, engine);
+        }
+    }
+
+    // hidden orders queue behind every displayed order of their level, this finds the back of the queue of one kind
+    fn get_last_order_info(&self, side: Side, price: Price, is_hidden: bool) -> Option<OrderInfo> {
+        let head_order_info = self.get_order_at_price(side, price).as_ref()?.head_order_info.clone();
+        let mut order_info = self.get_participant_order(head_order_info.clone()).as_ref().unwrap().prev_order_info.clone();
+
+        loop {
+            let order = self.get_participant_order(order_info.clone()).as_ref().unwrap();
+
+            if order.is_hidden == is_hidden {
+                return Some(order_info);
+            }
+
+            if is_hidden || order_info == head_order_info {
+                return None;
+            }
+
+            order_info = order.prev_order_info.clone();
+        }
+    }
+
+    fn get_next_priority(&self, side: Side, price: Price, is_hidden: bool) -> Priority {
+        if let Some(last_order_info) = self.get_last_order_info(side, price, is_hidden) {
             let last_order = self.get_participant_order(last_order_info).as_ref().unwrap();
 
             return last_order.priority + 1;
         }
 
-        1 
+        1
     }
 
     fn add_order_at_price(&mut self, mut new_order_at_price: PoolBox<OrderAtPrice>) {
-        let new_order_at_price_index = price_to_index(new_order_at_price.price);
+        let side = new_order_at_price.side;
+        let side_idx = side_to_index(side);
+        let new_order_at_price_index = self.price_to_index(new_order_at_price.price);
+        let best_order_at_price_index = self.get_best_idx(side);
 
-        let (best_order_at_price_index, is_not_better): (&mut _, Box<dyn Fn(&OrderAtPrice, &OrderAtPrice) -> bool>) = match new_order_at_price.side {
-            Side::Invalid => panic!("INVALID side aren't taken into account"),
-            Side::Buy => {
-                let compare = Box::new(|order1: &OrderAtPrice, order2: &OrderAtPrice| order1.price < order2.price);
-                (&mut self.best_bid_idx, compare)
-            },
-            Side::Sell => {
-                let compare = Box::new(|order1: &OrderAtPrice, order2: &OrderAtPrice| order1.price > order2.price);
-                (&mut self.best_ask_idx, compare)
-            },
-        };
-        
-
-        if *best_order_at_price_index == MAX_PRICE_LEVELS {
+        if best_order_at_price_index == self.max_price_levels {
             new_order_at_price.next_idx = new_order_at_price_index;
             new_order_at_price.prev_idx = new_order_at_price_index;
-            *best_order_at_price_index = new_order_at_price_index;
+            self.set_best_idx(side, new_order_at_price_index);
         } else {
-            let mut target_idx = *best_order_at_price_index;
-            
-            let mut add_after = true;
+            // walk from the best level to the first one the new level beats, wrapping around to the best means it goes last
+            let mut target_idx = best_order_at_price_index;
 
-            let mut is_first_iter = true;
+            loop {
+                let order_at_price = self.orders_at_price_level[side_idx][target_idx].as_ref().unwrap();
 
-            while add_after && (is_first_iter || target_idx != *best_order_at_price_index) {
-                let order_at_price = self.orders_at_price_level[target_idx].as_ref().unwrap();
-                add_after = is_not_better(&new_order_at_price, &order_at_price);
-                if add_after {
-                    target_idx = order_at_price.next_idx;
+                if is_better(side, new_order_at_price.price, order_at_price.price) {
+                    break;
                 }
-                is_first_iter = false;  
-            }
 
-            if add_after {
-                let prev_order_at_price_next_idx: usize;
+                target_idx = order_at_price.next_idx;
 
-                {
-                    let prev_order_at_price = self.orders_at_price_level[target_idx].as_mut().unwrap();
-                    prev_order_at_price_next_idx = prev_order_at_price.next_idx;
-                    new_order_at_price.next_idx = prev_order_at_price_next_idx;
-                    new_order_at_price.prev_idx = target_idx;
-                    prev_order_at_price.next_idx = new_order_at_price_index;
+                if target_idx == best_order_at_price_index {
+                    break;
                 }
+            }
 
-                let next_order_at_price = self.orders_at_price_level[target_idx].as_mut().unwrap();
-                next_order_at_price.prev_idx = target_idx;
-            } else {
-                let prev_order_at_price_prev_idx: usize;
-                
-                {
-                    let next_order_at_price = self.orders_at_price_level[target_idx].as_mut().unwrap();
-                    prev_order_at_price_prev_idx = next_order_at_price.prev_idx;
-                    new_order_at_price.next_idx = target_idx;
-                    new_order_at_price.prev_idx = prev_order_at_price_prev_idx;
-                    next_order_at_price.prev_idx = new_order_at_price_index;
-                }
+            let prev_idx = self.orders_at_price_level[side_idx][target_idx].as_ref().unwrap().prev_idx;
+            new_order_at_price.prev_idx = prev_idx;
+            new_order_at_price.next_idx = target_idx;
+
+            self.orders_at_price_level[side_idx][prev_idx].as_mut().unwrap().next_idx = new_order_at_price_index;
+            self.orders_at_price_level[side_idx][target_idx].as_mut().unwrap().prev_idx = new_order_at_price_index;
 
-                let prev_order_at_price = self.orders_at_price_level[prev_order_at_price_prev_idx].as_mut().unwrap();
-                prev_order_at_price.next_idx = target_idx; 
+            let best_price = self.orders_at_price_level[side_idx][best_order_at_price_index].as_ref().unwrap().price;
+
+            if is_better(side, new_order_at_price.price, best_price) {
+                self.set_best_idx(side, new_order_at_price_index);
             }
+        }
+
+        self.orders_at_price_level[side_idx][new_order_at_price_index] = Some(new_order_at_price);
+    }
+
+    fn remove_order_at_price(&mut self, side: Side, price: Price) {
+        let side_idx = side_to_index(side);
+        let order_at_price_index = self.price_to_index(price);
+
+        let (prev_idx, next_idx) = {
+            let order_at_price = self.orders_at_price_level[side_idx][order_at_price_index].as_ref().unwrap();
+            (order_at_price.prev_idx, order_at_price.next_idx)
+        };
+
+        if next_idx == order_at_price_index {
+            self.set_best_idx(side, self.max_price_levels);
+        } else {
+            self.orders_at_price_level[side_idx][prev_idx].as_mut().unwrap().next_idx = next_idx;
+            self.orders_at_price_level[side_idx][next_idx].as_mut().unwrap().prev_idx = prev_idx;
 
-            let best_order_at_price = self.orders_at_price_level[*best_order_at_price_index].as_mut().unwrap();
-            
-            if !is_not_better(&new_order_at_price, &best_order_at_price) {
-                *best_order_at_price_index = new_order_at_price_index;
+            if self.get_best_idx(side) == order_at_price_index {
+                self.set_best_idx(side, next_idx);
             }
         }
 
-        
-        self.orders_at_price_level[new_order_at_price_index] = Some(new_order_at_price);
+        self.orders_at_price_level[side_idx][order_at_price_index] = None;
     }
 
     fn add_order(&mut self,  mut order: PoolBox<Order>) {
-        let order_at_price_level_opt = self.get_order_at_price(order.price);
+        let order_at_price_level_opt = self.get_order_at_price(order.side, order.price);
         let order_info = OrderInfo{order_id: order.order_info.order_id, participant_id: order.order_info.participant_id};
 
-        if let None = order_at_price_level_opt {
+        if order_at_price_level_opt.is_none() {
             order.next_order_info = order_info.clone();
             order.prev_order_info = order_info.clone();
 
             let new_order_at_price = PoolBox::new(&self.order_at_price_level_pool, OrderAtPrice{
-                side: order.side.clone(),
+                side: order.side,
                 price: order.price,
                 head_order_info: order_info.clone(),
-                prev_idx: MAX_PRICE_LEVELS,
-                next_idx: MAX_PRICE_LEVELS
+                prev_idx: self.max_price_levels,
+                next_idx: self.max_price_levels
             });
 
             self.add_order_at_price(new_order_at_price);
         } else {
             let head_order_info = order_at_price_level_opt.as_ref().unwrap().head_order_info.clone();
-            let last_order_info: OrderInfo;
+            let last_order_info = self.get_last_order_info(order.side, order.price, order.is_hidden);
+            let prev_order_info: OrderInfo;
+            let next_order_info: OrderInfo;
+
+            // a displayed order with only hidden orders ahead of it becomes the new head
+            let is_new_head = !order.is_hidden && last_order_info.is_none();
 
             {
                 let head_order = self.get_participant_order(head_order_info.clone()).as_ref().unwrap();
-                last_order_info = head_order.prev_order_info.clone();
+                prev_order_info = last_order_info.unwrap_or(head_order.prev_order_info.clone());
+                next_order_info = self.get_participant_order(prev_order_info.clone()).as_ref().unwrap().next_order_info.clone();
             }
 
             {
-                order.prev_order_info = last_order_info.clone();
-                order.next_order_info = head_order_info.clone();
-                let last_order = self.participants_orders[last_order_info.participant_id as usize][last_order_info.order_id as usize].as_mut().unwrap();
-                last_order.next_order_info = order_info.clone();
+                order.prev_order_info = prev_order_info.clone();
+                order.next_order_info = next_order_info.clone();
+                let prev_order = self.participants_orders[prev_order_info.participant_id as usize][prev_order_info.order_id as usize].as_mut().unwrap();
+                prev_order.next_order_info = order_info.clone();
             }
 
             {
-                let head_order = self.participants_orders[head_order_info.participant_id as usize][head_order_info.order_id as usize].as_mut().unwrap();
-                head_order.prev_order_info = order_info.clone();
-            } 
+                let next_order = self.participants_orders[next_order_info.participant_id as usize][next_order_info.order_id as usize].as_mut().unwrap();
+                next_order.prev_order_info = order_info.clone();
+            }
+
+            if is_new_head {
+                let order_at_price_index = self.price_to_index(order.price);
+                let order_at_price = self.orders_at_price_level[side_to_index(order.side)][order_at_price_index].as_mut().unwrap();
+                order_at_price.head_order_info = order_info.clone();
+            }
+        }
+
+        let orders = &mut self.participants_orders[order_info.participant_id as usize];
+        let order_idx = order_info.order_id as usize;
+
+        if orders.len() <= order_idx {
+            orders.resize_with(order_idx + 1, || None);
+        }
+
+        orders[order_idx] = Some(order);
+    }
+
+    fn is_valid(&self, new_order: &NewOrder) -> bool {
+        let is_valid_order = new_order.side != Side::Invalid &&
+                             new_order.qty > 0 && new_order.qty != INVALID_QUANTITY && new_order.display_qty > 0 &&
+                             new_order.order_info.order_id < self.max_order_ids as OrderId &&
+                             self.get_participant_order(new_order.order_info.clone()).is_none() &&
+                             !self.is_pending(&new_order.order_info);
+
+        let is_valid_price = |price: Price| price != INVALID_PRICE && self.is_price_available(new_order.side, price);
+
+        // a stop already reached by the last trade is on the wrong side of the market
+        let is_valid_stop_price = || new_order.stop_price != INVALID_PRICE &&
+                                     !TriggerBook::is_triggered(new_order.side, new_order.stop_price, self.circuit_breaker.last_trade_price());
+
+        // post-only only makes sense for a limit order, a hidden order can't have a displayed peak
+        let is_post_only = new_order.flags & (ORDER_FLAG_POST_ONLY | ORDER_FLAG_POST_ONLY_SLIDE) != 0;
+        let is_hidden = new_order.flags & ORDER_FLAG_HIDDEN != 0;
+        let is_valid_flags = (!is_post_only || new_order.order_type == OrderType::Limit) &&
+                             (!is_hidden || (new_order.display_qty == INVALID_QUANTITY && matches!(new_order.order_type, OrderType::Limit | OrderType::StopLimit)));
+
+        is_valid_order && is_valid_flags && match new_order.order_type {
+            OrderType::Limit => is_valid_price(new_order.price),
+            OrderType::Market => true,
+            OrderType::Stop => is_valid_stop_price(),
+            OrderType::StopLimit => is_valid_stop_price() && is_valid_price(new_order.price),
+            OrderType::Invalid => false,
+        }
+    }
+
+    // a stop waiting for its trigger and an order queued by a halt keep their id, like a resting order
+    fn is_pending(&self, order_info: &OrderInfo) -> bool {
+        self.trigger_book.contains(order_info) || self.queued_orders.iter().any(|queued_order| queued_order.order_info == *order_info)
+    }
+
+    // returns false if a post-only order has to be rejected, in slide mode a crossing order is repriced one tick away from the best opposite price.
+    // Hidden quantity counts, a post-only order can't rest through it without the book crossing. The reject or the slide
+    // tells its sender there is hidden liquidity at that price, which nothing else publishes
+    fn apply_post_only(&self, new_order: &mut NewOrder) -> bool {
+        if new_order.flags & (ORDER_FLAG_POST_ONLY | ORDER_FLAG_POST_ONLY_SLIDE) == 0 || self.circuit_breaker.state() != TradingState::Continuous {
+            return true;
+        }
+
+        let passive_price = match self.get_best_order_at_price(opposite_side(new_order.side)) {
+            Some(order_at_price) => order_at_price.price,
+            None => return true,
+        };
+
+        if !is_marketable(new_order.side, new_order.price, passive_price) {
+            return true;
         }
 
-        self.participants_orders[order_info.participant_id as usize][order_info.order_id as usize] = Some(order);
+        if new_order.flags & ORDER_FLAG_POST_ONLY_SLIDE == 0 {
+            return false;
+        }
+
+        let slid_price = match new_order.side {
+            Side::Buy => passive_price.checked_sub(1),
+            _ => passive_price.checked_add(1).filter(|price| *price != INVALID_PRICE),
+        };
+
+        match slid_price {
+            Some(price) if self.is_price_available(new_order.side, price) => {
+                new_order.price = price;
+                true
+            },
+            _ => false,
+        }
     }
 
-    pub fn add(&mut self, order_info: OrderInfo, side: Side, price: Price, qty: Quantity, engine: &mut MatchingEngine) {
+    pub fn add(&mut self, mut new_order: NewOrder, engine: &mut MatchingEngine) {
+        if !self.is_valid(&new_order) || !self.apply_post_only(&mut new_order) {
+            self.reject(new_order, engine);
+            return;
+        }
+
+        if self.circuit_breaker.state() == TradingState::Halted {
+            match self.circuit_breaker.halt_order_policy() {
+                HaltOrderPolicy::Reject => self.reject(new_order, engine),
+                HaltOrderPolicy::Queue => self.queued_orders.push_back(new_order),
+            }
+            return;
+        }
+
         let internal_order_id = self.generate_new_order_id();
         self.participant_response = ParticipantResponse {
             response_type: ParticipantResponseType::Accepted,
-            participant_id: order_info.participant_id,
-            participant_order_id: order_info.order_id,
+            participant_id: new_order.order_info.participant_id,
+            participant_order_id: new_order.order_info.order_id,
             symbol_id: self.symbol_id,
             internal_order_id,
-            side: side.clone(),
-            price,
+            side: new_order.side,
+            price: new_order.price,
             exec_qty: 0,
-            leaves_qty: qty
+            leaves_qty: new_order.qty,
+            aggressor: false,
+            trade_id: INVALID_TRADE_ID,
+            fee: 0,
         };
 
         engine.send_participant_response(&self.participant_response);
 
-        let leaves_qty = self.check_for_match(order_info.clone(), price, qty, internal_order_id);
-
-        if leaves_qty > 0 {
-            let priority = self.get_next_priority(price);
-            let order = PoolBox::new(&self.order_pool, 
-                          Order { 
-                                    symbol_id: self.symbol_id, 
-                                    order_info: order_info.clone(),
-                                    internal_order_id, 
-                                    side: side.clone(), 
-                                    price, 
-                                    qty, 
-                                    priority, 
-                                    prev_order_info: OrderInfo::default(), 
-                                    next_order_info: OrderInfo::default() });
-
-            self.add_order(order);
-
-            let market_update = MarketUpdate{
-                update_type: MarketUpdateType::Add,
-                order_id: internal_order_id,
+        match new_order.order_type {
+            OrderType::Stop | OrderType::StopLimit => self.trigger_book.add(StopOrder { new_order, internal_order_id }),
+            _ => {
+                self.execute(new_order, internal_order_id, engine);
+                self.release_triggered_stops(engine);
+            },
+        }
+    }
+
+    // stops triggered while releasing earlier ones join the queue, so cascades are handled within the same event
+    fn release_triggered_stops(&mut self, engine: &mut MatchingEngine) {
+        while self.circuit_breaker.state() == TradingState::Continuous {
+            let mut stop_order = match self.trigger_book.pop_triggered() {
+                Some(stop_order) => stop_order,
+                None => break,
+            };
+
+            stop_order.new_order.order_type = match stop_order.new_order.order_type {
+                OrderType::Stop => OrderType::Market,
+                _ => OrderType::Limit,
+            };
+
+            self.execute(stop_order.new_order, stop_order.internal_order_id, engine);
+        }
+    }
+
+    fn execute(&mut self, new_order: NewOrder, internal_order_id: OrderId, engine: &mut MatchingEngine) {
+        let NewOrder { order_info, order_type, side, price, qty, display_qty, flags, .. } = new_order;
+        let is_hidden = flags & ORDER_FLAG_HIDDEN != 0;
+
+        let limit_price = match (order_type, side) {
+            (OrderType::Market, Side::Buy) => Price::MAX,
+            (OrderType::Market, _) => 0,
+            _ => price,
+        };
+
+        // during a volatility auction orders only accumulate, the book is uncrossed when the auction ends
+        let leaves_qty = match self.circuit_breaker.state() {
+            TradingState::Continuous => self.check_for_match(order_info.clone(), side, limit_price, qty, internal_order_id, engine),
+            _ => qty,
+        };
+
+        if leaves_qty == 0 {
+            return;
+        }
+
+        // market orders never rest, neither does a released stop-limit whose price level is taken
+        if order_type == OrderType::Market || !self.is_price_available(side, price) {
+            self.participant_response = ParticipantResponse {
+                response_type: ParticipantResponseType::Cancelled,
+                participant_id: order_info.participant_id,
+                participant_order_id: order_info.order_id,
                 symbol_id: self.symbol_id,
-                side: side.clone(),
+                internal_order_id,
+                side,
                 price,
-                priority,
-                qty
+                exec_qty: INVALID_QUANTITY,
+                leaves_qty,
+                aggressor: false,
+                trade_id: INVALID_TRADE_ID,
+                fee: 0,
             };
-            
-            engine.send_market_update(&market_update);
+
+            engine.send_participant_response(&self.participant_response);
+            return;
+        }
+
+        let priority = self.get_next_priority(side, price, is_hidden);
+        let peak_qty = cmp::min(display_qty, leaves_qty);
+        let order = PoolBox::new(&self.order_pool,
+                      Order {
+                                symbol_id: self.symbol_id,
+                                order_info,
+                                internal_order_id,
+                                side,
+                                price,
+                                qty: peak_qty,
+                                display_qty,
+                                reserve_qty: leaves_qty - peak_qty,
+                                is_hidden,
+                                priority,
+                                prev_order_info: OrderInfo::default(),
+                                next_order_info: OrderInfo::default() });
+
+        if engine.logger().is_enabled(LogLevel::Debug) {
+            engine.logger().debug("order resting", LogPayload::Order((*order).clone()));
+        }
+        self.add_order(order);
+
+        if is_hidden {
+            return;
         }
+
+        self.market_update = MarketUpdate{
+            update_type: MarketUpdateType::Add,
+            order_id: internal_order_id,
+            symbol_id: self.symbol_id,
+            side,
+            price,
+            priority,
+            qty: peak_qty,
+            trade_id: INVALID_TRADE_ID,
+        };
+
+        engine.send_market_update(&self.market_update);
     }
 
-    fn remove_order(&mut self, order_info: OrderInfo) {
-        
+    fn reject(&mut self, new_order: NewOrder, engine: &mut MatchingEngine) {
+        self.participant_response = ParticipantResponse {
+            response_type: ParticipantResponseType::Rejected,
+            participant_id: new_order.order_info.participant_id,
+            participant_order_id: new_order.order_info.order_id,
+            symbol_id: self.symbol_id,
+            internal_order_id: INVALID_ORDER_ID,
+            side: new_order.side,
+            price: new_order.price,
+            exec_qty: INVALID_QUANTITY,
+            leaves_qty: new_order.qty,
+            aggressor: false,
+            trade_id: INVALID_TRADE_ID,
+            fee: 0,
+        };
+
+        engine.send_participant_response(&self.participant_response);
     }
 
-    fn cancel(&mut self, order_info: OrderInfo, engine: &mut MatchingEngine) {
-        let is_cancelable = order_info.order_id < MAX_ORDER_IDS.try_into().unwrap() && 
-                                  self.participants_orders[order_info.participant_id as usize][order_info.order_id as usize].is_some();
+    fn remove_order(&mut self, order_info: OrderInfo) -> PoolBox<Order> {
+        let (side, price, prev_order_info, next_order_info) = {
+            let order = self.get_participant_order(order_info.clone()).as_ref().unwrap();
+            (order.side, order.price, order.prev_order_info.clone(), order.next_order_info.clone())
+        };
+
+        if prev_order_info == order_info {
+            self.remove_order_at_price(side, price);
+        } else {
+            {
+                let prev_order = self.participants_orders[prev_order_info.participant_id as usize][prev_order_info.order_id as usize].as_mut().unwrap();
+                prev_order.next_order_info = next_order_info.clone();
+            }
+
+            {
+                let next_order = self.participants_orders[next_order_info.participant_id as usize][next_order_info.order_id as usize].as_mut().unwrap();
+                next_order.prev_order_info = prev_order_info;
+            }
+
+            let order_at_price_index = self.price_to_index(price);
+            let order_at_price = self.orders_at_price_level[side_to_index(side)][order_at_price_index].as_mut().unwrap();
+
+            if order_at_price.head_order_info == order_info {
+                order_at_price.head_order_info = next_order_info;
+            }
+        }
+
+        self.participants_orders[order_info.participant_id as usize][order_info.order_id as usize].take().unwrap()
+    }
+
+    fn remove_queued_order(&mut self, order_info: &OrderInfo) -> Option<NewOrder> {
+        let position = self.queued_orders.iter().position(|queued_order| queued_order.order_info == *order_info)?;
+        self.queued_orders.remove(position)
+    }
+
+    pub fn cancel(&mut self, order_info: OrderInfo, engine: &mut MatchingEngine) {
+        let is_cancelable = order_info.order_id < self.max_order_ids as OrderId &&
+                                  self.get_participant_order(order_info.clone()).is_some();
 
         if is_cancelable {
             {
@@ -253,26 +941,62 @@ impl OrderBook {
                     symbol_id: self.symbol_id,
                     participant_order_id: order_info.order_id,
                     internal_order_id: order_to_cancel.internal_order_id,
-                    side: order_to_cancel.side.clone(),
+                    side: order_to_cancel.side,
                     price: order_to_cancel.price,
                     exec_qty: INVALID_QUANTITY,
-                    leaves_qty: order_to_cancel.qty
+                    leaves_qty: order_to_cancel.qty + order_to_cancel.reserve_qty,
+                    aggressor: false,
+                    trade_id: INVALID_TRADE_ID,
+                    fee: 0,
                 };
-    
+
                 self.market_update = MarketUpdate{
                     update_type: MarketUpdateType::Cancel,
                     order_id: order_to_cancel.internal_order_id,
                     symbol_id: self.symbol_id,
-                    side: order_to_cancel.side.clone(),
+                    side: order_to_cancel.side,
                     price: order_to_cancel.price,
                     qty: order_to_cancel.qty,
                     priority: order_to_cancel.priority,
+                    trade_id: INVALID_TRADE_ID,
                 };
-    
-                engine.send_market_update(&self.market_update);
+
+                if !order_to_cancel.is_hidden {
+                    engine.send_market_update(&self.market_update);
+                }
             }
 
             self.remove_order(order_info);
+        } else if let Some(StopOrder { new_order, internal_order_id }) = self.trigger_book.cancel(&order_info) {
+            self.participant_response = ParticipantResponse {
+                response_type: ParticipantResponseType::Cancelled,
+                participant_id: order_info.participant_id,
+                symbol_id: self.symbol_id,
+                participant_order_id: order_info.order_id,
+                internal_order_id,
+                side: new_order.side,
+                price: new_order.price,
+                exec_qty: INVALID_QUANTITY,
+                leaves_qty: new_order.qty,
+                aggressor: false,
+                trade_id: INVALID_TRADE_ID,
+                fee: 0,
+            };
+        } else if let Some(queued_order) = self.remove_queued_order(&order_info) {
+            self.participant_response = ParticipantResponse {
+                response_type: ParticipantResponseType::Cancelled,
+                participant_id: order_info.participant_id,
+                symbol_id: self.symbol_id,
+                participant_order_id: order_info.order_id,
+                internal_order_id: INVALID_ORDER_ID,
+                side: queued_order.side,
+                price: queued_order.price,
+                exec_qty: INVALID_QUANTITY,
+                leaves_qty: queued_order.qty,
+                aggressor: false,
+                trade_id: INVALID_TRADE_ID,
+                fee: 0,
+            };
         } else {
             self.participant_response = ParticipantResponse {
                 response_type: ParticipantResponseType::CancelRejected,
@@ -284,18 +1008,199 @@ impl OrderBook {
                 price: INVALID_PRICE,
                 exec_qty: INVALID_QUANTITY,
                 leaves_qty: INVALID_QUANTITY,
+                aggressor: false,
+                trade_id: INVALID_TRADE_ID,
+                fee: 0,
             }
         }
 
 
         engine.send_participant_response(&self.participant_response);
     }
+
+    // resting orders per level, best level first and in queue order, with their displayed plus reserve quantity
+    pub fn resting_orders(&self, side: Side) -> Vec<(Price, Vec<(OrderInfo, Quantity)>)> {
+        let mut levels = Vec::new();
+        let best_idx = self.get_best_idx(side);
+
+        if best_idx == self.max_price_levels {
+            return levels;
+        }
+
+        let mut level_idx = best_idx;
+
+        loop {
+            let order_at_price = self.orders_at_price_level[side_to_index(side)][level_idx].as_ref().unwrap();
+            let mut orders = Vec::new();
+            let mut order_info = order_at_price.head_order_info.clone();
+
+            loop {
+                let order = self.get_participant_order(order_info.clone()).as_ref().unwrap();
+                orders.push((order_info.clone(), order.qty + order.reserve_qty));
+                order_info = order.next_order_info.clone();
+
+                if order_info == order_at_price.head_order_info {
+                    break;
+                }
+            }
+
+            levels.push((order_at_price.price, orders));
+            level_idx = order_at_price.next_idx;
+
+            if level_idx == best_idx {
+                break;
+            }
+        }
+
+        levels
+    }
+
+    // walks both sides and reports the first broken link, ordering or bookkeeping, used by the property and fuzz tests
+    pub fn check_invariants(&self) -> Result<(), String> {
+        let mut resting_orders = 0;
+
+        for side in [Side::Buy, Side::Sell] {
+            let side_idx = side_to_index(side);
+            let levels = self.orders_at_price_level[side_idx].iter().filter(|level| level.is_some()).count();
+            let best_idx = self.get_best_idx(side);
+
+            if best_idx == self.max_price_levels {
+                if levels != 0 {
+                    return Err(format!("{} side has {} levels but no best level", side, levels));
+                }
+                continue;
+            }
+
+            let mut level_idx = best_idx;
+            let mut visited_levels = 0;
+            let mut previous_price = None;
+
+            loop {
+                let order_at_price = self.orders_at_price_level[side_idx][level_idx].as_ref()
+                    .ok_or_else(|| format!("{} level index {} is linked but empty", side, level_idx))?;
+
+                if order_at_price.side != side || self.price_to_index(order_at_price.price) != level_idx {
+                    return Err(format!("level {} is stored at index {} of the {} side", order_at_price, level_idx, side));
+                }
+
+                if let Some(previous_price) = previous_price {
+                    if !is_better(side, previous_price, order_at_price.price) {
+                        return Err(format!("{} level {} follows level {}", side, order_at_price.price, previous_price));
+                    }
+                }
+
+                let next_level = self.orders_at_price_level[side_idx][order_at_price.next_idx].as_ref()
+                    .ok_or_else(|| format!("{} level {} links to an empty next level", side, order_at_price.price))?;
+
+                if next_level.prev_idx != level_idx {
+                    return Err(format!("{} level {} isn't the previous level of its next level", side, order_at_price.price));
+                }
+
+                resting_orders += self.check_level_invariants(order_at_price)?;
+                previous_price = Some(order_at_price.price);
+                visited_levels += 1;
+                level_idx = order_at_price.next_idx;
+
+                if level_idx == best_idx || visited_levels > levels {
+                    break;
+                }
+            }
+
+            if visited_levels != levels {
+                return Err(format!("{} side holds {} levels but {} are linked from the best one", side, levels, visited_levels));
+            }
+        }
+
+        let stored_orders: usize = self.participants_orders.iter().map(|orders| orders.iter().filter(|order| order.is_some()).count()).sum();
+
+        if stored_orders != resting_orders {
+            return Err(format!("{} orders are stored but {} are linked in the levels", stored_orders, resting_orders));
+        }
+
+        if self.circuit_breaker.state() == TradingState::Continuous {
+            if let (Some(best_bid), Some(best_ask)) = (self.get_best_order_at_price(Side::Buy), self.get_best_order_at_price(Side::Sell)) {
+                if best_bid.price >= best_ask.price {
+                    return Err(format!("book is crossed, best bid {} best ask {}", best_bid.price, best_ask.price));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    // returns the number of orders of the level
+    fn check_level_invariants(&self, order_at_price: &OrderAtPrice) -> Result<usize, String> {
+        let head_order_info = &order_at_price.head_order_info;
+        let mut order_info = head_order_info.clone();
+        let mut previous: Option<(Priority, bool)> = None;
+        let mut orders = 0;
+
+        loop {
+            let order = self.get_participant_order(order_info.clone()).as_ref()
+                .ok_or_else(|| format!("level {} links to missing order {}", order_at_price.price, order_info))?;
+
+            if order.side != order_at_price.side || order.price != order_at_price.price || order.qty == 0 {
+                return Err(format!("{} doesn't belong to level {}", order, order_at_price));
+            }
+
+            // priorities increase within the displayed orders and within the hidden ones behind them
+            if let Some((previous_priority, previous_is_hidden)) = previous {
+                if (previous_is_hidden && !order.is_hidden) || (previous_is_hidden == order.is_hidden && order.priority <= previous_priority) {
+                    return Err(format!("{} is out of priority order at level {}", order, order_at_price.price));
+                }
+            }
+
+            let next_order = self.get_participant_order(order.next_order_info.clone()).as_ref()
+                .ok_or_else(|| format!("{} links to a missing next order", order))?;
+
+            if next_order.prev_order_info != order_info {
+                return Err(format!("{} isn't the previous order of its next order", order));
+            }
+
+            previous = Some((order.priority, order.is_hidden));
+            orders += 1;
+            order_info = order.next_order_info.clone();
+
+            if order_info == *head_order_info {
+                return Ok(orders);
+            }
+
+            if orders > self.stored_order_slots() {
+                return Err(format!("level {} never loops back to its head", order_at_price.price));
+            }
+        }
+    }
+
+    fn stored_order_slots(&self) -> usize {
+        self.participants_orders.iter().map(|orders| orders.len()).sum()
+    }
 }
 
 
-fn price_to_index(price: Price) -> usize {
-    price as usize % MAX_PRICE_LEVELS
+fn opposite_side(side: Side) -> Side {
+    match side {
+        Side::Buy => Side::Sell,
+        Side::Sell => Side::Buy,
+        Side::Invalid => Side::Invalid,
+    }
 }
 
-type OrderbookHashmap = Vec<OrderBook>; //map symbol id with orderbook
+// true if `price` is strictly more aggressive than `other_price` for `side`
+fn is_better(side: Side, price: Price, other_price: Price) -> bool {
+    match side {
+        Side::Buy => price > other_price,
+        Side::Sell => price < other_price,
+        Side::Invalid => false,
+    }
+}
+
+// true if an order on `side` at `price` can trade against a resting order at `passive_price`
+fn is_marketable(side: Side, price: Price, passive_price: Price) -> bool {
+    match side {
+        Side::Buy => price >= passive_price,
+        Side::Sell => price <= passive_price,
+        Side::Invalid => false,
+    }
+}
 
+pub type OrderbookHashmap = Vec<Option<OrderBook>>; //map symbol id with orderbook
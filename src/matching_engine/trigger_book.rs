@@ -0,0 +1,113 @@
+use std::collections::{BTreeMap, VecDeque};
+
+use crate::common::{OrderId, Price, Side, INVALID_PRICE};
+
+use super::order::{NewOrder, OrderInfo};
+
+pub struct StopOrder {
+    pub new_order: NewOrder,
+    pub internal_order_id: OrderId,
+}
+
+type StopLevels = BTreeMap<Price, VecDeque<StopOrder>>;
+
+// stop orders waiting off-book for the last trade price to reach them, buy stops sit above the market and sell stops below
+pub struct TriggerBook {
+    buy_stops: StopLevels,
+    sell_stops: StopLevels,
+    triggered: VecDeque<StopOrder>,
+}
+
+impl Default for TriggerBook {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TriggerBook {
+    pub fn new() -> Self {
+        Self {
+            buy_stops: BTreeMap::new(),
+            sell_stops: BTreeMap::new(),
+            triggered: VecDeque::new(),
+        }
+    }
+
+    pub fn is_triggered(side: Side, stop_price: Price, last_trade_price: Price) -> bool {
+        if last_trade_price == INVALID_PRICE {
+            return false;
+        }
+
+        match side {
+            Side::Buy => last_trade_price >= stop_price,
+            Side::Sell => last_trade_price <= stop_price,
+            Side::Invalid => false,
+        }
+    }
+
+    pub fn add(&mut self, stop_order: StopOrder) {
+        let stops = match stop_order.new_order.side {
+            Side::Buy => &mut self.buy_stops,
+            Side::Sell => &mut self.sell_stops,
+            Side::Invalid => panic!("INVALID side aren't taken into account"),
+        };
+
+        stops.entry(stop_order.new_order.stop_price).or_default().push_back(stop_order);
+    }
+
+    // a pending stop holds on to its order id until it is released or cancelled
+    pub fn contains(&self, order_info: &OrderInfo) -> bool {
+        self.buy_stops.values().chain(self.sell_stops.values()).flatten().chain(self.triggered.iter())
+            .any(|stop_order| stop_order.new_order.order_info == *order_info)
+    }
+
+    // stops are rare compared to resting orders, a linear search keeps the book free of an extra index
+    pub fn cancel(&mut self, order_info: &OrderInfo) -> Option<StopOrder> {
+        for stops in [&mut self.buy_stops, &mut self.sell_stops] {
+            let mut found = None;
+
+            for (stop_price, level) in stops.iter_mut() {
+                if let Some(position) = level.iter().position(|stop_order| stop_order.new_order.order_info == *order_info) {
+                    found = Some((*stop_price, level.remove(position).unwrap()));
+                    break;
+                }
+            }
+
+            if let Some((stop_price, stop_order)) = found {
+                if stops[&stop_price].is_empty() {
+                    stops.remove(&stop_price);
+                }
+
+                return Some(stop_order);
+            }
+        }
+
+        let position = self.triggered.iter().position(|stop_order| stop_order.new_order.order_info == *order_info)?;
+        self.triggered.remove(position)
+    }
+
+    // moves every stop the trade reaches to the triggered queue, closest stop price first then arrival order
+    pub fn on_trade(&mut self, price: Price) {
+        while let Some(mut entry) = self.buy_stops.first_entry() {
+            if *entry.key() > price {
+                break;
+            }
+
+            self.triggered.extend(entry.get_mut().drain(..));
+            entry.remove();
+        }
+
+        while let Some(mut entry) = self.sell_stops.last_entry() {
+            if *entry.key() < price {
+                break;
+            }
+
+            self.triggered.extend(entry.get_mut().drain(..));
+            entry.remove();
+        }
+    }
+
+    pub fn pop_triggered(&mut self) -> Option<StopOrder> {
+        self.triggered.pop_front()
+    }
+}
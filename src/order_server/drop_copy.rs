@@ -0,0 +1,328 @@
+use std::{io::{self, BufReader, BufWriter, Write}, net::{SocketAddr, TcpListener, TcpStream}, sync::{mpsc::{self, Receiver, RecvTimeoutError, Sender}, Arc, Mutex}, thread, time::Duration};
+
+use serde::Deserialize;
+
+use crate::common::{ParticipantId, SeqNum, Sequenced};
+use crate::logging::logger::{LogPayload, Logger};
+use crate::market_data::{market_update::{MarketUpdate, MarketUpdateType}, publisher::MarketDataPublisher};
+use crate::positions::Position;
+use crate::wire::{MarketUpdateFrame, PositionFrame, ResponseFrame, WireFrame};
+
+use super::participants_response::{ParticipantResponse, ParticipantResponseType};
+use super::response_router::ResponseRouter;
+use super::soup_bin::{self, Connection, LoginAccepted, LoginRequest};
+
+// an empty address leaves the drop copy server off
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DropCopyConfig {
+    pub address: String,
+    pub heartbeat_interval_ms: u64,
+    pub idle_timeout_ms: u64, //a client silent this long is disconnected
+    pub logins: Vec<DropCopyLogin>,
+}
+
+impl Default for DropCopyConfig {
+    fn default() -> Self {
+        Self {
+            address: String::new(),
+            heartbeat_interval_ms: 1_000,
+            idle_timeout_ms: 15_000,
+            logins: Vec::new(),
+        }
+    }
+}
+
+// a login without participants follows all of them
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DropCopyLogin {
+    pub username: String,
+    pub password: String,
+    #[serde(default)]
+    pub participants: Vec<ParticipantId>,
+}
+
+// the stream of one login: the fills, cancels, busts and corrections and the position reports of the participants it
+// follows, and every trade, bust and correction, trades being public. Each message is the wire frame of the response,
+// the position or the market update
+pub struct DropCopySession {
+    participants: Vec<ParticipantId>,
+    journal: Vec<u8>,
+    offsets: Vec<usize>, //start of every message in the journal, indexed by sequence number - 1
+}
+
+impl DropCopySession {
+    pub fn new(participants: &[ParticipantId]) -> Self {
+        Self {
+            participants: participants.to_vec(),
+            journal: Vec::new(),
+            offsets: Vec::new(),
+        }
+    }
+
+    pub fn next_sequence_number(&self) -> SeqNum {
+        self.offsets.len() as SeqNum + 1
+    }
+
+    pub fn message(&self, sequence_number: SeqNum) -> Option<&[u8]> {
+        let idx = sequence_number.checked_sub(1)? as usize;
+        let start = *self.offsets.get(idx)?;
+        let end = self.offsets.get(idx + 1).copied().unwrap_or(self.journal.len());
+        Some(&self.journal[start..end])
+    }
+
+    pub fn on_response(&mut self, response: &ParticipantResponse) {
+        let execution = matches!(response.response_type, ParticipantResponseType::Filled | ParticipantResponseType::Cancelled | ParticipantResponseType::TradeBusted | ParticipantResponseType::TradeCorrected);
+
+        if execution && (self.participants.is_empty() || self.participants.contains(&response.participant_id)) {
+            self.append(ResponseFrame::encode(response).as_bytes());
+        }
+    }
+
+    pub fn on_position(&mut self, position: &Position) {
+        if self.participants.is_empty() || self.participants.contains(&position.participant_id) {
+            self.append(PositionFrame::encode(position).as_bytes());
+        }
+    }
+
+    pub fn on_market_update(&mut self, update: &MarketUpdate) {
+        if is_trade(update) {
+            self.append(MarketUpdateFrame::encode(update).as_bytes());
+        }
+    }
+
+    fn append(&mut self, message: &[u8]) {
+        self.offsets.push(self.journal.len());
+        self.journal.extend_from_slice(message);
+    }
+}
+
+enum Event {
+    Response(ParticipantResponse),
+    Trade(MarketUpdate),
+    Position(Position),
+}
+
+struct Login {
+    account: DropCopyLogin,
+    session: DropCopySession,
+    connection: Option<Connection>,
+    next_connection_id: u64,
+}
+
+impl Login {
+    // a connection that fails is dropped
+    fn send_pending(&mut self) {
+        let session = &self.session;
+
+        if let Some(connection) = self.connection.as_mut() {
+            if connection.send_pending(|sequence_number| session.message(sequence_number)).is_err() {
+                self.disconnect();
+            }
+        }
+    }
+
+    fn send(&mut self, packet_type: u8, payload: &[u8]) {
+        if let Some(connection) = self.connection.as_mut() {
+            if connection.send(packet_type, payload).is_err() {
+                self.disconnect();
+            }
+        }
+    }
+
+    fn heartbeat(&mut self, heartbeat_interval: Duration) {
+        if self.connection.as_ref().is_some_and(|connection| connection.idle_for() >= heartbeat_interval) {
+            self.send(soup_bin::PACKET_SERVER_HEARTBEAT, &[]);
+        }
+    }
+
+    fn disconnect(&mut self) {
+        if let Some(connection) = self.connection.take() {
+            connection.shutdown();
+        }
+    }
+}
+
+// read only sessions over the same SoupBinTCP style layer as OUCH order entry, one connection per login at a time.
+// The sessions are fed for the life of the server whether a client is connected or not, a client logging in again
+// replays from the sequence number it asks for. Returns the address it listens on
+pub fn spawn(config: &DropCopyConfig, response_router: &mut ResponseRouter, market_data_publisher: &mut MarketDataPublisher, position_reports: Receiver<Position>, logger: Logger) -> io::Result<SocketAddr> {
+    let listener = TcpListener::bind(&config.address)?;
+    let local_addr = listener.local_addr()?;
+
+    let heartbeat_interval = Duration::from_millis(config.heartbeat_interval_ms);
+    let idle_timeout = Duration::from_millis(config.idle_timeout_ms);
+    let session_name = soup_bin::session_name();
+
+    let logins = config.logins.iter().cloned().map(|account| Login {
+        session: DropCopySession::new(&account.participants),
+        account,
+        connection: None,
+        next_connection_id: 0,
+    }).collect();
+    let logins = Arc::new(Mutex::new(logins));
+
+    // responses, trades and position reports come from three streams, merged into one, which ends once they all do
+    let (events_tx, events_rx) = mpsc::channel();
+    let responses = response_router.subscribe_all();
+    let market_updates = market_data_publisher.subscribe();
+
+    let responses_tx = events_tx.clone();
+    thread::spawn(move || forward(responses, responses_tx, |response| Some(Event::Response(response))));
+    let market_updates_tx = events_tx.clone();
+    thread::spawn(move || forward(market_updates, market_updates_tx, |update| is_trade(&update).then_some(Event::Trade(update))));
+    thread::spawn(move || {
+        for position in position_reports.iter() {
+            if events_tx.send(Event::Position(position)).is_err() {
+                return;
+            }
+        }
+    });
+
+    let pump_logins = logins.clone();
+    thread::spawn(move || pump(pump_logins, events_rx, heartbeat_interval));
+
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    let logins = logins.clone();
+                    let session_name = session_name.clone();
+                    let logger = logger.clone();
+                    thread::spawn(move || serve(stream, &logins, &session_name, idle_timeout, logger));
+                },
+                Err(_) => logger.warn("drop copy: cannot accept connection", LogPayload::None),
+            }
+        }
+    });
+
+    Ok(local_addr)
+}
+
+fn is_trade(update: &MarketUpdate) -> bool {
+    matches!(update.update_type, MarketUpdateType::Trade | MarketUpdateType::TradeCancel | MarketUpdateType::TradeCorrect)
+}
+
+fn forward<T>(input: Receiver<Sequenced<T>>, events: Sender<Event>, event: impl Fn(T) -> Option<Event>) {
+    for message in input.iter() {
+        if let Some(event) = event(message.payload) {
+            if events.send(event).is_err() {
+                return;
+            }
+        }
+    }
+}
+
+// a login whose participants are quiet still gets its heartbeats while the others' executions flow
+fn pump(logins: Arc<Mutex<Vec<Login>>>, events: Receiver<Event>, heartbeat_interval: Duration) {
+    loop {
+        let event = match events.recv_timeout(heartbeat_interval) {
+            Ok(event) => Some(event),
+            Err(RecvTimeoutError::Timeout) => None,
+            Err(RecvTimeoutError::Disconnected) => {
+                for login in logins.lock().unwrap().iter_mut() {
+                    login.send(soup_bin::PACKET_END_OF_SESSION, &[]);
+                    login.disconnect();
+                }
+                return;
+            },
+        };
+
+        for login in logins.lock().unwrap().iter_mut() {
+            match &event {
+                Some(Event::Response(response)) => login.session.on_response(response),
+                Some(Event::Trade(update)) => login.session.on_market_update(update),
+                Some(Event::Position(position)) => login.session.on_position(position),
+                None => {},
+            }
+
+            login.send_pending();
+            login.heartbeat(heartbeat_interval);
+        }
+    }
+}
+
+fn serve(stream: TcpStream, logins: &Mutex<Vec<Login>>, session_name: &str, idle_timeout: Duration, logger: Logger) {
+    let _ = stream.set_nodelay(true);
+    let _ = stream.set_read_timeout(Some(idle_timeout));
+
+    let mut reader = match stream.try_clone() {
+        Ok(stream) => BufReader::new(stream),
+        Err(_) => return,
+    };
+
+    let mut payload = Vec::new();
+
+    let login_request = match soup_bin::read_packet(&mut reader, &mut payload) {
+        Ok(soup_bin::PACKET_LOGIN_REQUEST) => LoginRequest::decode(&payload),
+        _ => None,
+    };
+
+    let (idx, connection_id) = match login(stream, login_request, logins, session_name) {
+        Ok(login) => login,
+        Err(reason) => {
+            logger.warn(if reason == soup_bin::REJECT_NOT_AUTHORIZED { "drop copy: login not authorized" } else { "drop copy: session not available" }, LogPayload::None);
+            return;
+        },
+    };
+
+    logger.info("drop copy: logged in", LogPayload::None);
+
+    // a read timing out means the client went quiet for too long
+    while let Ok(packet_type) = soup_bin::read_packet(&mut reader, &mut payload) {
+        match packet_type {
+            soup_bin::PACKET_CLIENT_HEARTBEAT => {},
+            soup_bin::PACKET_LOGOUT_REQUEST => break,
+            _ => logger.warn("drop copy: unexpected packet", LogPayload::None),
+        }
+    }
+
+    let login = &mut logins.lock().unwrap()[idx];
+    if login.connection.as_ref().is_some_and(|connection| connection.id == connection_id) {
+        login.disconnect();
+    }
+
+    logger.info("drop copy: logged out", LogPayload::None);
+}
+
+// accepts the login, then replays the messages from the requested one on. Returns the soup reject reason otherwise
+fn login(stream: TcpStream, login_request: Option<LoginRequest>, logins: &Mutex<Vec<Login>>, session_name: &str) -> Result<(usize, u64), u8> {
+    let reject = |reason: u8| {
+        let mut writer = BufWriter::new(&stream);
+        let _ = soup_bin::write_packet(&mut writer, soup_bin::PACKET_LOGIN_REJECTED, &[reason]).and_then(|_| writer.flush());
+        Err(reason)
+    };
+
+    let login_request = match login_request {
+        Some(login_request) => login_request,
+        None => return reject(soup_bin::REJECT_NOT_AUTHORIZED),
+    };
+
+    let mut logins = logins.lock().unwrap();
+
+    let idx = match logins.iter().position(|login| login.account.username == login_request.username && login.account.password == login_request.password) {
+        Some(idx) => idx,
+        None => return reject(soup_bin::REJECT_NOT_AUTHORIZED),
+    };
+
+    let login = &mut logins[idx];
+
+    if login.connection.is_some() || !(login_request.requested_session.is_empty() || login_request.requested_session == session_name) {
+        return reject(soup_bin::REJECT_SESSION_NOT_AVAILABLE);
+    }
+
+    let sequence_number = soup_bin::replay_from(login_request.requested_sequence_number, login.session.next_sequence_number());
+
+    let mut payload = Vec::new();
+    LoginAccepted { session: session_name.to_string(), sequence_number }.encode(&mut payload);
+
+    let connection_id = login.next_connection_id;
+    login.next_connection_id += 1;
+    login.connection = Some(Connection::new(connection_id, stream, sequence_number));
+
+    login.send(soup_bin::PACKET_LOGIN_ACCEPTED, &payload);
+    login.send_pending();
+
+    Ok((idx, connection_id))
+}
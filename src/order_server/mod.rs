@@ -1,2 +1,10 @@
+pub mod drop_copy;
+pub mod ouch;
+pub mod ouch_gateway;
+pub mod ouch_session;
 pub mod participants_request;
-pub mod participants_response;
\ No newline at end of file
+pub mod participants_response;
+pub mod response_router;
+pub mod shard_router;
+pub mod soup_bin;
+pub mod tcp_gateway;
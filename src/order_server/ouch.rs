@@ -0,0 +1,295 @@
+use std::{mem, slice};
+
+// NASDAQ OUCH 4.2 message layouts, field for field, with the integers big endian as in the specification.
+// Prices are in ticks and the stock is the symbol name. Like the ITCH messages they are made of bytes only,
+// the session layer carries them as the payloads of its packets
+
+// a big endian unsigned integer of N bytes, with no alignment requirement
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[repr(transparent)]
+pub struct BeInt<const N: usize>([u8; N]);
+
+impl<const N: usize> Default for BeInt<N> {
+    fn default() -> Self {
+        Self([0; N])
+    }
+}
+
+impl<const N: usize> BeInt<N> {
+    // the value is truncated to N bytes
+    pub fn new(value: u64) -> Self {
+        let mut bytes = [0; N];
+        bytes.copy_from_slice(&value.to_be_bytes()[8 - N..]);
+        Self(bytes)
+    }
+
+    pub fn get(&self) -> u64 {
+        let mut bytes = [0; 8];
+        bytes[8 - N..].copy_from_slice(&self.0);
+        u64::from_be_bytes(bytes)
+    }
+}
+
+pub const MESSAGE_ENTER_ORDER: u8 = b'O';
+pub const MESSAGE_REPLACE_ORDER: u8 = b'U';
+pub const MESSAGE_CANCEL_ORDER: u8 = b'X';
+
+pub const MESSAGE_ACCEPTED: u8 = b'A';
+pub const MESSAGE_REPLACED: u8 = b'U';
+pub const MESSAGE_CANCELED: u8 = b'C';
+pub const MESSAGE_EXECUTED: u8 = b'E';
+pub const MESSAGE_REJECTED: u8 = b'J';
+pub const MESSAGE_CANCEL_REJECT: u8 = b'I';
+
+// sell short and sell short exempt are plain sells here
+pub const SIDE_BUY: u8 = b'B';
+pub const SIDE_SELL: u8 = b'S';
+pub const SIDE_SELL_SHORT: u8 = b'T';
+pub const SIDE_SELL_SHORT_EXEMPT: u8 = b'E';
+
+pub const DISPLAY_VISIBLE: u8 = b'Y';
+pub const DISPLAY_HIDDEN: u8 = b'N';
+pub const DISPLAY_POST_ONLY: u8 = b'P';
+
+// any other time in force is a number of seconds
+pub const TIME_IN_FORCE_IOC: u64 = 0;
+pub const TIME_IN_FORCE_MARKET_HOURS: u64 = 99_998;
+pub const TIME_IN_FORCE_SYSTEM_HOURS: u64 = 99_999;
+
+// $200,000.0000, the price of a market order
+pub const PRICE_MARKET: u64 = 0x7735_9400;
+
+pub const ORDER_STATE_LIVE: u8 = b'L';
+pub const ORDER_STATE_DEAD: u8 = b'D';
+
+pub const LIQUIDITY_ADDED: u8 = b'A';
+pub const LIQUIDITY_REMOVED: u8 = b'R';
+
+pub const CANCEL_REASON_USER: u8 = b'U';
+pub const CANCEL_REASON_SYSTEM: u8 = b'Z';
+
+pub const REJECT_REASON_INVALID_STOCK: u8 = b'S';
+pub const REJECT_REASON_INVALID_SHARES: u8 = b'Z';
+pub const REJECT_REASON_INVALID_DISPLAY: u8 = b'D';
+pub const REJECT_REASON_OTHER: u8 = b'O';
+
+// identifies an order for the life of the session, chosen by the client
+pub type Token = [u8; 14];
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+#[repr(C)]
+pub struct EnterOrder {
+    pub message_type: u8,
+    pub order_token: Token,
+    pub side: u8,
+    pub shares: BeInt<4>,
+    pub stock: [u8; 8],
+    pub price: BeInt<4>,
+    pub time_in_force: BeInt<4>,
+    pub firm: [u8; 4],
+    pub display: u8,
+    pub capacity: u8,
+    pub intermarket_sweep: u8,
+    pub minimum_quantity: BeInt<4>,
+    pub cross_type: u8,
+    pub customer_type: u8,
+}
+
+// the shares are the size of the replacement order
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+#[repr(C)]
+pub struct ReplaceOrder {
+    pub message_type: u8,
+    pub existing_order_token: Token,
+    pub replacement_order_token: Token,
+    pub shares: BeInt<4>,
+    pub price: BeInt<4>,
+    pub time_in_force: BeInt<4>,
+    pub display: u8,
+    pub intermarket_sweep: u8,
+    pub minimum_quantity: BeInt<4>,
+}
+
+// the shares the order is left with, 0 cancels all of it
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+#[repr(C)]
+pub struct CancelOrder {
+    pub message_type: u8,
+    pub order_token: Token,
+    pub shares: BeInt<4>,
+}
+
+// timestamps are nanoseconds since midnight, the order reference number is the engine's internal order id
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+#[repr(C)]
+pub struct Accepted {
+    pub message_type: u8,
+    pub timestamp: BeInt<8>,
+    pub order_token: Token,
+    pub side: u8,
+    pub shares: BeInt<4>,
+    pub stock: [u8; 8],
+    pub price: BeInt<4>,
+    pub time_in_force: BeInt<4>,
+    pub firm: [u8; 4],
+    pub display: u8,
+    pub order_reference_number: BeInt<8>,
+    pub capacity: u8,
+    pub intermarket_sweep: u8,
+    pub minimum_quantity: BeInt<4>,
+    pub cross_type: u8,
+    pub order_state: u8,
+    pub bbo_weight_indicator: u8,
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+#[repr(C)]
+pub struct Replaced {
+    pub message_type: u8,
+    pub timestamp: BeInt<8>,
+    pub replacement_order_token: Token,
+    pub side: u8,
+    pub shares: BeInt<4>,
+    pub stock: [u8; 8],
+    pub price: BeInt<4>,
+    pub time_in_force: BeInt<4>,
+    pub firm: [u8; 4],
+    pub display: u8,
+    pub order_reference_number: BeInt<8>,
+    pub capacity: u8,
+    pub intermarket_sweep: u8,
+    pub minimum_quantity: BeInt<4>,
+    pub cross_type: u8,
+    pub order_state: u8,
+    pub previous_order_token: Token,
+    pub bbo_weight_indicator: u8,
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+#[repr(C)]
+pub struct Canceled {
+    pub message_type: u8,
+    pub timestamp: BeInt<8>,
+    pub order_token: Token,
+    pub decrement_shares: BeInt<4>,
+    pub reason: u8,
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+#[repr(C)]
+pub struct Executed {
+    pub message_type: u8,
+    pub timestamp: BeInt<8>,
+    pub order_token: Token,
+    pub executed_shares: BeInt<4>,
+    pub execution_price: BeInt<4>,
+    pub liquidity_flag: u8,
+    pub match_number: BeInt<8>,
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+#[repr(C)]
+pub struct Rejected {
+    pub message_type: u8,
+    pub timestamp: BeInt<8>,
+    pub order_token: Token,
+    pub reason: u8,
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+#[repr(C)]
+pub struct CancelReject {
+    pub message_type: u8,
+    pub timestamp: BeInt<8>,
+    pub order_token: Token,
+}
+
+// the sizes of the OUCH 4.2 specification
+const _: () = assert!(mem::size_of::<EnterOrder>() == 49);
+const _: () = assert!(mem::size_of::<ReplaceOrder>() == 47);
+const _: () = assert!(mem::size_of::<CancelOrder>() == 19);
+const _: () = assert!(mem::size_of::<Accepted>() == 66);
+const _: () = assert!(mem::size_of::<Replaced>() == 80);
+const _: () = assert!(mem::size_of::<Canceled>() == 28);
+const _: () = assert!(mem::size_of::<Executed>() == 40);
+const _: () = assert!(mem::size_of::<Rejected>() == 24);
+const _: () = assert!(mem::size_of::<CancelReject>() == 23);
+
+/// # Safety
+/// Implementors are `#[repr(C)]` and only made of `u8`, byte arrays and `BeInt`s: they have an alignment of 1,
+/// no padding, and any bytes are a valid value.
+pub unsafe trait OuchMessage: Sized {
+    const MESSAGE_TYPE: u8;
+
+    fn as_bytes(&self) -> &[u8] {
+        unsafe { slice::from_raw_parts(self as *const Self as *const u8, mem::size_of::<Self>()) }
+    }
+
+    // None when the buffer isn't exactly one message of this type
+    fn view(buf: &[u8]) -> Option<&Self> {
+        if buf.len() != mem::size_of::<Self>() || buf[0] != Self::MESSAGE_TYPE {
+            return None;
+        }
+
+        Some(unsafe { &*(buf.as_ptr() as *const Self) })
+    }
+}
+
+unsafe impl OuchMessage for EnterOrder { const MESSAGE_TYPE: u8 = MESSAGE_ENTER_ORDER; }
+unsafe impl OuchMessage for ReplaceOrder { const MESSAGE_TYPE: u8 = MESSAGE_REPLACE_ORDER; }
+unsafe impl OuchMessage for CancelOrder { const MESSAGE_TYPE: u8 = MESSAGE_CANCEL_ORDER; }
+unsafe impl OuchMessage for Accepted { const MESSAGE_TYPE: u8 = MESSAGE_ACCEPTED; }
+unsafe impl OuchMessage for Replaced { const MESSAGE_TYPE: u8 = MESSAGE_REPLACED; }
+unsafe impl OuchMessage for Canceled { const MESSAGE_TYPE: u8 = MESSAGE_CANCELED; }
+unsafe impl OuchMessage for Executed { const MESSAGE_TYPE: u8 = MESSAGE_EXECUTED; }
+unsafe impl OuchMessage for Rejected { const MESSAGE_TYPE: u8 = MESSAGE_REJECTED; }
+unsafe impl OuchMessage for CancelReject { const MESSAGE_TYPE: u8 = MESSAGE_CANCEL_REJECT; }
+
+// sent by the client
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Inbound<'a> {
+    EnterOrder(&'a EnterOrder),
+    ReplaceOrder(&'a ReplaceOrder),
+    CancelOrder(&'a CancelOrder),
+}
+
+// sent by the exchange
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Outbound<'a> {
+    Accepted(&'a Accepted),
+    Replaced(&'a Replaced),
+    Canceled(&'a Canceled),
+    Executed(&'a Executed),
+    Rejected(&'a Rejected),
+    CancelReject(&'a CancelReject),
+}
+
+// a whole packet payload, None when the type is unknown or the length is wrong
+pub fn decode_inbound(buf: &[u8]) -> Option<Inbound<'_>> {
+    match *buf.first()? {
+        MESSAGE_ENTER_ORDER => EnterOrder::view(buf).map(Inbound::EnterOrder),
+        MESSAGE_REPLACE_ORDER => ReplaceOrder::view(buf).map(Inbound::ReplaceOrder),
+        MESSAGE_CANCEL_ORDER => CancelOrder::view(buf).map(Inbound::CancelOrder),
+        _ => None,
+    }
+}
+
+pub fn decode_outbound(buf: &[u8]) -> Option<Outbound<'_>> {
+    match *buf.first()? {
+        MESSAGE_ACCEPTED => Accepted::view(buf).map(Outbound::Accepted),
+        MESSAGE_REPLACED => Replaced::view(buf).map(Outbound::Replaced),
+        MESSAGE_CANCELED => Canceled::view(buf).map(Outbound::Canceled),
+        MESSAGE_EXECUTED => Executed::view(buf).map(Outbound::Executed),
+        MESSAGE_REJECTED => Rejected::view(buf).map(Outbound::Rejected),
+        MESSAGE_CANCEL_REJECT => CancelReject::view(buf).map(Outbound::CancelReject),
+        _ => None,
+    }
+}
+
+// the order token field, space padded or cut to 14 characters
+pub fn token(name: &str) -> Token {
+    let mut token = [b' '; 14];
+    let len = name.len().min(14);
+    token[..len].copy_from_slice(&name.as_bytes()[..len]);
+    token
+}
@@ -0,0 +1,269 @@
+use std::{io::{self, BufReader, BufWriter, Write}, net::{SocketAddr, TcpListener, TcpStream}, sync::{mpsc::{Receiver, RecvTimeoutError}, Arc, Mutex}, thread, time::Duration};
+
+use serde::Deserialize;
+
+use crate::common::{get_current_nanos, Nanos, ParticipantId, Sequenced, NANOS_PER_DAY};
+use crate::logging::logger::{LogPayload, Logger};
+
+use super::ouch::decode_inbound;
+use super::ouch_session::OuchSession;
+use super::participants_response::ParticipantResponse;
+use super::response_router::ResponseRouter;
+use super::shard_router::ShardRouter;
+use super::soup_bin::{self, Connection, LoginAccepted, LoginRequest};
+
+// an empty address leaves the OUCH gateway off. The username of a login is its participant's name
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OuchConfig {
+    pub address: String,
+    pub heartbeat_interval_ms: u64,
+    pub idle_timeout_ms: u64, //a client silent this long is disconnected
+    pub logins: Vec<OuchLogin>,
+}
+
+impl Default for OuchConfig {
+    fn default() -> Self {
+        Self {
+            address: String::new(),
+            heartbeat_interval_ms: 1_000,
+            idle_timeout_ms: 15_000,
+            logins: Vec::new(),
+        }
+    }
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OuchLogin {
+    pub participant: ParticipantId,
+    pub password: String,
+}
+
+pub struct OuchAccount {
+    pub participant_id: ParticipantId,
+    pub username: String,
+    pub password: String,
+}
+
+// the session outlives its connections, a client logging in again picks up where it asks to
+struct Participant {
+    account: OuchAccount,
+    session: OuchSession<ShardRouter>,
+    connection: Option<Connection>,
+    next_connection_id: u64,
+}
+
+impl Participant {
+    // a connection that fails is dropped
+    fn send_pending(&mut self) {
+        let session = &self.session;
+
+        if let Some(connection) = self.connection.as_mut() {
+            if connection.send_pending(|sequence_number| session.message(sequence_number)).is_err() {
+                self.disconnect();
+            }
+        }
+    }
+
+    fn send(&mut self, packet_type: u8, payload: &[u8]) {
+        if let Some(connection) = self.connection.as_mut() {
+            if connection.send(packet_type, payload).is_err() {
+                self.disconnect();
+            }
+        }
+    }
+
+    fn disconnect(&mut self) {
+        if let Some(connection) = self.connection.take() {
+            connection.shutdown();
+        }
+    }
+}
+
+// OUCH order entry over a SoupBinTCP style session layer. Every account has one session for the life of the gateway,
+// fed by its participant's responses whether a client is connected or not, and one connection at a time.
+// Returns the address it listens on, which tells the port when the configured one is 0
+pub fn spawn(config: &OuchConfig, accounts: Vec<OuchAccount>, symbol_names: &[&str], max_order_ids: usize, router: ShardRouter, response_router: &mut ResponseRouter, logger: Logger) -> io::Result<SocketAddr> {
+    let listener = TcpListener::bind(&config.address)?;
+    let local_addr = listener.local_addr()?;
+
+    let heartbeat_interval = Duration::from_millis(config.heartbeat_interval_ms);
+    let idle_timeout = Duration::from_millis(config.idle_timeout_ms);
+
+    let session_name = soup_bin::session_name();
+    let mut participants = Vec::new();
+
+    for account in accounts {
+        let responses = response_router.subscribe(account.participant_id);
+        let participant = Arc::new(Mutex::new(Participant {
+            session: OuchSession::new(router.clone(), account.participant_id, max_order_ids, symbol_names),
+            account,
+            connection: None,
+            next_connection_id: 0,
+        }));
+
+        let pump_participant = participant.clone();
+        let pump_logger = logger.clone();
+        thread::spawn(move || pump(pump_participant, responses, heartbeat_interval, pump_logger));
+
+        participants.push(participant);
+    }
+
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    let participants = participants.clone();
+                    let session_name = session_name.clone();
+                    let logger = logger.clone();
+                    thread::spawn(move || serve(stream, &participants, &session_name, idle_timeout, logger));
+                },
+                Err(_) => logger.warn("ouch: cannot accept connection", LogPayload::None),
+            }
+        }
+    });
+
+    Ok(local_addr)
+}
+
+// applies the participant's responses to its session and keeps its connection alive, ends the session once the engines stop
+fn pump(participant: Arc<Mutex<Participant>>, responses: Receiver<Sequenced<ParticipantResponse>>, heartbeat_interval: Duration, logger: Logger) {
+    loop {
+        match responses.recv_timeout(heartbeat_interval) {
+            Ok(response) => {
+                let mut participant = participant.lock().unwrap();
+
+                if participant.session.on_response(&response.payload, timestamp()).is_err() {
+                    logger.warn("ouch: unexpected response", LogPayload::None);
+                }
+
+                participant.send_pending();
+            },
+            Err(RecvTimeoutError::Timeout) => {
+                let mut participant = participant.lock().unwrap();
+
+                if participant.connection.as_ref().is_some_and(|connection| connection.idle_for() >= heartbeat_interval) {
+                    participant.send(soup_bin::PACKET_SERVER_HEARTBEAT, &[]);
+                }
+            },
+            Err(RecvTimeoutError::Disconnected) => {
+                let mut participant = participant.lock().unwrap();
+                participant.send(soup_bin::PACKET_END_OF_SESSION, &[]);
+                participant.disconnect();
+                return;
+            },
+        }
+    }
+}
+
+fn serve(stream: TcpStream, participants: &[Arc<Mutex<Participant>>], session_name: &str, idle_timeout: Duration, logger: Logger) {
+    let _ = stream.set_nodelay(true);
+    let _ = stream.set_read_timeout(Some(idle_timeout));
+
+    let mut reader = match stream.try_clone() {
+        Ok(stream) => BufReader::new(stream),
+        Err(_) => return,
+    };
+
+    let mut payload = Vec::new();
+
+    let login_request = match soup_bin::read_packet(&mut reader, &mut payload) {
+        Ok(soup_bin::PACKET_LOGIN_REQUEST) => LoginRequest::decode(&payload),
+        _ => None,
+    };
+
+    let (participant, connection_id) = match login(stream, login_request, participants, session_name) {
+        Ok(login) => login,
+        Err(reason) => {
+            logger.warn(if reason == soup_bin::REJECT_NOT_AUTHORIZED { "ouch: login not authorized" } else { "ouch: session not available" }, LogPayload::None);
+            return;
+        },
+    };
+
+    logger.info("ouch: logged in", LogPayload::None);
+
+    // a read timing out means the client went quiet for too long
+    while let Ok(packet_type) = soup_bin::read_packet(&mut reader, &mut payload) {
+        match packet_type {
+            soup_bin::PACKET_UNSEQUENCED_DATA => {
+                let message = match decode_inbound(&payload) {
+                    Some(message) => message,
+                    None => {
+                        logger.warn("ouch: malformed message", LogPayload::None);
+                        continue;
+                    },
+                };
+
+                let mut participant = participant.lock().unwrap();
+
+                if participant.session.on_message(message, timestamp()).is_err() {
+                    break;
+                }
+
+                participant.send_pending();
+            },
+            soup_bin::PACKET_CLIENT_HEARTBEAT => {},
+            soup_bin::PACKET_LOGOUT_REQUEST => break,
+            _ => logger.warn("ouch: unexpected packet", LogPayload::None),
+        }
+    }
+
+    let mut participant = participant.lock().unwrap();
+    if participant.connection.as_ref().is_some_and(|connection| connection.id == connection_id) {
+        participant.disconnect();
+    }
+
+    logger.info("ouch: logged out", LogPayload::None);
+}
+
+// accepts the login, then replays the messages from the requested one on. Returns the soup reject reason otherwise
+fn login(stream: TcpStream, login_request: Option<LoginRequest>, participants: &[Arc<Mutex<Participant>>], session_name: &str) -> Result<(Arc<Mutex<Participant>>, u64), u8> {
+    let reject = |reason: u8| {
+        let mut writer = BufWriter::new(&stream);
+        let _ = soup_bin::write_packet(&mut writer, soup_bin::PACKET_LOGIN_REJECTED, &[reason]).and_then(|_| writer.flush());
+        Err(reason)
+    };
+
+    let login_request = match login_request {
+        Some(login_request) => login_request,
+        None => return reject(soup_bin::REJECT_NOT_AUTHORIZED),
+    };
+
+    let participant = participants.iter().find(|participant| {
+        let account = &participant.lock().unwrap().account;
+        account.username == login_request.username && account.password == login_request.password
+    });
+
+    let participant = match participant {
+        Some(participant) => participant.clone(),
+        None => return reject(soup_bin::REJECT_NOT_AUTHORIZED),
+    };
+
+    let mut locked = participant.lock().unwrap();
+
+    if locked.connection.is_some() || !(login_request.requested_session.is_empty() || login_request.requested_session == session_name) {
+        drop(locked);
+        return reject(soup_bin::REJECT_SESSION_NOT_AVAILABLE);
+    }
+
+    let sequence_number = soup_bin::replay_from(login_request.requested_sequence_number, locked.session.next_sequence_number());
+
+    let mut payload = Vec::new();
+    LoginAccepted { session: session_name.to_string(), sequence_number }.encode(&mut payload);
+
+    let connection_id = locked.next_connection_id;
+    locked.next_connection_id += 1;
+    locked.connection = Some(Connection::new(connection_id, stream, sequence_number));
+
+    locked.send(soup_bin::PACKET_LOGIN_ACCEPTED, &payload);
+    locked.send_pending();
+    drop(locked);
+
+    Ok((participant, connection_id))
+}
+
+// OUCH timestamps are nanoseconds since midnight
+fn timestamp() -> Nanos {
+    get_current_nanos() % NANOS_PER_DAY
+}
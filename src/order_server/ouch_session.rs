@@ -0,0 +1,412 @@
+use std::collections::HashMap;
+
+use crate::client::order_manager::{ClientError, OrderGateway, OrderManager, OrderState};
+use crate::common::{Nanos, OrderId, OrderType, ParticipantId, Price, Quantity, SeqNum, Side, SymbolId, INVALID_PRICE, NO_ORDER_FLAGS, ORDER_FLAG_HIDDEN, ORDER_FLAG_POST_ONLY};
+use crate::market_data::itch;
+
+use super::ouch::{self, Accepted, BeInt, CancelOrder, CancelReject, Canceled, EnterOrder, Executed, Inbound, OuchMessage, Rejected, ReplaceOrder, Replaced, Token};
+use super::participants_request::ParticipantRequest;
+use super::participants_response::{ParticipantResponse, ParticipantResponseType};
+
+// what the client said about an order that the engine doesn't keep
+#[derive(Clone, Copy)]
+struct TokenOrder {
+    token: Token,
+    firm: [u8; 4],
+    display: u8,
+    time_in_force: BeInt<4>,
+    replaces: Option<Token>, //until the replacement is accepted
+    replaced_shares: Quantity, //cancelled from the order it replaces
+    cancel_requested: bool,
+}
+
+// one participant's OUCH session: turns its inbound messages into requests and the engine's responses into
+// sequenced outbound messages, kept for the whole session so that a client logging in again can be sent them again.
+// Order tokens map onto client order ids of an order manager, which also holds replacements back until the
+// replaced order is cancelled. The engine can't reduce an order in place, a cancel leaving shares gets a Cancel Reject
+pub struct OuchSession<G: OrderGateway> {
+    orders: OrderManager<G>,
+    stocks: Vec<[u8; 8]>, //indexed by symbol id
+    order_ids: HashMap<Token, OrderId>, //live orders, indexed by token
+    token_orders: Vec<Option<TokenOrder>>, //indexed by client order id
+    journal: Vec<u8>,
+    offsets: Vec<usize>, //start of every outbound message in the journal, indexed by sequence number - 1
+}
+
+impl<G: OrderGateway> OuchSession<G> {
+    // symbol names indexed by symbol id
+    pub fn new(gateway: G, participant_id: ParticipantId, max_order_ids: usize, symbol_names: &[&str]) -> Self {
+        Self {
+            orders: OrderManager::new(gateway, participant_id, max_order_ids),
+            stocks: symbol_names.iter().map(|name| itch::stock(name)).collect(),
+            order_ids: HashMap::new(),
+            token_orders: Vec::new(),
+            journal: Vec::new(),
+            offsets: Vec::new(),
+        }
+    }
+
+    pub fn participant_id(&self) -> ParticipantId {
+        self.orders.participant_id()
+    }
+
+    // sequence numbers start at 1
+    pub fn next_sequence_number(&self) -> SeqNum {
+        self.offsets.len() as SeqNum + 1
+    }
+
+    pub fn message(&self, sequence_number: SeqNum) -> Option<&[u8]> {
+        let idx = sequence_number.checked_sub(1)? as usize;
+        let start = *self.offsets.get(idx)?;
+        let end = self.offsets.get(idx + 1).copied().unwrap_or(self.journal.len());
+        Some(&self.journal[start..end])
+    }
+
+    // only fails when the engine can't be reached, bad orders are rejected and unknown tokens ignored
+    pub fn on_message(&mut self, message: Inbound, timestamp: Nanos) -> Result<(), ClientError> {
+        match message {
+            Inbound::EnterOrder(enter_order) => self.enter_order(enter_order, timestamp),
+            Inbound::ReplaceOrder(replace_order) => self.replace_order(replace_order, timestamp),
+            Inbound::CancelOrder(cancel_order) => self.cancel_order(cancel_order, timestamp),
+        }
+    }
+
+    pub fn on_response(&mut self, response: &ParticipantResponse, timestamp: Nanos) -> Result<(), ClientError> {
+        if response.participant_id != self.participant_id() {
+            return Ok(());
+        }
+
+        let order_id = response.participant_order_id;
+        let replacement_id = self.orders.order(order_id).and_then(|order| order.replaced_by);
+
+        let state = match self.orders.on_response(response)? {
+            Some(order) => order.state,
+            None => return Ok(()),
+        };
+
+        // the replacement of an order that filled, or whose cancel was rejected, is dropped before it's sent
+        let is_replaced = match replacement_id {
+            Some(replacement_id) if self.orders.order(replacement_id).is_some_and(|replacement| replacement.state == OrderState::Cancelled) => {
+                self.release(replacement_id);
+                false
+            },
+            Some(_) => true,
+            None => false,
+        };
+
+        let token_order = match self.token_orders.get(order_id as usize).and_then(Option::as_ref) {
+            Some(token_order) => *token_order,
+            None => return Ok(()),
+        };
+
+        match response.response_type {
+            ParticipantResponseType::Accepted => {
+                self.token_orders[order_id as usize].as_mut().unwrap().replaces = None;
+
+                match token_order.replaces {
+                    Some(previous_order_token) => self.publish(&Replaced {
+                        message_type: Replaced::MESSAGE_TYPE,
+                        timestamp: BeInt::new(timestamp),
+                        replacement_order_token: token_order.token,
+                        side: side(response.side),
+                        shares: BeInt::new(response.leaves_qty as u64),
+                        stock: self.stocks[response.symbol_id as usize],
+                        price: BeInt::new(price(response.price)),
+                        time_in_force: token_order.time_in_force,
+                        firm: token_order.firm,
+                        display: token_order.display,
+                        order_reference_number: BeInt::new(response.internal_order_id),
+                        order_state: ouch::ORDER_STATE_LIVE,
+                        previous_order_token,
+                        ..Replaced::default()
+                    }),
+                    None => self.publish(&Accepted {
+                        message_type: Accepted::MESSAGE_TYPE,
+                        timestamp: BeInt::new(timestamp),
+                        order_token: token_order.token,
+                        side: side(response.side),
+                        shares: BeInt::new(response.leaves_qty as u64),
+                        stock: self.stocks[response.symbol_id as usize],
+                        price: BeInt::new(price(response.price)),
+                        time_in_force: token_order.time_in_force,
+                        firm: token_order.firm,
+                        display: token_order.display,
+                        order_reference_number: BeInt::new(response.internal_order_id),
+                        order_state: ouch::ORDER_STATE_LIVE,
+                        ..Accepted::default()
+                    }),
+                }
+            },
+            // the match number is the trade id, the same on both sides and in the market data
+            ParticipantResponseType::Filled => self.publish(&Executed {
+                message_type: Executed::MESSAGE_TYPE,
+                timestamp: BeInt::new(timestamp),
+                order_token: token_order.token,
+                executed_shares: BeInt::new(response.exec_qty as u64),
+                execution_price: BeInt::new(price(response.price)),
+                liquidity_flag: if response.aggressor { ouch::LIQUIDITY_REMOVED } else { ouch::LIQUIDITY_ADDED },
+                match_number: BeInt::new(response.trade_id),
+            }),
+            // the replaced order goes quietly, its replacement is reported once accepted
+            ParticipantResponseType::Cancelled if is_replaced && state == OrderState::Cancelled => {
+                if let Some(replacement) = self.token_orders[replacement_id.unwrap() as usize].as_mut() {
+                    replacement.replaced_shares = response.leaves_qty;
+                }
+            },
+            ParticipantResponseType::Cancelled => self.publish(&Canceled {
+                message_type: Canceled::MESSAGE_TYPE,
+                timestamp: BeInt::new(timestamp),
+                order_token: token_order.token,
+                decrement_shares: BeInt::new(response.leaves_qty as u64),
+                reason: if token_order.cancel_requested { ouch::CANCEL_REASON_USER } else { ouch::CANCEL_REASON_SYSTEM },
+            }),
+            ParticipantResponseType::CancelRejected => {
+                if token_order.cancel_requested && !state.is_terminal() {
+                    self.token_orders[order_id as usize].as_mut().unwrap().cancel_requested = false;
+                    self.cancel_reject(token_order.token, timestamp);
+                }
+            },
+            ParticipantResponseType::Rejected => {
+                // the order a rejected replacement was meant to replace is already gone
+                if let Some(previous_order_token) = token_order.replaces {
+                    self.publish(&Canceled {
+                        message_type: Canceled::MESSAGE_TYPE,
+                        timestamp: BeInt::new(timestamp),
+                        order_token: previous_order_token,
+                        decrement_shares: BeInt::new(token_order.replaced_shares as u64),
+                        reason: ouch::CANCEL_REASON_SYSTEM,
+                    });
+                }
+
+                self.reject(token_order.token, ouch::REJECT_REASON_OTHER, timestamp);
+            },
+            // the order manager doesn't tie them to an order, and the token of a filled order is gone
+            ParticipantResponseType::TradeBusted | ParticipantResponseType::TradeCorrected | ParticipantResponseType::Invalid => {},
+        }
+
+        if state.is_terminal() {
+            self.release(order_id);
+        }
+
+        Ok(())
+    }
+
+    fn enter_order(&mut self, enter_order: &EnterOrder, timestamp: Nanos) -> Result<(), ClientError> {
+        let token = enter_order.order_token;
+
+        // tokens must be unique among the live orders, a duplicate is ignored
+        if self.order_ids.contains_key(&token) {
+            return Ok(());
+        }
+
+        let request = match self.new_order_request(enter_order) {
+            Ok(request) => request,
+            Err(reason) => {
+                self.reject(token, reason, timestamp);
+                return Ok(());
+            },
+        };
+
+        let order_id = match self.orders.send_new(request) {
+            Ok(order_id) => order_id,
+            Err(ClientError::NoFreeOrderId) => {
+                self.reject(token, ouch::REJECT_REASON_OTHER, timestamp);
+                return Ok(());
+            },
+            Err(error) => return Err(error),
+        };
+
+        self.insert(order_id, TokenOrder {
+            token,
+            firm: enter_order.firm,
+            display: enter_order.display,
+            time_in_force: enter_order.time_in_force,
+            replaces: None,
+            replaced_shares: 0,
+            cancel_requested: false,
+        });
+
+        Ok(())
+    }
+
+    // the reject reason when the order can't be sent
+    fn new_order_request(&self, enter_order: &EnterOrder) -> Result<ParticipantRequest, u8> {
+        let symbol_id = self.stocks.iter().position(|stock| *stock == enter_order.stock).ok_or(ouch::REJECT_REASON_INVALID_STOCK)?;
+
+        let side = match enter_order.side {
+            ouch::SIDE_BUY => Side::Buy,
+            ouch::SIDE_SELL | ouch::SIDE_SELL_SHORT | ouch::SIDE_SELL_SHORT_EXEMPT => Side::Sell,
+            _ => return Err(ouch::REJECT_REASON_OTHER),
+        };
+
+        let flags = match enter_order.display {
+            ouch::DISPLAY_VISIBLE => NO_ORDER_FLAGS,
+            ouch::DISPLAY_HIDDEN => ORDER_FLAG_HIDDEN,
+            ouch::DISPLAY_POST_ONLY => ORDER_FLAG_POST_ONLY,
+            _ => return Err(ouch::REJECT_REASON_INVALID_DISPLAY),
+        };
+
+        let qty = enter_order.shares.get() as Quantity;
+        if qty == 0 {
+            return Err(ouch::REJECT_REASON_INVALID_SHARES);
+        }
+
+        let (order_type, price) = match enter_order.price.get() {
+            ouch::PRICE_MARKET => (OrderType::Market, INVALID_PRICE),
+            price => (OrderType::Limit, price as Price),
+        };
+
+        // orders rest until cancelled or the end of the day, only a market order, which never rests, can be IOC
+        match enter_order.time_in_force.get() {
+            ouch::TIME_IN_FORCE_MARKET_HOURS | ouch::TIME_IN_FORCE_SYSTEM_HOURS => {},
+            ouch::TIME_IN_FORCE_IOC if order_type == OrderType::Market => {},
+            _ => return Err(ouch::REJECT_REASON_OTHER),
+        }
+
+        Ok(ParticipantRequest {
+            symbol_id: symbol_id as SymbolId,
+            order_type,
+            side,
+            price,
+            qty,
+            flags,
+            ..ParticipantRequest::default()
+        })
+    }
+
+    // the display and time in force stay those of the replaced order
+    fn replace_order(&mut self, replace_order: &ReplaceOrder, timestamp: Nanos) -> Result<(), ClientError> {
+        let (existing_token, replacement_token) = (replace_order.existing_order_token, replace_order.replacement_order_token);
+
+        let order_id = match self.order_ids.get(&existing_token) {
+            Some(order_id) if !self.order_ids.contains_key(&replacement_token) => *order_id,
+            _ => return Ok(()),
+        };
+
+        // one replace at a time, once the order is accepted
+        let token_order = self.token_orders[order_id as usize].unwrap();
+        if token_order.replaces.is_some() || self.orders.order(order_id).is_none_or(|order| order.state == OrderState::PendingNew) {
+            return Ok(());
+        }
+
+        let shares = replace_order.shares.get() as Quantity;
+        if shares == 0 {
+            self.reject(replacement_token, ouch::REJECT_REASON_INVALID_SHARES, timestamp);
+            return Ok(());
+        }
+
+        match self.orders.send_modify(order_id, replace_order.price.get() as Price, shares) {
+            Ok(replacement_id) => self.insert(replacement_id, TokenOrder {
+                token: replacement_token,
+                replaces: Some(existing_token),
+                cancel_requested: false,
+                ..token_order
+            }),
+            Err(ClientError::NoFreeOrderId) => self.reject(replacement_token, ouch::REJECT_REASON_OTHER, timestamp),
+            // already being cancelled or done
+            Err(ClientError::NotCancelable(..) | ClientError::UnknownOrder(_)) => {},
+            Err(error) => return Err(error),
+        }
+
+        Ok(())
+    }
+
+    fn cancel_order(&mut self, cancel_order: &CancelOrder, timestamp: Nanos) -> Result<(), ClientError> {
+        let token = cancel_order.order_token;
+
+        let order = match self.order_ids.get(&token).and_then(|order_id| self.orders.order(*order_id)) {
+            Some(order) => order,
+            None => return Ok(()),
+        };
+
+        let (order_id, shares) = (order.order_id(), cancel_order.shares.get());
+        if shares >= order.leaves_qty as u64 {
+            return Ok(());
+        }
+
+        if shares > 0 {
+            self.cancel_reject(token, timestamp);
+            return Ok(());
+        }
+
+        // a replacement still held back is dropped, the cancel of the order it replaces is already on its way
+        let held_back_replaces = order.replaces;
+
+        match self.orders.send_cancel(order_id) {
+            Ok(()) => {},
+            Err(ClientError::NotCancelable(..) | ClientError::UnknownOrder(_)) => return Ok(()),
+            Err(error) => return Err(error),
+        }
+
+        let cancelled_id = match held_back_replaces {
+            Some(replaced_id) => {
+                self.release(order_id);
+                replaced_id
+            },
+            None => order_id,
+        };
+
+        if let Some(token_order) = self.token_orders[cancelled_id as usize].as_mut() {
+            token_order.cancel_requested = true;
+        }
+
+        Ok(())
+    }
+
+    fn cancel_reject(&mut self, order_token: Token, timestamp: Nanos) {
+        self.publish(&CancelReject {
+            message_type: CancelReject::MESSAGE_TYPE,
+            timestamp: BeInt::new(timestamp),
+            order_token,
+        });
+    }
+
+    fn reject(&mut self, order_token: Token, reason: u8, timestamp: Nanos) {
+        self.publish(&Rejected {
+            message_type: Rejected::MESSAGE_TYPE,
+            timestamp: BeInt::new(timestamp),
+            order_token,
+            reason,
+        });
+    }
+
+    fn publish(&mut self, message: &impl OuchMessage) {
+        self.offsets.push(self.journal.len());
+        self.journal.extend_from_slice(message.as_bytes());
+    }
+
+    fn insert(&mut self, order_id: OrderId, token_order: TokenOrder) {
+        let idx = order_id as usize;
+
+        if self.token_orders.len() <= idx {
+            self.token_orders.resize_with(idx + 1, || None);
+        }
+
+        self.order_ids.insert(token_order.token, order_id);
+        self.token_orders[idx] = Some(token_order);
+    }
+
+    // the order is done, its client order id and token can be used again
+    fn release(&mut self, order_id: OrderId) {
+        if let Some(token_order) = self.token_orders.get_mut(order_id as usize).and_then(Option::take) {
+            if self.order_ids.get(&token_order.token) == Some(&order_id) {
+                self.order_ids.remove(&token_order.token);
+            }
+        }
+    }
+}
+
+fn side(side: Side) -> u8 {
+    match side {
+        Side::Sell => ouch::SIDE_SELL,
+        _ => ouch::SIDE_BUY,
+    }
+}
+
+// OUCH prices are 4 bytes
+fn price(price: Price) -> u64 {
+    match price {
+        INVALID_PRICE => ouch::PRICE_MARKET,
+        price => price.min(u32::MAX as Price),
+    }
+}
@@ -2,7 +2,7 @@ use std::fmt;
 
 use crate::common;
 
-#[derive(Clone)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 #[repr(u8)]
 pub enum ParticipantRequestType {
     Invalid = 0,
@@ -20,19 +20,37 @@ impl fmt::Display for ParticipantRequestType {
     }
 }
 
+impl TryFrom<u8> for ParticipantRequestType {
+    type Error = u8;
+
+    fn try_from(value: u8) -> Result<Self, u8> {
+        [ParticipantRequestType::Invalid, ParticipantRequestType::New, ParticipantRequestType::Cancel].into_iter().find(|variant| *variant as u8 == value).ok_or(value)
+    }
+}
+
+// an INVALID_QUANTITY display_qty displays the whole order, a smaller one makes it an iceberg
+// stop_price is only used by stop and stop-limit orders, price is ignored by market and stop orders
+// recv_time is the latency clock time the gateway received the request, 0 when it wasn't stamped
+#[derive(Clone)]
 pub struct ParticipantRequest {
     pub request_type: ParticipantRequestType,
     pub participant_id: common::ParticipantId,
     pub symbol_id: common::SymbolId,
     pub order_id: common::OrderId,
+    pub order_type: common::OrderType,
     pub side: common::Side,
-    pub price: common::Price
+    pub price: common::Price,
+    pub stop_price: common::Price,
+    pub qty: common::Quantity,
+    pub display_qty: common::Quantity,
+    pub flags: common::OrderFlags,
+    pub recv_time: common::Nanos
 }
 
 impl fmt::Display for ParticipantRequest {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "ParticipantRequest [type: {}, ptid:{}, symb:{}, order:{}, side:{}, price:{}]", 
-        self.request_type, self.participant_id, self.symbol_id, self.order_id, self.side, self.price)
+        write!(f, "ParticipantRequest [type: {}, ptid:{}, symb:{}, order:{}, order_type:{}, side:{}, price:{}, stop_price:{}, qty:{}, display_qty:{}, flags:{:#04x}]", 
+        self.request_type, self.participant_id, self.symbol_id, self.order_id, self.order_type, self.side, self.price, self.stop_price, self.qty, self.display_qty, self.flags)
     }
 }
 
@@ -43,8 +61,14 @@ impl Default for ParticipantRequest {
             participant_id: common::INVALID_PARTICIPANT_ID,
             symbol_id: common::INVALID_SYMBOL_ID,
             order_id: common::INVALID_ORDER_ID,
+            order_type: common::OrderType::Invalid,
             side: common::Side::Invalid,
-            price: common::INVALID_PRICE
+            price: common::INVALID_PRICE,
+            stop_price: common::INVALID_PRICE,
+            qty: common::INVALID_QUANTITY,
+            display_qty: common::INVALID_QUANTITY,
+            flags: common::NO_ORDER_FLAGS,
+            recv_time: 0
         }
     }
 }
\ No newline at end of file
@@ -2,14 +2,17 @@ use std::fmt;
 
 use crate::common;
 
-#[derive(Clone)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 #[repr(u8)]
 pub enum ParticipantResponseType {
     Invalid = 0,
     Accepted,
     Cancelled,
     Filled,
-    CancelRejected
+    CancelRejected,
+    Rejected,
+    TradeBusted,
+    TradeCorrected
 }
 
 impl fmt::Display for ParticipantResponseType {
@@ -19,10 +22,26 @@ impl fmt::Display for ParticipantResponseType {
             ParticipantResponseType::Cancelled => write!(f, "CANCELLED"),
             ParticipantResponseType::Filled => write!(f, "FILLED"),
             ParticipantResponseType::CancelRejected => write!(f, "CANCEL-REJECTED"),
+            ParticipantResponseType::Rejected => write!(f, "REJECTED"),
+            ParticipantResponseType::TradeBusted => write!(f, "TRADE-BUSTED"),
+            ParticipantResponseType::TradeCorrected => write!(f, "TRADE-CORRECTED"),
             ParticipantResponseType::Invalid => write!(f, "INVALID"),
         }
     }
 }
+
+impl TryFrom<u8> for ParticipantResponseType {
+    type Error = u8;
+
+    fn try_from(value: u8) -> Result<Self, u8> {
+        [ParticipantResponseType::Invalid, ParticipantResponseType::Accepted, ParticipantResponseType::Cancelled, ParticipantResponseType::Filled, ParticipantResponseType::CancelRejected, ParticipantResponseType::Rejected, ParticipantResponseType::TradeBusted, ParticipantResponseType::TradeCorrected].into_iter().find(|variant| *variant as u8 == value).ok_or(value)
+    }
+}
+
+// aggressor is only set on the fills of the order that took liquidity, trade_id only on fills, busts and corrections.
+// A bust or correction carries the trade's price and quantity after it in price and exec_qty, its quantity before in leaves_qty.
+// The fee is charged on fills and refunded, negative, on busts and corrections that take shares back
+#[derive(Clone)]
 pub struct ParticipantResponse {
     pub response_type: ParticipantResponseType,
     pub participant_id: common::ParticipantId,
@@ -33,12 +52,15 @@ pub struct ParticipantResponse {
     pub price: common::Price,
     pub exec_qty: common::Quantity,
     pub leaves_qty: common::Quantity,
+    pub aggressor: bool,
+    pub trade_id: common::TradeId,
+    pub fee: common::Fee,
 }
 
 
 impl fmt::Display for ParticipantResponse {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f,  "ParticipantResponse [type: {}, ptid: {}, symb: {}, poid: {}, ioid: {}, side: {}, exec_qty: {}, leaves_qty: {}, price: {}]",
+        write!(f,  "ParticipantResponse [type: {}, ptid: {}, symb: {}, poid: {}, ioid: {}, side: {}, exec_qty: {}, leaves_qty: {}, price: {}, aggr: {}, trade: {}, fee: {}]",
         self.response_type,
         self.participant_id,
         self.symbol_id,
@@ -47,7 +69,10 @@ impl fmt::Display for ParticipantResponse {
         self.side,
         self.exec_qty,
         self.leaves_qty,
-        self.price)
+        self.price,
+        self.aggressor,
+        self.trade_id,
+        self.fee)
     }
 }
 
@@ -62,7 +87,10 @@ impl Default for ParticipantResponse {
             side: common::Side::Invalid,
             exec_qty: common::INVALID_QUANTITY,
             leaves_qty: common::INVALID_QUANTITY,
-            price: common::INVALID_PRICE
+            price: common::INVALID_PRICE,
+            aggressor: false,
+            trade_id: common::INVALID_TRADE_ID,
+            fee: 0,
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,60 @@
+use std::sync::mpsc::{self, Receiver, Sender};
+
+use crate::common::{ParticipantId, Sequenced};
+
+use super::participants_response::ParticipantResponse;
+
+// forwards every response to its participant's stream, responses for participants without one are dropped
+// the streams are unbounded so that a client blocked sending to the engine never blocks the engine in turn
+pub struct ResponseRouter {
+    participants: Vec<Option<Sender<Sequenced<ParticipantResponse>>>>, //indexed by participant id
+    observers: Vec<Sender<Sequenced<ParticipantResponse>>>,
+}
+
+impl ResponseRouter {
+    pub fn new() -> Self {
+        Self {
+            participants: Vec::new(),
+            observers: Vec::new(),
+        }
+    }
+
+    pub fn subscribe(&mut self, participant_id: ParticipantId) -> Receiver<Sequenced<ParticipantResponse>> {
+        let (tx, rx) = mpsc::channel();
+        let idx = participant_id as usize;
+
+        if self.participants.len() <= idx {
+            self.participants.resize_with(idx + 1, || None);
+        }
+
+        self.participants[idx] = Some(tx);
+        rx
+    }
+
+    // a copy of every participant's responses, for the back office rather than the participant
+    pub fn subscribe_all(&mut self) -> Receiver<Sequenced<ParticipantResponse>> {
+        let (tx, rx) = mpsc::channel();
+        self.observers.push(tx);
+        rx
+    }
+
+    // returns once every engine has stopped
+    pub fn run(mut self, responses: Receiver<Sequenced<ParticipantResponse>>) {
+        for response in responses.iter() {
+            self.observers.retain(|observer| observer.send(response.clone()).is_ok());
+            let idx = response.payload.participant_id as usize;
+
+            if let Some(Some(participant)) = self.participants.get(idx) {
+                if participant.send(response).is_err() {
+                    self.participants[idx] = None;
+                }
+            }
+        }
+    }
+}
+
+impl Default for ResponseRouter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,37 @@
+use std::sync::mpsc::{SendError, SyncSender};
+
+use crate::common::{ShardId, SymbolId};
+
+use super::participants_request::ParticipantRequest;
+
+// sends each request to the engine shard owning its symbol, unknown symbols go to shard 0 which rejects them
+#[derive(Clone)]
+pub struct ShardRouter {
+    symbol_shards: Vec<ShardId>, //indexed by symbol id
+    shards: Vec<SyncSender<ParticipantRequest>>, //indexed by shard id
+}
+
+impl ShardRouter {
+    pub fn new(symbol_shards: Vec<ShardId>, shards: Vec<SyncSender<ParticipantRequest>>) -> Self {
+        assert!(!shards.is_empty(), "at least one shard is needed");
+        assert!(symbol_shards.iter().all(|shard_id| (*shard_id as usize) < shards.len()), "symbol routed to a missing shard");
+
+        Self {
+            symbol_shards,
+            shards,
+        }
+    }
+
+    pub fn shard_count(&self) -> usize {
+        self.shards.len()
+    }
+
+    pub fn shard_of(&self, symbol_id: SymbolId) -> ShardId {
+        self.symbol_shards.get(symbol_id as usize).copied().unwrap_or(0)
+    }
+
+    // blocks while the shard's queue is full, fails once the shard has stopped
+    pub fn route(&self, request: ParticipantRequest) -> Result<(), SendError<ParticipantRequest>> {
+        self.shards[self.shard_of(request.symbol_id) as usize].send(request)
+    }
+}
@@ -0,0 +1,199 @@
+use std::{io::{self, BufWriter, ErrorKind, Read, Write}, net::{Shutdown, TcpStream}, time::{Duration, Instant}};
+
+use crate::common::{get_current_nanos, SeqNum, NANOS_TO_SECS};
+
+// SoupBinTCP 3.0 framing: every packet is its big endian length on 2 bytes, counting the type but not itself,
+// the packet type and the payload
+
+// sent by the server
+pub const PACKET_DEBUG: u8 = b'+';
+pub const PACKET_LOGIN_ACCEPTED: u8 = b'A';
+pub const PACKET_LOGIN_REJECTED: u8 = b'J';
+pub const PACKET_SEQUENCED_DATA: u8 = b'S';
+pub const PACKET_SERVER_HEARTBEAT: u8 = b'H';
+pub const PACKET_END_OF_SESSION: u8 = b'Z';
+
+// sent by the client
+pub const PACKET_LOGIN_REQUEST: u8 = b'L';
+pub const PACKET_UNSEQUENCED_DATA: u8 = b'U';
+pub const PACKET_CLIENT_HEARTBEAT: u8 = b'R';
+pub const PACKET_LOGOUT_REQUEST: u8 = b'O';
+
+pub const REJECT_NOT_AUTHORIZED: u8 = b'A';
+pub const REJECT_SESSION_NOT_AVAILABLE: u8 = b'S';
+
+pub const MAX_PAYLOAD_SIZE: usize = u16::MAX as usize - 1;
+
+const USERNAME_SIZE: usize = 6;
+const PASSWORD_SIZE: usize = 10;
+const SESSION_SIZE: usize = 10;
+const SEQUENCE_NUMBER_SIZE: usize = 20;
+
+pub fn write_packet(out: &mut impl Write, packet_type: u8, payload: &[u8]) -> io::Result<()> {
+    if payload.len() > MAX_PAYLOAD_SIZE {
+        return Err(io::Error::new(ErrorKind::InvalidInput, "packet payload too long"));
+    }
+
+    let len = (payload.len() + 1) as u16;
+    out.write_all(&len.to_be_bytes())?;
+    out.write_all(&[packet_type])?;
+    out.write_all(payload)
+}
+
+// reads the next packet's payload into `payload` and returns its type
+pub fn read_packet(input: &mut impl Read, payload: &mut Vec<u8>) -> io::Result<u8> {
+    let mut header = [0; 3];
+    input.read_exact(&mut header[..2])?;
+
+    let len = u16::from_be_bytes([header[0], header[1]]) as usize;
+    if len == 0 {
+        return Err(io::Error::new(ErrorKind::InvalidData, "empty packet"));
+    }
+
+    input.read_exact(&mut header[2..])?;
+    payload.resize(len - 1, 0);
+    input.read_exact(payload)?;
+
+    Ok(header[2])
+}
+
+// named after the second the server started, a client can't resume the session of a previous run
+pub fn session_name() -> String {
+    (get_current_nanos() / NANOS_TO_SECS % 10_000_000_000).to_string()
+}
+
+// the first message to send a client logging in. 0 asks for new messages only, past the last one means nothing to replay
+pub fn replay_from(requested_sequence_number: SeqNum, next_sequence_number: SeqNum) -> SeqNum {
+    match requested_sequence_number {
+        0 => next_sequence_number,
+        requested => requested.min(next_sequence_number),
+    }
+}
+
+// the server's side of a logged in client, writes are flushed as they go
+pub struct Connection {
+    pub id: u64,
+    pub next_sequence_number: SeqNum, //the next sequenced message to send it
+    stream: BufWriter<TcpStream>,
+    last_sent: Instant,
+}
+
+impl Connection {
+    pub fn new(id: u64, stream: TcpStream, next_sequence_number: SeqNum) -> Self {
+        Self {
+            id,
+            next_sequence_number,
+            stream: BufWriter::new(stream),
+            last_sent: Instant::now(),
+        }
+    }
+
+    pub fn send(&mut self, packet_type: u8, payload: &[u8]) -> io::Result<()> {
+        self.last_sent = Instant::now();
+        write_packet(&mut self.stream, packet_type, payload)?;
+        self.stream.flush()
+    }
+
+    // sends the sequenced messages it hasn't had yet, `message` looks them up by sequence number
+    pub fn send_pending<'a>(&mut self, message: impl Fn(SeqNum) -> Option<&'a [u8]>) -> io::Result<()> {
+        while let Some(message) = message(self.next_sequence_number) {
+            write_packet(&mut self.stream, PACKET_SEQUENCED_DATA, message)?;
+            self.next_sequence_number += 1;
+            self.last_sent = Instant::now();
+        }
+
+        self.stream.flush()
+    }
+
+    pub fn idle_for(&self) -> Duration {
+        self.last_sent.elapsed()
+    }
+
+    pub fn shutdown(&self) {
+        let _ = self.stream.get_ref().shutdown(Shutdown::Both);
+    }
+}
+
+// a blank session logs into the current one, sequence number 0 asks for new messages only
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct LoginRequest {
+    pub username: String,
+    pub password: String,
+    pub requested_session: String,
+    pub requested_sequence_number: SeqNum,
+}
+
+impl LoginRequest {
+    pub fn encode(&self, out: &mut Vec<u8>) {
+        alpha(&self.username, USERNAME_SIZE, out);
+        alpha(&self.password, PASSWORD_SIZE, out);
+        alpha(&self.requested_session, SESSION_SIZE, out);
+        numeric(self.requested_sequence_number, out);
+    }
+
+    pub fn decode(payload: &[u8]) -> Option<Self> {
+        if payload.len() != USERNAME_SIZE + PASSWORD_SIZE + SESSION_SIZE + SEQUENCE_NUMBER_SIZE {
+            return None;
+        }
+
+        let (username, rest) = payload.split_at(USERNAME_SIZE);
+        let (password, rest) = rest.split_at(PASSWORD_SIZE);
+        let (requested_session, requested_sequence_number) = rest.split_at(SESSION_SIZE);
+
+        Some(Self {
+            username: parse_alpha(username)?,
+            password: parse_alpha(password)?,
+            requested_session: parse_alpha(requested_session)?,
+            requested_sequence_number: parse_numeric(requested_sequence_number)?,
+        })
+    }
+}
+
+// the sequence number of the next sequenced message the client will receive
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct LoginAccepted {
+    pub session: String,
+    pub sequence_number: SeqNum,
+}
+
+impl LoginAccepted {
+    pub fn encode(&self, out: &mut Vec<u8>) {
+        alpha(&self.session, SESSION_SIZE, out);
+        numeric(self.sequence_number, out);
+    }
+
+    pub fn decode(payload: &[u8]) -> Option<Self> {
+        if payload.len() != SESSION_SIZE + SEQUENCE_NUMBER_SIZE {
+            return None;
+        }
+
+        let (session, sequence_number) = payload.split_at(SESSION_SIZE);
+
+        Some(Self {
+            session: parse_alpha(session)?,
+            sequence_number: parse_numeric(sequence_number)?,
+        })
+    }
+}
+
+// alphanumeric fields are left justified and space padded, cut to their size
+fn alpha(value: &str, size: usize, out: &mut Vec<u8>) {
+    let len = value.len().min(size);
+    out.extend_from_slice(&value.as_bytes()[..len]);
+    out.resize(out.len() + size - len, b' ');
+}
+
+// numeric fields are right justified and space padded
+fn numeric(value: SeqNum, out: &mut Vec<u8>) {
+    out.extend_from_slice(format!("{:>1$}", value, SEQUENCE_NUMBER_SIZE).as_bytes());
+}
+
+fn parse_alpha(field: &[u8]) -> Option<String> {
+    std::str::from_utf8(field).ok().map(|value| value.trim_end().to_string())
+}
+
+// a blank field is 0
+fn parse_numeric(field: &[u8]) -> Option<SeqNum> {
+    let value = std::str::from_utf8(field).ok()?.trim();
+    if value.is_empty() { Some(0) } else { value.parse().ok() }
+}
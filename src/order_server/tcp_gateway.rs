@@ -0,0 +1,231 @@
+use std::{io::{self, BufReader, BufWriter, ErrorKind, Write}, net::{Shutdown, SocketAddr, TcpListener, TcpStream}, sync::{mpsc::Receiver, Arc, Mutex}, thread};
+
+use serde::Deserialize;
+
+use crate::common::{ParticipantId, Sequenced, INVALID_ORDER_ID, INVALID_QUANTITY, INVALID_TRADE_ID};
+use crate::latency::clock;
+use crate::logging::logger::{LogPayload, Logger};
+use crate::wire::{self, LoginFrame, RequestFrame, ResponseFrame, WireFrame, MAX_FRAME_SIZE};
+
+use super::participants_request::ParticipantRequest;
+use super::participants_response::{ParticipantResponse, ParticipantResponseType};
+use super::response_router::ResponseRouter;
+use super::shard_router::ShardRouter;
+
+// an empty address leaves the gateway off
+#[derive(Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GatewayConfig {
+    pub address: String,
+    pub logins: Vec<GatewayLogin>,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GatewayLogin {
+    pub participant: ParticipantId,
+    pub password: String,
+}
+
+// the first frame of a connection, and the gateway's answer to it
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct Login {
+    pub participant_id: ParticipantId,
+    pub password: String,
+    pub accepted: bool,
+}
+
+// one connection at a time, the responses arriving while none is logged in are dropped
+struct Participant {
+    login: GatewayLogin,
+    connection: Option<(u64, BufWriter<TcpStream>)>,
+    next_connection_id: u64,
+}
+
+impl Participant {
+    // a connection that fails is dropped
+    fn send(&mut self, frame: &[u8]) {
+        if let Some((_, writer)) = self.connection.as_mut() {
+            if writer.write_all(frame).is_err() {
+                self.disconnect();
+            }
+        }
+    }
+
+    fn flush(&mut self) {
+        if let Some((_, writer)) = self.connection.as_mut() {
+            if writer.flush().is_err() {
+                self.disconnect();
+            }
+        }
+    }
+
+    fn disconnect(&mut self) {
+        if let Some((_, writer)) = self.connection.take() {
+            let _ = writer.get_ref().shutdown(Shutdown::Both);
+        }
+    }
+}
+
+// accepts order entry connections, each one read by its own thread and routed to the shards. A connection logs in as
+// one of the `logins` first, only sends requests of its participant and gets that participant's responses back.
+// Returns the address it listens on, which tells the port when the configured one is 0
+pub fn spawn(config: &GatewayConfig, router: ShardRouter, response_router: &mut ResponseRouter, stamp_latency: bool, logger: Logger) -> io::Result<SocketAddr> {
+    let listener = TcpListener::bind(&config.address)?;
+    let local_addr = listener.local_addr()?;
+
+    let mut participants = Vec::new();
+
+    for login in &config.logins {
+        let responses = response_router.subscribe(login.participant);
+        let participant = Arc::new(Mutex::new(Participant { login: login.clone(), connection: None, next_connection_id: 0 }));
+
+        let pump_participant = participant.clone();
+        thread::spawn(move || pump(pump_participant, responses));
+
+        participants.push(participant);
+    }
+
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    let participants = participants.clone();
+                    let router = router.clone();
+                    let logger = logger.clone();
+                    thread::spawn(move || serve(stream, &participants, router, stamp_latency, logger));
+                },
+                Err(_) => logger.warn("gateway: cannot accept connection", LogPayload::None),
+            }
+        }
+    });
+
+    Ok(local_addr)
+}
+
+// writes the participant's responses to its connection, flushing once a burst is drained. Disconnects once the engines stop
+fn pump(participant: Arc<Mutex<Participant>>, responses: Receiver<Sequenced<ParticipantResponse>>) {
+    for response in responses.iter() {
+        let mut participant = participant.lock().unwrap();
+        participant.send(ResponseFrame::encode(&response.payload).as_bytes());
+
+        for response in responses.try_iter() {
+            participant.send(ResponseFrame::encode(&response.payload).as_bytes());
+        }
+
+        participant.flush();
+    }
+
+    participant.lock().unwrap().disconnect();
+}
+
+fn serve(stream: TcpStream, participants: &[Arc<Mutex<Participant>>], router: ShardRouter, stamp_latency: bool, logger: Logger) {
+    let _ = stream.set_nodelay(true);
+
+    let mut reader = match stream.try_clone() {
+        Ok(stream) => BufReader::new(stream),
+        Err(_) => return,
+    };
+
+    let mut buf = [0; MAX_FRAME_SIZE];
+
+    let login = wire::read_frame(&mut reader, &mut buf).ok().and_then(|length| wire::decode::<LoginFrame>(&buf[..length]).ok()).map(|(login, _)| login);
+
+    let (participant, participant_id, connection_id) = match self::login(stream, login, participants) {
+        Some(login) => login,
+        None => {
+            logger.warn("gateway: login refused", LogPayload::None);
+            return;
+        },
+    };
+
+    logger.info("gateway: logged in", LogPayload::None);
+
+    loop {
+        let length = match wire::read_frame(&mut reader, &mut buf) {
+            Ok(length) => length,
+            Err(error) => {
+                if error.kind() != ErrorKind::UnexpectedEof {
+                    logger.warn("gateway: connection failed", LogPayload::None);
+                }
+                break;
+            },
+        };
+
+        let mut request = match wire::decode::<RequestFrame>(&buf[..length]) {
+            Ok((request, _)) => request,
+            Err(_) => {
+                logger.warn("gateway: malformed request", LogPayload::None);
+                continue;
+            },
+        };
+
+        // the connection only trades for the participant it logged in as
+        if request.participant_id != participant_id {
+            logger.warn("gateway: request for another participant", LogPayload::None);
+            let mut participant = participant.lock().unwrap();
+            participant.send(ResponseFrame::encode(&rejection(&request)).as_bytes());
+            participant.flush();
+            continue;
+        }
+
+        // the engine's clock, not the client's
+        request.recv_time = if stamp_latency { clock::now() } else { 0 };
+
+        if router.route(request).is_err() {
+            break;
+        }
+    }
+
+    let mut participant = participant.lock().unwrap();
+    if participant.connection.as_ref().is_some_and(|(id, _)| *id == connection_id) {
+        participant.disconnect();
+    }
+
+    logger.info("gateway: logged out", LogPayload::None);
+}
+
+// binds the connection to the participant the login names, when its password matches and it isn't connected already.
+// A login frame is answered either way
+fn login(stream: TcpStream, login: Option<Login>, participants: &[Arc<Mutex<Participant>>]) -> Option<(Arc<Mutex<Participant>>, ParticipantId, u64)> {
+    let login = login.filter(|login| !login.accepted)?;
+
+    let reply = |accepted: bool| LoginFrame::encode(&Login { participant_id: login.participant_id, password: String::new(), accepted });
+
+    for participant in participants {
+        let mut locked = participant.lock().unwrap();
+
+        if locked.login.participant != login.participant_id || locked.login.password != login.password || locked.connection.is_some() {
+            continue;
+        }
+
+        let connection_id = locked.next_connection_id;
+        locked.next_connection_id += 1;
+        locked.connection = Some((connection_id, BufWriter::new(stream)));
+        locked.send(reply(true).as_bytes());
+        locked.flush();
+        drop(locked);
+
+        return Some((participant.clone(), login.participant_id, connection_id));
+    }
+
+    let _ = (&stream).write_all(reply(false).as_bytes());
+    None
+}
+
+fn rejection(request: &ParticipantRequest) -> ParticipantResponse {
+    ParticipantResponse {
+        response_type: ParticipantResponseType::Rejected,
+        participant_id: request.participant_id,
+        participant_order_id: request.order_id,
+        symbol_id: request.symbol_id,
+        internal_order_id: INVALID_ORDER_ID,
+        side: request.side,
+        price: request.price,
+        exec_qty: INVALID_QUANTITY,
+        leaves_qty: request.qty,
+        aggressor: false,
+        trade_id: INVALID_TRADE_ID,
+        fee: 0,
+    }
+}
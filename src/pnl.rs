@@ -0,0 +1,61 @@
+use crate::common::{Price, Quantity, Side};
+
+// average cost position keeping, in price ticks. The open cost is what the shares still open were bought or sold for,
+// a fill reducing the position books realized PnL against their average price. A position taken flat or through zero
+// starts over, what opens it next is all its open cost
+#[derive(Clone, Copy, Default)]
+pub struct PositionPnl {
+    pub position: i64,
+    pub open_cost: f64,
+    pub realized: f64,
+    pub traded_qty: u64,
+    pub fills: u64,
+}
+
+impl PositionPnl {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn on_fill(&mut self, side: Side, price: Price, qty: Quantity) {
+        let signed_qty = match side {
+            Side::Buy => qty as i64,
+            _ => -(qty as i64),
+        };
+
+        self.traded_qty += qty as u64;
+        self.fills += 1;
+
+        // adding to the position, or opening one
+        if self.position == 0 || self.position.signum() == signed_qty.signum() {
+            self.position += signed_qty;
+            self.open_cost += price as f64 * qty as f64;
+            return;
+        }
+
+        let closed_qty = signed_qty.abs().min(self.position.abs());
+        let closed_cost = self.open_cost * closed_qty as f64 / self.position.abs() as f64;
+        self.realized += (price as f64 * closed_qty as f64 - closed_cost) * self.position.signum() as f64;
+        self.open_cost -= closed_cost;
+        self.position += signed_qty;
+
+        // a fill larger than the position flips it, the rest opens at the fill price
+        if self.position == 0 {
+            self.open_cost = 0.0;
+        } else if self.position.signum() == signed_qty.signum() {
+            self.open_cost = price as f64 * self.position.abs() as f64;
+        }
+    }
+
+    // the average price of the open shares, 0 when flat
+    pub fn open_price(&self) -> f64 {
+        match self.position {
+            0 => 0.0,
+            position => self.open_cost / position.abs() as f64,
+        }
+    }
+
+    pub fn unrealized(&self, mark_price: Price) -> f64 {
+        self.position as f64 * (mark_price as f64 - self.open_price())
+    }
+}
@@ -0,0 +1,223 @@
+use std::{fmt, sync::{mpsc::{Receiver, RecvTimeoutError, Sender}, Arc, Mutex}, time::Duration};
+
+use serde::Deserialize;
+
+use crate::common::{get_current_nanos, Nanos, ParticipantId, Price, Quantity, Sequenced, Side, SymbolId, TradeId, INVALID_PRICE, NANOS_TO_SECS};
+use crate::market_data::{market_book::MarketBook, market_update::MarketUpdate};
+use crate::order_server::participants_response::{ParticipantResponse, ParticipantResponseType};
+use crate::pnl::PositionPnl;
+use crate::trades::trade::TradeAmendment;
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum MarkPrice {
+    #[default]
+    LastTrade,
+    Mid,
+}
+
+// positions are reported every report interval, 0 reports none. Without the configured mark price, the other is used
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PositionsConfig {
+    #[serde(rename = "report_interval_ns")]
+    pub report_interval: Nanos,
+    pub mark_price: MarkPrice,
+}
+
+impl Default for PositionsConfig {
+    fn default() -> Self {
+        Self {
+            report_interval: 60 * NANOS_TO_SECS,
+            mark_price: MarkPrice::LastTrade,
+        }
+    }
+}
+
+// what a participant holds in a symbol, prices and PnL in price ticks. The open price is the average cost of the shares
+// still open, unrealized PnL is 0 until the symbol has a mark price
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Position {
+    pub participant_id: ParticipantId,
+    pub symbol_id: SymbolId,
+    pub position: i64,
+    pub open_price: f64,
+    pub realized: f64,
+    pub unrealized: f64,
+    pub mark_price: Price,
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Position [ptid: {}, symb: {}, position: {}, open_price: {:.2}, realized: {:.2}, unrealized: {:.2}, mark: {}]",
+        self.participant_id,
+        self.symbol_id,
+        self.position,
+        self.open_price,
+        self.realized,
+        self.unrealized,
+        self.mark_price)
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Fill {
+    trade_id: TradeId,
+    side: Side,
+    price: Price,
+    qty: Quantity,
+}
+
+// the fills are kept so an amended trade can be replayed as it now is, average cost depends on their order
+#[derive(Clone, Default)]
+struct Holding {
+    pnl: PositionPnl,
+    fills: Vec<Fill>,
+}
+
+impl Holding {
+    fn on_fill(&mut self, fill: Fill) {
+        self.pnl.on_fill(fill.side, fill.price, fill.qty);
+        self.fills.push(fill);
+    }
+
+    // a bust leaves no quantity and drops the fill
+    fn amend(&mut self, trade_id: TradeId, side: Side, price: Price, qty: Quantity) {
+        let Some(idx) = self.fills.iter().position(|fill| fill.trade_id == trade_id && fill.side == side) else {
+            return;
+        };
+
+        if qty == 0 {
+            self.fills.remove(idx);
+        } else {
+            self.fills[idx] = Fill { price, qty, ..self.fills[idx] };
+        }
+
+        self.pnl = PositionPnl::new();
+        for fill in &self.fills {
+            self.pnl.on_fill(fill.side, fill.price, fill.qty);
+        }
+    }
+}
+
+// the positions of every participant, built from the fills of all shards and marked with the market data
+pub struct PositionKeeper {
+    holdings: Vec<Vec<Option<Holding>>>, //indexed by participant id then symbol id, None until traded
+    books: Vec<MarketBook>, //indexed by symbol id
+    mark_price: MarkPrice,
+}
+
+impl PositionKeeper {
+    pub fn new(config: &PositionsConfig) -> Self {
+        Self {
+            holdings: Vec::new(),
+            books: Vec::new(),
+            mark_price: config.mark_price,
+        }
+    }
+
+    pub fn on_response(&mut self, response: &ParticipantResponse) {
+        if response.response_type == ParticipantResponseType::Filled {
+            let fill = Fill { trade_id: response.trade_id, side: response.side, price: response.price, qty: response.exec_qty };
+            self.holding_mut(response.participant_id, response.symbol_id).on_fill(fill);
+        }
+    }
+
+    // the busts and corrections come from the admin console rather than their responses, which lack the price the
+    // trade had. Both sides replay their fills as if the trade had always been as it now is
+    pub fn amend(&mut self, amendment: &TradeAmendment) {
+        let (original, amended) = (&amendment.original, &amendment.amended);
+
+        for (participant_id, side) in [(original.buy_participant_id, Side::Buy), (original.sell_participant_id, Side::Sell)] {
+            self.holding_mut(participant_id, original.symbol_id).amend(original.trade_id, side, amended.price, amended.qty);
+        }
+    }
+
+    pub fn on_market_update(&mut self, update: &MarketUpdate) {
+        if self.books.len() <= update.symbol_id as usize {
+            self.books.resize_with(update.symbol_id as usize + 1, MarketBook::new);
+        }
+        self.books[update.symbol_id as usize].on_update(update);
+    }
+
+    pub fn mark_price(&self, symbol_id: SymbolId) -> Option<Price> {
+        let book = self.books.get(symbol_id as usize)?;
+
+        match self.mark_price {
+            MarkPrice::LastTrade => book.last_trade_price().or(book.mid_price()),
+            MarkPrice::Mid => book.mid_price().or(book.last_trade_price()),
+        }
+    }
+
+    pub fn position(&self, participant_id: ParticipantId, symbol_id: SymbolId) -> Option<Position> {
+        let pnl = &self.holdings.get(participant_id as usize)?.get(symbol_id as usize)?.as_ref()?.pnl;
+        let mark_price = self.mark_price(symbol_id);
+
+        Some(Position {
+            participant_id,
+            symbol_id,
+            position: pnl.position,
+            open_price: pnl.open_price(),
+            realized: pnl.realized,
+            unrealized: mark_price.map_or(0.0, |mark_price| pnl.unrealized(mark_price)),
+            mark_price: mark_price.unwrap_or(INVALID_PRICE),
+        })
+    }
+
+    // every symbol the participant traded, by symbol id
+    pub fn positions(&self, participant_id: ParticipantId) -> Vec<Position> {
+        let symbols = self.holdings.get(participant_id as usize).map_or(0, Vec::len);
+        (0..symbols as SymbolId).filter_map(|symbol_id| self.position(participant_id, symbol_id)).collect()
+    }
+
+    pub fn all(&self) -> Vec<Position> {
+        (0..self.holdings.len() as ParticipantId).flat_map(|participant_id| self.positions(participant_id)).collect()
+    }
+
+    fn holding_mut(&mut self, participant_id: ParticipantId, symbol_id: SymbolId) -> &mut Holding {
+        if self.holdings.len() <= participant_id as usize {
+            self.holdings.resize(participant_id as usize + 1, Vec::new());
+        }
+
+        let symbols = &mut self.holdings[participant_id as usize];
+        if symbols.len() <= symbol_id as usize {
+            symbols.resize(symbol_id as usize + 1, None);
+        }
+
+        symbols[symbol_id as usize].get_or_insert_with(Holding::default)
+    }
+}
+
+// applies every response and market update, and sends every position to `reports` each interval, whether anyone
+// listens or not. Returns once the market data stops
+pub fn run(keeper: Arc<Mutex<PositionKeeper>>, responses: Receiver<Sequenced<ParticipantResponse>>, market_updates: Receiver<Sequenced<MarketUpdate>>, reports: Sender<Position>, config: &PositionsConfig) {
+    let mut next_report_time = get_current_nanos() + config.report_interval;
+
+    loop {
+        let update = market_updates.recv_timeout(Duration::from_millis(1));
+        let mut keeper = keeper.lock().unwrap();
+
+        for response in responses.try_iter() {
+            keeper.on_response(&response.payload);
+        }
+
+        match update {
+            Ok(update) => keeper.on_market_update(&update.payload),
+            Err(RecvTimeoutError::Timeout) => {},
+            Err(RecvTimeoutError::Disconnected) => return,
+        }
+
+        for update in market_updates.try_iter() {
+            keeper.on_market_update(&update.payload);
+        }
+
+        let now = get_current_nanos();
+
+        if config.report_interval > 0 && now >= next_report_time {
+            for position in keeper.all() {
+                let _ = reports.send(position);
+            }
+            next_report_time = now + config.report_interval;
+        }
+    }
+}
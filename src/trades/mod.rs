@@ -0,0 +1,2 @@
+pub mod trade;
+pub mod trade_store;
@@ -0,0 +1,78 @@
+use std::fmt;
+
+use crate::common::{self, Fee, Nanos, OrderId, ParticipantId, Price, Quantity, Side, SymbolId, TradeId};
+
+// one match, with the participants' order ids of both sides. An auction trade has no aggressor
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Trade {
+    pub trade_id: TradeId,
+    pub symbol_id: SymbolId,
+    pub aggressor_side: Side,
+    pub price: Price,
+    pub qty: Quantity,
+    pub buy_participant_id: ParticipantId,
+    pub buy_order_id: OrderId,
+    pub sell_participant_id: ParticipantId,
+    pub sell_order_id: OrderId,
+    pub timestamp: Nanos,
+}
+
+impl Trade {
+    pub fn involves(&self, participant_id: ParticipantId) -> bool {
+        self.buy_participant_id == participant_id || self.sell_participant_id == participant_id
+    }
+
+    // a busted trade is kept with no quantity left
+    pub fn is_busted(&self) -> bool {
+        self.qty == 0
+    }
+}
+
+impl fmt::Display for Trade {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Trade [id: {}, symb: {}, aggr: {}, price: {}, qty: {}, buyer: {}/{}, seller: {}/{}, time: {}]",
+        self.trade_id,
+        self.symbol_id,
+        self.aggressor_side,
+        self.price,
+        self.qty,
+        self.buy_participant_id,
+        self.buy_order_id,
+        self.sell_participant_id,
+        self.sell_order_id,
+        self.timestamp)
+    }
+}
+
+impl Default for Trade {
+    fn default() -> Self {
+        Self {
+            trade_id: common::INVALID_TRADE_ID,
+            symbol_id: common::INVALID_SYMBOL_ID,
+            aggressor_side: Side::Invalid,
+            price: common::INVALID_PRICE,
+            qty: common::INVALID_QUANTITY,
+            buy_participant_id: common::INVALID_PARTICIPANT_ID,
+            buy_order_id: common::INVALID_ORDER_ID,
+            sell_participant_id: common::INVALID_PARTICIPANT_ID,
+            sell_order_id: common::INVALID_ORDER_ID,
+            timestamp: 0,
+        }
+    }
+}
+
+// a bust or correction of a trade, as it was and as it is now. Only the price and quantity change, a bust leaves no quantity.
+// The fees are what each side was charged for the trade as it was, its shares are refunded or charged at the same rate
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct TradeAmendment {
+    pub original: Trade,
+    pub amended: Trade,
+    pub buy_fee: Fee,
+    pub sell_fee: Fee,
+}
+
+impl TradeAmendment {
+    pub fn is_bust(&self) -> bool {
+        self.amended.is_busted()
+    }
+}
@@ -0,0 +1,187 @@
+use std::{collections::HashMap, fs::{self, File, OpenOptions}, io::{self, BufReader, BufWriter, ErrorKind, Write}, path::{Path, PathBuf}, sync::{mpsc::Receiver, Arc, Mutex}};
+
+use serde::Deserialize;
+
+use crate::common::{Nanos, ParticipantId, ShardId, SymbolId, TradeId, TRADE_ID_SHARD_SHIFT};
+use crate::logging::logger::{LogPayload, Logger};
+use crate::wire::{self, TradeFrame, WireFrame, MAX_FRAME_SIZE};
+
+use super::trade::Trade;
+
+// an empty path leaves the trades uncaptured
+#[derive(Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TradeStoreConfig {
+    pub path: PathBuf,
+}
+
+// every filter is optional, the time range includes `from` and excludes `to`. Busted trades never match
+#[derive(Clone, Default)]
+pub struct TradeQuery {
+    pub symbol_id: Option<SymbolId>,
+    pub participant_id: Option<ParticipantId>,
+    pub from: Option<Nanos>,
+    pub to: Option<Nanos>,
+}
+
+impl TradeQuery {
+    pub fn matches(&self, trade: &Trade) -> bool {
+        !trade.is_busted() &&
+        self.symbol_id.is_none_or(|symbol_id| trade.symbol_id == symbol_id) &&
+        self.participant_id.is_none_or(|participant_id| trade.involves(participant_id)) &&
+        self.from.is_none_or(|from| trade.timestamp >= from) &&
+        self.to.is_none_or(|to| trade.timestamp < to)
+    }
+}
+
+// an append-only file of trade frames, also kept in memory to be queried. A trade is appended once under its id, a bust
+// or correction is written after it and replaces it. A frame cut short by a crash is dropped when the file is opened again,
+// anything else that doesn't decode fails the open
+pub struct TradeStore {
+    file: BufWriter<File>,
+    trades: Vec<Trade>,
+    trade_ids: HashMap<TradeId, usize>, //index in trades
+    symbols: Vec<Vec<usize>>, //indexes in trades, indexed by symbol id
+    participants: Vec<Vec<usize>>, //indexes in trades, indexed by participant id
+}
+
+impl TradeStore {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        if let Some(directory) = path.parent().filter(|directory| !directory.as_os_str().is_empty()) {
+            fs::create_dir_all(directory)?;
+        }
+
+        let file = OpenOptions::new().read(true).append(true).create(true).open(path)?;
+        let mut reader = BufReader::new(&file);
+        let mut buf = [0; MAX_FRAME_SIZE];
+        let mut trades = Vec::new();
+        let mut valid_len = 0;
+
+        loop {
+            match wire::read_frame(&mut reader, &mut buf) {
+                Ok(length) => {
+                    let (trade, _) = wire::decode::<TradeFrame>(&buf[..length]).map_err(|error| io::Error::new(ErrorKind::InvalidData, error))?;
+                    trades.push(trade);
+                    valid_len += length as u64;
+                },
+                Err(error) if error.kind() == ErrorKind::UnexpectedEof => break,
+                Err(error) => return Err(error),
+            }
+        }
+
+        file.set_len(valid_len)?;
+
+        let mut store = Self {
+            file: BufWriter::new(file),
+            trades: Vec::new(),
+            trade_ids: HashMap::new(),
+            symbols: Vec::new(),
+            participants: Vec::new(),
+        };
+
+        for trade in trades {
+            store.insert(trade);
+        }
+
+        Ok(store)
+    }
+
+    // written out on the next flush, an id already stored is refused
+    pub fn append(&mut self, trade: Trade) -> io::Result<()> {
+        if self.trade_ids.contains_key(&trade.trade_id) {
+            return Err(io::Error::new(ErrorKind::AlreadyExists, format!("trade {} is already stored", trade.trade_id)));
+        }
+
+        self.write(trade)
+    }
+
+    // a bust or correction of a stored trade, written out on the next flush
+    pub fn amend(&mut self, trade: Trade) -> io::Result<()> {
+        if !self.trade_ids.contains_key(&trade.trade_id) {
+            return Err(io::Error::new(ErrorKind::NotFound, format!("trade {} is not stored", trade.trade_id)));
+        }
+
+        self.write(trade)
+    }
+
+    fn write(&mut self, trade: Trade) -> io::Result<()> {
+        let result = self.file.write_all(TradeFrame::encode(&trade).as_bytes());
+        self.insert(trade);
+        result
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+
+    pub fn len(&self) -> usize {
+        self.trades.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.trades.is_empty()
+    }
+
+    pub fn trade(&self, trade_id: TradeId) -> Option<&Trade> {
+        self.trade_ids.get(&trade_id).map(|&idx| &self.trades[idx])
+    }
+
+    // the highest id the shard gave a stored trade
+    pub fn last_trade_id(&self, shard_id: ShardId) -> Option<TradeId> {
+        self.trade_ids.keys().copied().filter(|&trade_id| trade_id >> TRADE_ID_SHARD_SHIFT == shard_id as TradeId).max()
+    }
+
+    // in the order the trades were stored
+    pub fn query(&self, query: &TradeQuery) -> Vec<&Trade> {
+        let candidates = match (query.symbol_id, query.participant_id) {
+            (Some(symbol_id), _) => self.symbols.get(symbol_id as usize),
+            (None, Some(participant_id)) => self.participants.get(participant_id as usize),
+            (None, None) => return self.trades.iter().filter(|trade| query.matches(trade)).collect(),
+        };
+
+        candidates.map_or(&[][..], Vec::as_slice).iter().map(|&idx| &self.trades[idx]).filter(|trade| query.matches(trade)).collect()
+    }
+
+    fn insert(&mut self, trade: Trade) {
+        if let Some(&idx) = self.trade_ids.get(&trade.trade_id) {
+            self.trades[idx] = trade;
+            return;
+        }
+
+        let idx = self.trades.len();
+        self.trade_ids.insert(trade.trade_id, idx);
+
+        push_index(&mut self.symbols, trade.symbol_id as usize, idx);
+        push_index(&mut self.participants, trade.buy_participant_id as usize, idx);
+
+        if trade.sell_participant_id != trade.buy_participant_id {
+            push_index(&mut self.participants, trade.sell_participant_id as usize, idx);
+        }
+
+        self.trades.push(trade);
+    }
+}
+
+fn push_index(indexes: &mut Vec<Vec<usize>>, key: usize, idx: usize) {
+    if indexes.len() <= key {
+        indexes.resize_with(key + 1, Vec::new);
+    }
+
+    indexes[key].push(idx);
+}
+
+// stores the engines' trades as they come, flushing once a burst is drained. Returns once every engine has stopped
+pub fn run(store: Arc<Mutex<TradeStore>>, trades: Receiver<Trade>, logger: Logger) {
+    for trade in trades.iter() {
+        let mut store = store.lock().unwrap();
+        let mut result = store.append(trade);
+
+        for trade in trades.try_iter() {
+            result = result.and(store.append(trade));
+        }
+
+        if result.and_then(|_| store.flush()).is_err() {
+            logger.error("trade store: cannot write trades", LogPayload::None);
+        }
+    }
+}
@@ -0,0 +1,477 @@
+use std::{cmp, fmt, io::{self, ErrorKind, Read}, mem, slice};
+
+use crate::common::{OrderType, Side};
+use crate::market_data::market_update::{MarketUpdate, MarketUpdateType};
+use crate::order_server::{participants_request::{ParticipantRequest, ParticipantRequestType}, participants_response::{ParticipantResponse, ParticipantResponseType}, tcp_gateway::Login};
+use crate::positions::Position;
+use crate::trades::trade::Trade;
+
+// the fixed layout every transport uses for the engine's messages. A frame is a header and the message's fields,
+// packed and little endian, so it can be read in place from any offset of a buffer and written as its bytes.
+// Enums are bytes on the wire and only become enums once decoded, an unknown discriminant fails the decode
+
+pub const WIRE_VERSION: u8 = 3;
+
+pub const MESSAGE_REQUEST: u8 = b'Q';
+pub const MESSAGE_RESPONSE: u8 = b'R';
+pub const MESSAGE_MARKET_UPDATE: u8 = b'M';
+pub const MESSAGE_TRADE: u8 = b'T';
+pub const MESSAGE_LOGIN: u8 = b'L';
+pub const MESSAGE_POSITION: u8 = b'P';
+
+pub const LOGIN_PASSWORD_SIZE: usize = 16;
+
+pub const HEADER_SIZE: usize = mem::size_of::<FrameHeader>();
+pub const MAX_FRAME_SIZE: usize = mem::size_of::<TradeFrame>();
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum WireError {
+    Truncated(usize), //the frame size
+    Version(u8),
+    MessageType(u8),
+    Length(u16),
+    Discriminant(&'static str, u8),
+    Text(&'static str),
+}
+
+impl fmt::Display for WireError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WireError::Truncated(size) => write!(f, "frame cut short of its {} bytes", size),
+            WireError::Version(version) => write!(f, "unsupported wire version {}", version),
+            WireError::MessageType(message_type) => write!(f, "unexpected message type {:#04x}", message_type),
+            WireError::Length(length) => write!(f, "wrong frame length {}", length),
+            WireError::Discriminant(field, value) => write!(f, "invalid {} {}", field, value),
+            WireError::Text(field) => write!(f, "invalid {} text", field),
+        }
+    }
+}
+
+impl std::error::Error for WireError {}
+
+// the length counts the whole frame, header included
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[repr(C, packed)]
+pub struct FrameHeader {
+    pub version: u8,
+    pub message_type: u8,
+    pub length: u16,
+}
+
+// the engine doesn't trust recv_time, the gateway stamps it again
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[repr(C, packed)]
+pub struct RequestFrame {
+    pub header: FrameHeader,
+    pub request_type: u8,
+    pub order_type: u8,
+    pub side: u8,
+    pub flags: u8,
+    pub participant_id: u32,
+    pub symbol_id: u32,
+    pub qty: u32,
+    pub display_qty: u32,
+    pub order_id: u64,
+    pub price: u64,
+    pub stop_price: u64,
+    pub recv_time: u64,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[repr(C, packed)]
+pub struct ResponseFrame {
+    pub header: FrameHeader,
+    pub response_type: u8,
+    pub side: u8,
+    pub aggressor: u8,
+    pub reserved: u8,
+    pub participant_id: u32,
+    pub symbol_id: u32,
+    pub exec_qty: u32,
+    pub leaves_qty: u32,
+    pub participant_order_id: u64,
+    pub internal_order_id: u64,
+    pub price: u64,
+    pub trade_id: u64,
+    pub fee: i64,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[repr(C, packed)]
+pub struct MarketUpdateFrame {
+    pub header: FrameHeader,
+    pub update_type: u8,
+    pub side: u8,
+    pub reserved: [u8; 2],
+    pub symbol_id: u32,
+    pub qty: u32,
+    pub order_id: u64,
+    pub price: u64,
+    pub priority: u64,
+    pub trade_id: u64,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[repr(C, packed)]
+pub struct TradeFrame {
+    pub header: FrameHeader,
+    pub aggressor_side: u8,
+    pub reserved: [u8; 3],
+    pub symbol_id: u32,
+    pub qty: u32,
+    pub buy_participant_id: u32,
+    pub sell_participant_id: u32,
+    pub trade_id: u64,
+    pub price: u64,
+    pub buy_order_id: u64,
+    pub sell_order_id: u64,
+    pub timestamp: u64,
+}
+
+// the password is UTF-8, padded with zeros. The gateway answers with the same frame, without the password
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[repr(C, packed)]
+pub struct LoginFrame {
+    pub header: FrameHeader,
+    pub accepted: u8,
+    pub reserved: [u8; 3],
+    pub participant_id: u32,
+    pub password: [u8; LOGIN_PASSWORD_SIZE],
+}
+
+// prices and PnL are f64 bit patterns
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[repr(C, packed)]
+pub struct PositionFrame {
+    pub header: FrameHeader,
+    pub participant_id: u32,
+    pub symbol_id: u32,
+    pub position: i64,
+    pub open_price: u64,
+    pub realized: u64,
+    pub unrealized: u64,
+    pub mark_price: u64,
+}
+
+// a layout change is a new WIRE_VERSION
+const _: () = assert!(HEADER_SIZE == 4);
+const _: () = assert!(mem::size_of::<RequestFrame>() == 56);
+const _: () = assert!(mem::size_of::<ResponseFrame>() == 64);
+const _: () = assert!(mem::size_of::<MarketUpdateFrame>() == 48);
+const _: () = assert!(mem::size_of::<TradeFrame>() == 64);
+const _: () = assert!(mem::size_of::<LoginFrame>() == 28);
+const _: () = assert!(mem::size_of::<PositionFrame>() == 52);
+const _: () = assert!(mem::size_of::<RequestFrame>() <= MAX_FRAME_SIZE);
+const _: () = assert!(mem::size_of::<PositionFrame>() <= MAX_FRAME_SIZE);
+
+/// # Safety
+/// Implementors are `#[repr(C, packed)]` and only made of integers and byte arrays: they have an alignment of 1,
+/// no padding, and any bytes are a valid value.
+pub unsafe trait WireFrame: Copy {
+    const MESSAGE_TYPE: u8;
+    type Message;
+
+    fn encode(message: &Self::Message) -> Self;
+    fn decode(&self) -> Result<Self::Message, WireError>;
+
+    fn as_bytes(&self) -> &[u8] {
+        unsafe { slice::from_raw_parts(self as *const Self as *const u8, mem::size_of::<Self>()) }
+    }
+
+    // the frame at the start of the buffer, its header checked
+    fn view(buf: &[u8]) -> Result<&Self, WireError> {
+        let size = mem::size_of::<Self>();
+        if buf.len() < size {
+            return Err(WireError::Truncated(size));
+        }
+
+        let frame = unsafe { &*(buf.as_ptr() as *const Self) };
+        let header = unsafe { *(buf.as_ptr() as *const FrameHeader) };
+        check_header(&header, Self::MESSAGE_TYPE, size)?;
+
+        Ok(frame)
+    }
+
+    fn header() -> FrameHeader {
+        FrameHeader {
+            version: WIRE_VERSION,
+            message_type: Self::MESSAGE_TYPE,
+            length: (mem::size_of::<Self>() as u16).to_le(),
+        }
+    }
+}
+
+fn check_header(header: &FrameHeader, message_type: u8, size: usize) -> Result<(), WireError> {
+    if header.version != WIRE_VERSION {
+        return Err(WireError::Version(header.version));
+    }
+
+    if header.message_type != message_type {
+        return Err(WireError::MessageType(header.message_type));
+    }
+
+    let length = u16::from_le(header.length);
+    if length as usize != size {
+        return Err(WireError::Length(length));
+    }
+
+    Ok(())
+}
+
+fn discriminant<T: TryFrom<u8, Error = u8>>(field: &'static str, value: u8) -> Result<T, WireError> {
+    T::try_from(value).map_err(|value| WireError::Discriminant(field, value))
+}
+
+unsafe impl WireFrame for RequestFrame {
+    const MESSAGE_TYPE: u8 = MESSAGE_REQUEST;
+    type Message = ParticipantRequest;
+
+    fn encode(request: &ParticipantRequest) -> Self {
+        Self {
+            header: Self::header(),
+            request_type: request.request_type as u8,
+            order_type: request.order_type as u8,
+            side: request.side as u8,
+            flags: request.flags,
+            participant_id: request.participant_id.to_le(),
+            symbol_id: request.symbol_id.to_le(),
+            qty: request.qty.to_le(),
+            display_qty: request.display_qty.to_le(),
+            order_id: request.order_id.to_le(),
+            price: request.price.to_le(),
+            stop_price: request.stop_price.to_le(),
+            recv_time: request.recv_time.to_le(),
+        }
+    }
+
+    fn decode(&self) -> Result<ParticipantRequest, WireError> {
+        Ok(ParticipantRequest {
+            request_type: discriminant::<ParticipantRequestType>("request type", self.request_type)?,
+            order_type: discriminant::<OrderType>("order type", self.order_type)?,
+            side: discriminant::<Side>("side", self.side)?,
+            flags: self.flags,
+            participant_id: u32::from_le(self.participant_id),
+            symbol_id: u32::from_le(self.symbol_id),
+            qty: u32::from_le(self.qty),
+            display_qty: u32::from_le(self.display_qty),
+            order_id: u64::from_le(self.order_id),
+            price: u64::from_le(self.price),
+            stop_price: u64::from_le(self.stop_price),
+            recv_time: u64::from_le(self.recv_time),
+        })
+    }
+}
+
+unsafe impl WireFrame for ResponseFrame {
+    const MESSAGE_TYPE: u8 = MESSAGE_RESPONSE;
+    type Message = ParticipantResponse;
+
+    fn encode(response: &ParticipantResponse) -> Self {
+        Self {
+            header: Self::header(),
+            response_type: response.response_type as u8,
+            side: response.side as u8,
+            aggressor: response.aggressor as u8,
+            reserved: 0,
+            participant_id: response.participant_id.to_le(),
+            symbol_id: response.symbol_id.to_le(),
+            exec_qty: response.exec_qty.to_le(),
+            leaves_qty: response.leaves_qty.to_le(),
+            participant_order_id: response.participant_order_id.to_le(),
+            internal_order_id: response.internal_order_id.to_le(),
+            price: response.price.to_le(),
+            trade_id: response.trade_id.to_le(),
+            fee: response.fee.to_le(),
+        }
+    }
+
+    fn decode(&self) -> Result<ParticipantResponse, WireError> {
+        Ok(ParticipantResponse {
+            response_type: discriminant::<ParticipantResponseType>("response type", self.response_type)?,
+            side: discriminant::<Side>("side", self.side)?,
+            aggressor: match self.aggressor {
+                0 => false,
+                1 => true,
+                value => return Err(WireError::Discriminant("aggressor", value)),
+            },
+            participant_id: u32::from_le(self.participant_id),
+            symbol_id: u32::from_le(self.symbol_id),
+            exec_qty: u32::from_le(self.exec_qty),
+            leaves_qty: u32::from_le(self.leaves_qty),
+            participant_order_id: u64::from_le(self.participant_order_id),
+            internal_order_id: u64::from_le(self.internal_order_id),
+            price: u64::from_le(self.price),
+            trade_id: u64::from_le(self.trade_id),
+            fee: i64::from_le(self.fee),
+        })
+    }
+}
+
+unsafe impl WireFrame for MarketUpdateFrame {
+    const MESSAGE_TYPE: u8 = MESSAGE_MARKET_UPDATE;
+    type Message = MarketUpdate;
+
+    fn encode(update: &MarketUpdate) -> Self {
+        Self {
+            header: Self::header(),
+            update_type: update.update_type as u8,
+            side: update.side as u8,
+            reserved: [0; 2],
+            symbol_id: update.symbol_id.to_le(),
+            qty: update.qty.to_le(),
+            order_id: update.order_id.to_le(),
+            price: update.price.to_le(),
+            priority: update.priority.to_le(),
+            trade_id: update.trade_id.to_le(),
+        }
+    }
+
+    fn decode(&self) -> Result<MarketUpdate, WireError> {
+        Ok(MarketUpdate {
+            update_type: discriminant::<MarketUpdateType>("update type", self.update_type)?,
+            side: discriminant::<Side>("side", self.side)?,
+            symbol_id: u32::from_le(self.symbol_id),
+            qty: u32::from_le(self.qty),
+            order_id: u64::from_le(self.order_id),
+            price: u64::from_le(self.price),
+            priority: u64::from_le(self.priority),
+            trade_id: u64::from_le(self.trade_id),
+        })
+    }
+}
+
+unsafe impl WireFrame for TradeFrame {
+    const MESSAGE_TYPE: u8 = MESSAGE_TRADE;
+    type Message = Trade;
+
+    fn encode(trade: &Trade) -> Self {
+        Self {
+            header: Self::header(),
+            aggressor_side: trade.aggressor_side as u8,
+            reserved: [0; 3],
+            symbol_id: trade.symbol_id.to_le(),
+            qty: trade.qty.to_le(),
+            buy_participant_id: trade.buy_participant_id.to_le(),
+            sell_participant_id: trade.sell_participant_id.to_le(),
+            trade_id: trade.trade_id.to_le(),
+            price: trade.price.to_le(),
+            buy_order_id: trade.buy_order_id.to_le(),
+            sell_order_id: trade.sell_order_id.to_le(),
+            timestamp: trade.timestamp.to_le(),
+        }
+    }
+
+    fn decode(&self) -> Result<Trade, WireError> {
+        Ok(Trade {
+            trade_id: u64::from_le(self.trade_id),
+            symbol_id: u32::from_le(self.symbol_id),
+            aggressor_side: discriminant::<Side>("side", self.aggressor_side)?,
+            price: u64::from_le(self.price),
+            qty: u32::from_le(self.qty),
+            buy_participant_id: u32::from_le(self.buy_participant_id),
+            buy_order_id: u64::from_le(self.buy_order_id),
+            sell_participant_id: u32::from_le(self.sell_participant_id),
+            sell_order_id: u64::from_le(self.sell_order_id),
+            timestamp: u64::from_le(self.timestamp),
+        })
+    }
+}
+
+unsafe impl WireFrame for LoginFrame {
+    const MESSAGE_TYPE: u8 = MESSAGE_LOGIN;
+    type Message = Login;
+
+    // a longer password is cut short, the configuration keeps them within the field
+    fn encode(login: &Login) -> Self {
+        let mut password = [0; LOGIN_PASSWORD_SIZE];
+        let length = cmp::min(login.password.len(), LOGIN_PASSWORD_SIZE);
+        password[..length].copy_from_slice(&login.password.as_bytes()[..length]);
+
+        Self {
+            header: Self::header(),
+            accepted: login.accepted as u8,
+            reserved: [0; 3],
+            participant_id: login.participant_id.to_le(),
+            password,
+        }
+    }
+
+    // the padding has to be zeros all the way, so that a frame only decodes one way
+    fn decode(&self) -> Result<Login, WireError> {
+        let password = self.password;
+        let length = password.iter().position(|byte| *byte == 0).unwrap_or(LOGIN_PASSWORD_SIZE);
+
+        if password[length..].iter().any(|byte| *byte != 0) {
+            return Err(WireError::Text("password"));
+        }
+
+        Ok(Login {
+            participant_id: u32::from_le(self.participant_id),
+            password: String::from_utf8(password[..length].to_vec()).map_err(|_| WireError::Text("password"))?,
+            accepted: match self.accepted {
+                0 => false,
+                1 => true,
+                value => return Err(WireError::Discriminant("accepted", value)),
+            },
+        })
+    }
+}
+
+unsafe impl WireFrame for PositionFrame {
+    const MESSAGE_TYPE: u8 = MESSAGE_POSITION;
+    type Message = Position;
+
+    fn encode(position: &Position) -> Self {
+        Self {
+            header: Self::header(),
+            participant_id: position.participant_id.to_le(),
+            symbol_id: position.symbol_id.to_le(),
+            position: position.position.to_le(),
+            open_price: position.open_price.to_bits().to_le(),
+            realized: position.realized.to_bits().to_le(),
+            unrealized: position.unrealized.to_bits().to_le(),
+            mark_price: position.mark_price.to_le(),
+        }
+    }
+
+    fn decode(&self) -> Result<Position, WireError> {
+        Ok(Position {
+            participant_id: u32::from_le(self.participant_id),
+            symbol_id: u32::from_le(self.symbol_id),
+            position: i64::from_le(self.position),
+            open_price: f64::from_bits(u64::from_le(self.open_price)),
+            realized: f64::from_bits(u64::from_le(self.realized)),
+            unrealized: f64::from_bits(u64::from_le(self.unrealized)),
+            mark_price: u64::from_le(self.mark_price),
+        })
+    }
+}
+
+pub fn encode<F: WireFrame>(message: &F::Message, out: &mut Vec<u8>) {
+    out.extend_from_slice(F::encode(message).as_bytes());
+}
+
+// the message at the start of the buffer and the size of its frame
+pub fn decode<F: WireFrame>(buf: &[u8]) -> Result<(F::Message, usize), WireError> {
+    Ok((F::view(buf)?.decode()?, mem::size_of::<F>()))
+}
+
+// reads one frame of a stream transport into the start of `buf`, returns its size. A frame whose header is wrong
+// leaves the stream out of step and is an InvalidData error
+pub fn read_frame(input: &mut impl Read, buf: &mut [u8; MAX_FRAME_SIZE]) -> io::Result<usize> {
+    input.read_exact(&mut buf[..HEADER_SIZE])?;
+
+    let header = FrameHeader {
+        version: buf[0],
+        message_type: buf[1],
+        length: u16::from_le_bytes([buf[2], buf[3]]),
+    };
+
+    let length = header.length as usize;
+    if header.version != WIRE_VERSION || !(HEADER_SIZE..=MAX_FRAME_SIZE).contains(&length) {
+        return Err(io::Error::new(ErrorKind::InvalidData, "bad frame header"));
+    }
+
+    input.read_exact(&mut buf[HEADER_SIZE..length])?;
+    Ok(length)
+}
@@ -0,0 +1,248 @@
+mod common;
+
+use std::{env, fs, io::{BufRead, BufReader, Write}, net::TcpStream, path::PathBuf, process, sync::{mpsc::{self, Receiver, Sender}, Arc, Mutex}};
+
+use rexchange::{admin::{admin::{Admin, AdminCommand, AdminError, AdminReply}, admin_server::{self, AdminConfig, AdminUser}, audit::AuditLog}, client::order_manager::OrderManager, common::Side, fees::billing::Billing, positions::{PositionKeeper, PositionsConfig}, logging::logger::Logger, market_data::{itch::{self, Message}, itch_encoder::ItchEncoder, market_update::{MarketUpdate, MarketUpdateType}}, order_server::{participants_request::ParticipantRequest, participants_response::{ParticipantResponse, ParticipantResponseType}}, trades::{trade::{Trade, TradeAmendment}, trade_store::{TradeQuery, TradeStore}}};
+
+type Client = OrderManager<Sender<ParticipantRequest>>;
+
+fn temp_dir(name: &str) -> PathBuf {
+    let directory = env::temp_dir().join(format!("rexchange-admin-{}-{}", name, process::id()));
+    let _ = fs::remove_dir_all(&directory);
+    directory
+}
+
+// a single symbol engine driven on the test thread, with a buyer and a seller, its trades stored, its fills kept by the
+// position keeper and its amendments made by an admin
+struct Venue {
+    venue: common::Venue,
+    requests: Receiver<ParticipantRequest>,
+    trades: Receiver<Trade>,
+    amendments: Receiver<TradeAmendment>,
+    buyer: Client,
+    seller: Client,
+    trade_store: Arc<Mutex<TradeStore>>,
+    keeper: Arc<Mutex<PositionKeeper>>,
+    admin: Admin,
+    audit_path: PathBuf,
+}
+
+impl Venue {
+    fn new(name: &str) -> Self {
+        let directory = temp_dir(name);
+        let (requests_tx, requests_rx) = mpsc::channel();
+        let (amendments_tx, amendments_rx) = mpsc::sync_channel(16);
+        let mut venue = common::Venue::new();
+        let trades = venue.trade_sink();
+
+        let trade_store = Arc::new(Mutex::new(TradeStore::open(&directory.join("trades.dat")).unwrap()));
+        let audit_path = directory.join("audit.csv");
+        let keeper = Arc::new(Mutex::new(PositionKeeper::new(&PositionsConfig::default())));
+        let admin = Admin::new(trade_store.clone(), Arc::new(Mutex::new(Billing::new())), keeper.clone(), vec![0], vec![amendments_tx], AuditLog::open(&audit_path).unwrap());
+
+        Self {
+            venue,
+            requests: requests_rx,
+            trades,
+            amendments: amendments_rx,
+            buyer: OrderManager::new(requests_tx.clone(), 0, 64),
+            seller: OrderManager::new(requests_tx, 1, 64),
+            trade_store,
+            keeper,
+            admin,
+            audit_path,
+        }
+    }
+
+    // runs what was sent and amended, applies the responses to both clients and returns those of busts and corrections
+    fn pump(&mut self) -> Vec<ParticipantResponse> {
+        for request in self.requests.try_iter() {
+            self.venue.request(&request);
+        }
+
+        for amendment in self.amendments.try_iter() {
+            self.venue.engine.amend_trade(&amendment);
+        }
+
+        let mut trade_store = self.trade_store.lock().unwrap();
+        for trade in self.trades.try_iter() {
+            trade_store.append(trade).unwrap();
+        }
+
+        let mut keeper = self.keeper.lock().unwrap();
+        self.venue.responses().into_iter().filter(|response| {
+            keeper.on_response(response);
+            self.buyer.on_response(response).unwrap();
+            self.seller.on_response(response).unwrap();
+            matches!(response.response_type, ParticipantResponseType::TradeBusted | ParticipantResponseType::TradeCorrected)
+        }).collect()
+    }
+
+    fn trade(&mut self, qty: u32) {
+        self.buyer.send_limit(0, Side::Buy, 100, qty).unwrap();
+        self.seller.send_limit(0, Side::Sell, 100, qty).unwrap();
+        self.pump();
+    }
+
+    fn execute(&mut self, line: &str) -> Result<Trade, AdminError> {
+        match AdminCommand::parse(line).and_then(|command| self.admin.execute("ops", &command, 99))? {
+            AdminReply::Trade(trade) => Ok(trade),
+            reply => panic!("not a trade: {}", reply),
+        }
+    }
+
+    fn positions(&self) -> (i64, i64) {
+        (self.buyer.position(0), self.seller.position(0))
+    }
+
+    // (position, open price) of the buyer then the seller, as the exchange keeps them
+    fn kept_positions(&self) -> [(i64, f64); 2] {
+        let keeper = self.keeper.lock().unwrap();
+        [0, 1].map(|participant_id| keeper.position(participant_id, 0).map_or((0, 0.0), |position| (position.position, position.open_price)))
+    }
+
+    fn trade_updates(&self) -> Vec<(MarketUpdateType, u64, u32)> {
+        self.venue.market_updates().into_iter().filter(|update| update.order_id == u64::MAX).map(|update| (update.update_type, update.price, update.qty)).collect()
+    }
+}
+
+// (participant, side, exec_qty, leaves_qty)
+fn quantities(responses: &[ParticipantResponse]) -> Vec<(u32, Side, u32, u32)> {
+    responses.iter().map(|response| (response.participant_id, response.side, response.exec_qty, response.leaves_qty)).collect()
+}
+
+#[test]
+fn a_bust_is_stored_audited_and_unwinds_both_positions() {
+    let mut venue = Venue::new("bust");
+    venue.trade(4);
+    venue.trade(2);
+    venue.trade_updates();
+    assert_eq!(venue.positions(), (6, -6));
+    assert_eq!(venue.kept_positions(), [(6, 100.0), (-6, 100.0)]);
+
+    let busted = venue.execute("bust 1 fat finger, \"ACME\"").unwrap();
+    assert_eq!((busted.trade_id, busted.price, busted.qty), (1, 100, 0));
+
+    let responses = venue.pump();
+    assert!(responses.iter().all(|response| response.response_type == ParticipantResponseType::TradeBusted && response.trade_id == 1));
+    assert_eq!(quantities(&responses), [(0, Side::Buy, 0, 4), (1, Side::Sell, 0, 4)]);
+    assert_eq!(venue.positions(), (2, -2));
+    assert_eq!(venue.kept_positions(), [(2, 100.0), (-2, 100.0)]);
+    assert_eq!(venue.trade_updates(), [(MarketUpdateType::TradeCancel, 100, 0)]);
+
+    // the busted trade stays, with no quantity, but no longer counts
+    let trade_store = venue.trade_store.lock().unwrap();
+    assert!(trade_store.trade(1).unwrap().is_busted());
+    assert_eq!(trade_store.query(&TradeQuery::default()).iter().map(|trade| trade.trade_id).collect::<Vec<_>>(), [2]);
+    drop(trade_store);
+
+    let audit = fs::read_to_string(&venue.audit_path).unwrap();
+    assert_eq!(audit.lines().collect::<Vec<_>>(), [
+        "timestamp,user,action,trade_id,symbol_id,price,qty,new_price,new_qty,reason",
+        "99,\"ops\",bust,1,0,100,4,100,0,\"fat finger, \"\"ACME\"\"\"",
+    ]);
+
+    assert!(matches!(venue.execute("bust 1 again"), Err(AdminError::AlreadyBusted(1))));
+    assert!(matches!(venue.execute("correct 1 100 4 undo"), Err(AdminError::AlreadyBusted(1))));
+    assert!(venue.pump().is_empty());
+    assert_eq!(venue.kept_positions(), [(2, 100.0), (-2, 100.0)]);
+}
+
+#[test]
+fn a_correction_moves_positions_by_the_difference() {
+    let mut venue = Venue::new("correct");
+    venue.trade(4);
+    venue.trade_updates();
+
+    let corrected = venue.execute("correct 1 101 3 wrong size").unwrap();
+    assert_eq!((corrected.price, corrected.qty), (101, 3));
+
+    let responses = venue.pump();
+    assert!(responses.iter().all(|response| response.response_type == ParticipantResponseType::TradeCorrected && response.price == 101));
+    assert_eq!(quantities(&responses), [(0, Side::Buy, 3, 4), (1, Side::Sell, 3, 4)]);
+    assert_eq!(venue.positions(), (3, -3));
+    assert_eq!(venue.kept_positions(), [(3, 101.0), (-3, 101.0)]);
+    assert_eq!(venue.trade_updates(), [(MarketUpdateType::TradeCorrect, 101, 3)]);
+
+    // corrected again, from what it is now
+    venue.execute("correct 1 101 5 wrong size again").unwrap();
+    venue.pump();
+    assert_eq!(venue.positions(), (5, -5));
+    assert_eq!(venue.kept_positions(), [(5, 101.0), (-5, 101.0)]);
+    assert_eq!(fs::read_to_string(&venue.audit_path).unwrap().lines().last(), Some("99,\"ops\",correct,1,0,101,3,101,5,\"wrong size again\""));
+
+    assert!(matches!(venue.execute("correct 1 101 5 same"), Err(AdminError::Unchanged(1))));
+    assert!(matches!(venue.execute("correct 1 101 0 none"), Err(AdminError::BadArgument(_))));
+    assert!(matches!(venue.execute("correct 7 101 1 unknown"), Err(AdminError::UnknownTrade(7))));
+    assert!(matches!(venue.execute("correct 1 101 1"), Err(AdminError::MissingReason)));
+    assert!(matches!(venue.execute("correct 1 x 1 price"), Err(AdminError::BadArgument("price"))));
+    assert!(matches!(venue.execute("cancel 1"), Err(AdminError::UnknownCommand(_))));
+    assert!(venue.pump().is_empty());
+    assert_eq!(venue.kept_positions(), [(5, 101.0), (-5, 101.0)]);
+}
+
+#[test]
+fn the_console_needs_a_login() {
+    let directory = temp_dir("console");
+    let trade_store = Arc::new(Mutex::new(TradeStore::open(&directory.join("trades.dat")).unwrap()));
+    trade_store.lock().unwrap().append(Trade { trade_id: 5, symbol_id: 0, price: 100, qty: 2, buy_participant_id: 0, sell_participant_id: 1, ..Trade::default() }).unwrap();
+
+    let (amendments_tx, amendments_rx) = mpsc::sync_channel(16);
+    let admin = Admin::new(trade_store, Arc::new(Mutex::new(Billing::new())), Arc::new(Mutex::new(PositionKeeper::new(&PositionsConfig::default()))), vec![0], vec![amendments_tx], AuditLog::open(&directory.join("audit.csv")).unwrap());
+    let config = AdminConfig {
+        address: "127.0.0.1:0".to_string(),
+        users: vec![AdminUser { username: "ops".to_string(), password: "secret".to_string() }],
+        ..AdminConfig::default()
+    };
+    let address = admin_server::spawn(&config, admin, Logger::disabled()).unwrap();
+
+    let session = |lines: &[&str]| -> Vec<String> {
+        let mut stream = TcpStream::connect(address).unwrap();
+        for line in lines {
+            writeln!(stream, "{}", line).unwrap();
+        }
+        stream.shutdown(std::net::Shutdown::Write).unwrap();
+        BufReader::new(stream).lines().map(Result::unwrap).collect()
+    };
+
+    assert_eq!(session(&["login ops wrong", "bust 5 nope"]), ["ERROR not authorized"]);
+    assert!(amendments_rx.try_recv().is_err());
+
+    let replies = session(&["login ops secret", "trade 5", "", "bust 5", "bust 5 test"]);
+    assert_eq!(replies.len(), 4);
+    assert_eq!(replies[0], "OK");
+    assert!(replies[1].starts_with("OK Trade [id: 5,") && replies[1].contains("qty: 2"));
+    assert_eq!(replies[2], "ERROR a reason is required");
+    assert!(replies[3].starts_with("OK Trade [id: 5,") && replies[3].contains("qty: 0"));
+
+    let amendment = amendments_rx.try_recv().unwrap();
+    assert_eq!((amendment.original.qty, amendment.amended.qty), (2, 0));
+}
+
+#[test]
+fn busts_and_corrections_are_broken_trades_on_the_itch_feed() {
+    let mut encoder = ItchEncoder::new(&["ACME"], 0);
+    let mut buf = Vec::new();
+
+    for update_type in [MarketUpdateType::TradeCancel, MarketUpdateType::TradeCorrect] {
+        encoder.encode(&MarketUpdate { update_type, symbol_id: 0, side: Side::Buy, price: 101, qty: 3, trade_id: 9, ..MarketUpdate::default() }, 0, &mut buf);
+    }
+
+    let mut messages = Vec::new();
+    let mut offset = 0;
+    while let Some((message, len)) = itch::decode(&buf[offset..]) {
+        messages.push(message);
+        offset += len;
+    }
+
+    assert_eq!(offset, buf.len());
+    assert!(matches!(messages[..], [Message::BrokenTrade(_), Message::BrokenTrade(_), Message::Trade(_)]));
+
+    match messages[..] {
+        [Message::BrokenTrade(bust), Message::BrokenTrade(_), Message::Trade(trade)] => {
+            assert_eq!(bust.match_number.get(), 9);
+            assert_eq!((trade.match_number.get(), trade.price.get(), trade.shares.get(), trade.side), (9, 101, 3, itch::SIDE_SELL));
+        },
+        _ => unreachable!(),
+    }
+}
@@ -0,0 +1,60 @@
+use rexchange::{common::Quantity, matching_engine::allocation::AllocationPolicy};
+
+// the buffer is reused by the engine, it starts dirty
+fn allocate(policy: AllocationPolicy, qty: Quantity, resting_qtys: &[Quantity]) -> Vec<Quantity> {
+    let mut allocations = vec![7; 9];
+    policy.allocate(qty, resting_qtys, &mut allocations);
+    allocations
+}
+
+#[test]
+fn fifo_fills_in_time_priority() {
+    assert_eq!(allocate(AllocationPolicy::Fifo, 25, &[10, 20, 30]), [10, 15, 0]);
+    assert_eq!(allocate(AllocationPolicy::Fifo, 100, &[10, 20, 30]), [10, 20, 30]);
+    assert!(allocate(AllocationPolicy::Fifo, 10, &[]).is_empty());
+}
+
+#[test]
+fn pro_rata_shares_by_size_and_gives_the_rounding_remainder_fifo() {
+    let pro_rata = AllocationPolicy::ProRata { min_allocation: 0 };
+
+    assert_eq!(allocate(pro_rata, 30, &[10, 20, 30]), [5, 10, 15]);
+
+    // 1, 3 and 5 rounded down, the share left over goes to the first order
+    assert_eq!(allocate(pro_rata, 10, &[10, 20, 30]), [2, 3, 5]);
+
+    // an order already given all it has passes the remainder on
+    assert_eq!(allocate(pro_rata, 5, &[1, 3, 4]), [1, 2, 2]);
+
+    // never more than the level
+    assert_eq!(allocate(pro_rata, 100, &[10, 20, 30]), [10, 20, 30]);
+}
+
+#[test]
+fn shares_below_the_minimum_allocation_go_fifo() {
+    // 0, 4 and 5 shares: with no minimum only rounding is left over, with 5 the 4 shares are dropped too
+    assert_eq!(allocate(AllocationPolicy::ProRata { min_allocation: 0 }, 10, &[5, 25, 30]), [1, 4, 5]);
+    assert_eq!(allocate(AllocationPolicy::ProRata { min_allocation: 5 }, 10, &[5, 25, 30]), [5, 0, 5]);
+
+    // nobody reaches it, everything goes FIFO
+    assert_eq!(allocate(AllocationPolicy::ProRata { min_allocation: 20 }, 10, &[5, 25, 30]), [5, 5, 0]);
+}
+
+#[test]
+fn the_top_order_is_filled_before_the_rest_is_shared() {
+    assert_eq!(allocate(AllocationPolicy::TopOrderProRata { min_allocation: 0 }, 20, &[10, 20, 30]), [10, 4, 6]);
+    assert_eq!(allocate(AllocationPolicy::TopOrderProRata { min_allocation: 0 }, 6, &[10, 20, 30]), [6, 0, 0]);
+}
+
+#[test]
+fn split_gives_fifo_pct_in_time_priority_and_shares_the_rest() {
+    // 4 FIFO, then 6 over the 6, 20 and 30 left open: 0, 2 and 3 rounded down, the remainder to the first order
+    assert_eq!(allocate(AllocationPolicy::SplitFifoProRata { fifo_pct: 40, min_allocation: 0 }, 10, &[10, 20, 30]), [5, 2, 3]);
+
+    // the FIFO part rounds down, 2 of 5, then 1 each and the remainder to the first order
+    assert_eq!(allocate(AllocationPolicy::SplitFifoProRata { fifo_pct: 50, min_allocation: 0 }, 5, &[10, 10]), [4, 1]);
+
+    assert_eq!(allocate(AllocationPolicy::SplitFifoProRata { fifo_pct: 0, min_allocation: 0 }, 30, &[10, 20, 30]), [5, 10, 15]);
+    assert_eq!(allocate(AllocationPolicy::SplitFifoProRata { fifo_pct: 100, min_allocation: 0 }, 25, &[10, 20, 30]), [10, 15, 0]);
+    assert_eq!(allocate(AllocationPolicy::SplitFifoProRata { fifo_pct: 150, min_allocation: 0 }, 25, &[10, 20, 30]), [10, 15, 0]);
+}
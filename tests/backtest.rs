@@ -0,0 +1,102 @@
+use std::sync::mpsc::Sender;
+
+use rexchange::{backtest::{backtester::{BacktestReport, Backtester, LatencyModel}, historical_data::{DataError, HistoricalEvent, HistoricalEventType, HistoricalReader}}, client::{order_manager::{ClientError, OrderManager}, strategy::Strategy}, common::{Nanos, Price, Quantity, Side, SymbolId}, market_data::market_update::MarketUpdate, matching_engine::orderbook::OrderBookConfig, order_server::{participants_request::ParticipantRequest, participants_response::ParticipantResponse}};
+
+const PRICE: Price = 9_999;
+
+// sends one limit order as soon as the replay starts
+struct OneShot {
+    orders: OrderManager<Sender<ParticipantRequest>>,
+    order: Option<(SymbolId, Side, Price, Quantity)>,
+}
+
+impl Strategy for OneShot {
+    fn on_response(&mut self, response: &ParticipantResponse) {
+        self.orders.on_response(response).unwrap();
+    }
+
+    fn on_market_update(&mut self, _update: &MarketUpdate) {}
+
+    fn act(&mut self) -> Result<(), ClientError> {
+        if let Some((symbol_id, side, price, qty)) = self.order.take() {
+            self.orders.send_limit(symbol_id, side, price, qty)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn backtest(data: &str, order_entry_ns: Nanos, order: (SymbolId, Side, Price, Quantity)) -> BacktestReport {
+    let order_book_config = OrderBookConfig { max_order_ids: 64, max_participants: 8, ..OrderBookConfig::default() };
+    let latency = LatencyModel { order_entry_ns, ..LatencyModel::default() };
+    let backtester = Backtester::new(&[order_book_config.clone(), order_book_config], 0, latency);
+
+    let mut strategy = OneShot { orders: OrderManager::new(backtester.gateway(), 0, 64), order: Some(order) };
+    backtester.run(&mut strategy, HistoricalReader::new(data.as_bytes())).unwrap()
+}
+
+#[test]
+fn reads_l3_and_l2_events() {
+    let data = "# comment\n\n100,0,ADD,7,B,9999,50\n100,0,EXECUTE,7,20\n200,0,DELETE,7\n300,1,LEVEL,S,10001,80\n400,1,TRADE,B,10001,30\n";
+    let events: Vec<HistoricalEvent> = HistoricalReader::new(data.as_bytes()).map(Result::unwrap).collect();
+
+    let event_types: Vec<HistoricalEventType> = events.iter().map(|event| event.event_type).collect();
+    assert_eq!(event_types, vec![
+        HistoricalEventType::Add { order_id: 7, side: Side::Buy, price: 9_999, qty: 50 },
+        HistoricalEventType::Execute { order_id: 7, qty: 20 },
+        HistoricalEventType::Delete { order_id: 7 },
+        HistoricalEventType::Level { side: Side::Sell, price: 10_001, qty: 80 },
+        HistoricalEventType::Trade { aggressor_side: Side::Buy, price: 10_001, qty: 30 },
+    ]);
+    assert_eq!((events[3].timestamp, events[3].symbol_id), (300, 1));
+
+    for (data, line) in [("100,0,ADD,7,X,9999,50\n", 1), ("# header\n100,0,DELETE\n", 2), ("200,0,DELETE,1\n100,0,DELETE,2\n", 2), ("100,0,CROSS,1\n", 1)] {
+        match HistoricalReader::new(data.as_bytes()).find_map(Result::err) {
+            Some(DataError::Parse(error_line, _)) => assert_eq!(error_line, line, "{}", data),
+            _ => panic!("{} should not parse", data),
+        }
+    }
+}
+
+#[test]
+fn historical_trades_take_the_queue_as_it_is() {
+    // order 2 joins the bid behind the strategy if its order gets there within 1000ns
+    let data = "1000,0,ADD,1,B,9999,100\n2000,0,ADD,2,B,9999,20\n3000,0,EXECUTE,1,100\n4000,0,EXECUTE,2,15\n";
+
+    let report = backtest(data, 500, (0, Side::Buy, PRICE, 10));
+    assert_eq!(report.historical_events, 4);
+    assert_eq!(report.symbols.len(), 1);
+    assert_eq!((report.symbols[0].pnl.position, report.symbols[0].pnl.open_price()), (10, PRICE as f64));
+    assert_eq!((report.queue.rested_orders, report.queue.queue_ahead_qty), (1, 100));
+    assert_eq!((report.queue.filled_orders, report.queue.passive_fill_qty, report.queue.time_to_first_fill), (1, 10, 2_500));
+
+    // too slow, it queues behind order 2 and the trade stops short of it
+    let report = backtest(data, 2_500, (0, Side::Buy, PRICE, 10));
+    assert!(report.symbols.is_empty());
+    assert_eq!((report.queue.rested_orders, report.queue.queue_ahead_qty, report.queue.filled_orders), (1, 20, 0));
+}
+
+#[test]
+fn level_changes_join_the_back_of_the_queue() {
+    let data = "1000,1,LEVEL,B,9999,50\n2000,1,LEVEL,B,9999,80\n3000,1,TRADE,S,9999,60\n4000,1,LEVEL,B,9999,20\n";
+
+    let report = backtest(data, 500, (1, Side::Buy, PRICE, 10));
+    assert_eq!(report.symbols[0].pnl.position, 10);
+    assert_eq!(report.symbols[0].mark_price, Some(PRICE));
+    assert_eq!((report.queue.queue_ahead_qty, report.queue.passive_fill_qty), (50, 10));
+    assert_eq!(report.dropped_events, 0);
+}
+
+#[test]
+fn strategy_trades_after_its_latency() {
+    let data = "1000,0,ADD,1,S,10001,50\n1300,0,DELETE,1\n";
+
+    // a marketable buy gets there before the offer is pulled
+    let report = backtest(data, 200, (0, Side::Buy, 10_001, 10));
+    assert_eq!((report.symbols[0].pnl.position, report.symbols[0].pnl.open_price()), (10, 10_001.0));
+    assert_eq!(report.queue.rested_orders, 0);
+
+    let report = backtest(data, 400, (0, Side::Buy, 10_001, 10));
+    assert!(report.symbols.is_empty());
+    assert_eq!(report.queue.rested_orders, 1);
+}
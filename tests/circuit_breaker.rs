@@ -0,0 +1,166 @@
+mod common;
+
+use rexchange::{common::{OrderId, Price, Quantity, Side, INVALID_PRICE}, market_data::market_update::MarketUpdateType, matching_engine::{circuit_breaker::{BreachAction, CircuitBreaker, CircuitBreakerConfig, HaltOrderPolicy, TradingState}, orderbook::OrderBookConfig}, order_server::participants_response::{ParticipantResponse, ParticipantResponseType}};
+
+use common::Venue;
+
+// symbol 0 with the breaker, the clock starts at 0
+fn venue(circuit_breaker: CircuitBreakerConfig) -> Venue {
+    let mut venue = Venue::with_config(&common::engine_config(&[OrderBookConfig { circuit_breaker, ..OrderBookConfig::default() }], &[0, 1]));
+    venue.engine.set_simulated_time(0);
+    venue
+}
+
+fn trading_state(venue: &Venue) -> TradingState {
+    venue.engine.order_book(0).unwrap().trading_state()
+}
+
+// (participant, order id, price, qty, aggressor) of every fill
+fn fills(responses: &[ParticipantResponse]) -> Vec<(u32, OrderId, Price, Quantity, bool)> {
+    responses.iter().filter(|response| response.response_type == ParticipantResponseType::Filled)
+        .map(|response| (response.participant_id, response.participant_order_id, response.price, response.exec_qty, response.aggressor)).collect()
+}
+
+fn response_types(responses: &[ParticipantResponse]) -> Vec<ParticipantResponseType> {
+    responses.iter().map(|response| response.response_type).collect()
+}
+
+// (price, quantities in queue order) of each level, best first
+fn levels(venue: &Venue, side: Side) -> Vec<(Price, Vec<Quantity>)> {
+    venue.engine.order_book(0).unwrap().resting_orders(side).into_iter().map(|(price, orders)| (price, orders.into_iter().map(|(_, qty)| qty).collect())).collect()
+}
+
+// (update type, price) of the trades and trading status changes
+fn trades_and_status(venue: &Venue) -> Vec<(MarketUpdateType, Price)> {
+    venue.market_updates().iter()
+        .filter(|update| matches!(update.update_type, MarketUpdateType::Trade | MarketUpdateType::Halt | MarketUpdateType::VolatilityAuction | MarketUpdateType::Resume))
+        .map(|update| (update.update_type, update.price)).collect()
+}
+
+#[test]
+fn the_bands_are_basis_points_of_the_previous_close_and_of_the_last_trade() {
+    let breaker = CircuitBreaker::new(CircuitBreakerConfig { previous_close: 200, static_band_bps: 1_000, dynamic_band_bps: 250, ..CircuitBreakerConfig::default() });
+    assert!(breaker.is_within_bands(195) && breaker.is_within_bands(205));
+    assert!(!breaker.is_within_bands(194) && !breaker.is_within_bands(206));
+
+    // the dynamic band moves with the trades, inside the static one
+    let mut breaker = breaker;
+    breaker.on_trade(215);
+    assert!(breaker.is_within_bands(220) && !breaker.is_within_bands(221));
+    assert!(breaker.is_within_bands(210) && !breaker.is_within_bands(209));
+
+    // no reference price or a band of 0 doesn't limit anything
+    let breaker = CircuitBreaker::new(CircuitBreakerConfig { previous_close: INVALID_PRICE, static_band_bps: 100, ..CircuitBreakerConfig::default() });
+    assert!(breaker.is_within_bands(1) && breaker.is_within_bands(1_000_000));
+    assert!(CircuitBreaker::new(CircuitBreakerConfig { previous_close: 100, ..CircuitBreakerConfig::default() }).is_within_bands(1_000));
+}
+
+#[test]
+fn a_trade_outside_the_static_band_halts_the_symbol() {
+    let mut venue = venue(CircuitBreakerConfig { previous_close: 100, static_band_bps: 500, ..CircuitBreakerConfig::default() });
+
+    venue.limit(1, 1, Side::Sell, 105, 1);
+    venue.limit(1, 2, Side::Sell, 106, 5);
+    venue.limit(0, 1, Side::Buy, 106, 3);
+
+    // the fill at 105 is at the edge of the band, the one at 106 isn't made and the rest of the order rests crossed
+    assert_eq!(fills(&venue.responses()), [(0, 1, 105, 1, true), (1, 1, 105, 1, false)]);
+    assert_eq!(trades_and_status(&venue), [(MarketUpdateType::Trade, 105), (MarketUpdateType::Halt, 106)]);
+    assert_eq!(trading_state(&venue), TradingState::Halted);
+    assert_eq!(levels(&venue, Side::Buy), [(106, vec![2])]);
+}
+
+#[test]
+fn a_trade_outside_the_dynamic_band_halts_the_symbol() {
+    let mut venue = venue(CircuitBreakerConfig { dynamic_band_bps: 100, ..CircuitBreakerConfig::default() });
+
+    // without a previous close the first trade sets the reference, 1% of 100 then of 101 is a tick
+    venue.limit(1, 1, Side::Sell, 100, 1);
+    venue.limit(0, 1, Side::Buy, 100, 1);
+    venue.limit(1, 2, Side::Sell, 101, 1);
+    venue.limit(0, 2, Side::Buy, 101, 1);
+    venue.limit(1, 3, Side::Sell, 103, 1);
+    venue.limit(0, 3, Side::Buy, 103, 1);
+
+    assert_eq!(trades_and_status(&venue), [(MarketUpdateType::Trade, 100), (MarketUpdateType::Trade, 101), (MarketUpdateType::Halt, 103)]);
+    assert_eq!(trading_state(&venue), TradingState::Halted);
+}
+
+#[test]
+fn a_halt_rejects_new_orders_and_resumes_after_its_duration() {
+    let mut venue = venue(CircuitBreakerConfig { previous_close: 100, static_band_bps: 500, halt_order_policy: HaltOrderPolicy::Reject, halt_duration: 1_000, ..CircuitBreakerConfig::default() });
+
+    venue.limit(1, 1, Side::Sell, 110, 1);
+    venue.limit(0, 1, Side::Buy, 110, 1);
+    venue.responses();
+    trades_and_status(&venue);
+
+    venue.limit(0, 2, Side::Buy, 100, 1);
+    venue.limit(1, 2, Side::Sell, 101, 1);
+    assert_eq!(response_types(&venue.responses()), [ParticipantResponseType::Rejected, ParticipantResponseType::Rejected]);
+
+    // still halted a tick before the end
+    venue.engine.check_timers(999);
+    assert_eq!(trading_state(&venue), TradingState::Halted);
+    assert!(venue.responses().is_empty());
+
+    // the crossed orders left by the breach are uncrossed before trading goes on
+    venue.engine.check_timers(1_000);
+    assert_eq!(trading_state(&venue), TradingState::Continuous);
+    assert_eq!(fills(&venue.responses()), [(0, 1, 110, 1, false), (1, 1, 110, 1, false)]);
+    assert_eq!(trades_and_status(&venue), [(MarketUpdateType::Trade, 110), (MarketUpdateType::Resume, 110)]);
+
+    venue.limit(0, 2, Side::Buy, 100, 1);
+    assert_eq!(response_types(&venue.responses()), [ParticipantResponseType::Accepted]);
+}
+
+#[test]
+fn a_halt_queues_new_orders_and_releases_them_in_arrival_order() {
+    let mut venue = venue(CircuitBreakerConfig { previous_close: 100, static_band_bps: 500, halt_order_policy: HaltOrderPolicy::Queue, halt_duration: 1_000, ..CircuitBreakerConfig::default() });
+
+    venue.limit(1, 1, Side::Sell, 110, 1);
+    venue.limit(0, 1, Side::Buy, 110, 1);
+    venue.responses();
+
+    venue.limit(1, 2, Side::Sell, 102, 2);
+    venue.limit(0, 2, Side::Buy, 102, 1);
+    venue.limit(0, 3, Side::Buy, 102, 1);
+    assert!(venue.responses().is_empty());
+
+    // a queued order can be cancelled before it is released
+    venue.cancel(0, 3);
+    assert_eq!(response_types(&venue.responses()), [ParticipantResponseType::Cancelled]);
+
+    venue.engine.check_timers(1_000);
+    let responses = venue.responses();
+    assert_eq!(response_types(&responses[2..]), [ParticipantResponseType::Accepted, ParticipantResponseType::Accepted, ParticipantResponseType::Filled, ParticipantResponseType::Filled]);
+    assert_eq!(fills(&responses[2..]), [(0, 2, 102, 1, true), (1, 2, 102, 1, false)]);
+}
+
+#[test]
+fn a_volatility_auction_collects_orders_and_uncrosses_at_the_price_trading_the_most() {
+    let mut venue = venue(CircuitBreakerConfig { previous_close: 100, static_band_bps: 500, breach_action: BreachAction::VolatilityAuction, halt_duration: 1_000, ..CircuitBreakerConfig::default() });
+
+    venue.limit(1, 1, Side::Sell, 110, 1);
+    venue.limit(0, 1, Side::Buy, 110, 1);
+    assert_eq!(trades_and_status(&venue), [(MarketUpdateType::VolatilityAuction, 110)]);
+    assert_eq!(trading_state(&venue), TradingState::VolatilityAuction);
+
+    // orders are accepted but don't match, however crossed
+    venue.limit(0, 2, Side::Buy, 104, 5);
+    venue.limit(1, 2, Side::Sell, 102, 3);
+    venue.limit(1, 3, Side::Sell, 105, 4);
+    let responses = venue.responses();
+    assert!(fills(&responses).is_empty());
+    assert!(responses.iter().all(|response| response.response_type == ParticipantResponseType::Accepted));
+
+    // 3 shares trade at 102 or 104, 102 is closer to the last price. Every fill of an auction makes liquidity
+    venue.engine.check_timers(1_000);
+    assert_eq!(fills(&venue.responses()), [(0, 1, 102, 1, false), (1, 2, 102, 1, false), (0, 2, 102, 2, false), (1, 2, 102, 2, false)]);
+    assert_eq!(trades_and_status(&venue), [(MarketUpdateType::Trade, 102), (MarketUpdateType::Trade, 102), (MarketUpdateType::Resume, 102)]);
+    assert_eq!(trading_state(&venue), TradingState::Continuous);
+
+    venue.engine.order_book(0).unwrap().check_invariants().unwrap();
+    assert_eq!(levels(&venue, Side::Buy), [(104, vec![3])]);
+    assert_eq!(levels(&venue, Side::Sell), [(105, vec![4]), (110, vec![1])]);
+}
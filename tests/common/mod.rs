@@ -0,0 +1,111 @@
+// the engine harnesses of the integration tests, each test crate only uses some of it
+#![allow(dead_code)]
+
+use std::{sync::mpsc::{self, Receiver, SyncSender}, thread::{self, JoinHandle}};
+
+use rexchange::{common::{OrderId, OrderType, ParticipantId, Price, Quantity, Sequenced, Side, INVALID_QUANTITY, NO_ORDER_FLAGS}, latency::recorder::LatencyConfig, logging::logger::Logger, market_data::market_update::MarketUpdate, matching_engine::{matching_engine::{MatchingEngine, MatchingEngineConfig}, orderbook::OrderBookConfig}, order_server::{participants_request::{ParticipantRequest, ParticipantRequestType}, participants_response::ParticipantResponse}, trades::trade::Trade};
+
+// a single shard owning the books, symbol ids in order, without latency recording
+pub fn engine_config(order_books: &[OrderBookConfig], participant_ids: &[ParticipantId]) -> MatchingEngineConfig {
+    MatchingEngineConfig::single_shard(order_books, participant_ids, LatencyConfig { enabled: false, ..LatencyConfig::default() })
+}
+
+// a new limit order on symbol 0, fully displayed
+pub fn limit(participant_id: ParticipantId, order_id: OrderId, side: Side, price: Price, qty: Quantity) -> ParticipantRequest {
+    ParticipantRequest {
+        request_type: ParticipantRequestType::New,
+        participant_id,
+        symbol_id: 0,
+        order_id,
+        order_type: OrderType::Limit,
+        side,
+        price,
+        qty,
+        display_qty: INVALID_QUANTITY,
+        flags: NO_ORDER_FLAGS,
+        ..ParticipantRequest::default()
+    }
+}
+
+pub fn cancel(participant_id: ParticipantId, order_id: OrderId) -> ParticipantRequest {
+    ParticipantRequest { request_type: ParticipantRequestType::Cancel, participant_id, symbol_id: 0, order_id, ..ParticipantRequest::default() }
+}
+
+// an engine driven on the test thread, what it publishes waits in the channels until read
+pub struct Venue {
+    pub engine: MatchingEngine,
+    pub responses: Receiver<Sequenced<ParticipantResponse>>,
+    pub market_updates: Receiver<Sequenced<MarketUpdate>>,
+}
+
+impl Default for Venue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Venue {
+    // one symbol with the default book, participants 0 and 1
+    pub fn new() -> Self {
+        Self::with_config(&engine_config(&[OrderBookConfig::default()], &[0, 1]))
+    }
+
+    pub fn with_config(config: &MatchingEngineConfig) -> Self {
+        let (_, requests_rx) = mpsc::sync_channel(1);
+        let (responses_tx, responses_rx) = mpsc::sync_channel(1 << 16);
+        let (market_updates_tx, market_updates_rx) = mpsc::sync_channel(1 << 16);
+
+        Self {
+            engine: MatchingEngine::new(requests_rx, responses_tx, market_updates_tx, config, Logger::disabled()),
+            responses: responses_rx,
+            market_updates: market_updates_rx,
+        }
+    }
+
+    pub fn trade_sink(&mut self) -> Receiver<Trade> {
+        let (trades_tx, trades_rx) = mpsc::sync_channel(1 << 16);
+        self.engine.set_trade_sink(trades_tx);
+        trades_rx
+    }
+
+    pub fn request(&mut self, request: &ParticipantRequest) {
+        self.engine.process_participant_request(request);
+    }
+
+    pub fn limit(&mut self, participant_id: ParticipantId, order_id: OrderId, side: Side, price: Price, qty: Quantity) {
+        self.request(&limit(participant_id, order_id, side, price, qty));
+    }
+
+    pub fn cancel(&mut self, participant_id: ParticipantId, order_id: OrderId) {
+        self.request(&cancel(participant_id, order_id));
+    }
+
+    // what was published since the last call
+    pub fn responses(&self) -> Vec<ParticipantResponse> {
+        self.responses.try_iter().map(|response| response.payload).collect()
+    }
+
+    pub fn market_updates(&self) -> Vec<MarketUpdate> {
+        self.market_updates.try_iter().map(|update| update.payload).collect()
+    }
+}
+
+// an engine running on its own thread until every request sender is dropped
+pub struct EngineThread {
+    pub requests: SyncSender<ParticipantRequest>,
+    pub responses: Receiver<Sequenced<ParticipantResponse>>,
+    pub market_updates: Receiver<Sequenced<MarketUpdate>>,
+    pub handle: JoinHandle<()>,
+}
+
+impl EngineThread {
+    pub fn start(config: MatchingEngineConfig) -> Self {
+        let (requests_tx, requests_rx) = mpsc::sync_channel(1024);
+        let (responses_tx, responses_rx) = mpsc::sync_channel(1024);
+        let (market_updates_tx, market_updates_rx) = mpsc::sync_channel(1024);
+
+        let handle = thread::spawn(move || MatchingEngine::new(requests_rx, responses_tx, market_updates_tx, &config, Logger::disabled()).run());
+
+        Self { requests: requests_tx, responses: responses_rx, market_updates: market_updates_rx, handle }
+    }
+}
@@ -0,0 +1,167 @@
+use std::{env, fs, path::Path, process};
+
+use rexchange::{config::{Config, ConfigError, DEFAULT_CONFIG_PATH}, wire::LOGIN_PASSWORD_SIZE};
+
+const PARTICIPANTS: &str = "
+[[participants]]
+id = 0
+name = \"ALPHA\"
+
+[[participants]]
+id = 1
+name = \"BRAVO\"
+";
+
+const SYMBOLS: &str = "
+[[symbols]]
+id = 0
+name = \"ACME\"
+
+[[symbols]]
+id = 1
+name = \"GLOBEX\"
+";
+
+// the tables of a rule, then two participants and two symbols
+fn with_defaults(tables: &str) -> String {
+    format!("{}\n{}\n{}", tables, PARTICIPANTS, SYMBOLS)
+}
+
+fn validate(toml: &str) -> Result<(), String> {
+    let config: Config = toml::from_str(toml).unwrap_or_else(|error| panic!("cannot parse {}: {}", toml, error));
+
+    config.validate().map_err(|error| match error {
+        ConfigError::Invalid(message) => message,
+        error => panic!("{}", error),
+    })
+}
+
+#[test]
+fn the_shipped_config_is_valid() {
+    let config = Config::load(Path::new(DEFAULT_CONFIG_PATH)).unwrap();
+    assert_eq!((config.engine.shards, config.symbol_names(), config.participant_ids()), (2, vec!["ACME", "GLOBEX"], vec![0, 1, 2, 3, 4]));
+
+    assert_eq!(validate(&with_defaults("")), Ok(()));
+}
+
+#[test]
+fn unreadable_and_unparsable_files_name_their_path() {
+    let directory = env::temp_dir().join(format!("rexchange-config-{}", process::id()));
+    fs::create_dir_all(&directory).unwrap();
+
+    let missing = directory.join("missing.toml");
+    assert!(matches!(Config::load(&missing), Err(ConfigError::Io(path, _)) if path == missing));
+
+    let unknown_field = directory.join("unknown_field.toml");
+    fs::write(&unknown_field, with_defaults("[engine]\nthreads = 2")).unwrap();
+    assert!(matches!(Config::load(&unknown_field), Err(ConfigError::Parse(path, _)) if path == unknown_field));
+
+    // loading validates
+    let invalid = directory.join("invalid.toml");
+    fs::write(&invalid, with_defaults("[engine]\nshards = 0")).unwrap();
+    assert!(matches!(Config::load(&invalid), Err(ConfigError::Invalid(message)) if message == "engine.shards must be greater than 0"));
+}
+
+#[test]
+fn every_rule_rejects_with_its_own_message() {
+    let core_count = core_affinity::get_core_ids().map_or(0, |core_ids| core_ids.len());
+    let market_maker = "[[market_makers]]\nparticipant = 0\nsymbols = [0]";
+    let liquidity_taker = "[[liquidity_takers]]\nparticipant = 0\nsymbols = [0]";
+    let order_flow = "[order_flow]\nin_process = true\nparticipants = [0]\nsymbols = [0]";
+    let ouch = "[ouch]\naddress = \"127.0.0.1:0\"";
+    let gateway = "[gateway]\naddress = \"127.0.0.1:0\"";
+    let drop_copy = "[drop_copy]\naddress = \"127.0.0.1:0\"";
+    let admin = "[trade_store]\npath = \"trades.dat\"\n\n[admin]\naddress = \"127.0.0.1:0\"\naudit_path = \"audit.csv\"";
+
+    let cases = [
+        // limits
+        (with_defaults("[limits]\nmarket_updates_capacity = 0"), "limits.market_updates_capacity must be greater than 0".to_string()),
+
+        // cores
+        (with_defaults("[engine]\nshards = 0"), "engine.shards must be greater than 0".to_string()),
+        (with_defaults("[engine]\nshards = 2\n\n[cores]\nmatching_engines = [-1]"), "cores.matching_engines lists 1 cores but there are 2 shards".to_string()),
+        (with_defaults("[cores]\nlogger = 4096"), format!("cores.logger is 4096 but only {} cores are available", core_count)),
+        (with_defaults(&format!("{}\ncore = 4096", liquidity_taker)), format!("cores.liquidity_takers.core is 4096 but only {} cores are available", core_count)),
+
+        // logging
+        (with_defaults("[logging]\nfile_name = \"\""), "logging.file_name must not be empty".to_string()),
+        (with_defaults("[logging]\nmax_files = 0"), "logging.queue_capacity, logging.max_file_size and logging.max_files must be greater than 0".to_string()),
+
+        // participants
+        (format!("[limits]\nmax_participants = 1\n{}{}", PARTICIPANTS, SYMBOLS), "participant 1 (BRAVO) is not below limits.max_participants (1)".to_string()),
+        (format!("{}{}\n[[participants]]\nid = 1\nname = \"CHARLIE\"", SYMBOLS, PARTICIPANTS), "participant id 1 is defined more than once".to_string()),
+
+        // symbols
+        (format!("symbols = []\n{}", PARTICIPANTS), "at least one symbol must be defined".to_string()),
+        (format!("{}{}\n[[symbols]]\nid = 2\nname = \"ACME\"", PARTICIPANTS, SYMBOLS), "symbol 2 must have a unique, non empty name".to_string()),
+        (format!("{}\n[[symbols]]\nid = 1\nname = \"ACME\"", PARTICIPANTS), "symbol 1 (ACME): ids must be unique and go from 0 to 0".to_string()),
+        (format!("{}{}\n[[symbols]]\nid = 1\nname = \"INITECH\"", PARTICIPANTS, SYMBOLS), "symbol 1 (INITECH): ids must be unique and go from 0 to 2".to_string()),
+        (format!("{}\n[[symbols]]\nid = 0\nname = \"ACME\"\nshard = 1", PARTICIPANTS), "symbol ACME: shard 1 but there are 1 shards".to_string()),
+        (format!("{}\n[[symbols]]\nid = 0\nname = \"ACME\"\ncircuit_breaker = {{ static_band_bps = 500 }}", PARTICIPANTS), "symbol ACME: a static band needs a previous_close".to_string()),
+        (format!("{}\n[[symbols]]\nid = 0\nname = \"ACME\"\nallocation = {{ policy = \"split-fifo-pro-rata\", fifo_pct = 101, min_allocation = 1 }}", PARTICIPANTS), "symbol ACME: fifo_pct is 101 but must be at most 100".to_string()),
+
+        // bots
+        (with_defaults("[[market_makers]]\nparticipant = 5"), "market maker participant 5 is not a configured participant".to_string()),
+        (with_defaults(&format!("{}\n\n{}", market_maker, liquidity_taker)), "participant 0 runs more than one bot".to_string()),
+        (with_defaults("[[market_makers]]\nparticipant = 0\nsymbols = [0, 2]"), "market maker 0: unknown symbol 2".to_string()),
+        (with_defaults(&format!("[limits]\nmax_order_ids = 512\n\n{}", liquidity_taker)), "liquidity taker 0: order_ids must be between 1 and limits.max_order_ids (512)".to_string()),
+        (with_defaults(&format!("{}\nrequote_threshold = 0", market_maker)), "market maker 0: half_spread, quote_qty and requote_threshold must be greater than 0".to_string()),
+        (with_defaults(&format!("{}\norder_qty = 0", liquidity_taker)), "liquidity taker 0: window_trades and order_qty must be greater than 0".to_string()),
+        (with_defaults(&format!("{}\nimbalance_threshold = 1.5", liquidity_taker)), "liquidity taker 0: imbalance_threshold must be between 0 and 1".to_string()),
+
+        // order flow
+        (with_defaults("[order_flow]\nin_process = true\nparticipants = [0]"), "order_flow needs at least one participant and one symbol".to_string()),
+        (with_defaults("[order_flow]\nin_process = true\nparticipants = [0, 7]\nsymbols = [0]"), "order_flow participant 7 is not a configured participant".to_string()),
+        (with_defaults(&format!("{}\n\n{}", order_flow, market_maker)), "order_flow participant 0 already runs a bot".to_string()),
+        (with_defaults("[order_flow]\nin_process = true\nparticipants = [0]\nsymbols = [2]"), "order_flow: unknown symbol 2".to_string()),
+        (with_defaults(&format!("{}\ncancel_weight = 0\nnew_weight = 0\nmodify_weight = 0", order_flow)), "order_flow: price_range, max_qty, burst_size and the sum of the weights must be greater than 0".to_string()),
+        (with_defaults(&format!("{}\ninitial_mid = 20", order_flow)), "order_flow: initial_mid must be above price_range and marketable_pct at most 100".to_string()),
+        (with_defaults(&format!("{}\nmarketable_pct = 101", order_flow)), "order_flow: initial_mid must be above price_range and marketable_pct at most 100".to_string()),
+        (with_defaults(&format!("{}\norder_ids = 0", order_flow)), "order_flow: order_ids must be between 1 and limits.max_order_ids (1048576)".to_string()),
+        (with_defaults("[order_flow]\nparticipants = [0]\nsymbols = [0]"), "order_flow is sent over TCP but gateway.address is empty".to_string()),
+        (with_defaults(&format!("{}\nlogins = [{{ participant = 0, password = \"alpha\" }}]\n\n[order_flow]\nparticipants = [0, 1]\nsymbols = [0]", gateway)), "order_flow participant 1 has no gateway login".to_string()),
+
+        // ouch
+        (with_defaults(&format!("{}\nheartbeat_interval_ms = 15000", ouch)), "ouch: heartbeat_interval_ms must be greater than 0 and below idle_timeout_ms".to_string()),
+        (with_defaults(&format!("{}\nlogins = [{{ participant = 9, password = \"nine\" }}]", ouch)), "ouch login participant 9 is not a configured participant".to_string()),
+        (with_defaults(&format!("{}\nlogins = [{{ participant = 0, password = \"alpha\" }}]\n\n{}", ouch, market_maker)), "ouch login participant 0 already sends orders".to_string()),
+        (with_defaults(&format!("{}\nlogins = [{{ participant = 0, password = \"alpha\" }}, {{ participant = 0, password = \"alpha\" }}]", ouch)), "ouch login participant 0 already sends orders".to_string()),
+        (with_defaults(&format!("{}\nlogins = [{{ participant = 0, password = \"much too long\" }}]", ouch)), "ouch login ALPHA: the name must be at most 6 characters and the password 1 to 10".to_string()),
+
+        // gateway
+        (with_defaults(&format!("{}\nlogins = [{{ participant = 9, password = \"nine\" }}]", gateway)), "gateway login participant 9 is not a configured participant".to_string()),
+        (with_defaults(&format!("{}\nlogins = [{{ participant = 0, password = \"alpha\" }}]\n\n[ouch]\nlogins = [{{ participant = 0, password = \"alpha\" }}]", gateway)), "gateway login participant 0 already sends orders".to_string()),
+        (with_defaults(&format!("{}\nlogins = [{{ participant = 0, password = \"al\\u0000pha\" }}]", gateway)), format!("gateway login 0: the password must be 1 to {} bytes, without NUL", LOGIN_PASSWORD_SIZE)),
+
+        // drop copy
+        (with_defaults(&format!("{}\nheartbeat_interval_ms = 0", drop_copy)), "drop_copy: heartbeat_interval_ms must be greater than 0 and below idle_timeout_ms".to_string()),
+        (with_defaults(&format!("{}\nlogins = [{{ username = \"BACKOFFICE\", password = \"backoffice\" }}]", drop_copy)), "drop copy login BACKOFFICE: the username must be 1 to 6 characters and the password 1 to 10".to_string()),
+        (with_defaults(&format!("{}\nlogins = [{{ username = \"BACKOF\", password = \"one\" }}, {{ username = \"BACKOF\", password = \"two\" }}]", drop_copy)), "drop copy login BACKOF is duplicated".to_string()),
+        (with_defaults(&format!("{}\nlogins = [{{ username = \"BACKOF\", password = \"one\", participants = [1, 9] }}]", drop_copy)), "drop copy login BACKOF: participant 9 is not a configured participant".to_string()),
+
+        // admin
+        (with_defaults("[admin]\naddress = \"127.0.0.1:0\"\naudit_path = \"audit.csv\"\nusers = [{ username = \"ops\", password = \"operations\" }]"), "admin: trade_store.path and admin.audit_path must be set".to_string()),
+        (with_defaults(admin), "admin: at least one user must be defined".to_string()),
+        (with_defaults(&format!("{}\nusers = [{{ username = \"ops\", password = \"two words\" }}]", admin)), "admin user ops: the username and password must be non empty and without spaces".to_string()),
+        (with_defaults(&format!("{}\nusers = [{{ username = \"ops\", password = \"one\" }}, {{ username = \"ops\", password = \"two\" }}]", admin)), "admin user ops is duplicated".to_string()),
+
+        // fees
+        (with_defaults("[fees]\ntiers = [{ min_volume = 0, maker_fee = -3, taker_fee = 2 }]"), "fees: tier min_volume 0 must be above 0 and above the tier before".to_string()),
+        (with_defaults("[fees]\nsymbols = [{ symbol = 2, maker_fee = 0, taker_fee = 2 }]"), "fees: symbol 2 is unknown or has two schedules".to_string()),
+        (with_defaults("[fees]\nsymbols = [{ symbol = 1, maker_fee = 0, taker_fee = 2 }, { symbol = 1, maker_fee = 0, taker_fee = 1 }]"), "fees: symbol 1 is unknown or has two schedules".to_string()),
+        (with_defaults("[fees]\nsymbols = [{ symbol = 1, maker_fee = 0, taker_fee = 2, tiers = [{ min_volume = 20, maker_fee = 0, taker_fee = 1 }, { min_volume = 10, maker_fee = 0, taker_fee = 0 }] }]"), "fees of symbol 1: tier min_volume 10 must be above 0 and above the tier before".to_string()),
+
+        // end of day
+        (with_defaults("[eod]\ndirectory = \"eod\""), "eod: trade_store.path must be set".to_string()),
+        (with_defaults("[trade_store]\npath = \"trades.dat\"\n\n[eod]\ndirectory = \"eod\"\nclose_time = \"25:00\""), "eod: close_time 25:00 is not a HH:MM time".to_string()),
+
+        // backtest
+        (with_defaults(&format!("[backtest]\n\n{}", market_maker)), "backtest.data must name a historical data file".to_string()),
+        (with_defaults(&format!("[backtest]\ndata = \"history.csv\"\nstrategy = \"liquidity-taker\"\n\n{}", market_maker)), "backtest.strategy is LiquidityTaker but no such bot is configured".to_string()),
+        (with_defaults(&format!("[limits]\nmax_participants = 3\n\n[backtest]\ndata = \"history.csv\"\n\n{}", market_maker.replace("participant = 0", "participant = 1"))), "backtest: participant 1 must be below limits.max_participants - 2".to_string()),
+    ];
+
+    for (toml, message) in cases {
+        assert_eq!(validate(&toml), Err(message), "{}", toml);
+    }
+}
@@ -0,0 +1,202 @@
+mod common;
+
+use std::{io::{BufReader, Write}, net::{SocketAddr, TcpStream}, sync::mpsc, thread, time::Duration};
+
+use rexchange::{common::{OrderType, ParticipantId, Price, Quantity, Side, NO_ORDER_FLAGS}, logging::logger::Logger, market_data::{market_update::{MarketUpdate, MarketUpdateType}, publisher::MarketDataPublisher}, matching_engine::orderbook::OrderBookConfig, order_server::{drop_copy::{self, DropCopyConfig, DropCopyLogin, DropCopySession}, participants_request::{ParticipantRequest, ParticipantRequestType}, participants_response::{ParticipantResponse, ParticipantResponseType}, response_router::ResponseRouter, shard_router::ShardRouter, soup_bin::{self, LoginAccepted, LoginRequest}}, positions::Position, wire::{self, MarketUpdateFrame, PositionFrame, ResponseFrame}};
+
+use common::EngineThread;
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+fn response(response_type: ParticipantResponseType, participant_id: ParticipantId, qty: Quantity) -> ParticipantResponse {
+    ParticipantResponse { response_type, participant_id, symbol_id: 0, participant_order_id: 1, exec_qty: qty, leaves_qty: qty, ..ParticipantResponse::default() }
+}
+
+// F for a fill and C for a cancel with the participant and the quantity, T and the quantity for a trade, P for a
+// position with the participant and what it holds
+fn describe(message: &[u8]) -> String {
+    if let Ok((response, _)) = wire::decode::<ResponseFrame>(message) {
+        return match response.response_type {
+            ParticipantResponseType::Filled => format!("F{}:{}", response.participant_id, response.exec_qty),
+            _ => format!("C{}:{}", response.participant_id, response.leaves_qty),
+        };
+    }
+
+    if let Ok((position, _)) = wire::decode::<PositionFrame>(message) {
+        return format!("P{}:{}", position.participant_id, position.position);
+    }
+
+    let (update, _) = wire::decode::<MarketUpdateFrame>(message).unwrap();
+    format!("T:{}", update.qty)
+}
+
+fn position(participant_id: ParticipantId, position: i64) -> Position {
+    Position { participant_id, symbol_id: 0, position, open_price: 100.0, realized: 0.0, unrealized: 0.0, mark_price: 100 }
+}
+
+fn sorted(mut messages: Vec<String>) -> Vec<String> {
+    messages.sort();
+    messages
+}
+
+#[test]
+fn sessions_keep_the_executions_of_their_participants_and_every_trade() {
+    let mut all = DropCopySession::new(&[]);
+    let mut one = DropCopySession::new(&[1]);
+
+    let trade = MarketUpdate { update_type: MarketUpdateType::Trade, qty: 7, ..MarketUpdate::default() };
+    let add = MarketUpdate { update_type: MarketUpdateType::Add, qty: 7, ..MarketUpdate::default() };
+
+    for session in [&mut all, &mut one] {
+        session.on_response(&response(ParticipantResponseType::Accepted, 1, 0));
+        session.on_response(&response(ParticipantResponseType::Filled, 1, 7));
+        session.on_response(&response(ParticipantResponseType::Filled, 2, 7));
+        session.on_market_update(&add);
+        session.on_market_update(&trade);
+        session.on_response(&response(ParticipantResponseType::Rejected, 2, 0));
+        session.on_response(&response(ParticipantResponseType::Cancelled, 1, 3));
+        session.on_position(&position(1, 4));
+        session.on_position(&position(2, -4));
+    }
+
+    let messages = |session: &DropCopySession| (1..session.next_sequence_number()).map(|sequence_number| describe(session.message(sequence_number).unwrap())).collect::<Vec<_>>();
+    assert_eq!(messages(&all), ["F1:7", "F2:7", "T:7", "C1:3", "P1:4", "P2:-4"]);
+    assert_eq!(messages(&one), ["F1:7", "T:7", "C1:3", "P1:4"]);
+    assert!(one.message(0).is_none() && one.message(5).is_none());
+}
+
+struct Client {
+    stream: TcpStream,
+    reader: BufReader<TcpStream>,
+    payload: Vec<u8>,
+}
+
+impl Client {
+    fn login(address: SocketAddr, username: &str, password: &str, requested_sequence_number: u64) -> (Self, u8, Vec<u8>) {
+        let stream = TcpStream::connect(address).unwrap();
+        stream.set_read_timeout(Some(TIMEOUT)).unwrap();
+        let mut client = Client { reader: BufReader::new(stream.try_clone().unwrap()), stream, payload: Vec::new() };
+
+        let mut payload = Vec::new();
+        LoginRequest { username: username.to_string(), password: password.to_string(), requested_session: String::new(), requested_sequence_number }.encode(&mut payload);
+        soup_bin::write_packet(&mut client.stream, soup_bin::PACKET_LOGIN_REQUEST, &payload).unwrap();
+        client.stream.flush().unwrap();
+
+        let packet_type = client.read();
+        let payload = client.payload.clone();
+        (client, packet_type, payload)
+    }
+
+    fn read(&mut self) -> u8 {
+        soup_bin::read_packet(&mut self.reader, &mut self.payload).unwrap()
+    }
+
+    fn messages(&mut self, count: usize) -> Vec<String> {
+        let mut messages = Vec::new();
+
+        while messages.len() < count {
+            match self.read() {
+                soup_bin::PACKET_SERVER_HEARTBEAT => continue,
+                soup_bin::PACKET_SEQUENCED_DATA => messages.push(describe(&self.payload)),
+                packet_type => panic!("unexpected packet {}", packet_type as char),
+            }
+        }
+
+        messages
+    }
+}
+
+fn order(request_type: ParticipantRequestType, participant_id: ParticipantId, order_id: u64, side: Side, price: Price, qty: Quantity) -> ParticipantRequest {
+    ParticipantRequest {
+        request_type,
+        participant_id,
+        symbol_id: 0,
+        order_id,
+        order_type: OrderType::Limit,
+        side,
+        price,
+        stop_price: 0,
+        qty,
+        display_qty: qty,
+        flags: NO_ORDER_FLAGS,
+        recv_time: 0,
+    }
+}
+
+#[test]
+fn streams_executions_and_replays_on_reconnect() {
+    let EngineThread { requests: requests_tx, responses: responses_rx, market_updates: market_updates_rx, .. } = EngineThread::start(common::engine_config(&[OrderBookConfig::default()], &[0, 1]));
+
+    let router = ShardRouter::new(vec![0], vec![requests_tx]);
+    let mut response_router = ResponseRouter::new();
+    let mut market_data_publisher = MarketDataPublisher::new();
+
+    let config = DropCopyConfig {
+        address: "127.0.0.1:0".to_string(),
+        heartbeat_interval_ms: 20,
+        idle_timeout_ms: 2_000,
+        logins: vec![
+            DropCopyLogin { username: "ALL".to_string(), password: "secret".to_string(), participants: Vec::new() },
+            DropCopyLogin { username: "ZERO".to_string(), password: "secret".to_string(), participants: vec![0] },
+        ],
+    };
+
+    let (position_reports_tx, position_reports_rx) = mpsc::channel();
+    let address = drop_copy::spawn(&config, &mut response_router, &mut market_data_publisher, position_reports_rx, Logger::disabled()).unwrap();
+    thread::spawn(move || response_router.run(responses_rx));
+    thread::spawn(move || market_data_publisher.run(market_updates_rx));
+
+    let (_, packet_type, payload) = Client::login(address, "ALL", "wrong", 0);
+    assert_eq!((packet_type, payload), (soup_bin::PACKET_LOGIN_REJECTED, vec![soup_bin::REJECT_NOT_AUTHORIZED]));
+
+    let (mut all, packet_type, _) = Client::login(address, "ALL", "secret", 1);
+    assert_eq!(packet_type, soup_bin::PACKET_LOGIN_ACCEPTED);
+
+    // 0 rests 10, 1 takes 4 of them, 0 cancels the other 6. Responses and trades reach the server on two streams,
+    // their relative order isn't fixed
+    router.route(order(ParticipantRequestType::New, 0, 1, Side::Buy, 100, 10)).unwrap();
+    router.route(order(ParticipantRequestType::New, 1, 1, Side::Sell, 100, 4)).unwrap();
+    router.route(order(ParticipantRequestType::Cancel, 0, 1, Side::Buy, 100, 10)).unwrap();
+
+    let delivered = all.messages(4);
+    assert_eq!(sorted(delivered.clone()), ["C0:6", "F0:4", "F1:4", "T:4"]);
+
+    // idle, the server heartbeats
+    assert_eq!(all.read(), soup_bin::PACKET_SERVER_HEARTBEAT);
+
+    // its messages were kept while nobody was logged in
+    let (mut zero, packet_type, payload) = Client::login(address, "ZERO", "secret", 1);
+    assert_eq!((packet_type, LoginAccepted::decode(&payload).unwrap().sequence_number), (soup_bin::PACKET_LOGIN_ACCEPTED, 1));
+    assert_eq!(sorted(zero.messages(3)), ["C0:6", "F0:4", "T:4"]);
+
+    // one connection per login, a new one replays from where it asks to
+    let (_, packet_type, _) = Client::login(address, "ALL", "secret", 1);
+    assert_eq!(packet_type, soup_bin::PACKET_LOGIN_REJECTED);
+
+    soup_bin::write_packet(&mut all.stream, soup_bin::PACKET_LOGOUT_REQUEST, &[]).unwrap();
+    drop(all);
+
+    let login = || {
+        for _ in 0..100 {
+            let (client, packet_type, _) = Client::login(address, "ALL", "secret", 3);
+            if packet_type == soup_bin::PACKET_LOGIN_ACCEPTED {
+                return client;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        panic!("cannot log in again");
+    };
+
+    let mut all = login();
+    assert_eq!(all.messages(2), &delivered[2..]);
+
+    router.route(order(ParticipantRequestType::New, 1, 2, Side::Sell, 101, 5)).unwrap();
+    router.route(order(ParticipantRequestType::Cancel, 1, 2, Side::Sell, 101, 5)).unwrap();
+    assert_eq!(all.messages(1), ["C1:5"]);
+
+    // the position reports go to the logins following the participant
+    position_reports_tx.send(position(1, -4)).unwrap();
+    position_reports_tx.send(position(0, 4)).unwrap();
+    assert_eq!(all.messages(2), ["P1:-4", "P0:4"]);
+    assert_eq!(zero.messages(1), ["P0:4"]);
+}
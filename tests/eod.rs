@@ -0,0 +1,137 @@
+use std::{env, fs, path::{Path, PathBuf}, process, sync::{mpsc, Arc, Mutex}};
+
+use rexchange::{admin::{admin::{Admin, AdminCommand, AdminReply}, audit::AuditLog}, common::{Fee, ParticipantId, Price, Quantity, Side, TradeId, INVALID_PRICE}, eod::{end_of_day::{EndOfDay, EodConfig}, report::{EodReport, SettlementPrice}}, fees::billing::Billing, order_server::participants_response::{ParticipantResponse, ParticipantResponseType}, positions::{PositionKeeper, PositionsConfig}, trades::{trade::Trade, trade_store::TradeStore}};
+
+const SESSION: u64 = 1_709_251_200_000_000_000; // 2024-03-01 00:00:00 UTC
+const SECOND: u64 = 1_000_000_000;
+
+fn trade(trade_id: TradeId, symbol_id: u32, buyer: ParticipantId, seller: ParticipantId, price: Price, qty: Quantity, timestamp: u64) -> Trade {
+    Trade { trade_id, symbol_id, aggressor_side: Side::Buy, price, qty, buy_participant_id: buyer, buy_order_id: trade_id, sell_participant_id: seller, sell_order_id: trade_id, timestamp }
+}
+
+fn fill(participant_id: ParticipantId, symbol_id: u32, side: Side, price: Price, qty: Quantity, trade_id: TradeId, fee: Fee) -> ParticipantResponse {
+    ParticipantResponse { response_type: ParticipantResponseType::Filled, participant_id, symbol_id, side, price, exec_qty: qty, leaves_qty: 0, trade_id, fee, aggressor: fee > 0, ..ParticipantResponse::default() }
+}
+
+fn directory(name: &str) -> PathBuf {
+    let directory = env::temp_dir().join(format!("rexchange-eod-{}-{}", name, process::id()));
+    let _ = fs::remove_dir_all(&directory);
+    directory
+}
+
+struct Exchange {
+    trade_store: Arc<Mutex<TradeStore>>,
+    billing: Arc<Mutex<Billing>>,
+    positions: Arc<Mutex<PositionKeeper>>,
+}
+
+impl Exchange {
+    fn end_of_day(&self, config: &EodConfig, previous_closes: Vec<Price>) -> EndOfDay {
+        EndOfDay::new(config, self.trade_store.clone(), self.billing.clone(), self.positions.clone(), previous_closes)
+    }
+}
+
+// participants 0 and 1 trade symbol 0 back and forth, 2's trade of the day is busted and it holds symbol 2 from the day before
+fn exchange(directory: &Path) -> Exchange {
+    let mut trade_store = TradeStore::open(&directory.join("trades.dat")).unwrap();
+    trade_store.append(trade(9, 2, 2, 1, 45, 4, SESSION - SECOND)).unwrap();
+    trade_store.append(trade(1, 0, 0, 1, 100, 10, SESSION + SECOND)).unwrap();
+    trade_store.append(trade(2, 0, 1, 0, 110, 10, SESSION + 2 * SECOND)).unwrap();
+    trade_store.append(trade(3, 0, 0, 2, 104, 5, SESSION + 3 * SECOND)).unwrap();
+    trade_store.amend(trade(3, 0, 0, 2, 104, 0, SESSION + 3 * SECOND)).unwrap();
+    trade_store.flush().unwrap();
+
+    let mut billing = Billing::new();
+    let mut keeper = PositionKeeper::new(&PositionsConfig::default());
+
+    for response in [
+        fill(0, 0, Side::Buy, 100, 10, 1, 30),
+        fill(1, 0, Side::Sell, 100, 10, 1, -20),
+        fill(1, 0, Side::Buy, 110, 10, 2, 30),
+        fill(0, 0, Side::Sell, 110, 10, 2, -20),
+        fill(2, 2, Side::Buy, 45, 4, 9, 12),
+    ] {
+        billing.on_response(&response);
+        keeper.on_response(&response);
+    }
+
+    Exchange { trade_store: Arc::new(Mutex::new(trade_store)), billing: Arc::new(Mutex::new(billing)), positions: Arc::new(Mutex::new(keeper)) }
+}
+
+#[test]
+fn the_report_summarizes_settles_and_nets_the_session() {
+    let directory = directory("report");
+    let exchange = exchange(&directory);
+    let config = EodConfig { directory: directory.join("eod"), ..EodConfig::default() };
+    let end_of_day = exchange.end_of_day(&config, vec![95, INVALID_PRICE, 50]);
+
+    let report = end_of_day.report(SESSION + 12 * 3600 * SECOND);
+    assert_eq!(report.date, (2024, 3, 1));
+
+    let symbol = report.symbols[0];
+    assert_eq!((symbol.open, symbol.high, symbol.low, symbol.close, symbol.volume, symbol.trades, symbol.value), (100, 110, 100, 110, 20, 2, 2100));
+    assert_eq!((symbol.vwap(), symbol.settlement_price), (Some(105), Some(110)));
+    assert_eq!(report.symbols.iter().skip(1).map(|symbol| symbol.settlement_price).collect::<Vec<_>>(), [None, Some(50)]);
+
+    // the busted trade is left out, participant 2 only has its position
+    assert_eq!(report.statements.iter().map(|statement| (statement.participant_id, statement.trades.len())).collect::<Vec<_>>(), [(0, 2), (1, 2), (2, 0)]);
+    assert_eq!(report.statements[0].cash.net(), 90);
+    assert_eq!(report.statements[1].cash.net(), -110);
+    let position = report.statements[2].positions[0];
+    assert_eq!((position.symbol_id, position.position, position.settlement_price, position.unrealized), (2, 4, Some(50), 20.0));
+
+    assert_eq!(report.obligations.iter().map(|obligation| (obligation.participant_id, obligation.net_qty(), obligation.cash.net())).collect::<Vec<_>>(), [(0, 0, 90), (1, 0, -110)]);
+
+    let trades: Vec<&Trade> = Vec::new();
+    let vwap = EodReport::build(report.date, &trades, &[], |_, _| 0, SettlementPrice::Vwap, &[95]);
+    assert_eq!(vwap.symbols[0].settlement_price, Some(95));
+}
+
+#[test]
+fn the_reports_are_written_as_csv_and_json() {
+    let directory = directory("files");
+    let exchange = exchange(&directory);
+    let config = EodConfig { directory: directory.join("eod"), settlement_price: SettlementPrice::Vwap, ..EodConfig::default() };
+    let end_of_day = exchange.end_of_day(&config, vec![95, INVALID_PRICE, 50]);
+
+    let written = end_of_day.run(SESSION + 12 * 3600 * SECOND).unwrap();
+    assert_eq!(written, directory.join("eod").join("2024-03-01"));
+
+    let read = |name: &str| fs::read_to_string(written.join(name)).unwrap();
+
+    assert_eq!(read("symbols.csv"), "symbol_id,open,high,low,close,volume,trades,value,vwap,settlement_price\n0,100,110,100,110,20,2,2100,105,105\n1,,,,,0,0,0,,\n2,,,,,0,0,0,,50\n");
+    assert_eq!(read("obligations.csv"), "participant_id,symbol_id,bought_qty,sold_qty,net_qty,bought_value,sold_value,fees,net_cash\n0,0,10,10,0,1000,1100,10,90\n1,0,10,10,0,1100,1000,10,-110\n");
+    assert_eq!(read("statement-0.csv"), format!("trade_id,timestamp,symbol_id,side,price,qty,fee,cash\n1,{},0,BUY,100,10,30,-1030\n2,{},0,SELL,110,10,-20,1120\n", SESSION + SECOND, SESSION + 2 * SECOND));
+    assert!(read("symbols.json").starts_with("{\"date\":\"2024-03-01\",\"symbols\":[{\"symbol_id\":0,\"open\":100,"));
+    assert!(read("symbols.json").contains("{\"symbol_id\":1,\"open\":null,"));
+    assert_eq!(read("statement-2.json"), "{\"date\":\"2024-03-01\",\"participant_id\":2,\"trades\":[],\"positions\":[{\"symbol_id\":2,\"position\":4,\"open_price\":45.00,\"realized\":0.00,\"settlement_price\":50,\"unrealized\":20.00}],\"cash\":{\"bought_value\":0,\"sold_value\":0,\"fees\":0,\"net_cash\":0}}\n");
+    assert!(!written.join("symbols.csv.tmp").exists());
+}
+
+#[test]
+fn the_console_runs_the_end_of_day() {
+    let directory = directory("admin");
+    let exchange = exchange(&directory);
+    let (amendments_tx, _amendments_rx) = mpsc::sync_channel(1);
+    let mut admin = Admin::new(exchange.trade_store.clone(), exchange.billing.clone(), exchange.positions.clone(), vec![0, 0, 0], vec![amendments_tx], AuditLog::open(&directory.join("audit.csv")).unwrap());
+
+    assert_eq!(admin.execute("ops", &AdminCommand::parse("eod").unwrap(), SESSION).unwrap_err().to_string(), "end of day reports are not configured");
+
+    let config = EodConfig { directory: directory.join("eod"), ..EodConfig::default() };
+    admin.set_end_of_day(exchange.end_of_day(&config, vec![INVALID_PRICE; 3]));
+
+    let reply = admin.execute("ops", &AdminCommand::parse("eod").unwrap(), SESSION + 3600 * SECOND).unwrap();
+    assert_eq!(reply, AdminReply::EndOfDay(directory.join("eod").join("2024-03-01")));
+    assert!(directory.join("eod").join("2024-03-01").join("statement-1.json").exists());
+}
+
+#[test]
+fn the_close_time_is_a_utc_time_of_day() {
+    let close_time = |close_time: &str| EodConfig { close_time: close_time.to_string(), ..EodConfig::default() }.close_time();
+
+    assert_eq!(close_time("21:30"), Some((21 * 3600 + 30 * 60) * SECOND));
+    assert_eq!(close_time("00:00"), Some(0));
+    assert_eq!(close_time(""), None);
+    assert_eq!(close_time("24:00"), None);
+    assert_eq!(close_time("9h"), None);
+}
@@ -0,0 +1,154 @@
+mod common;
+
+use std::sync::Arc;
+
+use rexchange::{common::{ParticipantId, Side}, fees::{billing::{Billing, FeeTotals}, fee_schedule::{FeeConfig, FeeSchedule, FeeTier, SymbolFeeConfig}}, matching_engine::{circuit_breaker::{BreachAction, CircuitBreakerConfig}, orderbook::OrderBookConfig}, order_server::participants_response::ParticipantResponseType, trades::trade::{Trade, TradeAmendment}};
+
+use common::Venue;
+
+const JANUARY: u64 = 1_706_745_599_000_000_000; // 2024-01-31 23:59:59 UTC
+const FEBRUARY: u64 = JANUARY + 1_000_000_000;
+
+// a 2 tick rebate to makers and a 3 tick fee to takers, lower past 100 then 1000 shares, symbol 1 is free to make
+fn config() -> FeeConfig {
+    FeeConfig {
+        maker_fee: -2,
+        taker_fee: 3,
+        tiers: vec![FeeTier { min_volume: 100, maker_fee: -3, taker_fee: 2 }, FeeTier { min_volume: 1000, maker_fee: -4, taker_fee: 1 }],
+        symbols: vec![SymbolFeeConfig { symbol: 1, maker_fee: 0, taker_fee: 5, tiers: Vec::new() }],
+    }
+}
+
+#[test]
+fn the_tier_goes_by_the_monthly_volume_before_the_fill() {
+    let schedule = FeeSchedule::new(&config(), 4);
+
+    assert_eq!(schedule.charge(0, 0, true, 60, JANUARY), 180);
+    assert_eq!(schedule.charge(0, 0, true, 60, JANUARY), 180);
+    assert_eq!(schedule.charge(0, 0, false, 10, JANUARY), -30);
+    assert_eq!(schedule.volume(0), 130);
+
+    // other participants have their own volume, the override has no tiers
+    assert_eq!(schedule.charge(1, 0, false, 10, JANUARY), -20);
+    assert_eq!(schedule.charge(0, 1, true, 10, JANUARY), 50);
+    assert_eq!(schedule.charge(0, 1, false, 10, JANUARY), 0);
+    assert_eq!(schedule.volume(0), 150);
+
+    // a bust takes the shares off the month of its trade, a trade of a past month leaves the volume alone
+    schedule.amend(0, -100, JANUARY, JANUARY);
+    assert_eq!(schedule.volume(0), 50);
+    schedule.amend(0, 20, JANUARY - 40 * 86_400_000_000_000, JANUARY);
+    assert_eq!(schedule.volume(0), 50);
+
+    assert_eq!(schedule.tier(0, 999).taker_fee, 2);
+    assert_eq!(schedule.tier(0, 1000).taker_fee, 1);
+
+    // unknown participants are free
+    assert_eq!(schedule.charge(9, 0, true, 10, JANUARY), 0);
+}
+
+#[test]
+fn volumes_start_again_every_month_and_are_restored_from_the_trades() {
+    let schedule = FeeSchedule::new(&config(), 4);
+    assert_eq!(schedule.charge(0, 0, true, 200, JANUARY), 600);
+    assert_eq!(schedule.charge(0, 0, true, 10, JANUARY), 20);
+
+    assert_eq!(schedule.charge(0, 0, true, 10, FEBRUARY), 30);
+    assert_eq!(schedule.volume(0), 10);
+
+    let trade = |qty, timestamp| Trade { symbol_id: 0, qty, buy_participant_id: 0, sell_participant_id: 1, timestamp, ..Trade::default() };
+    let trades = [trade(500, JANUARY), trade(80, FEBRUARY), trade(40, FEBRUARY)];
+
+    let restored = FeeSchedule::new(&config(), 4);
+    restored.restore(&trades, FEBRUARY);
+    assert_eq!((restored.volume(0), restored.volume(1)), (120, 120));
+    assert_eq!(restored.charge(1, 0, false, 10, FEBRUARY), -30);
+}
+
+// one symbol, buyer 0 and seller 1, priced by the schedule
+fn priced_venue() -> Venue {
+    let mut venue = Venue::new();
+    venue.engine.set_fee_schedule(Arc::new(FeeSchedule::new(&config(), 4)));
+    venue.engine.set_simulated_time(JANUARY);
+    venue
+}
+
+// (participant, response type, fee) of every response with a fee, once billed
+fn fees(venue: &Venue, billing: &mut Billing) -> Vec<(ParticipantId, ParticipantResponseType, i64)> {
+    let responses = venue.responses();
+
+    for response in &responses {
+        billing.on_response(response);
+    }
+
+    responses.iter().filter(|response| response.fee != 0).map(|response| (response.participant_id, response.response_type, response.fee)).collect()
+}
+
+#[test]
+fn fills_carry_their_fee_and_busts_refund_it() {
+    let mut venue = priced_venue();
+    let mut billing = Billing::new();
+
+    venue.limit(0, 1, Side::Buy, 100, 10);
+    venue.limit(1, 1, Side::Sell, 100, 4);
+    assert_eq!(fees(&venue, &mut billing), [(1, ParticipantResponseType::Filled, 12), (0, ParticipantResponseType::Filled, -8)]);
+
+    let trade = Trade { trade_id: 1, symbol_id: 0, aggressor_side: Side::Sell, price: 100, qty: 4, buy_participant_id: 0, buy_order_id: 1, sell_participant_id: 1, sell_order_id: 1, timestamp: JANUARY };
+    venue.engine.amend_trade(&TradeAmendment { original: trade.clone(), amended: Trade { qty: 0, ..trade }, buy_fee: billing.side_fee(0, 1, Side::Buy), sell_fee: billing.side_fee(1, 1, Side::Sell) });
+    assert_eq!(fees(&venue, &mut billing), [(0, ParticipantResponseType::TradeBusted, 8), (1, ParticipantResponseType::TradeBusted, -12)]);
+
+    venue.limit(1, 2, Side::Sell, 100, 6);
+    fees(&venue, &mut billing);
+
+    assert_eq!(billing.totals(0), FeeTotals { fills: 2, maker_qty: 6, taker_qty: 0, maker_fees: -12, taker_fees: 0 });
+    assert_eq!(billing.totals(1), FeeTotals { fills: 2, maker_qty: 0, taker_qty: 6, maker_fees: 0, taker_fees: 18 });
+    assert_eq!(billing.totals(1).net(), 18);
+    assert_eq!(billing.iter().map(|(participant_id, _)| participant_id).collect::<Vec<_>>(), [0, 1]);
+}
+
+#[test]
+fn an_amendment_refunds_what_was_charged_whatever_the_tier_now() {
+    let mut venue = priced_venue();
+    let mut billing = Billing::new();
+
+    // 60 shares at the base fees, then 50 more move both sides past the first tier
+    venue.limit(0, 1, Side::Buy, 100, 60);
+    venue.limit(1, 1, Side::Sell, 100, 60);
+    venue.limit(0, 2, Side::Buy, 100, 50);
+    venue.limit(1, 2, Side::Sell, 100, 50);
+    assert_eq!(fees(&venue, &mut billing), [(1, ParticipantResponseType::Filled, 180), (0, ParticipantResponseType::Filled, -120), (1, ParticipantResponseType::Filled, 150), (0, ParticipantResponseType::Filled, -100)]);
+
+    // correcting the first trade down to 20 shares refunds 40 of them at the base fees
+    let trade = Trade { trade_id: 1, symbol_id: 0, aggressor_side: Side::Sell, price: 100, qty: 60, buy_participant_id: 0, buy_order_id: 1, sell_participant_id: 1, sell_order_id: 1, timestamp: JANUARY };
+    let amend = |billing: &Billing, original: &Trade, qty| TradeAmendment { original: original.clone(), amended: Trade { qty, ..original.clone() }, buy_fee: billing.side_fee(0, 1, Side::Buy), sell_fee: billing.side_fee(1, 1, Side::Sell) };
+    venue.engine.amend_trade(&amend(&billing, &trade, 20));
+    assert_eq!(fees(&venue, &mut billing), [(0, ParticipantResponseType::TradeCorrected, 80), (1, ParticipantResponseType::TradeCorrected, -120)]);
+
+    // then busting it refunds the rest, the volume is back below the tier
+    venue.engine.amend_trade(&amend(&billing, &Trade { qty: 20, ..trade }, 0));
+    assert_eq!(fees(&venue, &mut billing), [(0, ParticipantResponseType::TradeBusted, 40), (1, ParticipantResponseType::TradeBusted, -60)]);
+    assert_eq!((billing.trade_fee(0, 1), billing.trade_fee(1, 1)), (0, 0));
+
+    venue.limit(0, 3, Side::Buy, 100, 1);
+    venue.limit(1, 3, Side::Sell, 100, 1);
+    assert_eq!(fees(&venue, &mut billing), [(1, ParticipantResponseType::Filled, 3), (0, ParticipantResponseType::Filled, -2)]);
+}
+
+#[test]
+fn both_sides_of_an_auction_trade_pay_the_maker_fee() {
+    let circuit_breaker = CircuitBreakerConfig { previous_close: 100, static_band_bps: 500, breach_action: BreachAction::VolatilityAuction, halt_duration: 1_000, ..CircuitBreakerConfig::default() };
+    let mut venue = Venue::with_config(&common::engine_config(&[OrderBookConfig { circuit_breaker, ..OrderBookConfig::default() }], &[0, 1]));
+    venue.engine.set_fee_schedule(Arc::new(FeeSchedule::new(&config(), 4)));
+    venue.engine.set_simulated_time(JANUARY);
+    let mut billing = Billing::new();
+
+    // the breach starts the auction, whoever came last
+    venue.limit(1, 1, Side::Sell, 110, 4);
+    venue.limit(0, 1, Side::Buy, 110, 4);
+    assert!(fees(&venue, &mut billing).is_empty());
+
+    venue.engine.check_timers(JANUARY + 1_000);
+    assert_eq!(fees(&venue, &mut billing), [(0, ParticipantResponseType::Filled, -8), (1, ParticipantResponseType::Filled, -8)]);
+    assert_eq!(billing.totals(0), FeeTotals { fills: 1, maker_qty: 4, taker_qty: 0, maker_fees: -8, taker_fees: 0 });
+    assert_eq!(billing.totals(1), FeeTotals { fills: 1, maker_qty: 4, taker_qty: 0, maker_fees: -8, taker_fees: 0 });
+}
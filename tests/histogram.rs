@@ -0,0 +1,76 @@
+use rexchange::latency::histogram::Histogram;
+
+fn histogram(values: impl IntoIterator<Item = u64>) -> Histogram {
+    let mut histogram = Histogram::new();
+    for value in values {
+        histogram.record(value);
+    }
+    histogram
+}
+
+// (p50, p99, max)
+fn summary(histogram: &Histogram) -> (u64, u64, u64) {
+    (histogram.value_at_percentile(50.0), histogram.value_at_percentile(99.0), histogram.max())
+}
+
+#[test]
+fn values_below_128_have_a_bucket_each() {
+    let uniform = histogram(1..=100);
+    assert_eq!(summary(&uniform), (50, 99, 100));
+    assert_eq!((uniform.count(), uniform.min(), uniform.mean()), (100, 1, 50));
+    assert_eq!((uniform.value_at_percentile(0.0), uniform.value_at_percentile(1.0), uniform.value_at_percentile(100.0)), (1, 1, 100));
+
+    assert_eq!(summary(&histogram([0, 0, 127])), (0, 127, 127));
+}
+
+#[test]
+fn above_that_each_power_of_two_has_128_buckets() {
+    // 128 to 255 are still exact, 256 to 511 two to a bucket, the percentile is its highest value
+    assert_eq!(summary(&histogram([255, 255, 1_000])), (255, 1_000, 1_000));
+    let pairs = histogram([256, 257, 258, 1_000_000]);
+    assert_eq!([25.0, 50.0, 75.0].map(|percentile| pairs.value_at_percentile(percentile)), [257, 257, 259]);
+
+    // 1000 is in 1000..=1003, 1004 starts the next bucket
+    assert_eq!(histogram([1_000, 1_010]).value_at_percentile(50.0), 1_003);
+    assert_eq!(histogram([1_004, 1_010]).value_at_percentile(50.0), 1_007);
+
+    // never more than 1/128 above the value
+    for value in [129, 1_000, 65_537, 1_000_003, 123_456_789_012] {
+        let reported = histogram([value, u64::MAX]).value_at_percentile(50.0);
+        assert!(reported >= value && reported - value <= value / 128, "{} reported as {}", value, reported);
+    }
+}
+
+#[test]
+fn percentiles_of_known_distributions() {
+    // the buckets holding 5000 and 9900 are 4992..=5023 and 9856..=9919
+    assert_eq!(summary(&histogram(1..=10_000)), (5_023, 9_919, 10_000));
+
+    // a tail of 1% slow values only shows past p99, capped at the largest one rather than its bucket's 1003519
+    let tail = histogram(std::iter::repeat_n(100, 990).chain(std::iter::repeat_n(1_000_000, 10)));
+    assert_eq!(summary(&tail), (100, 100, 1_000_000));
+    assert_eq!(tail.value_at_percentile(99.9), 1_000_000);
+    assert_eq!(tail.mean(), 10_099);
+
+    // merged, a sample holds the counts of both
+    let mut merged = histogram(1..=5_000);
+    merged.add(&histogram(5_001..=10_000));
+    assert_eq!(summary(&merged), (5_023, 9_919, 10_000));
+
+    merged.reset();
+    assert_eq!((summary(&merged), merged.count(), merged.min(), merged.mean()), ((0, 0, 0), 0, 0, 0));
+}
+
+#[test]
+fn the_largest_values_land_in_the_last_bucket() {
+    let largest = histogram([u64::MAX, u64::MAX - 1, 1 << 63]);
+    assert_eq!(summary(&largest), (u64::MAX, u64::MAX, u64::MAX));
+    assert_eq!(largest.value_at_percentile(1.0), (1 << 63) + (1 << 56) - 1);
+
+    // summed without overflowing
+    assert_eq!(largest.mean(), ((u64::MAX as u128 * 2 - 1 + (1 << 63)) / 3) as u64);
+
+    let spread = histogram([1, u64::MAX]);
+    assert_eq!(summary(&spread), (1, u64::MAX, u64::MAX));
+    assert_eq!(spread.mean(), 1 << 63);
+}
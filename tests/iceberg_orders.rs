@@ -0,0 +1,78 @@
+mod common;
+
+use rexchange::{common::{OrderId, ParticipantId, Price, Priority, Quantity, Side}, market_data::market_update::MarketUpdateType, order_server::{participants_request::ParticipantRequest, participants_response::ParticipantResponseType}};
+
+use common::Venue;
+
+fn iceberg(participant_id: ParticipantId, order_id: OrderId, side: Side, price: Price, qty: Quantity, display_qty: Quantity) -> ParticipantRequest {
+    ParticipantRequest { display_qty, ..common::limit(participant_id, order_id, side, price, qty) }
+}
+
+// the engine's id of the order just accepted
+fn accepted(venue: &Venue) -> OrderId {
+    venue.responses().iter().find(|response| response.response_type == ParticipantResponseType::Accepted).unwrap().internal_order_id
+}
+
+// (order id, exec qty, leaves qty) of participant 1's fills
+fn fills(venue: &Venue) -> Vec<(OrderId, Quantity, Quantity)> {
+    venue.responses().iter().filter(|response| response.participant_id == 1 && response.response_type == ParticipantResponseType::Filled)
+        .map(|response| (response.participant_order_id, response.exec_qty, response.leaves_qty)).collect()
+}
+
+// (update type, engine order id, qty, priority) of what the book publishes, trades left out
+fn book_updates(venue: &Venue) -> Vec<(MarketUpdateType, OrderId, Quantity, Priority)> {
+    venue.market_updates().iter().filter(|update| update.update_type != MarketUpdateType::Trade)
+        .map(|update| (update.update_type, update.order_id, update.qty, update.priority)).collect()
+}
+
+#[test]
+fn only_the_peak_of_an_iceberg_is_published() {
+    let mut venue = Venue::new();
+
+    venue.request(&iceberg(1, 1, Side::Sell, 100, 10, 3));
+    let iceberg_id = accepted(&venue);
+    assert_eq!(book_updates(&venue), [(MarketUpdateType::Add, iceberg_id, 3, 1)]);
+
+    // a fill within the peak shows what is left of it, the reserve stays out of sight
+    venue.limit(0, 1, Side::Buy, 100, 2);
+    assert_eq!(fills(&venue), [(1, 2, 8)]);
+    assert_eq!(book_updates(&venue), [(MarketUpdateType::Modify, iceberg_id, 1, 1)]);
+
+    // alone at its price each peak starts the level again, the last one is what is left of the reserve
+    venue.limit(0, 2, Side::Buy, 100, 7);
+    assert_eq!(fills(&venue), [(1, 1, 7), (1, 3, 4), (1, 3, 1)]);
+    assert_eq!(book_updates(&venue), [
+        (MarketUpdateType::Cancel, iceberg_id, 0, 1),
+        (MarketUpdateType::Add, iceberg_id, 3, 1),
+        (MarketUpdateType::Cancel, iceberg_id, 0, 1),
+        (MarketUpdateType::Add, iceberg_id, 3, 1),
+        (MarketUpdateType::Cancel, iceberg_id, 0, 1),
+        (MarketUpdateType::Add, iceberg_id, 1, 1),
+    ]);
+    assert!(venue.market_updates().is_empty());
+}
+
+#[test]
+fn a_replenished_peak_goes_behind_the_orders_already_at_its_price() {
+    let mut venue = Venue::new();
+
+    venue.request(&iceberg(1, 1, Side::Sell, 100, 6, 3));
+    let iceberg_id = accepted(&venue);
+    venue.limit(1, 2, Side::Sell, 100, 2);
+    let order_id = accepted(&venue);
+    venue.market_updates();
+
+    // the first peak is taken, the second one gets a priority after the plain order
+    venue.limit(0, 1, Side::Buy, 100, 3);
+    assert_eq!(fills(&venue), [(1, 3, 3)]);
+    assert_eq!(book_updates(&venue), [(MarketUpdateType::Cancel, iceberg_id, 0, 1), (MarketUpdateType::Add, iceberg_id, 3, 3)]);
+
+    let order_book = venue.engine.order_book(0).unwrap();
+    order_book.check_invariants().unwrap();
+    assert_eq!(order_book.resting_orders(Side::Sell)[0].1.iter().map(|(order_info, qty)| (order_info.order_id, *qty)).collect::<Vec<_>>(), [(2, 2), (1, 3)]);
+
+    // so the plain order, which came later, trades first
+    venue.limit(0, 2, Side::Buy, 100, 3);
+    assert_eq!(fills(&venue), [(2, 2, 0), (1, 1, 2)]);
+    assert_eq!(book_updates(&venue), [(MarketUpdateType::Cancel, order_id, 0, 2), (MarketUpdateType::Modify, iceberg_id, 2, 3)]);
+}
@@ -0,0 +1,159 @@
+mod common;
+
+use rexchange::{common::{OrderId, OrderType, ParticipantId, Price, Quantity, Side, INVALID_QUANTITY, NO_ORDER_FLAGS, ORDER_FLAG_HIDDEN}, market_data::{itch::{self, AddOrder, ItchMessage, LeInt, Message, MessageHeader}, itch_encoder::{order_reference, ItchEncoder}}, order_server::participants_request::{ParticipantRequest, ParticipantRequestType}};
+
+use common::Venue;
+
+// a single symbol engine driven on the test thread, its market data encoded as it comes out and its responses left unread
+struct Feed {
+    venue: Venue,
+    encoder: ItchEncoder,
+    buf: Vec<u8>,
+    timestamp: u64,
+}
+
+impl Feed {
+    fn new() -> Self {
+        Self {
+            venue: Venue::new(),
+            encoder: ItchEncoder::new(&["ACME"], 3),
+            buf: Vec::new(),
+            timestamp: 0,
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn send(&mut self, request_type: ParticipantRequestType, participant_id: ParticipantId, order_id: OrderId, side: Side, price: Price, qty: Quantity, display_qty: Quantity, flags: u8) {
+        self.venue.request(&ParticipantRequest {
+            request_type,
+            participant_id,
+            symbol_id: 0,
+            order_id,
+            order_type: OrderType::Limit,
+            side,
+            price,
+            qty,
+            display_qty,
+            flags,
+            ..ParticipantRequest::default()
+        });
+
+        for update in self.venue.market_updates() {
+            self.timestamp += 1_000;
+            self.encoder.encode(&update, self.timestamp, &mut self.buf);
+        }
+    }
+
+    fn limit(&mut self, participant_id: ParticipantId, order_id: OrderId, side: Side, price: Price, qty: Quantity) {
+        self.send(ParticipantRequestType::New, participant_id, order_id, side, price, qty, INVALID_QUANTITY, NO_ORDER_FLAGS);
+    }
+
+    fn messages(&mut self) -> Vec<Message<'_>> {
+        self.encoder.flush(&mut self.buf);
+
+        let mut messages = Vec::new();
+        let mut buf = &self.buf[..];
+
+        while !buf.is_empty() {
+            let (message, len) = itch::decode(buf).expect("undecodable message");
+            messages.push(message);
+            buf = &buf[len..];
+        }
+
+        messages
+    }
+}
+
+#[test]
+fn layouts_are_little_endian_itch() {
+    let message = AddOrder {
+        header: MessageHeader { message_type: b'A', stock_locate: LeInt::new(0x0102), tracking_number: LeInt::new(7), timestamp: LeInt::new(0x0a0b0c0d0e0f) },
+        order_reference: LeInt::new(0x1122334455667788),
+        side: itch::SIDE_SELL,
+        shares: LeInt::new(300),
+        stock: itch::stock("ACME"),
+        price: LeInt::new(10_001),
+    };
+
+    let bytes = message.as_bytes();
+    assert_eq!(bytes.len(), 36);
+    assert_eq!(&bytes[..11], &[b'A', 0x02, 0x01, 7, 0, 0x0f, 0x0e, 0x0d, 0x0c, 0x0b, 0x0a]);
+    assert_eq!(&bytes[11..19], &0x1122334455667788u64.to_le_bytes());
+    assert_eq!(bytes[19], b'S');
+    assert_eq!(&bytes[20..24], &300u32.to_le_bytes());
+    assert_eq!(&bytes[24..32], b"ACME    ");
+    assert_eq!(&bytes[32..36], &10_001u32.to_le_bytes());
+
+    // read in place, from an unaligned offset
+    let mut buf = vec![0; 1];
+    buf.extend_from_slice(bytes);
+    assert_eq!(itch::decode(&buf[1..]), Some((Message::AddOrder(&message), 36)));
+
+    assert_eq!(itch::decode(&bytes[..35]), None);
+    assert_eq!(itch::decode(b"Z0000000000000000"), None);
+    assert_eq!(AddOrder::view(&[b'D'; 36]), None);
+}
+
+#[test]
+fn engine_updates_map_onto_itch_messages() {
+    let mut feed = Feed::new();
+    feed.encoder.system_event(b'O', 0, &mut feed.buf);
+    feed.encoder.stock_directory(0, 100, 0, &mut feed.buf);
+
+    feed.limit(0, 1, Side::Sell, 10_001, 10);
+    feed.send(ParticipantRequestType::New, 0, 2, Side::Sell, 10_001, 5, INVALID_QUANTITY, ORDER_FLAG_HIDDEN);
+
+    // a partial execution, then the rest of the displayed order and 3 of the hidden one
+    feed.limit(1, 1, Side::Buy, 10_001, 4);
+    feed.limit(1, 2, Side::Buy, 10_001, 9);
+
+    // hidden orders aren't published, cancelled either
+    feed.send(ParticipantRequestType::Cancel, 0, 2, Side::Sell, 0, 0, 0, NO_ORDER_FLAGS);
+
+    // an iceberg, executed a peak at a time
+    feed.send(ParticipantRequestType::New, 0, 3, Side::Sell, 10_002, 20, 5, NO_ORDER_FLAGS);
+    feed.limit(1, 3, Side::Buy, 10_002, 5);
+
+    feed.limit(0, 4, Side::Buy, 9_990, 7);
+    feed.send(ParticipantRequestType::Cancel, 0, 4, Side::Buy, 0, 0, 0, NO_ORDER_FLAGS);
+
+    let messages = feed.messages();
+    let types: String = messages.iter().map(|message| message.header().message_type as char).collect();
+    assert_eq!(types, "SRAEEPAEAAD");
+
+    // references follow the engine's internal order ids, the aggressors took some too
+    let added: Vec<u64> = messages.iter().filter_map(|message| match message {
+        Message::AddOrder(add) => Some(add.order_reference.get()),
+        _ => None,
+    }).collect();
+    let (first, iceberg, bid) = (order_reference(0, 1), order_reference(0, 5), order_reference(0, 7));
+    assert_eq!(added, vec![first, iceberg, iceberg, bid]);
+
+    match messages[2] {
+        Message::AddOrder(add) => {
+            assert_eq!((add.order_reference.get(), add.side, add.shares.get(), add.price.get()), (first, b'S', 10, 10_001));
+            assert_eq!((add.header.stock_locate.get(), add.header.tracking_number.get(), add.header.timestamp.get()), (0, 3, 1_000));
+            assert_eq!(&add.stock, b"ACME    ");
+        },
+        message => panic!("{:?}", message),
+    }
+
+    let executions: Vec<(u64, u64, u64)> = messages.iter().filter_map(|message| match message {
+        Message::OrderExecuted(executed) => Some((executed.order_reference.get(), executed.executed_shares.get(), executed.match_number.get())),
+        _ => None,
+    }).collect();
+    assert_eq!(executions, vec![(first, 4, 1), (first, 6, 2), (iceberg, 5, 4)]);
+
+    match messages[5] {
+        Message::Trade(trade) => assert_eq!((trade.order_reference.get(), trade.side, trade.shares.get(), trade.price.get(), trade.match_number.get()), (0, b'S', 3, 10_001, 3)),
+        message => panic!("{:?}", message),
+    }
+
+    match (messages[8], messages[10]) {
+        (Message::AddOrder(refresh), Message::OrderDelete(delete)) => {
+            assert_eq!((refresh.order_reference.get(), refresh.shares.get()), (iceberg, 5));
+            assert_eq!(delete.order_reference.get(), bid);
+        },
+        messages => panic!("{:?}", messages),
+    }
+}
@@ -0,0 +1,192 @@
+mod common;
+
+use std::{sync::mpsc::{Receiver, SyncSender}, thread, time::Duration};
+
+use rexchange::{client::{liquidity_taker::{LiquidityTaker, LiquidityTakerConfig, SignalDirection}, order_manager::OrderManager, strategy::Strategy}, common::{Price, Sequenced, Side, SymbolId}, logging::logger::Logger, market_data::market_update::MarketUpdate, matching_engine::orderbook::OrderBookConfig, pnl::PositionPnl, order_server::{participants_request::ParticipantRequest, participants_response::ParticipantResponse}};
+
+use common::EngineThread;
+
+const SYMBOL: SymbolId = 0;
+const PRICE: Price = 10_000;
+const QUIET: Duration = Duration::from_millis(50);
+
+type Gateway = SyncSender<ParticipantRequest>;
+
+// a liquidity taker (participant 0) and a plain client (participant 1) on a single shard engine thread
+struct Market {
+    taker: LiquidityTaker<Gateway>,
+    client: OrderManager<Gateway>,
+    responses: Receiver<Sequenced<ParticipantResponse>>,
+    market_updates: Receiver<Sequenced<MarketUpdate>>,
+    engine: thread::JoinHandle<()>,
+}
+
+impl Market {
+    fn start(config: LiquidityTakerConfig) -> Self {
+        let EngineThread { requests: requests_tx, responses: responses_rx, market_updates: market_updates_rx, handle: engine } = EngineThread::start(common::engine_config(&[OrderBookConfig::default()], &[0, 1]));
+
+        let config = LiquidityTakerConfig { participant: 0, symbols: vec![SYMBOL], ..config };
+
+        Self {
+            taker: LiquidityTaker::new(config, requests_tx.clone(), Logger::disabled()),
+            client: OrderManager::new(requests_tx, 1, 64),
+            responses: responses_rx,
+            market_updates: market_updates_rx,
+            engine,
+        }
+    }
+
+    // feeds both streams to the taker and lets it act until the exchange goes quiet
+    fn settle(&mut self) {
+        self.settle_with(false);
+    }
+
+    // with `is_trades_first` the market updates of each round go before its responses, as when the market data is ahead
+    fn settle_with(&mut self, is_trades_first: bool) {
+        loop {
+            let responses: Vec<_> = std::iter::from_fn(|| self.responses.recv_timeout(QUIET).ok()).collect();
+            let market_updates: Vec<_> = self.market_updates.try_iter().collect();
+
+            if is_trades_first {
+                market_updates.iter().for_each(|update| self.taker.on_market_update(&update.payload));
+            }
+
+            for response in &responses {
+                self.taker.on_response(&response.payload);
+                self.client.on_response(&response.payload).unwrap();
+            }
+
+            if !is_trades_first {
+                market_updates.iter().for_each(|update| self.taker.on_market_update(&update.payload));
+            }
+
+            self.taker.act().unwrap();
+
+            if responses.is_empty() && market_updates.is_empty() {
+                return;
+            }
+        }
+    }
+
+    fn stop(self) {
+        drop(self.taker);
+        drop(self.client);
+        self.engine.join().unwrap();
+    }
+}
+
+fn config(direction: SignalDirection) -> LiquidityTakerConfig {
+    LiquidityTakerConfig { window_trades: 4, min_window_qty: 30, imbalance_threshold: 0.5, direction, order_qty: 50, max_position: 80, ..LiquidityTakerConfig::default() }
+}
+
+#[test]
+fn average_cost_pnl() {
+    let mut pnl = PositionPnl::new();
+
+    pnl.on_fill(Side::Buy, 100, 10);
+    pnl.on_fill(Side::Buy, 110, 10);
+    assert_eq!((pnl.position, pnl.open_price()), (20, 105.0));
+    assert_eq!(pnl.unrealized(107), 40.0);
+
+    pnl.on_fill(Side::Sell, 108, 5);
+    assert_eq!((pnl.position, pnl.open_price(), pnl.realized), (15, 105.0, 15.0));
+
+    // flips to short 5 at 100, closing 15 at a 5 tick loss each
+    pnl.on_fill(Side::Sell, 100, 20);
+    assert_eq!((pnl.position, pnl.open_price(), pnl.realized), (-5, 100.0, -60.0));
+    assert_eq!(pnl.unrealized(98), 10.0);
+    assert_eq!((pnl.traded_qty, pnl.fills), (45, 4));
+
+    pnl.on_fill(Side::Buy, 98, 5);
+    assert_eq!((pnl.position, pnl.open_price(), pnl.realized), (0, 0.0, -50.0));
+}
+
+#[test]
+fn momentum_follows_the_buying_pressure() {
+    let mut market = Market::start(config(SignalDirection::Momentum));
+
+    market.client.send_limit(SYMBOL, Side::Sell, PRICE, 200).unwrap();
+    market.client.send_limit(SYMBOL, Side::Buy, PRICE - 5, 200).unwrap();
+    market.settle();
+
+    // 20 bought in total, short of min_window_qty
+    market.client.send_market(SYMBOL, Side::Buy, 10).unwrap();
+    market.client.send_market(SYMBOL, Side::Buy, 10).unwrap();
+    market.settle();
+    assert_eq!(market.taker.pnl(SYMBOL).unwrap().position, 0);
+    assert_eq!(market.taker.imbalance(SYMBOL), Some((20, 1.0)));
+
+    market.client.send_market(SYMBOL, Side::Buy, 10).unwrap();
+    market.settle();
+
+    let pnl = *market.taker.pnl(SYMBOL).unwrap();
+    assert_eq!((pnl.position, pnl.open_price(), pnl.fills), (50, PRICE as f64, 1));
+
+    // its own trade doesn't count towards the next signal
+    assert_eq!(market.taker.imbalance(SYMBOL), Some((0, 0.0)));
+    assert_eq!(market.taker.orders().open_orders().count(), 0);
+
+    // the position limit caps the next order at 30
+    for _ in 0..3 {
+        market.client.send_market(SYMBOL, Side::Buy, 10).unwrap();
+    }
+    market.settle();
+    assert_eq!(market.taker.pnl(SYMBOL).unwrap().position, 80);
+
+    market.stop();
+}
+
+// the taker buys 50 on the third 10 lot bought, then only the client's trades are in the window
+fn own_trade_left_out_of_the_window(is_trades_first: bool) {
+    let mut market = Market::start(config(SignalDirection::Momentum));
+
+    market.client.send_limit(SYMBOL, Side::Sell, PRICE, 200).unwrap();
+    market.client.send_limit(SYMBOL, Side::Buy, PRICE - 5, 200).unwrap();
+    market.settle();
+
+    for _ in 0..3 {
+        market.client.send_market(SYMBOL, Side::Buy, 10).unwrap();
+    }
+    market.settle_with(is_trades_first);
+    assert_eq!(market.taker.pnl(SYMBOL).unwrap().position, 50);
+    assert_eq!(market.taker.imbalance(SYMBOL), Some((0, 0.0)));
+
+    // nothing of the taker's fill is held against the next trades
+    market.client.send_market(SYMBOL, Side::Sell, 10).unwrap();
+    market.client.send_market(SYMBOL, Side::Buy, 5).unwrap();
+    market.settle_with(is_trades_first);
+    assert_eq!(market.taker.imbalance(SYMBOL), Some((15, -5.0 / 15.0)));
+
+    market.stop();
+}
+
+#[test]
+fn own_trade_seen_after_its_fill() {
+    own_trade_left_out_of_the_window(false);
+}
+
+#[test]
+fn own_trade_seen_before_its_fill() {
+    own_trade_left_out_of_the_window(true);
+}
+
+#[test]
+fn contrarian_fades_the_selling_pressure() {
+    let mut market = Market::start(config(SignalDirection::Contrarian));
+
+    market.client.send_limit(SYMBOL, Side::Sell, PRICE, 200).unwrap();
+    market.client.send_limit(SYMBOL, Side::Buy, PRICE - 5, 200).unwrap();
+    market.settle();
+
+    market.client.send_market(SYMBOL, Side::Sell, 20).unwrap();
+    market.client.send_market(SYMBOL, Side::Buy, 5).unwrap();
+    market.client.send_market(SYMBOL, Side::Sell, 20).unwrap();
+    market.settle();
+
+    // 40 sold against 5 bought, the taker buys at the offer
+    let pnl = *market.taker.pnl(SYMBOL).unwrap();
+    assert_eq!((pnl.position, pnl.open_price()), (50, PRICE as f64));
+    assert_eq!(pnl.unrealized(PRICE - 5), -250.0);
+
+    market.stop();
+}
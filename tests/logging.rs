@@ -0,0 +1,164 @@
+use std::{env, fs, path::PathBuf, process, sync::{atomic::{AtomicUsize, Ordering}, Arc, Mutex}, thread};
+
+use rexchange::{common::Side, logging::{lf_queue::LFQueue, logger::{LogLevel, LogPayload, Logger, LoggerConfig, OverflowPolicy, OwnFill}, rotating_file::RotatingFile}};
+
+fn directory(name: &str) -> PathBuf {
+    let directory = env::temp_dir().join(format!("rexchange-logging-{}-{}", name, process::id()));
+    let _ = fs::remove_dir_all(&directory);
+    directory
+}
+
+// logs from the test thread, the records are all written once the logger thread is joined
+fn log_lines(name: &str, level: LogLevel, overflow_policy: OverflowPolicy, queue_capacity: usize, log: impl FnOnce(&Logger)) -> (Vec<String>, u64) {
+    let config = LoggerConfig { directory: directory(name), file_name: name.to_string(), level, overflow_policy, queue_capacity, ..LoggerConfig::default() };
+    let (logger, handle) = Logger::spawn(&config, -1).unwrap();
+
+    log(&logger);
+    let dropped = logger.dropped();
+    drop(logger);
+    handle.join().unwrap();
+
+    let text = fs::read_to_string(config.directory.join(format!("{}.log", name))).unwrap();
+    (text.lines().map(str::to_string).collect(), dropped)
+}
+
+#[test]
+fn the_queue_hands_every_value_to_exactly_one_consumer() {
+    const PRODUCERS: usize = 4;
+    const VALUES: usize = 10_000;
+
+    let queue = Arc::new(LFQueue::new(64));
+    let popped = Arc::new(AtomicUsize::new(0));
+    let values = Arc::new(Mutex::new(Vec::new()));
+
+    let producers: Vec<_> = (0..PRODUCERS).map(|producer| {
+        let queue = queue.clone();
+        thread::spawn(move || for value in producer * VALUES..(producer + 1) * VALUES {
+            let mut value = value;
+            while let Err(rejected) = queue.push(value) {
+                value = rejected;
+                thread::yield_now();
+            }
+        })
+    }).collect();
+
+    let consumers: Vec<_> = (0..4).map(|_| {
+        let (queue, popped, values) = (queue.clone(), popped.clone(), values.clone());
+        thread::spawn(move || {
+            let mut own = Vec::new();
+            while popped.load(Ordering::Relaxed) < PRODUCERS * VALUES {
+                match queue.pop() {
+                    Some(value) => {
+                        own.push(value);
+                        popped.fetch_add(1, Ordering::Relaxed);
+                    },
+                    None => thread::yield_now(),
+                }
+            }
+            values.lock().unwrap().extend(own);
+        })
+    }).collect();
+
+    for thread in producers.into_iter().chain(consumers) {
+        thread.join().unwrap();
+    }
+
+    let mut values = values.lock().unwrap().clone();
+    values.sort_unstable();
+    assert_eq!(values, (0..PRODUCERS * VALUES).collect::<Vec<_>>());
+    assert!(queue.pop().is_none());
+}
+
+#[test]
+fn a_full_queue_hands_the_value_back() {
+    // rounded up to a power of two
+    let queue = LFQueue::new(3);
+    assert_eq!(queue.capacity(), 4);
+
+    for value in 0..4 {
+        assert_eq!(queue.push(value), Ok(()));
+    }
+    assert_eq!(queue.push(4), Err(4));
+
+    assert_eq!(queue.pop(), Some(0));
+    assert_eq!(queue.push(4), Ok(()));
+    assert_eq!((0..6).map(|_| queue.pop()).collect::<Vec<_>>(), [Some(1), Some(2), Some(3), Some(4), None, None]);
+}
+
+#[test]
+fn records_below_the_level_are_filtered_out() {
+    let (lines, dropped) = log_lines("level", LogLevel::Warn, OverflowPolicy::Block, 16, |logger| {
+        assert!(!logger.is_enabled(LogLevel::Info) && logger.is_enabled(LogLevel::Warn));
+        logger.debug("debug", LogPayload::None);
+        logger.info("info", LogPayload::None);
+        logger.warn("warn", LogPayload::Text("payload".to_string()));
+        logger.error("error", LogPayload::None);
+        logger.log(LogLevel::Off, "off", LogPayload::None);
+    });
+
+    assert_eq!(lines.iter().map(|line| line.split_once(' ').unwrap().1).collect::<Vec<_>>(), ["WARN  warn payload", "ERROR error"]);
+    assert_eq!(dropped, 0);
+
+    // formatted by the logger thread
+    let fill = OwnFill { participant_id: 3, symbol_id: 1, side: Side::Sell, price: 99, qty: 5, position: -5, open_price: 99.0, realized: 0.0, unrealized: 2.5 };
+    let (lines, _) = log_lines("own-fill", LogLevel::Info, OverflowPolicy::Block, 16, |logger| logger.info("fill", LogPayload::OwnFill(fill)));
+    assert!(lines[0].ends_with("INFO  fill OwnFill [ptid: 3, symb: 1, side: SELL, price: 99, qty: 5, position: -5, open_price: 99.00, realized: 0.00, unrealized: 2.50]"), "{}", lines[0]);
+
+    let disabled = Logger::disabled();
+    assert!(!disabled.is_enabled(LogLevel::Error));
+    disabled.error("error", LogPayload::None);
+    assert_eq!(disabled.dropped(), 0);
+}
+
+#[test]
+fn a_full_queue_blocks_or_drops_by_policy() {
+    const RECORDS: usize = 1_000;
+
+    // blocked until the logger thread makes room, nothing is lost and the order is kept
+    let (lines, dropped) = log_lines("block", LogLevel::Info, OverflowPolicy::Block, 2, |logger| for record in 0..RECORDS {
+        logger.info("record", LogPayload::Text(record.to_string()));
+    });
+    assert_eq!(dropped, 0);
+    assert_eq!(lines.iter().map(|line| line.rsplit(' ').next().unwrap().parse().unwrap()).collect::<Vec<usize>>(), (0..RECORDS).collect::<Vec<_>>());
+
+    // what doesn't fit is counted and the logger writes how many it missed
+    let (lines, dropped) = log_lines("drop", LogLevel::Info, OverflowPolicy::Drop, 2, |logger| for record in 0..RECORDS {
+        logger.info("record", LogPayload::Text(record.to_string()));
+    });
+    let (reports, records): (Vec<_>, Vec<_>) = lines.iter().partition(|line| line.starts_with("logger dropped"));
+    let reported: u64 = reports.iter().map(|line| line.split(' ').nth(2).unwrap().parse::<u64>().unwrap()).sum();
+
+    assert!(dropped > 0);
+    assert_eq!((records.len() as u64 + dropped, reported), (RECORDS as u64, dropped));
+    assert!(reports.iter().all(|line| line.ends_with("queue capacity is 2")));
+}
+
+#[test]
+fn files_rotate_at_the_size_limit_and_keep_the_newest() {
+    let directory = directory("rotation");
+    let read = |file_name: &str| fs::read_to_string(directory.join(file_name)).ok();
+
+    // two 6 byte lines to a file
+    let mut file = RotatingFile::open(&directory, "engine", 12, 3).unwrap();
+    for line in 0..7 {
+        file.write_line(format!("line{}\n", line).as_bytes()).unwrap();
+    }
+    file.flush().unwrap();
+
+    assert_eq!(read("engine.log").as_deref(), Some("line6\n"));
+    assert_eq!(read("engine.1.log").as_deref(), Some("line4\nline5\n"));
+    assert_eq!(read("engine.2.log").as_deref(), Some("line2\nline3\n"));
+    assert_eq!(read("engine.3.log"), None);
+
+    // reopened, the file is appended to and still counts what it held, a line longer than the limit gets a file alone
+    drop(file);
+    let mut file = RotatingFile::open(&directory, "engine", 12, 3).unwrap();
+    file.write_line(b"line7\n").unwrap();
+    file.write_line(b"a much longer line\n").unwrap();
+    file.write_line(b"line8\n").unwrap();
+    file.flush().unwrap();
+
+    assert_eq!(read("engine.log").as_deref(), Some("line8\n"));
+    assert_eq!(read("engine.1.log").as_deref(), Some("a much longer line\n"));
+    assert_eq!(read("engine.2.log").as_deref(), Some("line6\nline7\n"));
+}
@@ -0,0 +1,149 @@
+mod common;
+
+use std::{sync::mpsc::{Receiver, SyncSender}, thread, time::Duration};
+
+use rexchange::{client::{market_maker::{MarketMaker, MarketMakerConfig, RequoteMode}, order_manager::{OrderManager, OrderState}, strategy::Strategy}, common::{Price, Quantity, Sequenced, Side, SymbolId}, logging::logger::Logger, market_data::market_update::MarketUpdate, matching_engine::orderbook::OrderBookConfig, order_server::{participants_request::ParticipantRequest, participants_response::ParticipantResponse}};
+
+use common::EngineThread;
+
+const SYMBOL: SymbolId = 0;
+const REFERENCE_PRICE: Price = 10_000;
+const QUIET: Duration = Duration::from_millis(50);
+
+type Gateway = SyncSender<ParticipantRequest>;
+
+// a market maker (participant 0) and a plain client (participant 1) on a single shard engine thread
+struct Market {
+    maker: MarketMaker<Gateway>,
+    client: OrderManager<Gateway>,
+    responses: Receiver<Sequenced<ParticipantResponse>>,
+    market_updates: Receiver<Sequenced<MarketUpdate>>,
+    engine: thread::JoinHandle<()>,
+}
+
+impl Market {
+    fn start(config: MarketMakerConfig) -> Self {
+        let EngineThread { requests: requests_tx, responses: responses_rx, market_updates: market_updates_rx, handle: engine } = EngineThread::start(common::engine_config(&[OrderBookConfig::default()], &[0, 1]));
+
+        let config = MarketMakerConfig { participant: 0, symbols: vec![SYMBOL], reference_price: REFERENCE_PRICE, ..config };
+
+        Self {
+            maker: MarketMaker::new(config, requests_tx.clone(), Logger::disabled()),
+            client: OrderManager::new(requests_tx, 1, 64),
+            responses: responses_rx,
+            market_updates: market_updates_rx,
+            engine,
+        }
+    }
+
+    // feeds both streams to the market maker and lets it requote until the exchange goes quiet
+    fn settle(&mut self) {
+        loop {
+            self.maker.act().unwrap();
+
+            let mut is_quiet = true;
+
+            while let Ok(response) = self.responses.recv_timeout(QUIET) {
+                self.maker.on_response(&response.payload);
+                self.client.on_response(&response.payload).unwrap();
+                is_quiet = false;
+            }
+
+            while let Ok(update) = self.market_updates.try_recv() {
+                self.maker.on_market_update(&update.payload);
+                is_quiet = false;
+            }
+
+            if is_quiet {
+                return;
+            }
+        }
+    }
+
+    // price and quantity of the market maker's live quote on a side
+    fn quote(&self, side: Side) -> Option<(Price, Quantity)> {
+        let mut quotes = self.maker.orders().open_orders_for(SYMBOL).filter(|order| order.request.side == side);
+        let quote = quotes.next().map(|order| {
+            assert!(matches!(order.state, OrderState::Live | OrderState::PartiallyFilled));
+            (order.request.price, order.leaves_qty)
+        });
+
+        assert!(quotes.next().is_none(), "more than one quote on a side");
+        quote
+    }
+
+    fn stop(self) {
+        drop(self.maker);
+        drop(self.client);
+        self.engine.join().unwrap();
+    }
+}
+
+#[test]
+fn quotes_both_sides_around_the_reference_price() {
+    let mut market = Market::start(MarketMakerConfig::default());
+    market.settle();
+
+    assert_eq!(market.quote(Side::Buy), Some((REFERENCE_PRICE - 2, 100)));
+    assert_eq!(market.quote(Side::Sell), Some((REFERENCE_PRICE + 2, 100)));
+
+    let book = market.maker.book(SYMBOL).unwrap();
+    assert_eq!((book.best_bid(), book.best_ask()), (Some((REFERENCE_PRICE - 2, 100)), Some((REFERENCE_PRICE + 2, 100))));
+
+    market.stop();
+}
+
+#[test]
+fn fair_value_leaves_its_own_quotes_out() {
+    let mut market = Market::start(MarketMakerConfig::default());
+    market.settle();
+
+    market.client.send_limit(SYMBOL, Side::Buy, REFERENCE_PRICE - 7, 10).unwrap();
+    market.client.send_limit(SYMBOL, Side::Sell, REFERENCE_PRICE + 13, 10).unwrap();
+    market.settle();
+
+    assert_eq!(market.maker.fair_value(SYMBOL), REFERENCE_PRICE + 3);
+    assert_eq!(market.quote(Side::Buy), Some((REFERENCE_PRICE + 1, 100)));
+    assert_eq!(market.quote(Side::Sell), Some((REFERENCE_PRICE + 5, 100)));
+
+    market.stop();
+}
+
+#[test]
+fn skews_its_quotes_with_inventory() {
+    for requote in [RequoteMode::Modify, RequoteMode::CancelNew] {
+        let mut market = Market::start(MarketMakerConfig { inventory_skew: 0.05, requote, ..MarketMakerConfig::default() });
+        market.settle();
+
+        market.client.send_limit(SYMBOL, Side::Buy, REFERENCE_PRICE - 10, 10).unwrap();
+        market.client.send_limit(SYMBOL, Side::Sell, REFERENCE_PRICE + 10, 10).unwrap();
+        market.client.send_market(SYMBOL, Side::Sell, 60).unwrap();
+        market.settle();
+
+        // long 60, the fair value of 10000 is skewed down 3 ticks
+        assert_eq!(market.maker.orders().position(SYMBOL), 60);
+        assert_eq!(market.quote(Side::Buy), Some((REFERENCE_PRICE - 5, 100)));
+        assert_eq!(market.quote(Side::Sell), Some((REFERENCE_PRICE - 1, 100)));
+
+        market.stop();
+    }
+}
+
+#[test]
+fn stops_quoting_a_side_at_its_position_limit() {
+    let mut market = Market::start(MarketMakerConfig { max_position: 150, inventory_skew: 0.0, ..MarketMakerConfig::default() });
+    market.settle();
+
+    market.client.send_market(SYMBOL, Side::Sell, 100).unwrap();
+    market.settle();
+    assert_eq!(market.quote(Side::Buy).map(|(_, qty)| qty), Some(50));
+
+    market.client.send_market(SYMBOL, Side::Sell, 50).unwrap();
+    market.settle();
+
+    assert_eq!(market.maker.orders().position(SYMBOL), 150);
+    assert_eq!(market.quote(Side::Buy), None);
+    assert_eq!(market.quote(Side::Sell).map(|(_, qty)| qty), Some(100));
+
+    market.stop();
+}
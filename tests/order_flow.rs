@@ -0,0 +1,99 @@
+use std::{sync::mpsc, time::Duration};
+
+use rexchange::{client::{order_flow::{OrderFlow, OrderFlowConfig}, tcp_gateway::TcpOrderGateway}, common::{OrderType, Side}, logging::logger::Logger, order_server::{participants_request::{ParticipantRequest, ParticipantRequestType}, response_router::ResponseRouter, shard_router::ShardRouter, tcp_gateway::{self, GatewayConfig, GatewayLogin}}, wire::{self, RequestFrame, WireError}};
+
+fn config(seed: u64) -> OrderFlowConfig {
+    OrderFlowConfig { seed, participants: vec![0, 1], symbols: vec![0, 1], order_ids: 64, ..OrderFlowConfig::default() }
+}
+
+fn requests(config: OrderFlowConfig, count: usize) -> Vec<String> {
+    let mut order_flow = OrderFlow::new(config);
+    (0..count).map(|_| order_flow.next_request().to_string()).collect()
+}
+
+#[test]
+fn the_seed_alone_decides_the_flow() {
+    assert_eq!(requests(config(7), 10_000), requests(config(7), 10_000));
+    assert_ne!(requests(config(7), 10_000), requests(config(8), 10_000));
+}
+
+#[test]
+fn follows_the_configured_mix() {
+    let mut order_flow = OrderFlow::new(OrderFlowConfig { order_ids: 65_536, ..config(1) });
+    let (mut news, mut cancels) = (0, 0);
+
+    for _ in 0..100_000 {
+        let request = order_flow.next_request();
+
+        match request.request_type {
+            ParticipantRequestType::New => {
+                news += 1;
+                assert!(request.qty >= 1 && request.qty <= 100);
+                assert!(request.price.abs_diff(10_000) <= 20 + 100, "price {} is too far from the mid", request.price);
+            },
+            _ => cancels += 1,
+        }
+    }
+
+    // a modify is two messages, a cancel and a new order, 60/30/10 gives 70 new orders every 110 messages
+    let stats = order_flow.stats();
+    assert_eq!(news + cancels, 100_000);
+    assert_eq!(stats.new_orders + stats.modifies, news);
+    assert_eq!(stats.cancels + stats.modifies, cancels);
+    assert!((0.61..0.66).contains(&(news as f64 / 100_000.0)), "{} new orders", news);
+}
+
+#[test]
+fn codec_round_trips_and_rejects_bad_discriminants() {
+    let request = ParticipantRequest {
+        request_type: ParticipantRequestType::New,
+        participant_id: 3,
+        symbol_id: 1,
+        order_id: 1 << 40,
+        order_type: OrderType::StopLimit,
+        side: Side::Sell,
+        price: 10_005,
+        stop_price: 10_010,
+        qty: 250,
+        display_qty: 50,
+        flags: 0x01,
+        recv_time: 42,
+    };
+
+    let mut buf = Vec::new();
+    wire::encode::<RequestFrame>(&request, &mut buf);
+
+    let (decoded, size) = wire::decode::<RequestFrame>(&buf).unwrap();
+    assert_eq!(decoded.to_string(), request.to_string());
+    assert_eq!((decoded.recv_time, size), (42, buf.len()));
+
+    for (offset, field) in [(4, "request type"), (5, "order type"), (6, "side")] {
+        let mut bad = buf.clone();
+        bad[offset] = 9;
+        assert_eq!(wire::decode::<RequestFrame>(&bad).err(), Some(WireError::Discriminant(field, 9)));
+    }
+}
+
+#[test]
+fn sends_the_flow_over_tcp() {
+    let (requests_tx, requests_rx) = mpsc::sync_channel(4096);
+    let router = ShardRouter::new(vec![0, 0], vec![requests_tx]);
+    let logins = vec![GatewayLogin { participant: 0, password: "alpha".to_string() }, GatewayLogin { participant: 1, password: "bravo".to_string() }];
+    let gateway_config = GatewayConfig { address: "127.0.0.1:0".to_string(), logins: logins.clone() };
+    let mut response_router = ResponseRouter::new();
+    let address = tcp_gateway::spawn(&gateway_config, router, &mut response_router, false, Logger::disabled()).unwrap();
+
+    let (mut gateway, _responses) = TcpOrderGateway::connect(address, &logins).unwrap();
+    let stats = OrderFlow::new(OrderFlowConfig { messages: 2_500, burst_size: 1_000, ..config(3) }).run(&mut gateway).unwrap();
+    assert_eq!(stats.messages, 2_500);
+
+    // each participant's requests come in order, on a connection of its own
+    let mut order_flow = OrderFlow::new(config(3));
+    let expected: Vec<ParticipantRequest> = (0..2_500).map(|_| order_flow.next_request()).collect();
+    let received: Vec<ParticipantRequest> = (0..2_500).map(|_| requests_rx.recv_timeout(Duration::from_secs(5)).unwrap()).collect();
+
+    for participant_id in [0, 1] {
+        let of = |requests: &[ParticipantRequest]| requests.iter().filter(|request| request.participant_id == participant_id).map(ToString::to_string).collect::<Vec<_>>();
+        assert_eq!(of(&received), of(&expected));
+    }
+}
@@ -0,0 +1,154 @@
+mod common;
+
+use std::{sync::mpsc::{Receiver, SyncSender}, thread, time::Duration};
+
+use rexchange::{client::order_manager::{ClientError, OrderManager, OrderState}, common::{Price, Sequenced, Side, SymbolId}, market_data::market_update::MarketUpdate, matching_engine::orderbook::OrderBookConfig, order_server::{participants_request::ParticipantRequest, participants_response::{ParticipantResponse, ParticipantResponseType}}};
+
+use common::EngineThread;
+
+const SYMBOL: SymbolId = 0;
+const PRICE: Price = 100;
+const MAX_ORDER_IDS: usize = 8;
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+type Client = OrderManager<SyncSender<ParticipantRequest>>;
+
+// two clients trading against each other through a single shard engine thread
+struct Exchange {
+    maker: Client,
+    taker: Client,
+    responses: Receiver<Sequenced<ParticipantResponse>>,
+    _market_updates: Receiver<Sequenced<MarketUpdate>>,
+    engine: thread::JoinHandle<()>,
+}
+
+impl Exchange {
+    fn start() -> Self {
+        let config = OrderBookConfig { max_order_ids: MAX_ORDER_IDS, ..OrderBookConfig::default() };
+        let EngineThread { requests, responses, market_updates, handle } = EngineThread::start(common::engine_config(&[config], &[0, 1]));
+
+        Self {
+            maker: OrderManager::new(requests.clone(), 0, MAX_ORDER_IDS),
+            taker: OrderManager::new(requests, 1, MAX_ORDER_IDS),
+            responses,
+            _market_updates: market_updates,
+            engine: handle,
+        }
+    }
+
+    // applies the next `count` responses to both clients, each one ignores the other's
+    fn pump(&mut self, count: usize) -> Vec<ParticipantResponseType> {
+        (0..count).map(|_| {
+            let response = self.responses.recv_timeout(TIMEOUT).expect("missing response").payload;
+            self.maker.on_response(&response).unwrap();
+            self.taker.on_response(&response).unwrap();
+            response.response_type
+        }).collect()
+    }
+
+    fn stop(self) {
+        drop(self.maker);
+        drop(self.taker);
+        self.engine.join().unwrap();
+    }
+}
+
+#[test]
+fn partial_fill_then_cancel() {
+    let mut exchange = Exchange::start();
+
+    let bid = exchange.maker.send_limit(SYMBOL, Side::Buy, PRICE, 10).unwrap();
+    assert_eq!(exchange.maker.order(bid).unwrap().state, OrderState::PendingNew);
+    exchange.pump(1);
+    assert_eq!(exchange.maker.order(bid).unwrap().state, OrderState::Live);
+
+    let ask = exchange.taker.send_limit(SYMBOL, Side::Sell, PRICE, 4).unwrap();
+    exchange.pump(3);
+
+    let order = exchange.maker.order(bid).unwrap();
+    assert_eq!((order.state, order.filled_qty, order.leaves_qty), (OrderState::PartiallyFilled, 4, 6));
+    assert_eq!(exchange.taker.order(ask).unwrap().state, OrderState::Filled);
+    assert_eq!(exchange.maker.position(SYMBOL), 4);
+    assert_eq!(exchange.taker.position(SYMBOL), -4);
+    assert_eq!(exchange.taker.open_orders().count(), 0);
+
+    exchange.maker.send_cancel(bid).unwrap();
+    assert_eq!(exchange.maker.order(bid).unwrap().state, OrderState::PendingCancel);
+    assert!(matches!(exchange.maker.send_cancel(bid), Err(ClientError::NotCancelable(..))));
+    exchange.pump(1);
+
+    assert_eq!(exchange.maker.order(bid).unwrap().state, OrderState::Cancelled);
+    assert_eq!(exchange.maker.open_orders_for(SYMBOL).count(), 0);
+
+    exchange.stop();
+}
+
+#[test]
+fn modify_sends_the_replacement_once_cancelled() {
+    let mut exchange = Exchange::start();
+
+    let bid = exchange.maker.send_limit(SYMBOL, Side::Buy, PRICE, 10).unwrap();
+    let replacement = exchange.maker.send_modify(bid, PRICE + 1, 5).unwrap();
+    assert_eq!(exchange.maker.order(replacement).unwrap().state, OrderState::PendingNew);
+
+    assert_eq!(exchange.pump(3), [ParticipantResponseType::Accepted, ParticipantResponseType::Cancelled, ParticipantResponseType::Accepted]);
+    assert_eq!(exchange.maker.order(bid).unwrap().state, OrderState::Cancelled);
+
+    let order = exchange.maker.order(replacement).unwrap();
+    assert_eq!((order.state, order.request.price, order.leaves_qty), (OrderState::Live, PRICE + 1, 5));
+
+    exchange.stop();
+}
+
+#[test]
+fn replacement_is_dropped_when_the_order_fills_first() {
+    let mut exchange = Exchange::start();
+
+    let bid = exchange.maker.send_limit(SYMBOL, Side::Buy, PRICE, 10).unwrap();
+    exchange.pump(1);
+
+    exchange.taker.send_market(SYMBOL, Side::Sell, 10).unwrap();
+    let replacement = exchange.maker.send_modify(bid, PRICE - 1, 10).unwrap();
+
+    assert_eq!(exchange.pump(4), [ParticipantResponseType::Accepted, ParticipantResponseType::Filled, ParticipantResponseType::Filled, ParticipantResponseType::CancelRejected]);
+    assert_eq!(exchange.maker.order(bid).unwrap().state, OrderState::Filled);
+    assert_eq!(exchange.maker.order(replacement).unwrap().state, OrderState::Cancelled);
+    assert_eq!(exchange.maker.position(SYMBOL), 10);
+    assert!(exchange.responses.recv_timeout(Duration::from_millis(100)).is_err());
+
+    exchange.stop();
+}
+
+#[test]
+fn rejects_and_unexpected_transitions() {
+    let mut exchange = Exchange::start();
+
+    let bid = exchange.maker.send_limit(SYMBOL, Side::Buy, PRICE, 0).unwrap();
+    exchange.pump(1);
+    assert_eq!(exchange.maker.order(bid).unwrap().state, OrderState::Rejected);
+
+    let fill = ParticipantResponse { response_type: ParticipantResponseType::Filled, participant_id: 0, participant_order_id: bid, exec_qty: 1, leaves_qty: 0, ..ParticipantResponse::default() };
+    assert!(matches!(exchange.maker.on_response(&fill), Err(ClientError::UnexpectedTransition(_, OrderState::Rejected, ParticipantResponseType::Filled))));
+
+    let unknown = ParticipantResponse { participant_order_id: MAX_ORDER_IDS as u64, ..fill };
+    assert!(matches!(exchange.maker.on_response(&unknown), Err(ClientError::UnknownOrder(_))));
+
+    exchange.stop();
+}
+
+#[test]
+fn order_ids_are_recycled() {
+    let mut exchange = Exchange::start();
+
+    let order_ids: Vec<_> = (0..MAX_ORDER_IDS).map(|level| exchange.maker.send_limit(SYMBOL, Side::Buy, PRICE - level as Price, 1).unwrap()).collect();
+    assert!(matches!(exchange.maker.send_limit(SYMBOL, Side::Buy, PRICE, 1), Err(ClientError::NoFreeOrderId)));
+    exchange.pump(MAX_ORDER_IDS);
+
+    exchange.maker.send_cancel(order_ids[3]).unwrap();
+    exchange.pump(1);
+    assert_eq!(exchange.maker.send_limit(SYMBOL, Side::Buy, PRICE - 3, 1).unwrap(), order_ids[3]);
+    exchange.pump(1);
+    assert_eq!(exchange.maker.open_orders().count(), MAX_ORDER_IDS);
+
+    exchange.stop();
+}
@@ -0,0 +1,291 @@
+mod common;
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+use proptest::prelude::*;
+
+use rexchange::{common::{OrderFlags, Sequenced, OrderId, OrderType, ParticipantId, Price, Quantity, Side, INVALID_PRICE, INVALID_QUANTITY, NO_ORDER_FLAGS, ORDER_FLAG_HIDDEN}, market_data::market_update::MarketUpdateType, matching_engine::{order::{NewOrder, OrderInfo}, orderbook::{OrderBook, OrderBookConfig}}, order_server::participants_response::ParticipantResponseType};
+
+use common::Venue;
+
+// few levels and a wider price range so that level slots collide, few ids so that they get reused
+const MAX_PRICE_LEVELS: usize = 16;
+const MAX_ORDER_IDS: usize = 32;
+const PARTICIPANTS: ParticipantId = 3;
+const MIN_PRICE: Price = 90;
+const MAX_PRICE: Price = 130;
+
+type Levels = Vec<(Price, Vec<(OrderInfo, Quantity)>)>;
+
+#[derive(Clone, Debug)]
+enum Op {
+    Add { order_info: OrderInfo, side: Side, price: Price, qty: Quantity },
+    Cancel { order_info: OrderInfo },
+    // the book has no native modify, a participant cancels and sends the order again
+    Modify { order_info: OrderInfo, price: Price, qty: Quantity },
+}
+
+fn order_info() -> impl Strategy<Value = OrderInfo> {
+    (0..PARTICIPANTS, 0..MAX_ORDER_IDS as OrderId + 2).prop_map(|(participant_id, order_id)| OrderInfo { participant_id, order_id })
+}
+
+fn side() -> impl Strategy<Value = Side> {
+    prop_oneof![Just(Side::Buy), Just(Side::Sell)]
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        4 => (order_info(), side(), MIN_PRICE..MAX_PRICE, 1..20 as Quantity).prop_map(|(order_info, side, price, qty)| Op::Add { order_info, side, price, qty }),
+        2 => order_info().prop_map(|order_info| Op::Cancel { order_info }),
+        1 => (order_info(), MIN_PRICE..MAX_PRICE, 1..20 as Quantity).prop_map(|(order_info, price, qty)| Op::Modify { order_info, price, qty }),
+    ]
+}
+
+// price-time priority over a BTreeMap of FIFO queues, with the same admission rules as the book
+#[derive(Default)]
+struct ReferenceBook {
+    bids: BTreeMap<Price, VecDeque<(OrderInfo, Quantity)>>,
+    asks: BTreeMap<Price, VecDeque<(OrderInfo, Quantity)>>,
+    sides: HashMap<OrderInfo, (Side, Price)>,
+    traded_qty: u64,
+}
+
+impl ReferenceBook {
+    fn levels_mut(&mut self, side: Side) -> &mut BTreeMap<Price, VecDeque<(OrderInfo, Quantity)>> {
+        match side {
+            Side::Buy => &mut self.bids,
+            _ => &mut self.asks,
+        }
+    }
+
+    fn levels(&self, side: Side) -> Levels {
+        let levels: Vec<_> = match side {
+            Side::Buy => self.bids.iter().rev().collect(),
+            _ => self.asks.iter().collect(),
+        };
+
+        levels.into_iter().map(|(price, orders)| (*price, orders.iter().cloned().collect())).collect()
+    }
+
+    // a level slot is shared by prices MAX_PRICE_LEVELS apart, only one of them can rest at a time
+    fn is_price_available(&mut self, side: Side, price: Price) -> bool {
+        self.levels_mut(side).keys().all(|level_price| *level_price == price || *level_price as usize % MAX_PRICE_LEVELS != price as usize % MAX_PRICE_LEVELS)
+    }
+
+    fn add(&mut self, order_info: OrderInfo, side: Side, price: Price, mut qty: Quantity) -> bool {
+        if order_info.order_id >= MAX_ORDER_IDS as OrderId || self.sides.contains_key(&order_info) || !self.is_price_available(side, price) {
+            return false;
+        }
+
+        while qty > 0 && self.is_marketable(side, price) {
+            let mut level = match side {
+                Side::Buy => self.asks.first_entry(),
+                _ => self.bids.last_entry(),
+            }.unwrap();
+
+            let (passive_order_info, passive_qty) = level.get_mut().front_mut().unwrap();
+            let fill_qty = qty.min(*passive_qty);
+
+            *passive_qty -= fill_qty;
+            qty -= fill_qty;
+
+            if *passive_qty == 0 {
+                let passive_order_info = passive_order_info.clone();
+                level.get_mut().pop_front();
+                self.sides.remove(&passive_order_info);
+                if level.get().is_empty() {
+                    level.remove();
+                }
+            }
+
+            self.traded_qty += fill_qty as u64;
+        }
+
+        if qty > 0 {
+            self.levels_mut(side).entry(price).or_default().push_back((order_info.clone(), qty));
+            self.sides.insert(order_info, (side, price));
+        }
+
+        true
+    }
+
+    fn is_marketable(&self, side: Side, price: Price) -> bool {
+        match side {
+            Side::Buy => self.asks.keys().next().is_some_and(|ask| *ask <= price),
+            _ => self.bids.keys().next_back().is_some_and(|bid| *bid >= price),
+        }
+    }
+
+    fn cancel(&mut self, order_info: &OrderInfo) -> bool {
+        let (side, price) = match self.sides.remove(order_info) {
+            Some(entry) => entry,
+            None => return false,
+        };
+
+        let levels = self.levels_mut(side);
+        let level = levels.get_mut(&price).unwrap();
+        level.retain(|(resting_order_info, _)| resting_order_info != order_info);
+
+        if level.is_empty() {
+            levels.remove(&price);
+        }
+
+        true
+    }
+}
+
+// what the book reported about each order, to check that accepted quantity = filled + cancelled + resting
+#[derive(Default)]
+struct OrderLedger {
+    accepted: Quantity,
+    filled: Quantity,
+    cancelled: Quantity,
+}
+
+struct Harness {
+    book: OrderBook,
+    venue: Venue,
+    ledgers: HashMap<OrderInfo, OrderLedger>,
+    traded_qty: u64,
+}
+
+impl Harness {
+    fn new() -> Self {
+        let config = OrderBookConfig { max_price_levels: MAX_PRICE_LEVELS, max_order_ids: MAX_ORDER_IDS, max_participants: PARTICIPANTS as usize, ..OrderBookConfig::default() };
+        let participant_ids: Vec<ParticipantId> = (0..PARTICIPANTS).collect();
+
+        Self {
+            book: OrderBook::new(0, config.clone()),
+            venue: Venue::with_config(&common::engine_config(std::slice::from_ref(&config), &participant_ids)),
+            ledgers: HashMap::new(),
+            traded_qty: 0,
+        }
+    }
+
+    // returns true if the order was accepted
+    fn add(&mut self, order_info: OrderInfo, side: Side, price: Price, qty: Quantity, display_qty: Quantity, flags: OrderFlags) -> bool {
+        self.book.add(NewOrder {
+            order_info: order_info.clone(),
+            order_type: OrderType::Limit,
+            side,
+            price,
+            stop_price: INVALID_PRICE,
+            qty,
+            display_qty,
+            flags,
+        }, &mut self.venue.engine);
+
+        self.drain().first() == Some(&ParticipantResponseType::Accepted)
+    }
+
+    fn cancel(&mut self, order_info: OrderInfo) -> bool {
+        self.book.cancel(order_info, &mut self.venue.engine);
+        self.drain().first() == Some(&ParticipantResponseType::Cancelled)
+    }
+
+    fn drain(&mut self) -> Vec<ParticipantResponseType> {
+        let mut response_types = Vec::new();
+
+        while let Ok(Sequenced { payload: response, .. }) = self.venue.responses.try_recv() {
+            let order_info = OrderInfo { participant_id: response.participant_id, order_id: response.participant_order_id };
+
+            match response.response_type {
+                ParticipantResponseType::Accepted => { self.ledgers.insert(order_info, OrderLedger { accepted: response.leaves_qty, ..OrderLedger::default() }); },
+                ParticipantResponseType::Filled => self.ledgers.get_mut(&order_info).unwrap().filled += response.exec_qty,
+                ParticipantResponseType::Cancelled => self.ledgers.get_mut(&order_info).unwrap().cancelled += response.leaves_qty,
+                _ => {},
+            }
+
+            response_types.push(response.response_type);
+        }
+
+        while let Ok(Sequenced { payload: update, .. }) = self.venue.market_updates.try_recv() {
+            if update.update_type == MarketUpdateType::Trade {
+                self.traded_qty += update.qty as u64;
+            }
+        }
+
+        response_types
+    }
+
+    fn resting_orders(&self, side: Side) -> Levels {
+        self.book.resting_orders(side)
+    }
+
+    fn check(&self) -> Result<(), TestCaseError> {
+        self.book.check_invariants().map_err(TestCaseError::fail)?;
+
+        let resting: HashMap<OrderInfo, Quantity> = [Side::Buy, Side::Sell].into_iter()
+            .flat_map(|side| self.resting_orders(side))
+            .flat_map(|(_, orders)| orders)
+            .collect();
+
+        for (order_info, ledger) in &self.ledgers {
+            let resting_qty = resting.get(order_info).copied().unwrap_or(0);
+            prop_assert_eq!(ledger.accepted, ledger.filled + ledger.cancelled + resting_qty, "quantity of {} isn't conserved", order_info);
+        }
+
+        Ok(())
+    }
+}
+
+proptest! {
+    #[test]
+    fn book_matches_reference_model(ops in prop::collection::vec(op(), 1..200)) {
+        let mut harness = Harness::new();
+        let mut reference = ReferenceBook::default();
+
+        for op in ops {
+            match op.clone() {
+                Op::Add { order_info, side, price, qty } => {
+                    let is_accepted = harness.add(order_info.clone(), side, price, qty, INVALID_QUANTITY, NO_ORDER_FLAGS);
+                    prop_assert_eq!(is_accepted, reference.add(order_info, side, price, qty), "{:?}", op);
+                },
+                Op::Cancel { order_info } => {
+                    prop_assert_eq!(harness.cancel(order_info.clone()), reference.cancel(&order_info), "{:?}", op);
+                },
+                Op::Modify { order_info, price, qty } => {
+                    let side = reference.sides.get(&order_info).map(|(side, _)| *side);
+                    let is_cancelled = harness.cancel(order_info.clone());
+                    prop_assert_eq!(is_cancelled, reference.cancel(&order_info), "{:?}", op);
+
+                    if let Some(side) = side {
+                        let is_accepted = harness.add(order_info.clone(), side, price, qty, INVALID_QUANTITY, NO_ORDER_FLAGS);
+                        prop_assert_eq!(is_accepted, reference.add(order_info, side, price, qty), "{:?}", op);
+                    }
+                },
+            }
+
+            harness.check()?;
+            prop_assert_eq!(harness.resting_orders(Side::Buy), reference.levels(Side::Buy), "bids differ after {:?}", op);
+            prop_assert_eq!(harness.resting_orders(Side::Sell), reference.levels(Side::Sell), "asks differ after {:?}", op);
+            prop_assert_eq!(harness.traded_qty, reference.traded_qty, "traded quantity differs after {:?}", op);
+        }
+    }
+
+    // hidden orders and icebergs move around within their level, there is no reference model for them but the invariants must hold
+    #[test]
+    fn invariants_hold_with_hidden_and_iceberg_orders(ops in prop::collection::vec((op(), 0..3u8, 1..8 as Quantity), 1..200)) {
+        let mut harness = Harness::new();
+
+        for (op, kind, peak_qty) in ops {
+            let (display_qty, flags) = match kind {
+                0 => (INVALID_QUANTITY, NO_ORDER_FLAGS),
+                1 => (INVALID_QUANTITY, ORDER_FLAG_HIDDEN),
+                _ => (peak_qty, NO_ORDER_FLAGS),
+            };
+
+            match op {
+                Op::Add { order_info, side, price, qty } => { harness.add(order_info, side, price, qty, display_qty, flags); },
+                Op::Cancel { order_info } => { harness.cancel(order_info); },
+                Op::Modify { order_info, price, qty } => {
+                    if harness.cancel(order_info.clone()) {
+                        harness.add(order_info, Side::Buy, price, qty, display_qty, flags);
+                    }
+                },
+            }
+
+            harness.check()?;
+        }
+    }
+}
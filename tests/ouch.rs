@@ -0,0 +1,322 @@
+mod common;
+
+use std::{io::{BufReader, Write}, net::{SocketAddr, TcpStream}, sync::mpsc::{self, Receiver, Sender}, thread, time::Duration};
+
+use rexchange::{common::{Price, Quantity, Side}, logging::logger::Logger, market_data::itch, matching_engine::orderbook::OrderBookConfig, order_server::{ouch::{self, BeInt, CancelOrder, EnterOrder, Inbound, OuchMessage, Outbound, ReplaceOrder}, ouch_gateway::{self, OuchAccount, OuchConfig}, ouch_session::OuchSession, participants_request::ParticipantRequest, response_router::ResponseRouter, shard_router::ShardRouter, soup_bin::{self, LoginAccepted, LoginRequest}}};
+
+use common::{EngineThread, Venue};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+fn enter_order(token: &str, side: u8, shares: Quantity, stock: &str, price: Price) -> EnterOrder {
+    EnterOrder {
+        message_type: EnterOrder::MESSAGE_TYPE,
+        order_token: ouch::token(token),
+        side,
+        shares: BeInt::new(shares as u64),
+        stock: itch::stock(stock),
+        price: BeInt::new(price),
+        time_in_force: BeInt::new(ouch::TIME_IN_FORCE_SYSTEM_HOURS),
+        firm: *b"FIRM",
+        display: ouch::DISPLAY_VISIBLE,
+        ..EnterOrder::default()
+    }
+}
+
+fn cancel_order(token: &str, shares: Quantity) -> CancelOrder {
+    CancelOrder { message_type: CancelOrder::MESSAGE_TYPE, order_token: ouch::token(token), shares: BeInt::new(shares as u64) }
+}
+
+// participant 0 trades through an OUCH session, participant 1 sends its requests straight to the engine
+struct Market {
+    venue: Venue,
+    session: OuchSession<Sender<ParticipantRequest>>,
+    requests: Receiver<ParticipantRequest>,
+    sequence_number: u64,
+}
+
+impl Market {
+    fn new() -> Self {
+        let (session_tx, session_rx) = mpsc::channel();
+
+        Self {
+            venue: Venue::new(),
+            session: OuchSession::new(session_tx, 0, 64, &["ACME"]),
+            requests: session_rx,
+            sequence_number: 1,
+        }
+    }
+
+    fn send(&mut self, message: &impl OuchMessage) {
+        self.session.on_message(ouch::decode_inbound(message.as_bytes()).unwrap(), 0).unwrap();
+        self.settle();
+    }
+
+    fn counterparty(&mut self, order_id: u64, side: Side, price: Price, qty: Quantity) {
+        self.venue.limit(1, order_id, side, price, qty);
+        self.settle();
+    }
+
+    // until the session has nothing more to send and the engine nothing more to answer
+    fn settle(&mut self) {
+        loop {
+            let mut idle = true;
+
+            while let Ok(request) = self.requests.try_recv() {
+                self.venue.request(&request);
+                idle = false;
+            }
+
+            for response in self.venue.responses() {
+                self.session.on_response(&response, 0).unwrap();
+                idle = false;
+            }
+
+            if idle {
+                return;
+            }
+        }
+    }
+
+    // the outbound messages published since the last call
+    fn outbound(&mut self) -> Vec<Vec<u8>> {
+        let mut messages = Vec::new();
+
+        while let Some(message) = self.session.message(self.sequence_number) {
+            messages.push(message.to_vec());
+            self.sequence_number += 1;
+        }
+
+        messages
+    }
+}
+
+fn types(messages: &[Vec<u8>]) -> String {
+    messages.iter().map(|message| message[0] as char).collect()
+}
+
+#[test]
+fn layouts_and_framing_are_big_endian() {
+    let message = enter_order("ORDER1", ouch::SIDE_BUY, 300, "ACME", 10_001);
+    let bytes = message.as_bytes();
+    assert_eq!(bytes.len(), 49);
+    assert_eq!(&bytes[..16], b"OORDER1        B");
+    assert_eq!(&bytes[16..20], &300u32.to_be_bytes());
+    assert_eq!(&bytes[20..28], b"ACME    ");
+    assert_eq!(&bytes[28..32], &10_001u32.to_be_bytes());
+
+    assert_eq!(ouch::decode_inbound(bytes), Some(Inbound::EnterOrder(&message)));
+    assert_eq!(ouch::decode_inbound(&bytes[..48]), None);
+    assert_eq!(ouch::decode_inbound(&[b'Q'; 49]), None);
+
+    let login = LoginRequest { username: "DELTA".to_string(), password: "secret".to_string(), requested_session: String::new(), requested_sequence_number: 42 };
+    let mut payload = Vec::new();
+    login.encode(&mut payload);
+    assert_eq!(payload.len(), 46);
+    assert_eq!(&payload[..16], b"DELTA secret    ");
+    assert_eq!(LoginRequest::decode(&payload), Some(login));
+
+    let mut packet = Vec::new();
+    soup_bin::write_packet(&mut packet, soup_bin::PACKET_UNSEQUENCED_DATA, bytes).unwrap();
+    assert_eq!(&packet[..3], &[0, 50, b'U']);
+
+    let mut read = Vec::new();
+    assert_eq!(soup_bin::read_packet(&mut &packet[..], &mut read).unwrap(), b'U');
+    assert_eq!(read, bytes);
+}
+
+#[test]
+fn session_maps_messages_onto_the_engine() {
+    let mut market = Market::new();
+
+    market.send(&enter_order("A1", ouch::SIDE_BUY, 10, "ACME", 10_000));
+    market.send(&enter_order("A2", ouch::SIDE_BUY, 10, "NOPE", 10_000));
+    let messages = market.outbound();
+    assert_eq!(types(&messages), "AJ");
+
+    match (ouch::decode_outbound(&messages[0]), ouch::decode_outbound(&messages[1])) {
+        (Some(Outbound::Accepted(accepted)), Some(Outbound::Rejected(rejected))) => {
+            assert_eq!((accepted.order_token, accepted.side, accepted.shares.get(), accepted.price.get()), (ouch::token("A1"), b'B', 10, 10_000));
+            assert_eq!((&accepted.firm, accepted.time_in_force.get(), accepted.order_state), (b"FIRM", 99_999, ouch::ORDER_STATE_LIVE));
+            assert_eq!((rejected.order_token, rejected.reason), (ouch::token("A2"), ouch::REJECT_REASON_INVALID_STOCK));
+        },
+        messages => panic!("{:?}", messages),
+    }
+
+    // a cancel/replace underneath, reported as one Replaced once the replacement is accepted
+    market.send(&ReplaceOrder {
+        message_type: ReplaceOrder::MESSAGE_TYPE,
+        existing_order_token: ouch::token("A1"),
+        replacement_order_token: ouch::token("B1"),
+        shares: BeInt::new(20),
+        price: BeInt::new(10_001),
+        ..ReplaceOrder::default()
+    });
+
+    // a sell crossing the replacement, then a better bid the session's sell crosses
+    market.counterparty(1, Side::Sell, 10_001, 5);
+    market.counterparty(2, Side::Buy, 10_002, 7);
+    market.send(&enter_order("S1", ouch::SIDE_SELL_SHORT, 4, "ACME", 10_002));
+
+    let messages = market.outbound();
+    assert_eq!(types(&messages), "UEAE");
+
+    match messages.iter().map(|message| ouch::decode_outbound(message).unwrap()).collect::<Vec<_>>()[..] {
+        [Outbound::Replaced(replaced), Outbound::Executed(passive), Outbound::Accepted(sell), Outbound::Executed(aggressive)] => {
+            assert_eq!((replaced.replacement_order_token, replaced.previous_order_token), (ouch::token("B1"), ouch::token("A1")));
+            assert_eq!((replaced.shares.get(), replaced.price.get(), &replaced.firm), (20, 10_001, b"FIRM"));
+            assert_eq!((passive.order_token, passive.executed_shares.get(), passive.execution_price.get(), passive.liquidity_flag), (ouch::token("B1"), 5, 10_001, ouch::LIQUIDITY_ADDED));
+            assert_eq!(sell.side, ouch::SIDE_SELL);
+            assert_eq!((aggressive.order_token, aggressive.executed_shares.get(), aggressive.execution_price.get(), aggressive.liquidity_flag), (ouch::token("S1"), 4, 10_002, ouch::LIQUIDITY_REMOVED));
+        },
+        ref messages => panic!("{:?}", messages),
+    }
+
+    // the engine can't reduce an order, only cancel it
+    market.send(&cancel_order("B1", 5));
+    market.send(&cancel_order("B1", 0));
+    market.send(&cancel_order("B1", 0));
+    let messages = market.outbound();
+    assert_eq!(types(&messages), "IC");
+
+    match ouch::decode_outbound(&messages[1]) {
+        Some(Outbound::Canceled(canceled)) => assert_eq!((canceled.order_token, canceled.decrement_shares.get(), canceled.reason), (ouch::token("B1"), 15, ouch::CANCEL_REASON_USER)),
+        message => panic!("{:?}", message),
+    }
+
+    // the token of a finished order can be used again
+    market.send(&enter_order("A1", ouch::SIDE_BUY, 1, "ACME", 9_000));
+    assert_eq!(types(&market.outbound()), "A");
+    assert_eq!(market.session.next_sequence_number(), 10);
+}
+
+#[test]
+fn only_market_orders_can_be_immediate_or_cancel() {
+    let mut market = Market::new();
+    market.counterparty(1, Side::Sell, 10_000, 5);
+
+    let with_time_in_force = |token: &str, price: Price, time_in_force: u64| EnterOrder { time_in_force: BeInt::new(time_in_force), ..enter_order(token, ouch::SIDE_BUY, 10, "ACME", price) };
+
+    // a limit order would rest, a timed one would need expiring
+    market.send(&with_time_in_force("L1", 10_000, ouch::TIME_IN_FORCE_IOC));
+    market.send(&with_time_in_force("L2", 10_000, 60));
+    let messages = market.outbound();
+    assert_eq!(types(&messages), "JJ");
+    assert!(messages.iter().all(|message| matches!(ouch::decode_outbound(message), Some(Outbound::Rejected(rejected)) if rejected.reason == ouch::REJECT_REASON_OTHER)));
+
+    // the part of a market order left unfilled is cancelled
+    market.send(&with_time_in_force("M1", ouch::PRICE_MARKET as Price, ouch::TIME_IN_FORCE_IOC));
+    let messages = market.outbound();
+    assert_eq!(types(&messages), "AEC");
+
+    match ouch::decode_outbound(&messages[2]) {
+        Some(Outbound::Canceled(canceled)) => assert_eq!((canceled.order_token, canceled.decrement_shares.get()), (ouch::token("M1"), 5)),
+        message => panic!("{:?}", message),
+    }
+
+    market.send(&with_time_in_force("L3", 9_000, ouch::TIME_IN_FORCE_MARKET_HOURS));
+    assert_eq!(types(&market.outbound()), "A");
+}
+
+struct Client {
+    stream: TcpStream,
+    reader: BufReader<TcpStream>,
+    payload: Vec<u8>,
+}
+
+impl Client {
+    // the login answer's packet type and payload
+    fn login(address: SocketAddr, password: &str, requested_sequence_number: u64) -> (Self, u8, Vec<u8>) {
+        let stream = TcpStream::connect(address).unwrap();
+        stream.set_read_timeout(Some(TIMEOUT)).unwrap();
+        let mut client = Client { reader: BufReader::new(stream.try_clone().unwrap()), stream, payload: Vec::new() };
+
+        let mut payload = Vec::new();
+        LoginRequest { username: "DELTA".to_string(), password: password.to_string(), requested_session: String::new(), requested_sequence_number }.encode(&mut payload);
+        client.send(soup_bin::PACKET_LOGIN_REQUEST, &payload);
+
+        let packet_type = client.read();
+        let payload = client.payload.clone();
+        (client, packet_type, payload)
+    }
+
+    fn send(&mut self, packet_type: u8, payload: &[u8]) {
+        soup_bin::write_packet(&mut self.stream, packet_type, payload).unwrap();
+        self.stream.flush().unwrap();
+    }
+
+    fn read(&mut self) -> u8 {
+        soup_bin::read_packet(&mut self.reader, &mut self.payload).unwrap()
+    }
+
+    // the next sequenced message, skipping heartbeats
+    fn next_message(&mut self) -> Vec<u8> {
+        loop {
+            match self.read() {
+                soup_bin::PACKET_SERVER_HEARTBEAT => continue,
+                soup_bin::PACKET_SEQUENCED_DATA => return self.payload.clone(),
+                packet_type => panic!("unexpected packet {}", packet_type as char),
+            }
+        }
+    }
+}
+
+#[test]
+fn logs_in_heartbeats_and_replays_on_reconnect() {
+    let EngineThread { requests: requests_tx, responses: responses_rx, market_updates: _market_updates, .. } = EngineThread::start(common::engine_config(&[OrderBookConfig::default()], &[0]));
+
+    let router = ShardRouter::new(vec![0], vec![requests_tx]);
+    let mut response_router = ResponseRouter::new();
+    let config = OuchConfig { address: "127.0.0.1:0".to_string(), heartbeat_interval_ms: 20, idle_timeout_ms: 2_000, ..OuchConfig::default() };
+    let accounts = vec![OuchAccount { participant_id: 0, username: "DELTA".to_string(), password: "secret".to_string() }];
+
+    let address = ouch_gateway::spawn(&config, accounts, &["ACME"], 64, router, &mut response_router, Logger::disabled()).unwrap();
+    thread::spawn(move || response_router.run(responses_rx));
+
+    let (_, packet_type, payload) = Client::login(address, "wrong", 0);
+    assert_eq!((packet_type, payload), (soup_bin::PACKET_LOGIN_REJECTED, vec![soup_bin::REJECT_NOT_AUTHORIZED]));
+
+    let (mut client, packet_type, payload) = Client::login(address, "secret", 1);
+    assert_eq!(packet_type, soup_bin::PACKET_LOGIN_ACCEPTED);
+    let session = LoginAccepted::decode(&payload).unwrap();
+    assert_eq!(session.sequence_number, 1);
+
+    // one connection per session
+    let (_, packet_type, _) = Client::login(address, "secret", 1);
+    assert_eq!(packet_type, soup_bin::PACKET_LOGIN_REJECTED);
+
+    client.send(soup_bin::PACKET_UNSEQUENCED_DATA, enter_order("T1", ouch::SIDE_BUY, 10, "ACME", 10_000).as_bytes());
+    client.send(soup_bin::PACKET_UNSEQUENCED_DATA, cancel_order("T1", 0).as_bytes());
+    let accepted = client.next_message();
+    let canceled = client.next_message();
+    assert_eq!((accepted[0], canceled[0]), (ouch::MESSAGE_ACCEPTED, ouch::MESSAGE_CANCELED));
+
+    // idle, the server heartbeats
+    assert_eq!(client.read(), soup_bin::PACKET_SERVER_HEARTBEAT);
+
+    client.send(soup_bin::PACKET_LOGOUT_REQUEST, &[]);
+    drop(client);
+
+    // the login may race the logout being seen, the session only takes one connection
+    let login = || {
+        for _ in 0..100 {
+            let (client, packet_type, payload) = Client::login(address, "secret", 1);
+            if packet_type == soup_bin::PACKET_LOGIN_ACCEPTED {
+                return (client, LoginAccepted::decode(&payload).unwrap());
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        panic!("cannot log in again");
+    };
+
+    let (mut client, accepted_login) = login();
+    assert_eq!(accepted_login, LoginAccepted { session: session.session.clone(), sequence_number: 1 });
+    assert_eq!((client.next_message(), client.next_message()), (accepted, canceled));
+
+    client.send(soup_bin::PACKET_CLIENT_HEARTBEAT, &[]);
+    client.send(soup_bin::PACKET_UNSEQUENCED_DATA, enter_order("T2", ouch::SIDE_SELL, 5, "ACME", 10_010).as_bytes());
+    match ouch::decode_outbound(&client.next_message()) {
+        Some(Outbound::Accepted(accepted)) => assert_eq!((accepted.order_token, accepted.shares.get()), (ouch::token("T2"), 5)),
+        message => panic!("{:?}", message),
+    }
+}
@@ -0,0 +1,134 @@
+use std::{env, fs, process, sync::{mpsc, Arc, Mutex}, thread};
+
+use rexchange::{admin::{admin::{Admin, AdminCommand, AdminReply}, audit::AuditLog}, common::{ParticipantId, Price, Quantity, Sequenced, Side, TradeId}, fees::billing::Billing, market_data::market_update::{MarketUpdate, MarketUpdateType}, order_server::participants_response::{ParticipantResponse, ParticipantResponseType}, positions::{self, MarkPrice, Position, PositionKeeper, PositionsConfig}, trades::{trade::{Trade, TradeAmendment}, trade_store::TradeStore}};
+
+fn fill(participant_id: ParticipantId, side: Side, price: Price, qty: Quantity, trade_id: TradeId) -> ParticipantResponse {
+    ParticipantResponse { response_type: ParticipantResponseType::Filled, participant_id, symbol_id: 0, side, price, exec_qty: qty, leaves_qty: 0, trade_id, ..ParticipantResponse::default() }
+}
+
+fn update(update_type: MarketUpdateType, order_id: u64, side: Side, price: Price) -> MarketUpdate {
+    MarketUpdate { update_type, order_id, symbol_id: 0, side, price, qty: 1, ..MarketUpdate::default() }
+}
+
+fn summary(position: Position) -> (i64, f64, f64, f64, Price) {
+    (position.position, position.open_price, position.realized, position.unrealized, position.mark_price)
+}
+
+#[test]
+fn positions_are_marked_to_the_last_trade_or_the_mid() {
+    let mut keeper = PositionKeeper::new(&PositionsConfig::default());
+
+    keeper.on_response(&fill(0, Side::Buy, 100, 10, 1));
+    keeper.on_response(&fill(0, Side::Buy, 110, 10, 2));
+    keeper.on_response(&fill(0, Side::Sell, 120, 5, 3));
+    keeper.on_response(&fill(1, Side::Sell, 100, 10, 1));
+
+    // nothing to mark with yet
+    assert_eq!(summary(keeper.position(0, 0).unwrap()), (15, 105.0, 75.0, 0.0, u64::MAX));
+
+    keeper.on_market_update(&update(MarketUpdateType::Trade, u64::MAX, Side::Buy, 120));
+    assert_eq!(summary(keeper.position(0, 0).unwrap()), (15, 105.0, 75.0, 225.0, 120));
+    assert_eq!(summary(keeper.position(1, 0).unwrap()), (-10, 100.0, 0.0, -200.0, 120));
+    assert_eq!(keeper.all().iter().map(|position| position.participant_id).collect::<Vec<_>>(), [0, 1]);
+    assert!(keeper.position(2, 0).is_none());
+
+    let mut keeper = PositionKeeper::new(&PositionsConfig { mark_price: MarkPrice::Mid, ..PositionsConfig::default() });
+    keeper.on_response(&fill(0, Side::Buy, 100, 10, 1));
+    keeper.on_market_update(&update(MarketUpdateType::Trade, u64::MAX, Side::Buy, 100));
+    keeper.on_market_update(&update(MarketUpdateType::Add, 1, Side::Buy, 104));
+    keeper.on_market_update(&update(MarketUpdateType::Add, 2, Side::Sell, 108));
+    assert_eq!(summary(keeper.position(0, 0).unwrap()), (10, 100.0, 0.0, 60.0, 106));
+}
+
+#[test]
+fn busts_and_corrections_move_the_position_as_if_the_trade_had_always_been_so() {
+    let mut keeper = PositionKeeper::new(&PositionsConfig::default());
+    keeper.on_response(&fill(0, Side::Buy, 100, 10, 7));
+    keeper.on_response(&fill(1, Side::Sell, 100, 10, 7));
+    keeper.on_response(&fill(0, Side::Buy, 90, 10, 8));
+
+    let trade = Trade { trade_id: 7, symbol_id: 0, aggressor_side: Side::Sell, price: 100, qty: 10, buy_participant_id: 0, buy_order_id: 1, sell_participant_id: 1, sell_order_id: 1, timestamp: 0 };
+    let amendment = |original: &Trade, price, qty| TradeAmendment { original: original.clone(), amended: Trade { price, qty, ..original.clone() }, buy_fee: 0, sell_fee: 0 };
+
+    // the responses don't carry the price the trade had, the admin console's amendment does
+    let correction = ParticipantResponse { response_type: ParticipantResponseType::TradeCorrected, price: 120, exec_qty: 5, leaves_qty: 10, ..fill(0, Side::Buy, 0, 0, 7) };
+    keeper.on_response(&correction);
+    assert_eq!(summary(keeper.position(0, 0).unwrap()), (20, 95.0, 0.0, 0.0, u64::MAX));
+
+    // trade 7 was 5 at 120, for both sides
+    keeper.amend(&amendment(&trade, 120, 5));
+    assert_eq!(summary(keeper.position(0, 0).unwrap()), (15, 100.0, 0.0, 0.0, u64::MAX));
+    assert_eq!(summary(keeper.position(1, 0).unwrap()), (-5, 120.0, 0.0, 0.0, u64::MAX));
+
+    keeper.on_response(&fill(0, Side::Sell, 104, 15, 9));
+    assert_eq!(summary(keeper.position(0, 0).unwrap()), (0, 0.0, 60.0, 0.0, u64::MAX));
+
+    // without trade 7 the sell closed 10 and opened a short
+    keeper.amend(&amendment(&Trade { price: 120, qty: 5, ..trade }, 120, 0));
+    assert_eq!(summary(keeper.position(0, 0).unwrap()), (-5, 104.0, 140.0, 0.0, u64::MAX));
+    assert_eq!(summary(keeper.position(1, 0).unwrap()), (0, 0.0, 0.0, 0.0, u64::MAX));
+}
+
+#[test]
+fn a_position_reopened_after_going_flat_starts_at_its_new_price() {
+    let mut keeper = PositionKeeper::new(&PositionsConfig::default());
+    keeper.on_response(&fill(0, Side::Buy, 100, 10, 1));
+    keeper.on_response(&fill(0, Side::Sell, 110, 10, 2));
+    assert_eq!(summary(keeper.position(0, 0).unwrap()), (0, 0.0, 100.0, 0.0, u64::MAX));
+
+    keeper.on_response(&fill(0, Side::Buy, 120, 10, 3));
+    assert_eq!(summary(keeper.position(0, 0).unwrap()), (10, 120.0, 100.0, 0.0, u64::MAX));
+
+    // busting the sell never took it flat, both buys are open
+    let sell = Trade { trade_id: 2, symbol_id: 0, aggressor_side: Side::Sell, price: 110, qty: 10, buy_participant_id: 1, buy_order_id: 1, sell_participant_id: 0, sell_order_id: 2, timestamp: 0 };
+    keeper.amend(&TradeAmendment { original: sell.clone(), amended: Trade { qty: 0, ..sell }, buy_fee: 0, sell_fee: 0 });
+    assert_eq!(summary(keeper.position(0, 0).unwrap()), (20, 110.0, 0.0, 0.0, u64::MAX));
+}
+
+#[test]
+fn a_fill_through_zero_opens_the_rest_at_its_price() {
+    let mut keeper = PositionKeeper::new(&PositionsConfig::default());
+    keeper.on_response(&fill(0, Side::Buy, 100, 10, 1));
+    keeper.on_response(&fill(0, Side::Sell, 110, 15, 2));
+    assert_eq!(summary(keeper.position(0, 0).unwrap()), (-5, 110.0, 100.0, 0.0, u64::MAX));
+
+    // and back through zero, the short closes at its own price
+    keeper.on_response(&fill(0, Side::Buy, 104, 10, 3));
+    assert_eq!(summary(keeper.position(0, 0).unwrap()), (5, 104.0, 130.0, 0.0, u64::MAX));
+
+    keeper.on_response(&fill(0, Side::Sell, 100, 5, 4));
+    assert_eq!(summary(keeper.position(0, 0).unwrap()), (0, 0.0, 110.0, 0.0, u64::MAX));
+}
+
+#[test]
+fn the_service_follows_both_streams_and_answers_the_console() {
+    let (responses_tx, responses_rx) = mpsc::channel();
+    let (market_updates_tx, market_updates_rx) = mpsc::channel();
+    let (reports_tx, reports_rx) = mpsc::channel();
+    let keeper = Arc::new(Mutex::new(PositionKeeper::new(&PositionsConfig::default())));
+
+    let service_keeper = keeper.clone();
+    let service = thread::spawn(move || positions::run(service_keeper, responses_rx, market_updates_rx, reports_tx, &PositionsConfig { report_interval: 1, ..PositionsConfig::default() }));
+
+    responses_tx.send(Sequenced { shard_id: 0, seq_num: 1, payload: fill(3, Side::Sell, 100, 4, 1) }).unwrap();
+    market_updates_tx.send(Sequenced { shard_id: 0, seq_num: 1, payload: update(MarketUpdateType::Trade, u64::MAX, Side::Sell, 98) }).unwrap();
+    drop(market_updates_tx);
+    service.join().unwrap();
+
+    assert_eq!(summary(keeper.lock().unwrap().position(3, 0).unwrap()), (-4, 100.0, 0.0, 8.0, 98));
+
+    // reported each interval, the last report has it marked
+    let last_report = reports_rx.iter().last().unwrap();
+    assert_eq!((last_report.participant_id, summary(last_report)), (3, (-4, 100.0, 0.0, 8.0, 98)));
+
+    let directory = env::temp_dir().join(format!("rexchange-positions-{}", process::id()));
+    let _ = fs::remove_dir_all(&directory);
+    let trade_store = Arc::new(Mutex::new(TradeStore::open(&directory.join("trades.dat")).unwrap()));
+    let (amendments_tx, _amendments_rx) = mpsc::sync_channel(1);
+    let mut admin = Admin::new(trade_store, Arc::new(Mutex::new(Billing::new())), keeper, vec![0], vec![amendments_tx], AuditLog::open(&directory.join("audit.csv")).unwrap());
+
+    let reply = admin.execute("ops", &AdminCommand::parse("positions 3").unwrap(), 0).unwrap();
+    assert!(matches!(&reply, AdminReply::Positions(positions) if positions.len() == 1));
+    assert!(reply.to_string().starts_with("Position [ptid: 3, symb: 0, position: -4, open_price: 100.00"));
+    assert_eq!(admin.execute("ops", &AdminCommand::parse("positions 9").unwrap(), 0).unwrap().to_string(), "no positions");
+}
@@ -0,0 +1,98 @@
+mod common;
+
+use rexchange::{common::{OrderFlags, OrderId, ParticipantId, Price, Quantity, Side, ORDER_FLAG_HIDDEN, ORDER_FLAG_POST_ONLY, ORDER_FLAG_POST_ONLY_SLIDE}, market_data::market_update::MarketUpdateType, order_server::{participants_request::ParticipantRequest, participants_response::ParticipantResponseType}};
+
+use common::Venue;
+
+fn flagged(participant_id: ParticipantId, order_id: OrderId, side: Side, price: Price, qty: Quantity, flags: OrderFlags) -> ParticipantRequest {
+    ParticipantRequest { flags, ..common::limit(participant_id, order_id, side, price, qty) }
+}
+
+// (response type, order id, price) of participant 0's responses
+fn responses(venue: &Venue) -> Vec<(ParticipantResponseType, OrderId, Price)> {
+    venue.responses().iter().filter(|response| response.participant_id == 0).map(|response| (response.response_type, response.participant_order_id, response.price)).collect()
+}
+
+// (update type, side, price, qty) of what is published
+fn market_data(venue: &Venue) -> Vec<(MarketUpdateType, Side, Price, Quantity)> {
+    venue.market_updates().iter().map(|update| (update.update_type, update.side, update.price, update.qty)).collect()
+}
+
+#[test]
+fn a_crossing_post_only_order_is_rejected() {
+    let mut venue = Venue::new();
+    venue.limit(1, 1, Side::Sell, 100, 5);
+    venue.responses();
+    venue.market_updates();
+
+    venue.request(&flagged(0, 1, Side::Buy, 100, 5, ORDER_FLAG_POST_ONLY));
+    venue.request(&flagged(0, 2, Side::Buy, 101, 5, ORDER_FLAG_POST_ONLY));
+    assert_eq!(responses(&venue), [(ParticipantResponseType::Rejected, 1, 100), (ParticipantResponseType::Rejected, 2, 101)]);
+    assert!(market_data(&venue).is_empty());
+
+    // one that doesn't cross rests as usual
+    venue.request(&flagged(0, 3, Side::Buy, 99, 5, ORDER_FLAG_POST_ONLY));
+    assert_eq!(responses(&venue), [(ParticipantResponseType::Accepted, 3, 99)]);
+    assert_eq!(market_data(&venue), [(MarketUpdateType::Add, Side::Buy, 99, 5)]);
+}
+
+#[test]
+fn a_crossing_slide_order_rests_one_tick_inside_the_opposite_touch() {
+    let mut venue = Venue::new();
+    venue.limit(1, 1, Side::Sell, 100, 5);
+    venue.limit(1, 2, Side::Buy, 90, 5);
+    venue.responses();
+    venue.market_updates();
+
+    // the sell slides against the bid the buy slid to
+    venue.request(&flagged(0, 1, Side::Buy, 105, 2, ORDER_FLAG_POST_ONLY_SLIDE));
+    venue.request(&flagged(0, 2, Side::Sell, 80, 3, ORDER_FLAG_POST_ONLY_SLIDE));
+    assert_eq!(responses(&venue), [(ParticipantResponseType::Accepted, 1, 99), (ParticipantResponseType::Accepted, 2, 100)]);
+    assert_eq!(market_data(&venue), [(MarketUpdateType::Add, Side::Buy, 99, 2), (MarketUpdateType::Add, Side::Sell, 100, 3)]);
+
+    // without a crossing price it keeps its own
+    venue.request(&flagged(0, 3, Side::Buy, 95, 1, ORDER_FLAG_POST_ONLY_SLIDE));
+    assert_eq!(responses(&venue), [(ParticipantResponseType::Accepted, 3, 95)]);
+    venue.engine.order_book(0).unwrap().check_invariants().unwrap();
+}
+
+#[test]
+fn hidden_quantity_alone_rejects_or_slides_a_post_only_order() {
+    let mut venue = Venue::new();
+    venue.request(&flagged(1, 1, Side::Sell, 100, 5, ORDER_FLAG_HIDDEN));
+    venue.limit(1, 2, Side::Sell, 102, 5);
+    venue.responses();
+    assert_eq!(market_data(&venue), [(MarketUpdateType::Add, Side::Sell, 102, 5)]);
+
+    // the published touch is 102, the responses show there is more at 100
+    venue.request(&flagged(0, 1, Side::Buy, 100, 5, ORDER_FLAG_POST_ONLY));
+    venue.request(&flagged(0, 2, Side::Buy, 101, 5, ORDER_FLAG_POST_ONLY_SLIDE));
+    assert_eq!(responses(&venue), [(ParticipantResponseType::Rejected, 1, 100), (ParticipantResponseType::Accepted, 2, 99)]);
+    assert_eq!(market_data(&venue), [(MarketUpdateType::Add, Side::Buy, 99, 5)]);
+    venue.engine.order_book(0).unwrap().check_invariants().unwrap();
+}
+
+#[test]
+fn hidden_orders_trade_but_are_never_published() {
+    let mut venue = Venue::new();
+
+    venue.request(&flagged(1, 1, Side::Sell, 100, 5, ORDER_FLAG_HIDDEN));
+    venue.limit(1, 2, Side::Sell, 100, 2);
+    assert!(venue.responses().iter().all(|response| response.response_type == ParticipantResponseType::Accepted));
+    assert_eq!(market_data(&venue), [(MarketUpdateType::Add, Side::Sell, 100, 2)]);
+
+    // the displayed order goes first even though it came later, the hidden fill shows only as a trade
+    venue.limit(0, 1, Side::Buy, 100, 4);
+    let fills: Vec<(OrderId, Quantity)> = venue.responses().iter().filter(|response| response.participant_id == 1).map(|response| (response.participant_order_id, response.exec_qty)).collect();
+    assert_eq!(fills, [(2, 2), (1, 2)]);
+    assert_eq!(market_data(&venue), [
+        (MarketUpdateType::Trade, Side::Buy, 100, 2),
+        (MarketUpdateType::Cancel, Side::Sell, 100, 0),
+        (MarketUpdateType::Trade, Side::Buy, 100, 2),
+    ]);
+
+    // and so is its cancel
+    venue.cancel(1, 1);
+    assert_eq!(venue.responses()[0].response_type, ParticipantResponseType::Cancelled);
+    assert!(market_data(&venue).is_empty());
+}
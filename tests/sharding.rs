@@ -0,0 +1,108 @@
+mod common;
+
+use std::{collections::BTreeMap, sync::mpsc, thread};
+
+use rexchange::{common::{Sequenced, ShardId, Side, SymbolId}, config::Config, logging::logger::Logger, market_data::market_update::MarketUpdateType, matching_engine::matching_engine::MatchingEngine, order_server::{participants_request::ParticipantRequest, participants_response::ParticipantResponseType, shard_router::ShardRouter}};
+
+use common::limit;
+
+// four symbols on two shards, ACME placed on shard 1 rather than the shard its id would give
+const CONFIG: &str = "
+[engine]
+shards = 2
+
+[latency]
+enabled = false
+
+[[participants]]
+id = 0
+name = \"ALPHA\"
+
+[[participants]]
+id = 1
+name = \"BRAVO\"
+
+[[symbols]]
+id = 0
+name = \"ACME\"
+shard = 1
+
+[[symbols]]
+id = 1
+name = \"GLOBEX\"
+
+[[symbols]]
+id = 2
+name = \"INITECH\"
+
+[[symbols]]
+id = 3
+name = \"HOOLI\"
+";
+
+// shard id to the sequence numbers in the order they arrived
+fn seq_nums<T>(messages: &[Sequenced<T>]) -> BTreeMap<ShardId, Vec<u64>> {
+    let mut seq_nums = BTreeMap::new();
+    for message in messages {
+        seq_nums.entry(message.shard_id).or_insert_with(Vec::new).push(message.seq_num);
+    }
+    seq_nums
+}
+
+#[test]
+fn shards_trade_their_own_symbols_into_the_merged_streams() {
+    let config: Config = toml::from_str(CONFIG).unwrap();
+    config.validate().unwrap();
+    assert_eq!(config.symbol_shards(), [1, 1, 0, 1]);
+
+    // the engines share the two streams, as in the exchange
+    let (responses_tx, responses_rx) = mpsc::sync_channel(1024);
+    let (market_updates_tx, market_updates_rx) = mpsc::sync_channel(1024);
+    let mut shard_requests = Vec::new();
+    let mut engines = Vec::new();
+
+    for engine_config in config.engine_configs() {
+        let (requests_tx, requests_rx) = mpsc::sync_channel(1024);
+        let (responses_tx, market_updates_tx) = (responses_tx.clone(), market_updates_tx.clone());
+        engines.push(thread::spawn(move || MatchingEngine::new(requests_rx, responses_tx, market_updates_tx, &engine_config, Logger::disabled()).run()));
+        shard_requests.push(requests_tx);
+    }
+    drop((responses_tx, market_updates_tx));
+
+    // a trade in every symbol, then an order for a symbol no shard has
+    let router = ShardRouter::new(config.symbol_shards(), shard_requests);
+    assert_eq!((0..5).map(|symbol_id| router.shard_of(symbol_id)).collect::<Vec<_>>(), [1, 1, 0, 1, 0]);
+
+    for symbol_id in 0..4 {
+        let order_id = symbol_id as u64 * 2 + 1;
+        router.route(ParticipantRequest { symbol_id, ..limit(0, order_id, Side::Sell, 100, 10) }).unwrap();
+        router.route(ParticipantRequest { symbol_id, ..limit(1, order_id + 1, Side::Buy, 100, 10) }).unwrap();
+    }
+    router.route(ParticipantRequest { symbol_id: 4, ..limit(0, 9, Side::Buy, 100, 10) }).unwrap();
+
+    drop(router);
+    for engine in engines {
+        engine.join().unwrap();
+    }
+
+    let responses: Vec<_> = responses_rx.try_iter().collect();
+    let market_updates: Vec<_> = market_updates_rx.try_iter().collect();
+
+    // each message comes from the shard of its symbol, the unknown one is rejected by shard 0
+    assert!(responses.iter().all(|response| response.shard_id == config.symbol_shards().get(response.payload.symbol_id as usize).copied().unwrap_or(0)));
+    assert!(market_updates.iter().all(|update| update.shard_id == config.symbol_shards()[update.payload.symbol_id as usize]));
+
+    let fills = |symbol_id: SymbolId| responses.iter().filter(|response| response.payload.symbol_id == symbol_id && response.payload.response_type == ParticipantResponseType::Filled).count();
+    let trades = |symbol_id: SymbolId| market_updates.iter().filter(|update| update.payload.symbol_id == symbol_id && update.payload.update_type == MarketUpdateType::Trade).count();
+    assert_eq!((0..4).map(|symbol_id| (fills(symbol_id), trades(symbol_id))).collect::<Vec<_>>(), [(2, 1); 4]);
+    assert!(responses.iter().any(|response| response.payload.symbol_id == 4 && response.payload.response_type == ParticipantResponseType::Rejected));
+
+    // both shards number each stream from 1 without gaps, whatever the interleaving
+    for seq_nums in [seq_nums(&responses), seq_nums(&market_updates)] {
+        assert_eq!(seq_nums.keys().copied().collect::<Vec<_>>(), [0, 1]);
+
+        for shard_seq_nums in seq_nums.values() {
+            assert_eq!(*shard_seq_nums, (1..=shard_seq_nums.len() as u64).collect::<Vec<_>>());
+        }
+    }
+}
@@ -0,0 +1,124 @@
+mod common;
+
+use rexchange::{common::{OrderId, OrderType, ParticipantId, Price, Quantity, Side, INVALID_PRICE}, matching_engine::{circuit_breaker::{CircuitBreakerConfig, HaltOrderPolicy}, orderbook::OrderBookConfig}, order_server::{participants_request::ParticipantRequest, participants_response::{ParticipantResponse, ParticipantResponseType}}};
+
+use common::Venue;
+
+fn stop_limit(participant_id: ParticipantId, order_id: OrderId, side: Side, stop_price: Price, price: Price, qty: Quantity) -> ParticipantRequest {
+    ParticipantRequest { order_type: OrderType::StopLimit, stop_price, ..common::limit(participant_id, order_id, side, price, qty) }
+}
+
+fn stop(participant_id: ParticipantId, order_id: OrderId, side: Side, stop_price: Price, qty: Quantity) -> ParticipantRequest {
+    ParticipantRequest { order_type: OrderType::Stop, stop_price, ..common::limit(participant_id, order_id, side, INVALID_PRICE, qty) }
+}
+
+// (order id, price, qty) of participant 0's fills, in the order they came
+fn fills(responses: &[ParticipantResponse]) -> Vec<(OrderId, Price, Quantity)> {
+    responses.iter().filter(|response| response.participant_id == 0 && response.response_type == ParticipantResponseType::Filled)
+        .map(|response| (response.participant_order_id, response.price, response.exec_qty)).collect()
+}
+
+fn response_types(responses: &[ParticipantResponse]) -> Vec<ParticipantResponseType> {
+    responses.iter().map(|response| response.response_type).collect()
+}
+
+#[test]
+fn a_pending_stop_keeps_its_order_id() {
+    let mut venue = Venue::new();
+
+    venue.limit(1, 1, Side::Sell, 95, 10);
+    venue.request(&stop_limit(0, 5, Side::Buy, 95, 95, 3));
+    venue.limit(0, 5, Side::Buy, 90, 5);
+    assert_eq!(response_types(&venue.responses()), [ParticipantResponseType::Accepted, ParticipantResponseType::Accepted, ParticipantResponseType::Rejected]);
+
+    // the trade releases the stop, which takes 3 more at 95 instead of landing on a resting order of the same id
+    venue.limit(0, 6, Side::Buy, 95, 1);
+    assert_eq!(fills(&venue.responses()), [(6, 95, 1), (5, 95, 3)]);
+    venue.engine.order_book(0).unwrap().check_invariants().unwrap();
+
+    // released and filled, the id is free again
+    venue.limit(0, 5, Side::Buy, 90, 5);
+    venue.cancel(0, 5);
+    assert_eq!(response_types(&venue.responses()), [ParticipantResponseType::Accepted, ParticipantResponseType::Cancelled]);
+    venue.engine.order_book(0).unwrap().check_invariants().unwrap();
+}
+
+#[test]
+fn an_order_queued_by_a_halt_keeps_its_order_id() {
+    let circuit_breaker = CircuitBreakerConfig { previous_close: 100, static_band_bps: 500, halt_order_policy: HaltOrderPolicy::Queue, halt_duration: 1_000, ..CircuitBreakerConfig::default() };
+    let mut venue = Venue::with_config(&common::engine_config(&[OrderBookConfig { circuit_breaker, ..OrderBookConfig::default() }], &[0, 1]));
+    venue.engine.set_simulated_time(0);
+
+    // a trade at 110 is out of the 5% band, the symbol halts
+    venue.limit(1, 1, Side::Sell, 110, 1);
+    venue.limit(0, 1, Side::Buy, 110, 1);
+    venue.responses();
+
+    venue.limit(0, 2, Side::Buy, 100, 1);
+    venue.limit(0, 2, Side::Sell, 120, 1);
+    assert_eq!(response_types(&venue.responses()), [ParticipantResponseType::Rejected]);
+
+    venue.engine.check_timers(1_000);
+    assert_eq!(response_types(&venue.responses()), [ParticipantResponseType::Filled, ParticipantResponseType::Filled, ParticipantResponseType::Accepted]);
+    venue.engine.order_book(0).unwrap().check_invariants().unwrap();
+}
+
+#[test]
+fn triggered_stops_go_closest_stop_price_first_then_in_arrival_order() {
+    let mut venue = Venue::new();
+
+    venue.limit(1, 1, Side::Sell, 100, 10);
+    venue.request(&stop(0, 10, Side::Buy, 100, 1));
+    venue.request(&stop(0, 11, Side::Buy, 99, 1));
+    venue.request(&stop(0, 12, Side::Buy, 100, 1));
+    venue.request(&stop(0, 13, Side::Buy, 101, 1));
+    venue.request(&stop(0, 14, Side::Sell, 99, 1));
+    venue.responses();
+
+    // the buy stops at or below the trade go, the one above and the sell stop below wait
+    venue.limit(0, 20, Side::Buy, 100, 1);
+    assert_eq!(fills(&venue.responses()), [(20, 100, 1), (11, 100, 1), (10, 100, 1), (12, 100, 1)]);
+}
+
+#[test]
+fn a_released_stop_can_set_off_another() {
+    let mut venue = Venue::new();
+
+    venue.limit(1, 1, Side::Sell, 100, 2);
+    venue.limit(1, 2, Side::Sell, 105, 5);
+    venue.request(&stop(0, 10, Side::Buy, 100, 2));
+    venue.request(&stop(0, 11, Side::Buy, 104, 1));
+    venue.responses();
+
+    // the first stop sweeps to 105, which is past the second one
+    venue.limit(0, 20, Side::Buy, 100, 1);
+    assert_eq!(fills(&venue.responses()), [(20, 100, 1), (10, 100, 1), (10, 105, 1), (11, 105, 1)]);
+    venue.engine.order_book(0).unwrap().check_invariants().unwrap();
+}
+
+#[test]
+fn a_pending_stop_is_cancelled_or_modified_before_it_triggers() {
+    let mut venue = Venue::new();
+
+    venue.limit(1, 1, Side::Sell, 100, 1);
+    venue.limit(1, 2, Side::Sell, 102, 10);
+    venue.request(&stop(0, 10, Side::Buy, 100, 1));
+    venue.request(&stop_limit(0, 11, Side::Buy, 100, 102, 2));
+
+    // a modify is a cancel and a new stop, the id is free once the stop is cancelled
+    venue.cancel(0, 10);
+    venue.cancel(0, 11);
+    venue.request(&stop_limit(0, 11, Side::Buy, 101, 102, 3));
+    let responses = venue.responses();
+    assert_eq!(response_types(&responses[4..]), [ParticipantResponseType::Cancelled, ParticipantResponseType::Cancelled, ParticipantResponseType::Accepted]);
+    assert_eq!((responses[4].leaves_qty, responses[5].leaves_qty), (1, 2));
+
+    venue.cancel(0, 10);
+    assert_eq!(response_types(&venue.responses()), [ParticipantResponseType::CancelRejected]);
+
+    // 100 doesn't reach the modified stop, 102 does
+    venue.limit(0, 20, Side::Buy, 100, 1);
+    assert_eq!(fills(&venue.responses()), [(20, 100, 1)]);
+    venue.limit(0, 21, Side::Buy, 102, 1);
+    assert_eq!(fills(&venue.responses()), [(21, 102, 1), (11, 102, 3)]);
+}
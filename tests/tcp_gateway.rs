@@ -0,0 +1,141 @@
+mod common;
+
+use std::{io::{ErrorKind, Write}, net::{SocketAddr, TcpStream}, sync::mpsc::Receiver, thread, time::{Duration, Instant}};
+
+use rexchange::{client::{order_manager::{ClientError, OrderGateway}, tcp_gateway::TcpOrderGateway}, common::{ParticipantId, Side}, logging::logger::Logger, matching_engine::orderbook::OrderBookConfig, order_server::{participants_request::ParticipantRequest, participants_response::{ParticipantResponse, ParticipantResponseType}, response_router::ResponseRouter, shard_router::ShardRouter, tcp_gateway::{self, GatewayConfig, GatewayLogin, Login}}, wire::{self, LoginFrame, RequestFrame, ResponseFrame, WireFrame, MAX_FRAME_SIZE}};
+
+use common::EngineThread;
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+fn login(participant: ParticipantId, password: &str) -> GatewayLogin {
+    GatewayLogin { participant, password: password.to_string() }
+}
+
+// an engine with participants 0 and 1 behind a gateway where both can log in
+fn gateway() -> SocketAddr {
+    let EngineThread { requests, responses, market_updates, .. } = EngineThread::start(common::engine_config(&[OrderBookConfig::default()], &[0, 1]));
+    let mut response_router = ResponseRouter::new();
+
+    let config = GatewayConfig { address: "127.0.0.1:0".to_string(), logins: vec![login(0, "alpha"), login(1, "bravo")] };
+    let address = tcp_gateway::spawn(&config, ShardRouter::new(vec![0], vec![requests]), &mut response_router, false, Logger::disabled()).unwrap();
+
+    thread::spawn(move || response_router.run(responses));
+    thread::spawn(move || market_updates.iter().count());
+
+    address
+}
+
+// (participant, response type, order id)
+fn next(responses: &Receiver<ParticipantResponse>) -> (ParticipantId, ParticipantResponseType, u64) {
+    let response = responses.recv_timeout(TIMEOUT).unwrap();
+    (response.participant_id, response.response_type, response.participant_order_id)
+}
+
+// a connection speaking the frames itself
+struct Connection {
+    stream: TcpStream,
+}
+
+impl Connection {
+    fn open(address: SocketAddr) -> Self {
+        let stream = TcpStream::connect(address).unwrap();
+        stream.set_read_timeout(Some(TIMEOUT)).unwrap();
+        Self { stream }
+    }
+
+    // whether the login is accepted
+    fn log_in(&mut self, participant_id: ParticipantId, password: &str) -> bool {
+        self.stream.write_all(LoginFrame::encode(&Login { participant_id, password: password.to_string(), accepted: false }).as_bytes()).unwrap();
+        let mut buf = [0; MAX_FRAME_SIZE];
+        let length = wire::read_frame(&mut self.stream, &mut buf).unwrap();
+        let (reply, _) = wire::decode::<LoginFrame>(&buf[..length]).unwrap();
+        assert_eq!((reply.participant_id, reply.password.as_str()), (participant_id, ""));
+        reply.accepted
+    }
+
+    fn send(&mut self, request: &ParticipantRequest) {
+        self.stream.write_all(RequestFrame::encode(request).as_bytes()).unwrap();
+    }
+
+    fn response(&mut self) -> (ParticipantId, ParticipantResponseType, u64) {
+        let mut buf = [0; MAX_FRAME_SIZE];
+        let length = wire::read_frame(&mut self.stream, &mut buf).unwrap();
+        let (response, _) = wire::decode::<ResponseFrame>(&buf[..length]).unwrap();
+        (response.participant_id, response.response_type, response.participant_order_id)
+    }
+
+    fn is_closed(&mut self) -> bool {
+        let mut buf = [0; MAX_FRAME_SIZE];
+        wire::read_frame(&mut self.stream, &mut buf).is_err_and(|error| error.kind() == ErrorKind::UnexpectedEof)
+    }
+}
+
+#[test]
+fn a_connection_gets_the_responses_of_the_participant_it_logged_in_as() {
+    let address = gateway();
+    let (mut buyer, buyer_responses) = TcpOrderGateway::connect(address, &[login(0, "alpha")]).unwrap();
+    let (mut seller, seller_responses) = TcpOrderGateway::connect(address, &[login(1, "bravo")]).unwrap();
+
+    buyer.send(common::limit(0, 1, Side::Buy, 100, 5)).unwrap();
+    buyer.flush().unwrap();
+    assert_eq!(next(&buyer_responses), (0, ParticipantResponseType::Accepted, 1));
+
+    seller.send(common::limit(1, 7, Side::Sell, 100, 2)).unwrap();
+    seller.flush().unwrap();
+    assert_eq!(next(&seller_responses), (1, ParticipantResponseType::Accepted, 7));
+    assert_eq!(next(&seller_responses), (1, ParticipantResponseType::Filled, 7));
+    assert_eq!(next(&buyer_responses), (0, ParticipantResponseType::Filled, 1));
+    assert!(buyer_responses.try_recv().is_err());
+
+    // the client only sends on the connections it logged in
+    assert!(matches!(buyer.send(common::limit(1, 8, Side::Sell, 100, 1)), Err(ClientError::NotLoggedIn(1))));
+}
+
+#[test]
+fn a_request_for_another_participant_is_rejected() {
+    let address = gateway();
+    let (mut seller, seller_responses) = TcpOrderGateway::connect(address, &[login(1, "bravo")]).unwrap();
+    seller.send(common::limit(1, 7, Side::Sell, 100, 2)).unwrap();
+    seller.flush().unwrap();
+    assert_eq!(next(&seller_responses), (1, ParticipantResponseType::Accepted, 7));
+
+    let mut connection = Connection::open(address);
+    assert!(connection.log_in(0, "alpha"));
+
+    // answered on the connection that sent it, the engine never sees it
+    connection.send(&common::cancel(1, 7));
+    assert_eq!(connection.response(), (1, ParticipantResponseType::Rejected, 7));
+
+    connection.send(&common::limit(0, 1, Side::Buy, 99, 1));
+    assert_eq!(connection.response(), (0, ParticipantResponseType::Accepted, 1));
+    assert!(seller_responses.recv_timeout(Duration::from_millis(100)).is_err());
+}
+
+#[test]
+fn a_login_needs_the_password_and_a_participant_not_logged_in() {
+    let address = gateway();
+
+    for (participant_id, password) in [(0, "bravo"), (0, ""), (5, "alpha")] {
+        let mut connection = Connection::open(address);
+        assert!(!connection.log_in(participant_id, password));
+        assert!(connection.is_closed());
+    }
+
+    // anything but a login closes the connection unanswered
+    let mut connection = Connection::open(address);
+    connection.send(&common::limit(0, 1, Side::Buy, 99, 1));
+    assert!(connection.is_closed());
+
+    let mut first = Connection::open(address);
+    assert!(first.log_in(0, "alpha"));
+    assert_eq!(TcpOrderGateway::connect(address, &[login(0, "alpha")]).err().map(|error| error.kind()), Some(ErrorKind::PermissionDenied));
+
+    // free again once the first connection is gone
+    drop(first);
+    let start = Instant::now();
+    while !Connection::open(address).log_in(0, "alpha") {
+        assert!(start.elapsed() < TIMEOUT);
+        thread::sleep(Duration::from_millis(10));
+    }
+}
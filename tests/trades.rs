@@ -0,0 +1,166 @@
+mod common;
+
+use std::{env, fs::{self, OpenOptions}, io::{ErrorKind, Write}, path::PathBuf, process, sync::{mpsc, Arc, Mutex}, thread};
+
+use rexchange::{common::{ParticipantId, Side, INVALID_TRADE_ID}, logging::logger::Logger, market_data::market_update::MarketUpdateType, matching_engine::{matching_engine::MatchingEngineConfig, orderbook::OrderBookConfig}, order_server::participants_response::ParticipantResponseType, trades::{trade::Trade, trade_store::{self, TradeQuery, TradeStore}}, wire::WIRE_VERSION};
+
+use common::Venue;
+
+fn fills(venue: &Venue) -> Vec<(ParticipantId, u64)> {
+    venue.responses().iter().filter(|response| response.response_type == ParticipantResponseType::Filled).map(|response| (response.participant_id, response.trade_id)).collect()
+}
+
+fn public_trades(venue: &Venue) -> Vec<u64> {
+    venue.market_updates().iter().filter(|update| update.update_type == MarketUpdateType::Trade).map(|update| update.trade_id).collect()
+}
+
+#[test]
+fn every_match_gets_a_trade_id_shared_by_both_fills_and_the_trade() {
+    let mut venue = Venue::with_config(&MatchingEngineConfig { shard_id: 2, ..common::engine_config(&[OrderBookConfig::default()], &[0, 1]) });
+    let trades_rx = venue.trade_sink();
+    venue.engine.set_simulated_time(5_000);
+    let first = 2 << 48 | 1;
+
+    venue.limit(0, 1, Side::Buy, 100, 10);
+    venue.limit(1, 7, Side::Sell, 100, 4);
+    venue.limit(1, 8, Side::Sell, 99, 10);
+
+    assert_eq!(fills(&venue), [(1, first), (0, first), (1, first + 1), (0, first + 1)]);
+    assert_eq!(public_trades(&venue), [first, first + 1]);
+
+    let trades: Vec<Trade> = trades_rx.try_iter().collect();
+    assert_eq!(trades[0], Trade {
+        trade_id: first,
+        symbol_id: 0,
+        aggressor_side: Side::Sell,
+        price: 100,
+        qty: 4,
+        buy_participant_id: 0,
+        buy_order_id: 1,
+        sell_participant_id: 1,
+        sell_order_id: 7,
+        timestamp: 5_000,
+    });
+    assert_eq!((trades[1].trade_id, trades[1].sell_order_id, trades[1].qty), (first + 1, 8, 6));
+
+    // what rests isn't a trade
+    venue.limit(0, 2, Side::Buy, 98, 5);
+    assert!(venue.responses().iter().all(|response| response.trade_id == INVALID_TRADE_ID));
+    assert!(trades_rx.try_recv().is_err());
+}
+
+fn trade(trade_id: u64, symbol_id: u32, buyer: ParticipantId, seller: ParticipantId, timestamp: u64) -> Trade {
+    Trade { trade_id, symbol_id, aggressor_side: Side::Buy, price: 100, qty: 1, buy_participant_id: buyer, buy_order_id: trade_id, sell_participant_id: seller, sell_order_id: trade_id, timestamp }
+}
+
+fn store_path(name: &str) -> PathBuf {
+    let path = env::temp_dir().join(format!("rexchange-{}-{}", name, process::id())).join("trades.dat");
+    let _ = fs::remove_file(&path);
+    path
+}
+
+fn ids(trades: Vec<&Trade>) -> Vec<u64> {
+    trades.iter().map(|trade| trade.trade_id).collect()
+}
+
+#[test]
+fn the_store_is_queried_by_symbol_participant_and_time() {
+    let path = store_path("query");
+    let store = Arc::new(Mutex::new(TradeStore::open(&path).unwrap()));
+    let (trades_tx, trades_rx) = mpsc::sync_channel(16);
+
+    let writer_store = store.clone();
+    let writer = thread::spawn(move || trade_store::run(writer_store, trades_rx, Logger::disabled()));
+
+    for trade in [trade(1, 0, 0, 1, 10), trade(2, 1, 1, 2, 20), trade(3, 0, 2, 2, 30), trade(4, 1, 0, 2, 40)] {
+        trades_tx.send(trade).unwrap();
+    }
+
+    drop(trades_tx);
+    writer.join().unwrap();
+
+    let store = store.lock().unwrap();
+    assert_eq!(store.len(), 4);
+    assert_eq!(store.trade(3), Some(&trade(3, 0, 2, 2, 30)));
+    assert!(store.trade(5).is_none());
+
+    assert_eq!(ids(store.query(&TradeQuery::default())), [1, 2, 3, 4]);
+    assert_eq!(ids(store.query(&TradeQuery { symbol_id: Some(1), ..TradeQuery::default() })), [2, 4]);
+    assert_eq!(ids(store.query(&TradeQuery { participant_id: Some(2), ..TradeQuery::default() })), [2, 3, 4]);
+    assert_eq!(ids(store.query(&TradeQuery { symbol_id: Some(0), participant_id: Some(0), ..TradeQuery::default() })), [1]);
+    assert_eq!(ids(store.query(&TradeQuery { from: Some(20), to: Some(40), ..TradeQuery::default() })), [2, 3]);
+    assert!(store.query(&TradeQuery { participant_id: Some(9), ..TradeQuery::default() }).is_empty());
+}
+
+#[test]
+fn the_store_survives_a_restart_and_a_torn_write() {
+    let path = store_path("reopen");
+
+    let mut store = TradeStore::open(&path).unwrap();
+    store.append(trade(1, 0, 0, 1, 10)).unwrap();
+    store.append(trade(2, 0, 1, 0, 20)).unwrap();
+    store.flush().unwrap();
+    drop(store);
+
+    // a crash in the middle of a frame
+    let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+    file.write_all(&[WIRE_VERSION, b'T', 64, 0, 1, 2, 3]).unwrap();
+    drop(file);
+
+    let mut store = TradeStore::open(&path).unwrap();
+    assert_eq!(ids(store.query(&TradeQuery::default())), [1, 2]);
+
+    store.append(trade(3, 0, 0, 1, 30)).unwrap();
+    store.flush().unwrap();
+    drop(store);
+
+    let store = TradeStore::open(&path).unwrap();
+    assert_eq!(ids(store.query(&TradeQuery { participant_id: Some(0), ..TradeQuery::default() })), [1, 2, 3]);
+
+    // anything else that doesn't decode is an error rather than lost trades
+    fs::write(&path, [1, b'T', 64, 0]).unwrap();
+    assert!(TradeStore::open(&path).is_err());
+}
+
+#[test]
+fn a_restarted_shard_numbers_its_trades_after_the_stored_ones() {
+    let path = store_path("restart");
+    let config = MatchingEngineConfig { shard_id: 1, ..common::engine_config(&[OrderBookConfig::default()], &[0, 1]) };
+    let first = 1 << 48 | 1;
+
+    let session = |store: &mut TradeStore| {
+        let mut venue = Venue::with_config(&config);
+        let trades_rx = venue.trade_sink();
+        if let Some(last_trade_id) = store.last_trade_id(1) {
+            venue.engine.set_last_trade_id(last_trade_id);
+        }
+
+        venue.limit(0, 1, Side::Buy, 100, 2);
+        venue.limit(1, 1, Side::Sell, 100, 1);
+        venue.limit(1, 2, Side::Sell, 100, 1);
+
+        for trade in trades_rx.try_iter() {
+            store.append(trade).unwrap();
+        }
+        store.flush().unwrap();
+    };
+
+    let mut store = TradeStore::open(&path).unwrap();
+    session(&mut store);
+    drop(store);
+
+    let mut store = TradeStore::open(&path).unwrap();
+    assert_eq!((store.last_trade_id(1), store.last_trade_id(0)), (Some(first + 1), None));
+    session(&mut store);
+    drop(store);
+
+    // nothing from before the restart was written over
+    let mut store = TradeStore::open(&path).unwrap();
+    assert_eq!(ids(store.query(&TradeQuery::default())), [first, first + 1, first + 2, first + 3]);
+
+    // an id is stored once, a bust or correction amends it
+    assert_eq!(store.append(trade(first, 0, 0, 1, 10)).unwrap_err().kind(), ErrorKind::AlreadyExists);
+    assert_eq!(store.amend(trade(first + 4, 0, 0, 1, 10)).unwrap_err().kind(), ErrorKind::NotFound);
+    store.amend(Trade { qty: 0, ..*store.trade(first).unwrap() }).unwrap();
+    assert_eq!(ids(store.query(&TradeQuery::default())), [first + 1, first + 2, first + 3]);
+}
@@ -0,0 +1,186 @@
+use std::io::{Cursor, ErrorKind};
+
+use rexchange::{common::{OrderType, Side, NO_ORDER_FLAGS}, market_data::market_update::{MarketUpdate, MarketUpdateType}, order_server::{participants_request::{ParticipantRequest, ParticipantRequestType}, participants_response::{ParticipantResponse, ParticipantResponseType}, tcp_gateway::Login}, positions::Position, trades::trade::Trade, wire::{self, LoginFrame, MarketUpdateFrame, PositionFrame, RequestFrame, ResponseFrame, TradeFrame, WireError, WireFrame, HEADER_SIZE, MAX_FRAME_SIZE, WIRE_VERSION}};
+
+fn request() -> ParticipantRequest {
+    ParticipantRequest {
+        request_type: ParticipantRequestType::New,
+        participant_id: 2,
+        symbol_id: 1,
+        order_id: 77,
+        order_type: OrderType::Limit,
+        side: Side::Buy,
+        price: 10_001,
+        stop_price: 0,
+        qty: 300,
+        display_qty: 100,
+        flags: NO_ORDER_FLAGS,
+        recv_time: 123_456,
+    }
+}
+
+fn response() -> ParticipantResponse {
+    ParticipantResponse {
+        response_type: ParticipantResponseType::Filled,
+        participant_id: 2,
+        symbol_id: 1,
+        participant_order_id: 77,
+        internal_order_id: 1 << 33,
+        side: Side::Buy,
+        price: 10_001,
+        exec_qty: 200,
+        leaves_qty: 100,
+        aggressor: true,
+        trade_id: 12,
+        fee: -3,
+    }
+}
+
+fn market_update() -> MarketUpdate {
+    MarketUpdate {
+        update_type: MarketUpdateType::Trade,
+        order_id: 1 << 33,
+        symbol_id: 1,
+        side: Side::Sell,
+        price: 10_001,
+        qty: 200,
+        priority: 9,
+        trade_id: 12,
+    }
+}
+
+fn trade() -> Trade {
+    Trade {
+        trade_id: 1 << 48 | 12,
+        symbol_id: 1,
+        aggressor_side: Side::Sell,
+        price: 10_001,
+        qty: 200,
+        buy_participant_id: 2,
+        buy_order_id: 77,
+        sell_participant_id: 3,
+        sell_order_id: 5,
+        timestamp: 1_700_000_000_000_000_000,
+    }
+}
+
+fn position() -> Position {
+    Position { participant_id: 2, symbol_id: 1, position: -200, open_price: 10_001.5, realized: -0.25, unrealized: 300.0, mark_price: 10_000 }
+}
+
+#[test]
+fn frames_are_packed_little_endian_with_a_header() {
+    let mut buf = Vec::new();
+    wire::encode::<ResponseFrame>(&response(), &mut buf);
+
+    assert_eq!(buf.len(), 64);
+    assert_eq!(&buf[..HEADER_SIZE], &[WIRE_VERSION, wire::MESSAGE_RESPONSE, 64, 0]);
+    assert_eq!(&buf[4..8], &[ParticipantResponseType::Filled as u8, Side::Buy as u8, 1, 0]);
+    assert_eq!(&buf[8..12], &2u32.to_le_bytes());
+    assert_eq!(&buf[32..40], &(1u64 << 33).to_le_bytes());
+    assert_eq!(&buf[48..56], &12u64.to_le_bytes());
+    assert_eq!(&buf[56..64], &(-3i64).to_le_bytes());
+}
+
+#[test]
+fn every_message_round_trips() {
+    let mut buf = Vec::new();
+    wire::encode::<RequestFrame>(&request(), &mut buf);
+    wire::encode::<ResponseFrame>(&response(), &mut buf);
+    wire::encode::<MarketUpdateFrame>(&market_update(), &mut buf);
+    wire::encode::<TradeFrame>(&trade(), &mut buf);
+    wire::encode::<PositionFrame>(&position(), &mut buf);
+
+    let (decoded_request, size) = wire::decode::<RequestFrame>(&buf).unwrap();
+    assert_eq!(decoded_request.to_string(), request().to_string());
+    assert_eq!(decoded_request.recv_time, 123_456);
+
+    let (decoded_response, response_size) = wire::decode::<ResponseFrame>(&buf[size..]).unwrap();
+    assert_eq!(decoded_response.to_string(), response().to_string());
+
+    let (decoded_update, update_size) = wire::decode::<MarketUpdateFrame>(&buf[size + response_size..]).unwrap();
+    assert_eq!(decoded_update.to_string(), market_update().to_string());
+
+    let (decoded_trade, trade_size) = wire::decode::<TradeFrame>(&buf[size + response_size + update_size..]).unwrap();
+    assert_eq!(decoded_trade, trade());
+
+    let (decoded_position, position_size) = wire::decode::<PositionFrame>(&buf[size + response_size + update_size + trade_size..]).unwrap();
+    assert_eq!(decoded_position, position());
+    assert_eq!(size + response_size + update_size + trade_size + position_size, buf.len());
+}
+
+#[test]
+fn frames_are_viewed_in_place_at_any_offset() {
+    let mut buf = vec![0xff];
+    wire::encode::<MarketUpdateFrame>(&market_update(), &mut buf);
+
+    let frame = MarketUpdateFrame::view(&buf[1..]).unwrap();
+    assert_eq!(frame.as_bytes(), &buf[1..]);
+    assert_eq!({ frame.priority }, 9u64.to_le());
+    assert_eq!(frame.decode().unwrap().to_string(), market_update().to_string());
+}
+
+#[test]
+fn bad_headers_and_discriminants_are_errors() {
+    let mut buf = Vec::new();
+    wire::encode::<ResponseFrame>(&response(), &mut buf);
+
+    assert_eq!(wire::decode::<ResponseFrame>(&buf[..63]).err(), Some(WireError::Truncated(64)));
+    assert_eq!(wire::decode::<TradeFrame>(&buf).err(), Some(WireError::MessageType(wire::MESSAGE_RESPONSE)));
+    assert_eq!(wire::decode::<MarketUpdateFrame>(&buf).err(), Some(WireError::MessageType(wire::MESSAGE_RESPONSE)));
+
+    let corrupt = |offset: usize, value: u8| {
+        let mut bad = buf.clone();
+        bad[offset] = value;
+        wire::decode::<ResponseFrame>(&bad).err()
+    };
+
+    assert_eq!(corrupt(0, 1), Some(WireError::Version(1)));
+    assert_eq!(corrupt(2, 63), Some(WireError::Length(63)));
+    assert_eq!(corrupt(4, 200), Some(WireError::Discriminant("response type", 200)));
+    assert_eq!(corrupt(5, 3), Some(WireError::Discriminant("side", 3)));
+    assert_eq!(corrupt(6, 2), Some(WireError::Discriminant("aggressor", 2)));
+}
+
+#[test]
+fn login_passwords_are_nul_padded_text() {
+    let login = Login { participant_id: 4, password: "secret".to_string(), accepted: true };
+    let mut buf = Vec::new();
+    wire::encode::<LoginFrame>(&login, &mut buf);
+
+    assert_eq!(buf.len(), 28);
+    assert_eq!(&buf[12..18], b"secret");
+    assert_eq!(wire::decode::<LoginFrame>(&buf).unwrap().0, login);
+
+    let corrupt = |offset: usize, value: u8| {
+        let mut bad = buf.clone();
+        bad[offset] = value;
+        wire::decode::<LoginFrame>(&bad).err()
+    };
+
+    assert_eq!(corrupt(4, 2), Some(WireError::Discriminant("accepted", 2)));
+    assert_eq!(corrupt(20, b'x'), Some(WireError::Text("password")));
+    assert_eq!(corrupt(12, 0xff), Some(WireError::Text("password")));
+}
+
+#[test]
+fn streams_are_read_a_frame_at_a_time() {
+    let mut stream = Vec::new();
+    wire::encode::<MarketUpdateFrame>(&market_update(), &mut stream);
+    wire::encode::<RequestFrame>(&request(), &mut stream);
+
+    let mut input = Cursor::new(stream);
+    let mut buf = [0; MAX_FRAME_SIZE];
+
+    let length = wire::read_frame(&mut input, &mut buf).unwrap();
+    assert_eq!(wire::decode::<MarketUpdateFrame>(&buf[..length]).unwrap().0.to_string(), market_update().to_string());
+
+    let length = wire::read_frame(&mut input, &mut buf).unwrap();
+    assert_eq!(wire::decode::<RequestFrame>(&buf[..length]).unwrap().0.to_string(), request().to_string());
+
+    assert_eq!(wire::read_frame(&mut input, &mut buf).unwrap_err().kind(), ErrorKind::UnexpectedEof);
+
+    // a length that can't be a frame leaves no way to find the next one
+    let mut input = Cursor::new(vec![WIRE_VERSION, wire::MESSAGE_REQUEST, 200, 0]);
+    assert_eq!(wire::read_frame(&mut input, &mut buf).unwrap_err().kind(), ErrorKind::InvalidData);
+}